@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -117,6 +118,7 @@ impl PackageBackend for AppImageBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -196,6 +198,26 @@ impl PackageBackend for AppImageBackend {
     }
 }
 
+/// Classify an AppImage failure.
+///
+/// AppImages are plain files in the user's directories: nothing is fetched,
+/// locked or escalated, so the only failures are the file system's and
+/// LinGet's own refusals.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let code = if output.contains("(os error 13)") {
+        ProviderErrorCode::AuthorizationDenied
+    } else if output.contains("(os error 28)") {
+        ProviderErrorCode::DiskFull
+    } else if output.starts_with("AppImage '") && output.trim_end().ends_with("not found") {
+        ProviderErrorCode::NotFound
+    } else if output.contains("not supported") || output.contains("must be done manually") {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
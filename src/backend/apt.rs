@@ -1,4 +1,6 @@
+use super::dependency_graph::{ProtectedPackage, ProtectionReason};
 use super::streaming::StreamLine;
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::{run_pkexec, run_pkexec_with_logs, Suggest};
 use crate::models::{Package, PackageSource, PackageStatus, Repository};
@@ -10,6 +12,17 @@ use std::process::Stdio;
 use tokio::process::Command;
 use tokio::sync::mpsc;
 
+/// Limits `apt-cache depends` and `rdepends` to hard dependencies: a removal
+/// breaks what depends on a package, not what merely recommends it.
+const DEPENDS_ONLY: [&str; 6] = [
+    "--no-recommends",
+    "--no-suggests",
+    "--no-conflicts",
+    "--no-breaks",
+    "--no-replaces",
+    "--no-enhances",
+];
+
 pub struct AptBackend;
 
 impl AptBackend {
@@ -41,6 +54,79 @@ impl AptBackend {
         Ok(stdout)
     }
 
+    /// Parse `apt-cache depends` output into the names a package depends on.
+    /// Alternatives (`|Depends:`) are all listed; virtual packages, shown in
+    /// angle brackets, are not installed packages and are skipped.
+    fn parse_depends(stdout: &str) -> Vec<String> {
+        let mut deps: Vec<String> = Vec::new();
+        for line in stdout.lines() {
+            let line = line.trim().trim_start_matches('|');
+            let Some((kind, dep)) = line.split_once(':') else {
+                continue;
+            };
+            if kind != "Depends" && kind != "PreDepends" {
+                continue;
+            }
+            let dep = dep.trim();
+            if dep.is_empty() || dep.starts_with('<') {
+                continue;
+            }
+            let dep = dep.split(':').next().unwrap_or(dep);
+            if !deps.iter().any(|known| known == dep) {
+                deps.push(dep.to_string());
+            }
+        }
+        deps
+    }
+
+    /// Parse `apt-cache rdepends` output into the packages that would break
+    /// without `name`. A `|` entry depends on `name` as one of several
+    /// alternatives, which another installed alternative may still satisfy,
+    /// so it is not a hard dependent.
+    fn parse_rdepends(stdout: &str, name: &str) -> Vec<String> {
+        let mut deps: Vec<String> = Vec::new();
+        for line in stdout.lines().skip(2) {
+            let dep = line.trim();
+            if dep.is_empty() || dep.starts_with('|') || dep == name {
+                continue;
+            }
+            if !deps.iter().any(|known| known == dep) {
+                deps.push(dep.to_string());
+            }
+        }
+        deps
+    }
+
+    /// Parse `dpkg-query` lines of package, `Essential`, `Protected` and
+    /// status into the installed packages dpkg refuses to remove without
+    /// `--force-remove-essential` or `--force-remove-protected`.
+    fn parse_protected(stdout: &str) -> Vec<ProtectedPackage> {
+        stdout
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                let name = fields.next()?.trim();
+                let essential = fields.next()?.trim() == "yes";
+                let protected = fields.next()?.trim() == "yes";
+                let installed = fields.next()?.trim().starts_with("ii");
+                if name.is_empty() || !installed {
+                    return None;
+                }
+                let reason = if essential {
+                    ProtectionReason::Essential
+                } else if protected {
+                    ProtectionReason::Protected
+                } else {
+                    return None;
+                };
+                Some(ProtectedPackage {
+                    name: name.to_string(),
+                    reason,
+                })
+            })
+            .collect()
+    }
+
     /// Parse APT sources list files to extract repository information.
     /// Parses both /etc/apt/sources.list and /etc/apt/sources.list.d/*.list files.
     fn parse_sources_list(content: &str, filename: &str) -> Vec<Repository> {
@@ -289,6 +375,7 @@ impl PackageBackend for AptBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -337,6 +424,7 @@ impl PackageBackend for AptBackend {
                         install_date: None,
                         update_category: None,
                         enrichment: None,
+                        released_at: None,
                     };
                     pkg.update_category = Some(pkg.detect_update_category());
                     packages.push(pkg);
@@ -449,6 +537,48 @@ impl PackageBackend for AptBackend {
         }
     }
 
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["install", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "apt",
+            &args,
+            &format!("Failed to install packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo apt {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["remove", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "apt",
+            &args,
+            &format!("Failed to remove packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo apt {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["install", "--only-upgrade", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "apt",
+            &args,
+            &format!("Failed to update packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo apt {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
     async fn available_downgrade_versions(&self, name: &str) -> Result<Vec<String>> {
         // `apt-cache madison <pkg>` output:
         //  pkg | 1.2.3-1 | http://... focal/main amd64 Packages
@@ -545,6 +675,7 @@ impl PackageBackend for AptBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -736,6 +867,7 @@ impl PackageBackend for AptBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -764,24 +896,72 @@ impl PackageBackend for AptBackend {
 
     async fn get_reverse_dependencies(&self, name: &str) -> Result<Vec<String>> {
         let output = Command::new("apt-cache")
-            .args(["rdepends", "--installed", name])
+            .args(["rdepends", "--installed"])
+            .args(DEPENDS_ONLY)
+            .arg(name)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .output()
             .await
             .context("Failed to get reverse dependencies")?;
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut deps = Vec::new();
+        Ok(Self::parse_rdepends(
+            &String::from_utf8_lossy(&output.stdout),
+            name,
+        ))
+    }
 
-        for line in stdout.lines().skip(2) {
-            let dep = line.trim().trim_start_matches('|').trim();
-            if !dep.is_empty() && dep != name {
-                deps.push(dep.to_string());
-            }
+    async fn get_dependencies(&self, name: &str) -> Result<Vec<String>> {
+        let output = Command::new("apt-cache")
+            .args(["depends", "--installed"])
+            .args(DEPENDS_ONLY)
+            .arg(name)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to get dependencies")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "apt-cache depends failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
         }
+        Ok(Self::parse_depends(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
 
-        Ok(deps)
+    async fn list_auto_installed(&self) -> Result<Vec<String>> {
+        let output = Command::new("apt-mark")
+            .arg("showauto")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list automatically installed apt packages")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "apt-mark showauto failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.trim().split(':').next().unwrap_or_default())
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    async fn list_protected(&self) -> Result<Vec<ProtectedPackage>> {
+        let stdout = self
+            .run_dpkg_query(&[
+                "-W",
+                "-f=${Package}\t${Essential}\t${Protected}\t${db:Status-Abbrev}\n",
+            ])
+            .await?;
+        Ok(Self::parse_protected(&stdout))
     }
 
     fn source(&self) -> PackageSource {
@@ -869,6 +1049,121 @@ impl PackageBackend for AptBackend {
 
         status
     }
+
+    async fn hold(&self, name: &str) -> Result<()> {
+        run_pkexec(
+            "apt-mark",
+            &["hold", "--", name],
+            &format!("Failed to hold package {}", name),
+            Suggest {
+                command: format!("sudo apt-mark hold -- {}", name),
+            },
+        )
+        .await
+    }
+
+    async fn unhold(&self, name: &str) -> Result<()> {
+        run_pkexec(
+            "apt-mark",
+            &["unhold", "--", name],
+            &format!("Failed to release hold on package {}", name),
+            Suggest {
+                command: format!("sudo apt-mark unhold -- {}", name),
+            },
+        )
+        .await
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        let output = Command::new("apt-mark")
+            .arg("showhold")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list held apt packages")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "apt-mark showhold failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+}
+
+/// Classify an apt failure from its `E:` lines.
+///
+/// apt exits 100 for every error, so the status says nothing; the `E:` lines
+/// do, and their wording is stable. Lines are read in order and the first one
+/// that names a cause decides: apt follows the cause with summaries such as
+/// "Unable to fetch some archives" that would otherwise mislead.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let mut errors = output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("E: "))
+        .peekable();
+    errors.peek()?;
+    let code = errors
+        .find_map(classify_error_line)
+        .unwrap_or(ProviderErrorCode::Unknown);
+    Some(code.into())
+}
+
+fn classify_error_line(line: &str) -> Option<ProviderErrorCode> {
+    let code = if line.contains("are you root?") || line.contains("(13: Permission denied)") {
+        ProviderErrorCode::AuthorizationDenied
+    } else if line.starts_with("Could not get lock")
+        || line.starts_with("Unable to acquire the dpkg frontend lock")
+        || line.starts_with("Unable to lock directory")
+    {
+        ProviderErrorCode::LockBusy
+    } else if line.starts_with("Unable to locate package")
+        || line.starts_with("Couldn't find any package by")
+        || line.ends_with("has no installation candidate")
+    {
+        ProviderErrorCode::NotFound
+    } else if line.starts_with("Unmet dependencies")
+        || line.starts_with("Unable to correct problems")
+        || line.starts_with("Error, pkgProblemResolver")
+    {
+        ProviderErrorCode::DependencyConflict
+    } else if line.starts_with("You don't have enough free space")
+        || line.contains("No space left on device")
+    {
+        ProviderErrorCode::DiskFull
+    } else if line.starts_with("dpkg was interrupted") {
+        ProviderErrorCode::Interrupted
+    } else if line.ends_with("is not signed.")
+        || line.contains("signatures couldn't be verified")
+        || line.contains("signatures were invalid")
+    {
+        ProviderErrorCode::SignatureInvalid
+    } else if line.contains("does not have a Release file")
+        || line.contains("no longer has a Release file")
+    {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if line.starts_with("Failed to fetch") {
+        if line.contains("timed out") {
+            ProviderErrorCode::Timeout
+        } else if line.contains("404  Not Found")
+            || line.contains("404 Not Found")
+            || line.contains("Hash Sum mismatch")
+        {
+            // The index names a file the mirror no longer serves.
+            ProviderErrorCode::RepositoryUnavailable
+        } else {
+            ProviderErrorCode::Network
+        }
+    } else {
+        return None;
+    };
+    Some(code)
 }
 
 #[cfg(test)]
@@ -998,6 +1293,45 @@ deb http://enabled.example.com/repo stable main
         assert!(repos[1].enabled);
     }
 
+    #[test]
+    fn depends_output_lists_hard_dependencies_without_virtual_packages() {
+        let stdout = "vim\n  Depends: vim-common\n |Depends: libgpm2\n  Depends: libc6:amd64\n  PreDepends: dpkg\n  Depends: <python3-any>\n  Depends: vim-common\n";
+        assert_eq!(
+            AptBackend::parse_depends(stdout),
+            ["vim-common", "libgpm2", "libc6", "dpkg"]
+        );
+    }
+
+    #[test]
+    fn rdepends_output_skips_dependents_with_other_alternatives() {
+        // `init` depends on `systemd-sysv | sysvinit-core`; removing one
+        // alternative must not count the Essential `init` as a dependent.
+        let stdout =
+            "systemd-sysv\nReverse Depends:\n |init\n  systemd-cron\n |init\n  systemd-cron\n";
+        assert_eq!(
+            AptBackend::parse_rdepends(stdout, "systemd-sysv"),
+            ["systemd-cron"]
+        );
+    }
+
+    #[test]
+    fn essential_and_protected_packages_are_read_from_dpkg() {
+        let stdout = "base-files\tyes\t\tii \nsystemd\t\tyes\tii \nvim\tno\t\tii \nold-essential\tyes\t\trc \n";
+        assert_eq!(
+            AptBackend::parse_protected(stdout),
+            [
+                ProtectedPackage {
+                    name: "base-files".to_string(),
+                    reason: ProtectionReason::Essential,
+                },
+                ProtectedPackage {
+                    name: "systemd".to_string(),
+                    reason: ProtectionReason::Protected,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn test_is_available() {
         // This test verifies the availability check runs without panic
@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -62,6 +63,7 @@ impl PackageBackend for AurBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -107,6 +109,7 @@ impl PackageBackend for AurBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -204,6 +207,7 @@ impl PackageBackend for AurBackend {
                         install_date: None,
                         update_category: None,
                         enrichment: None,
+                        released_at: None,
                     });
                     if packages.len() >= 50 {
                         break;
@@ -223,6 +227,43 @@ impl PackageBackend for AurBackend {
     }
 }
 
+/// Classify an AUR helper failure.
+///
+/// yay and paru build with makepkg, which reports on `==> ERROR:` lines,
+/// escalate with sudo, and finish with pacman, whose messages decide once
+/// the build-specific ones name nothing.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| {
+        line.starts_with("sudo: a terminal is required")
+            || line.starts_with("sudo: a password is required")
+    }) {
+        ProviderErrorCode::NoPrivilegeAgent
+    } else if any(&|line| line.starts_with("sudo:") && line.contains("incorrect password")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.starts_with("==> ERROR: One or more PGP signatures could not be verified")
+            || line.starts_with("==> ERROR: One or more files did not pass the validity check")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| line.starts_with("==> ERROR: Could not resolve all dependencies")) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.starts_with("-> No AUR package found for")
+            || line.starts_with("error: could not find all required packages")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if let Some(class) = super::pacman::classify_failure(exit_code, output) {
+        return Some(class);
+    } else if any(&|line| line.starts_with("==> ERROR:")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
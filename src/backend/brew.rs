@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -308,6 +309,7 @@ impl PackageBackend for BrewBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -371,6 +373,7 @@ impl PackageBackend for BrewBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
             return Ok(packages);
@@ -446,6 +449,7 @@ impl PackageBackend for BrewBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
             if packages.len() >= 50 {
                 break;
@@ -485,6 +489,51 @@ impl PackageBackend for BrewBackend {
     }
 }
 
+/// Classify a Homebrew failure from its `Error:` lines and curl's errors.
+///
+/// brew exits 1 for every error. Downloads go through curl, whose error
+/// number names the cause. Homebrew refuses to run as root, so privilege
+/// prompts do not arise.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.contains("has already locked")) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| line.starts_with("curl: (28)")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| line.starts_with("curl: (6)") || line.starts_with("curl: (7)")) {
+        ProviderErrorCode::Network
+    } else if any(&|line| line.starts_with("curl: (22)")) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| {
+        line.starts_with("Error: SHA256 mismatch") || line.starts_with("Error: Checksum mismatch")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| line.contains("No space left on device")) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| {
+        line.contains("Permission denied @") || line.contains("are not writable by your user")
+    }) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.contains("because conflicting formulae are installed")
+            || line.starts_with("Error: An unsatisfied requirement failed")
+    }) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.starts_with("Error: No available formula")
+            || line.starts_with("Error: No formulae or casks found")
+            || line.starts_with("Error: No such keg")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| line.starts_with("Error:")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,7 +1,10 @@
+use super::registry::Registry;
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageEnrichment, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use std::process::Stdio;
 use std::time::Duration;
@@ -50,7 +53,8 @@ impl CargoBackend {
 
     /// Fetch crate metadata from crates.io API
     async fn fetch_crate_info(&self, name: &str) -> Option<CrateInfo> {
-        let url = format!("https://crates.io/api/v1/crates/{}", name);
+        let registry = Registry::crates_io();
+        let url = registry.url(&format!("api/v1/crates/{}", name));
         let resp = self.client.get(&url).send().await.ok()?;
 
         if !resp.status().is_success() {
@@ -111,12 +115,15 @@ impl CargoBackend {
                 .get("updated_at")
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string()),
+            released_at: registry
+                .parse_release(&json)
+                .and_then(|release| release.released_at),
         })
     }
 
     /// Fetch available versions for a crate
     async fn fetch_crate_versions(&self, name: &str) -> Result<Vec<CrateVersion>> {
-        let url = format!("https://crates.io/api/v1/crates/{}/versions", name);
+        let url = Registry::crates_io().url(&format!("api/v1/crates/{}/versions", name));
         let resp = self.client.get(&url).send().await?;
 
         if !resp.status().is_success() {
@@ -188,6 +195,8 @@ struct CrateInfo {
     categories: Vec<String>,
     keywords: Vec<String>,
     updated_at: Option<String>,
+    /// When `max_version` was published.
+    released_at: Option<DateTime<Utc>>,
 }
 
 /// Version information for a crate
@@ -252,6 +261,7 @@ impl PackageBackend for CargoBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -310,6 +320,7 @@ impl PackageBackend for CargoBackend {
                         install_date: None,
                         update_category: None,
                         enrichment: Some(enrichment),
+                        released_at: info.released_at,
                     };
                     pkg.update_category = Some(pkg.detect_update_category());
                     packages_with_updates.push(pkg);
@@ -595,6 +606,7 @@ impl PackageBackend for CargoBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -666,6 +678,44 @@ fn format_downloads(count: u64) -> String {
     }
 }
 
+/// Classify a `cargo install` failure from its `error:` lines.
+///
+/// cargo exits 101 for every error. Registry failures quote the curl error
+/// number, which says more than the surrounding text. cargo waits for a held
+/// package-cache lock rather than failing, and never escalates privileges.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let class = if any(&|line| line.starts_with("error: could not find `")) {
+        ProviderErrorCode::NotFound.into()
+    } else if any(&|line| line.contains("[28]") || line.contains("Operation timed out")) {
+        ProviderErrorCode::Timeout.into()
+    } else if any(&|line| {
+        line.contains("[6]") || line.contains("[7]") || line.contains("Could not resolve host")
+    }) {
+        ProviderErrorCode::Network.into()
+    } else if any(&|line| {
+        line.starts_with("failed to get successful HTTP response from") && line.contains("got 5")
+    }) {
+        ProviderErrorCode::RepositoryUnavailable.into()
+    } else if any(&|line| line.starts_with("error: failed to verify the checksum of")) {
+        ProviderErrorCode::SignatureInvalid.into()
+    } else if any(&|line| line.contains("it requires rustc")) {
+        FailureClass::RUNTIME_MISMATCH
+    } else if any(&|line| line.starts_with("error: failed to select a version for")) {
+        ProviderErrorCode::DependencyConflict.into()
+    } else if any(&|line| line.contains("(os error 28)")) {
+        ProviderErrorCode::DiskFull.into()
+    } else if any(&|line| line.contains("(os error 13)")) {
+        ProviderErrorCode::AuthorizationDenied.into()
+    } else if any(&|line| line.starts_with("error:")) {
+        ProviderErrorCode::Unknown.into()
+    } else {
+        return None;
+    };
+    Some(class)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,5 @@
+use super::streaming::CommandFailure;
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -125,6 +127,7 @@ impl PackageBackend for CondaBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
         Ok(packages)
@@ -194,6 +197,7 @@ impl PackageBackend for CondaBackend {
                                 install_date: None,
                                 update_category: None,
                                 enrichment: None,
+                                released_at: None,
                             });
                         }
                     }
@@ -205,42 +209,48 @@ impl PackageBackend for CondaBackend {
     }
 
     async fn install(&self, name: &str) -> Result<()> {
-        let status = Command::new("conda")
+        let output = Command::new("conda")
             .args(["install", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install conda package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install conda package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("conda", &output)
+            .with_message(format!("Failed to install conda package {}", name)))
     }
 
     async fn remove(&self, name: &str) -> Result<()> {
-        let status = Command::new("conda")
+        let output = Command::new("conda")
             .args(["remove", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to remove conda package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to remove conda package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("conda", &output)
+            .with_message(format!("Failed to remove conda package {}", name)))
     }
 
     async fn update(&self, name: &str) -> Result<()> {
-        let status = Command::new("conda")
+        let output = Command::new("conda")
             .args(["update", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to update conda package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to update conda package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("conda", &output)
+            .with_message(format!("Failed to update conda package {}", name)))
     }
 
     async fn downgrade_to(&self, name: &str, version: &str) -> Result<()> {
@@ -248,16 +258,18 @@ impl PackageBackend for CondaBackend {
             anyhow::bail!("Version is required");
         }
         let spec = format!("{}={}", name, version);
-        let status = Command::new("conda")
+        let output = Command::new("conda")
             .args(["install", "-n", "base", "-y", &spec])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install a specific conda package version")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install {} via conda", spec)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("conda", &output)
+            .with_message(format!("Failed to install {} via conda", spec)))
     }
 
     async fn search(&self, query: &str) -> Result<Vec<Package>> {
@@ -309,6 +321,7 @@ impl PackageBackend for CondaBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -405,6 +418,60 @@ impl PackageBackend for CondaBackend {
     }
 }
 
+/// Classify a conda failure from the exception it reports.
+///
+/// conda names every failure with an exception class (`PackagesNotFoundError`,
+/// `CondaHTTPError`, ...) at the start of a line. HTTP errors carry a status,
+/// where 000 means the server was never reached. conda runs as the user, so
+/// privilege prompts do not arise.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| {
+        line.starts_with("KeyboardInterrupt") || line.starts_with("CondaSignalInterrupt")
+    }) {
+        ProviderErrorCode::Interrupted
+    } else if any(&|line| {
+        line.starts_with("PackagesNotFoundError") || line.starts_with("PackageNotInstalledError")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| {
+        line.starts_with("UnsatisfiableError") || line.starts_with("LibMambaUnsatisfiableError")
+    }) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| line.contains("ReadTimeout") || line.contains("Read timed out")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| line.starts_with("CondaHTTPError: HTTP 000")) {
+        ProviderErrorCode::Network
+    } else if any(&|line| {
+        line.starts_with("CondaHTTPError:") || line.starts_with("UnavailableInvalidChannel")
+    }) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| {
+        line.starts_with("SafetyError")
+            || line.starts_with("CondaVerificationError")
+            || line.starts_with("ChecksumMismatchError")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| line.contains("[Errno 28]") || line.contains("No space left on device")) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| {
+        line.starts_with("NotWritableError") || line.starts_with("EnvironmentNotWritableError")
+    }) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| line.starts_with("LockError")) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| {
+        line.split_once(':')
+            .is_some_and(|(name, _)| name.ends_with("Error") && !name.contains(' '))
+    }) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -112,6 +113,7 @@ impl PackageBackend for DartBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -162,6 +164,7 @@ impl PackageBackend for DartBackend {
                                     install_date: None,
                                     update_category: None,
                                     enrichment: None,
+                                    released_at: None,
                                 });
                             }
                         }
@@ -270,6 +273,7 @@ impl PackageBackend for DartBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
 
             if packages.len() >= 50 {
@@ -305,6 +309,45 @@ impl PackageBackend for DartBackend {
     }
 }
 
+/// Classify a `dart pub` failure from its exit status and messages.
+///
+/// pub exits with sysexits codes: 69 (unavailable) when pub.dev cannot be
+/// reached, 65 for bad data and 1 otherwise. Its messages have no prefix, so
+/// they are matched by wording. pub runs as the user and takes no lock.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let class = if any(&|line| {
+        line.contains("doesn't exist (could not find package")
+            || (line.starts_with("Package ") && line.ends_with("is not currently active."))
+    }) {
+        ProviderErrorCode::NotFound.into()
+    } else if any(&|line| line.contains("TimeoutException")) {
+        ProviderErrorCode::Timeout.into()
+    } else if any(&|line| {
+        line.contains("Failed host lookup") || line.starts_with("Got socket error")
+    }) {
+        ProviderErrorCode::Network.into()
+    } else if any(&|line| line.contains("had wrong content-hash")) {
+        ProviderErrorCode::SignatureInvalid.into()
+    } else if any(&|line| line.contains("errno = 28")) {
+        ProviderErrorCode::DiskFull.into()
+    } else if any(&|line| line.contains("errno = 13")) {
+        ProviderErrorCode::AuthorizationDenied.into()
+    } else if any(&|line| line.contains("requires SDK version")) {
+        FailureClass::RUNTIME_MISMATCH
+    } else if any(&|line| line.contains("version solving failed")) {
+        ProviderErrorCode::DependencyConflict.into()
+    } else if exit_code == Some(69) || any(&|line| line.contains("Service Unavailable")) {
+        ProviderErrorCode::RepositoryUnavailable.into()
+    } else if exit_code.is_some() {
+        ProviderErrorCode::Unknown.into()
+    } else {
+        return None;
+    };
+    Some(class)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::{run_pkexec, Suggest};
 use crate::models::{Package, PackageSource, PackageStatus};
@@ -104,6 +105,7 @@ impl PackageBackend for DebBackend {
                                     install_date: None,
                                     update_category: None,
                                     enrichment: None,
+                                    released_at: None,
                                 });
                             }
                         }
@@ -190,6 +192,46 @@ impl PackageBackend for DebBackend {
     }
 }
 
+/// Classify a dpkg failure from its `dpkg:` lines.
+///
+/// dpkg exits 1 for a package it could not process and 2 for a fatal error.
+/// It installs the local file it is given, so nothing is fetched and nothing
+/// can fail on the network.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.contains("requires superuser privilege")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.contains("frontend lock was locked by another process")
+            || line.contains("status database is locked by another process")
+    }) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| line.starts_with("dpkg: dependency problems prevent")) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.starts_with("dpkg: error: cannot access archive")
+            || (line.starts_with(".deb file for") && line.ends_with("not found in Downloads"))
+    }) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| line.starts_with("Verification on package") && line.ends_with("failed!"))
+    {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| line.contains("No space left on device")) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| {
+        line.contains("subprocess was interrupted")
+            || line.starts_with("dpkg: error: dpkg was interrupted")
+    }) {
+        ProviderErrorCode::Interrupted
+    } else if any(&|line| line.starts_with("dpkg: error") || line.starts_with("dpkg-deb: error")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,722 @@
+//! Dependency graphs assembled from a provider's own queries.
+//!
+//! A provider's reverse-dependency query answers one level: what depends on
+//! this package directly. Removing it also breaks whatever depends on those,
+//! and can leave automatically installed dependencies that nothing needs any
+//! more. This module walks the provider's queries to the full closure and
+//! checks it against the packages the provider refuses to remove. The same
+//! walks answer why a package is installed and what it pulls in.
+
+use super::PackageBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Provider queries one analysis may make before it reports itself
+/// incomplete. A removal that reaches this far is not one to approve by
+/// reading a list anyway.
+const MAX_QUERIES: usize = 400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtectionReason {
+    /// Debian `Essential: yes`: other packages may rely on it without
+    /// declaring a dependency.
+    Essential,
+    /// Named in the provider's own protected list (Debian `Protected: yes`,
+    /// DNF `protected_packages`).
+    Protected,
+}
+
+impl ProtectionReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Essential => "essential",
+            Self::Protected => "protected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectedPackage {
+    pub name: String,
+    pub reason: ProtectionReason,
+}
+
+/// An installed package that stops working when the removal goes through.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependent {
+    pub name: String,
+    /// The removed package it depends on, so a chain can be followed back
+    /// to a target.
+    pub requires: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RemovalImpact {
+    /// Packages that depend on a target, directly or through another
+    /// dependent, in the order they were found.
+    pub dependents: Vec<Dependent>,
+    /// Automatically installed dependencies nothing else needs once the
+    /// targets and their dependents are gone. DNF removes these in the same
+    /// transaction; APT and pacman leave them for their autoremove.
+    pub auto_removed: Vec<String>,
+    /// Targets, dependents or auto-removed packages the provider protects.
+    pub protected: Vec<ProtectedPackage>,
+    /// A provider query failed or the walk hit its limit, so the lists may
+    /// be short.
+    pub incomplete: bool,
+}
+
+impl RemovalImpact {
+    /// A removal that would take a protected package with it is refused.
+    pub fn is_blocked(&self) -> bool {
+        !self.protected.is_empty()
+    }
+
+    /// Why a blocked removal is refused, naming the protected packages it
+    /// would take with it.
+    pub fn refusal(&self) -> String {
+        let names: Vec<String> = self
+            .protected
+            .iter()
+            .map(|package| format!("{} ({})", package.name, package.reason.label()))
+            .collect();
+        format!(
+            "The removal would take protected packages with it: {}; LinGet will not remove them",
+            names.join(", ")
+        )
+    }
+
+    /// The chain from `name` back to the target that pulls it into the
+    /// removal, starting with `name`.
+    pub fn chain(&self, name: &str) -> Vec<String> {
+        let mut chain = vec![name.to_string()];
+        let mut current = name;
+        while let Some(dependent) = self
+            .dependents
+            .iter()
+            .find(|dependent| dependent.name == current)
+        {
+            if chain.contains(&dependent.requires) {
+                break;
+            }
+            chain.push(dependent.requires.clone());
+            current = &dependent.requires;
+        }
+        chain
+    }
+}
+
+/// Work out everything removing `targets` takes with it, using `backend`'s
+/// reverse-dependency, dependency, install-reason and protection queries.
+/// Failed queries mark the result incomplete rather than failing it: a
+/// partial answer still names packages the user should know about.
+pub async fn removal_impact(backend: &dyn PackageBackend, targets: &[String]) -> RemovalImpact {
+    let mut impact = RemovalImpact::default();
+    let mut queries = 0;
+    let mut removed: HashSet<String> = targets.iter().cloned().collect();
+    let mut reverse: HashMap<String, Vec<String>> = HashMap::new();
+
+    let mut queue: VecDeque<String> = targets.iter().cloned().collect();
+    while let Some(name) = queue.pop_front() {
+        if queries >= MAX_QUERIES {
+            impact.incomplete = true;
+            break;
+        }
+        queries += 1;
+        let Ok(dependents) = backend.get_reverse_dependencies(&name).await else {
+            impact.incomplete = true;
+            continue;
+        };
+        for dependent in &dependents {
+            if removed.insert(dependent.clone()) {
+                impact.dependents.push(Dependent {
+                    name: dependent.clone(),
+                    requires: name.clone(),
+                });
+                queue.push_back(dependent.clone());
+            }
+        }
+        reverse.insert(name, dependents);
+    }
+
+    match backend.list_auto_installed().await {
+        Ok(auto) if !auto.is_empty() => {
+            let auto: HashSet<String> = auto.into_iter().collect();
+            collect_auto_removed(
+                backend,
+                targets,
+                &auto,
+                &mut removed,
+                &mut reverse,
+                &mut queries,
+                &mut impact,
+            )
+            .await;
+        }
+        Ok(_) => {}
+        Err(_) => impact.incomplete = true,
+    }
+
+    match backend.list_protected().await {
+        Ok(protected) => {
+            impact.protected = protected
+                .into_iter()
+                .filter(|package| removed.contains(&package.name))
+                .collect();
+            impact.protected.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        Err(_) => impact.incomplete = true,
+    }
+    impact
+}
+
+/// Grow `removed` with automatically installed dependencies whose every
+/// reverse dependency is already being removed, until nothing changes. A
+/// candidate rejected early can qualify later, once another auto-removed
+/// package was its last remaining user.
+async fn collect_auto_removed(
+    backend: &dyn PackageBackend,
+    targets: &[String],
+    auto: &HashSet<String>,
+    removed: &mut HashSet<String>,
+    reverse: &mut HashMap<String, Vec<String>>,
+    queries: &mut usize,
+    impact: &mut RemovalImpact,
+) {
+    let mut expand: VecDeque<String> = targets
+        .iter()
+        .cloned()
+        .chain(
+            impact
+                .dependents
+                .iter()
+                .map(|dependent| dependent.name.clone()),
+        )
+        .collect();
+    let mut candidates: Vec<String> = Vec::new();
+    loop {
+        while let Some(name) = expand.pop_front() {
+            if *queries >= MAX_QUERIES {
+                impact.incomplete = true;
+                return;
+            }
+            *queries += 1;
+            let Ok(dependencies) = backend.get_dependencies(&name).await else {
+                impact.incomplete = true;
+                continue;
+            };
+            for dependency in dependencies {
+                if auto.contains(&dependency)
+                    && !removed.contains(&dependency)
+                    && !candidates.contains(&dependency)
+                {
+                    candidates.push(dependency);
+                }
+            }
+        }
+
+        let mut freed = Vec::new();
+        for candidate in &candidates {
+            if removed.contains(candidate) {
+                continue;
+            }
+            if !reverse.contains_key(candidate) {
+                if *queries >= MAX_QUERIES {
+                    impact.incomplete = true;
+                    return;
+                }
+                *queries += 1;
+                match backend.get_reverse_dependencies(candidate).await {
+                    Ok(users) => {
+                        reverse.insert(candidate.clone(), users);
+                    }
+                    Err(_) => {
+                        impact.incomplete = true;
+                        continue;
+                    }
+                }
+            }
+            if reverse[candidate].iter().all(|user| removed.contains(user)) {
+                freed.push(candidate.clone());
+            }
+        }
+        if freed.is_empty() {
+            return;
+        }
+        for name in freed {
+            removed.insert(name.clone());
+            impact.auto_removed.push(name.clone());
+            expand.push_back(name);
+        }
+    }
+}
+
+/// The `candidates` that can be removed without touching anything else:
+/// installed explicitly, not protected, and needed by no installed package
+/// except other prunable candidates. `None` when a provider query failed or
+/// the walk hit its limit, since a short answer could remove something a
+/// kept package needs.
+pub async fn prunable(backend: &dyn PackageBackend, candidates: &[String]) -> Option<Vec<String>> {
+    let auto: HashSet<String> = backend
+        .list_auto_installed()
+        .await
+        .ok()?
+        .into_iter()
+        .collect();
+    let protected: HashSet<String> = backend
+        .list_protected()
+        .await
+        .ok()?
+        .into_iter()
+        .map(|package| package.name)
+        .collect();
+    let mut pruned: Vec<String> = candidates
+        .iter()
+        .filter(|name| !auto.contains(*name) && !protected.contains(*name))
+        .cloned()
+        .collect();
+    if pruned.len() > MAX_QUERIES {
+        return None;
+    }
+    let mut users = HashMap::new();
+    for name in &pruned {
+        users.insert(
+            name.clone(),
+            backend.get_reverse_dependencies(name).await.ok()?,
+        );
+    }
+    // Keeping one candidate keeps whatever it depends on, so repeat until
+    // every remaining candidate is only needed by other remaining ones.
+    loop {
+        let remaining: HashSet<&String> = pruned.iter().collect();
+        let kept: Vec<String> = pruned
+            .iter()
+            .filter(|name| users[*name].iter().any(|user| !remaining.contains(user)))
+            .cloned()
+            .collect();
+        if kept.is_empty() {
+            return Some(pruned);
+        }
+        pruned.retain(|name| !kept.contains(name));
+    }
+}
+
+/// Why a package is installed: the chains of installed packages that pull it
+/// in, each starting at a package installed explicitly (or one nothing else
+/// needs) and ending at the package itself.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WhyInstalled {
+    pub name: String,
+    /// The package was asked for rather than installed as a dependency.
+    /// `None` when the provider does not record install reasons.
+    pub explicit: Option<bool>,
+    pub chains: Vec<Vec<String>>,
+    pub incomplete: bool,
+}
+
+/// One package in a forward dependency tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyNode {
+    pub name: String,
+    /// Already expanded elsewhere in the tree, or part of a cycle; its
+    /// dependencies are listed there.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub repeated: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<DependencyNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyTree {
+    pub root: DependencyNode,
+    /// A query failed, or the depth or query limit cut the tree short.
+    pub incomplete: bool,
+}
+
+/// Walk up from `name` through its reverse dependencies until each path
+/// reaches a package installed explicitly, or one nothing depends on. The
+/// first path found to each such package is the shortest, and is the one
+/// reported.
+pub async fn why_installed(backend: &dyn PackageBackend, name: &str) -> WhyInstalled {
+    let mut incomplete = false;
+    let auto: Option<HashSet<String>> = match backend.list_auto_installed().await {
+        Ok(auto) if !auto.is_empty() => Some(auto.into_iter().collect()),
+        Ok(_) => None,
+        Err(_) => {
+            incomplete = true;
+            None
+        }
+    };
+    let explicit = |package: &str| auto.as_ref().map(|auto| !auto.contains(package));
+
+    let mut chains = Vec::new();
+    let mut pulled_by: HashMap<String, String> = HashMap::new();
+    let mut queue = VecDeque::from([name.to_string()]);
+    let mut queries = 0;
+    while let Some(current) = queue.pop_front() {
+        if queries >= MAX_QUERIES {
+            incomplete = true;
+            break;
+        }
+        queries += 1;
+        let Ok(dependents) = backend.get_reverse_dependencies(&current).await else {
+            incomplete = true;
+            continue;
+        };
+        let is_root =
+            current != name && (explicit(&current) == Some(true) || dependents.is_empty());
+        if is_root {
+            let mut chain = vec![current.clone()];
+            while let Some(next) = pulled_by.get(chain.last().unwrap_or(&current)) {
+                chain.push(next.clone());
+            }
+            chains.push(chain);
+            continue;
+        }
+        for dependent in dependents {
+            if dependent != name && !pulled_by.contains_key(&dependent) {
+                pulled_by.insert(dependent.clone(), current.clone());
+                queue.push_back(dependent);
+            }
+        }
+    }
+
+    WhyInstalled {
+        name: name.to_string(),
+        explicit: explicit(name),
+        chains,
+        incomplete,
+    }
+}
+
+/// The packages `name` depends on, and theirs, down to `max_depth` levels.
+/// Each package is expanded once; later occurrences are marked repeated.
+pub async fn dependency_tree(
+    backend: &dyn PackageBackend,
+    name: &str,
+    max_depth: usize,
+) -> DependencyTree {
+    let mut expanded = HashSet::new();
+    let mut queries = 0;
+    let mut incomplete = false;
+    let root = expand_node(
+        backend,
+        name.to_string(),
+        max_depth,
+        &mut expanded,
+        &mut queries,
+        &mut incomplete,
+    )
+    .await;
+    DependencyTree { root, incomplete }
+}
+
+fn expand_node<'a>(
+    backend: &'a dyn PackageBackend,
+    name: String,
+    depth: usize,
+    expanded: &'a mut HashSet<String>,
+    queries: &'a mut usize,
+    incomplete: &'a mut bool,
+) -> futures::future::BoxFuture<'a, DependencyNode> {
+    Box::pin(async move {
+        let mut node = DependencyNode {
+            name,
+            repeated: false,
+            dependencies: Vec::new(),
+        };
+        if !expanded.insert(node.name.clone()) {
+            node.repeated = true;
+            return node;
+        }
+        if *queries >= MAX_QUERIES {
+            *incomplete = true;
+            return node;
+        }
+        *queries += 1;
+        let dependencies = match backend.get_dependencies(&node.name).await {
+            Ok(dependencies) => dependencies,
+            Err(_) => {
+                *incomplete = true;
+                return node;
+            }
+        };
+        if depth == 0 {
+            *incomplete |= !dependencies.is_empty();
+            return node;
+        }
+        for dependency in dependencies {
+            let child = expand_node(
+                backend,
+                dependency,
+                depth - 1,
+                expanded,
+                queries,
+                incomplete,
+            )
+            .await;
+            node.dependencies.push(child);
+        }
+        node
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::models::{Package, PackageSource};
+    use anyhow::Result;
+    use async_trait::async_trait;
+
+    /// A provider whose dependency graph is given up front.
+    #[derive(Default)]
+    pub(crate) struct GraphBackend {
+        pub(crate) source: Option<PackageSource>,
+        /// Package to the packages it depends on.
+        pub(crate) depends: HashMap<String, Vec<String>>,
+        pub(crate) auto: Vec<String>,
+        pub(crate) protected: Vec<ProtectedPackage>,
+        pub(crate) installed: Vec<Package>,
+    }
+
+    impl GraphBackend {
+        pub(crate) fn with_edges(edges: &[(&str, &[&str])]) -> Self {
+            Self {
+                depends: edges
+                    .iter()
+                    .map(|(name, deps)| {
+                        (
+                            name.to_string(),
+                            deps.iter().map(|dep| dep.to_string()).collect(),
+                        )
+                    })
+                    .collect(),
+                ..Self::default()
+            }
+        }
+    }
+
+    #[async_trait]
+    impl PackageBackend for GraphBackend {
+        fn is_available() -> bool {
+            true
+        }
+
+        async fn list_installed(&self) -> Result<Vec<Package>> {
+            Ok(self.installed.clone())
+        }
+
+        async fn check_updates(&self) -> Result<Vec<Package>> {
+            Ok(Vec::new())
+        }
+
+        async fn install(&self, _name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn remove(&self, _name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn update(&self, _name: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn search(&self, _query: &str) -> Result<Vec<Package>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_reverse_dependencies(&self, name: &str) -> Result<Vec<String>> {
+            let mut users: Vec<String> = self
+                .depends
+                .iter()
+                .filter(|(_, deps)| deps.iter().any(|dep| dep == name))
+                .map(|(user, _)| user.clone())
+                .collect();
+            users.sort();
+            Ok(users)
+        }
+
+        async fn get_dependencies(&self, name: &str) -> Result<Vec<String>> {
+            Ok(self.depends.get(name).cloned().unwrap_or_default())
+        }
+
+        async fn list_auto_installed(&self) -> Result<Vec<String>> {
+            Ok(self.auto.clone())
+        }
+
+        async fn list_protected(&self) -> Result<Vec<ProtectedPackage>> {
+            Ok(self.protected.clone())
+        }
+
+        fn source(&self) -> PackageSource {
+            self.source.unwrap_or(PackageSource::Apt)
+        }
+    }
+
+    fn names(dependents: &[Dependent]) -> Vec<&str> {
+        dependents
+            .iter()
+            .map(|dependent| dependent.name.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn pruning_keeps_automatic_protected_and_still_needed_packages() {
+        let mut backend = GraphBackend::with_edges(&[
+            ("listed", &["libexplicit"]),
+            ("libexplicit", &["libdeep"]),
+            ("libdeep", &[]),
+            ("base-files", &[]),
+            ("libauto", &[]),
+            ("leftover", &["leftover-plugin-host"]),
+            ("leftover-plugin-host", &[]),
+        ]);
+        backend.auto = vec!["libauto".to_string()];
+        backend.protected = vec![ProtectedPackage {
+            name: "base-files".to_string(),
+            reason: ProtectionReason::Essential,
+        }];
+        let unlisted = [
+            "libexplicit",
+            "libdeep",
+            "base-files",
+            "libauto",
+            "leftover",
+            "leftover-plugin-host",
+        ]
+        .map(String::from);
+
+        // `libdeep` is only needed by `libexplicit`, which `listed` needs.
+        assert_eq!(
+            prunable(&backend, &unlisted).await,
+            Some(vec![
+                "leftover".to_string(),
+                "leftover-plugin-host".to_string()
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn dependents_are_followed_past_the_first_level() {
+        let backend = GraphBackend::with_edges(&[
+            ("libssl3", &[]),
+            ("curl", &["libssl3"]),
+            ("git", &["curl"]),
+            ("git-lfs", &["git"]),
+            ("vim", &[]),
+        ]);
+        let impact = removal_impact(&backend, &["libssl3".to_string()]).await;
+
+        assert_eq!(names(&impact.dependents), ["curl", "git", "git-lfs"]);
+        assert_eq!(
+            impact.chain("git-lfs"),
+            ["git-lfs", "git", "curl", "libssl3"]
+        );
+        assert!(impact.auto_removed.is_empty());
+        assert!(!impact.is_blocked());
+        assert!(!impact.incomplete);
+    }
+
+    #[tokio::test]
+    async fn dependencies_left_unneeded_are_auto_removed() {
+        let mut backend = GraphBackend::with_edges(&[
+            ("editor", &["editor-common", "libshared"]),
+            ("editor-common", &["editor-data"]),
+            ("editor-data", &[]),
+            ("libshared", &[]),
+            ("other", &["libshared"]),
+        ]);
+        backend.auto = ["editor-common", "editor-data", "libshared"]
+            .map(String::from)
+            .to_vec();
+        let impact = removal_impact(&backend, &["editor".to_string()]).await;
+
+        assert!(impact.dependents.is_empty());
+        // `libshared` is automatic too, but `other` still needs it.
+        assert_eq!(impact.auto_removed, ["editor-common", "editor-data"]);
+    }
+
+    #[tokio::test]
+    async fn protected_packages_anywhere_in_the_closure_block_the_removal() {
+        let mut backend = GraphBackend::with_edges(&[
+            ("libc-bin", &[]),
+            ("base-files", &["libc-bin"]),
+            ("unrelated", &[]),
+        ]);
+        backend.protected = vec![
+            ProtectedPackage {
+                name: "base-files".to_string(),
+                reason: ProtectionReason::Essential,
+            },
+            ProtectedPackage {
+                name: "unrelated".to_string(),
+                reason: ProtectionReason::Protected,
+            },
+        ];
+        let impact = removal_impact(&backend, &["libc-bin".to_string()]).await;
+
+        assert!(impact.is_blocked());
+        assert_eq!(
+            impact.protected,
+            [ProtectedPackage {
+                name: "base-files".to_string(),
+                reason: ProtectionReason::Essential,
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn dependency_cycles_terminate() {
+        let backend = GraphBackend::with_edges(&[("a", &["b"]), ("b", &["a"]), ("c", &["a"])]);
+        let impact = removal_impact(&backend, &["a".to_string()]).await;
+
+        assert_eq!(names(&impact.dependents), ["b", "c"]);
+        assert_eq!(impact.chain("b"), ["b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn why_follows_dependents_up_to_explicit_packages() {
+        let mut backend = GraphBackend::with_edges(&[
+            ("libssl3", &[]),
+            ("curl", &["libssl3"]),
+            ("git", &["curl"]),
+            ("git-lfs", &["git"]),
+            ("openssh-client", &["libssl3"]),
+        ]);
+        backend.auto = ["libssl3", "curl"].map(String::from).to_vec();
+        let why = why_installed(&backend, "libssl3").await;
+
+        assert_eq!(why.explicit, Some(false));
+        // `git` was asked for, so `git-lfs` above it is not part of the reason.
+        assert_eq!(
+            why.chains,
+            [
+                vec!["openssh-client", "libssl3"],
+                vec!["git", "curl", "libssl3"],
+            ]
+        );
+        assert!(!why.incomplete);
+    }
+
+    #[tokio::test]
+    async fn forward_trees_expand_each_package_once() {
+        let backend = GraphBackend::with_edges(&[
+            ("app", &["libfoo", "libbar"]),
+            ("libfoo", &["libc"]),
+            ("libbar", &["libc", "app"]),
+            ("libc", &[]),
+        ]);
+        let tree = dependency_tree(&backend, "app", 8).await;
+
+        let libbar = &tree.root.dependencies[1];
+        assert_eq!(tree.root.dependencies[0].dependencies[0].name, "libc");
+        assert!(libbar.dependencies[0].repeated);
+        assert!(libbar.dependencies[1].repeated);
+        assert!(!tree.incomplete);
+
+        let shallow = dependency_tree(&backend, "app", 1).await;
+        assert!(shallow.root.dependencies[0].dependencies.is_empty());
+        assert!(shallow.incomplete);
+    }
+}
@@ -1,3 +1,5 @@
+use super::dependency_graph::{ProtectedPackage, ProtectionReason};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::{run_pkexec, Suggest};
 use crate::models::{Package, PackageSource, PackageStatus, Repository};
@@ -37,13 +39,14 @@ impl PackageBackend for DnfBackend {
 
     async fn list_installed(&self) -> Result<Vec<Package>> {
         // dnf repoquery --installed with size information
-        // %{SIZE} returns the installed size in bytes
+        // %{SIZE} returns the installed size in bytes. The version carries the
+        // release so it matches `check-update` and simulated transaction plans.
         let output = Command::new("dnf")
             .args([
                 "repoquery",
                 "--installed",
                 "--queryformat",
-                "%{NAME}|%{VERSION}|%{SIZE}|%{SUMMARY}",
+                "%{NAME}|%{VERSION}-%{RELEASE}|%{SIZE}|%{SUMMARY}",
             ])
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -75,6 +78,7 @@ impl PackageBackend for DnfBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -139,6 +143,7 @@ impl PackageBackend for DnfBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -182,6 +187,48 @@ impl PackageBackend for DnfBackend {
         .await
     }
 
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["install", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "dnf",
+            &args,
+            &format!("Failed to install dnf packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo dnf {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["remove", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "dnf",
+            &args,
+            &format!("Failed to remove dnf packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo dnf {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        let mut args = vec!["update", "-y", "--"];
+        args.extend(names.iter().map(String::as_str));
+        run_pkexec(
+            "dnf",
+            &args,
+            &format!("Failed to update dnf packages {}", names.join(", ")),
+            Suggest {
+                command: format!("sudo dnf {}", args.join(" ")),
+            },
+        )
+        .await
+    }
+
     async fn downgrade(&self, name: &str) -> Result<()> {
         run_pkexec(
             "dnf",
@@ -234,6 +281,7 @@ impl PackageBackend for DnfBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -508,6 +556,7 @@ impl PackageBackend for DnfBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -560,6 +609,86 @@ impl PackageBackend for DnfBackend {
         Ok(deps)
     }
 
+    async fn get_dependencies(&self, name: &str) -> Result<Vec<String>> {
+        let output = Command::new("dnf")
+            .args([
+                "repoquery",
+                "--installed",
+                "--requires",
+                "--resolve",
+                "--qf",
+                "%{name}\n",
+                name,
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to get dependencies")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "dnf repoquery --requires failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let mut deps: Vec<String> = Vec::new();
+        for dep in String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+        {
+            if !dep.is_empty() && dep != name && !deps.iter().any(|known| known == dep) {
+                deps.push(dep.to_string());
+            }
+        }
+        Ok(deps)
+    }
+
+    async fn list_auto_installed(&self) -> Result<Vec<String>> {
+        let output = Command::new("dnf")
+            .args(["repoquery", "--installed", "--qf", "%{name} %{reason}\n"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list install reasons")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "dnf repoquery --installed failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(parse_auto_installed(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+
+    async fn list_protected(&self) -> Result<Vec<ProtectedPackage>> {
+        let mut names = Vec::new();
+        if let Ok(conf) = tokio::fs::read_to_string("/etc/dnf/dnf.conf").await {
+            names.extend(parse_protected_packages_option(&conf));
+        }
+        if let Ok(mut entries) = tokio::fs::read_dir("/etc/dnf/protected.d").await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.extension().is_some_and(|ext| ext == "conf") {
+                    if let Ok(content) = tokio::fs::read_to_string(&path).await {
+                        names.extend(parse_protected_list(&content));
+                    }
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        Ok(names
+            .into_iter()
+            .map(|name| ProtectedPackage {
+                name,
+                reason: ProtectionReason::Protected,
+            })
+            .collect())
+    }
+
     fn source(&self) -> PackageSource {
         PackageSource::Dnf
     }
@@ -619,6 +748,194 @@ impl PackageBackend for DnfBackend {
 
         status
     }
+
+    async fn hold(&self, name: &str) -> Result<()> {
+        run_pkexec(
+            "dnf",
+            &["versionlock", "add", "--", name],
+            &format!("Failed to hold dnf package {}", name),
+            Suggest {
+                command: format!("sudo dnf versionlock add -- {}", name),
+            },
+        )
+        .await
+    }
+
+    async fn unhold(&self, name: &str) -> Result<()> {
+        run_pkexec(
+            "dnf",
+            &["versionlock", "delete", "--", name],
+            &format!("Failed to release hold on dnf package {}", name),
+            Suggest {
+                command: format!("sudo dnf versionlock delete -- {}", name),
+            },
+        )
+        .await
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        let output = Command::new("dnf")
+            .args(["versionlock", "list"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list dnf version locks")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "dnf versionlock list failed (is the versionlock plugin installed?): {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(parse_versionlock_list(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+/// Package names from `dnf versionlock list`. dnf4 prints one locked
+/// `name-epoch:version-release.*` pattern per line; dnf5 prints a
+/// `Package name: <name>` block per lock. Exclude entries (`!pattern`) are
+/// not holds and are skipped.
+fn parse_versionlock_list(output: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+            continue;
+        }
+        let name = if let Some(name) = line.strip_prefix("Package name:") {
+            name.trim()
+        } else if let Some(pattern) = line.strip_suffix(".*") {
+            match pattern.rsplitn(3, '-').nth(2) {
+                Some(name) => name,
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+        if !name.is_empty() && !names.iter().any(|held| held == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Names from `repoquery --qf '%{name} %{reason}'` that were installed as a
+/// dependency, strong or weak, rather than asked for.
+fn parse_auto_installed(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().split_once(' '))
+        .filter(|(_, reason)| matches!(reason.trim(), "dependency" | "weak-dependency"))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Package names in a `/etc/dnf/protected.d/*.conf` file: one per line,
+/// `#` comments.
+fn parse_protected_list(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Package names from the `protected_packages` option in the `[main]`
+/// section of `dnf.conf`, which takes a comma or space separated list.
+fn parse_protected_packages_option(conf: &str) -> Vec<String> {
+    let mut in_main = false;
+    let mut names = Vec::new();
+    for line in conf.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main = line == "[main]";
+            continue;
+        }
+        if !in_main {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim() == "protected_packages" {
+            names = value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|name| !name.is_empty())
+                .map(ToString::to_string)
+                .collect();
+        }
+    }
+    names
+}
+
+/// Classify a dnf failure from its exit status and messages.
+///
+/// dnf exits 1 for errors it handled and reported, 3 for an unhandled error
+/// (a traceback, whose text is not a diagnosis), and dnf4 exits 200 when it
+/// cannot take its lock. Only status 1 output is read for a cause. Causes are
+/// checked most specific first, since dnf reports a curl error above the
+/// "Failed to download metadata" summary it leads to.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    match exit_code {
+        Some(200) => return Some(ProviderErrorCode::LockBusy.into()),
+        Some(3) => return Some(ProviderErrorCode::Unknown.into()),
+        _ => {}
+    }
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.starts_with("KeyboardInterrupt")) {
+        ProviderErrorCode::Interrupted
+    } else if any(&|line| line.contains("has to be run with superuser privileges")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.starts_with("Waiting for process with pid")
+            || line.starts_with("Failed to obtain rpm transaction lock")
+    }) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| line.contains("Curl error (28)")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| {
+        line.contains("Curl error (6)")
+            || line.contains("Curl error (7)")
+            || line.contains("Curl error (5)")
+    }) {
+        ProviderErrorCode::Network
+    } else if any(&|line| {
+        line.contains("GPG check FAILED")
+            || (line.starts_with("Public key for") && line.ends_with("is not installed"))
+            || line.contains("Bad GPG signature")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| {
+        line.contains("Failed to download metadata for repo")
+            || line.contains("Cannot download repomd.xml")
+    }) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| {
+        line.contains("more space on the")
+            || line.starts_with("Disk Requirements:")
+            || line.contains("No space left on device")
+    }) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| {
+        line.starts_with("Problem")
+            || line.starts_with("- nothing provides")
+            || line.starts_with("- conflicting requests")
+    }) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.starts_with("No match for argument")
+            || line.contains("Unable to find a match")
+            || line.starts_with("No packages marked for")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if exit_code == Some(1) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
 }
 
 #[cfg(test)]
@@ -632,6 +949,39 @@ mod tests {
         let _ = DnfBackend::is_available();
     }
 
+    #[test]
+    fn versionlock_list_reads_dnf4_and_dnf5_formats() {
+        let dnf4 = "Last metadata expiration check: 0:10:01 ago.\n\
+                    htop-0:3.3.0-1.fc40.*\n\
+                    python3-libs-0:3.12.2-2.fc40.*\n\
+                    !kernel-0:6.8.5-301.fc40.*\n";
+        assert_eq!(parse_versionlock_list(dnf4), vec!["htop", "python3-libs"]);
+
+        let dnf5 = "# Added by 'versionlock add' command on 2024-05-02 10:11:12\n\
+                    Package name: htop\n\
+                    evr = 3.3.0-1.fc40\n";
+        assert_eq!(parse_versionlock_list(dnf5), vec!["htop"]);
+    }
+
+    #[test]
+    fn dependency_install_reasons_are_auto_installed() {
+        let output =
+            "bash user\nglibc dependency\nfonts-extra weak-dependency\ngnome-shell group\n";
+        assert_eq!(parse_auto_installed(output), ["glibc", "fonts-extra"]);
+    }
+
+    #[test]
+    fn protected_packages_come_from_protected_d_and_dnf_conf() {
+        let protected_d = "# Protected by the distribution\nsystemd\nsudo # keep admin access\n\n";
+        assert_eq!(parse_protected_list(protected_d), ["systemd", "sudo"]);
+
+        let conf = "[main]\ngpgcheck=1\nprotected_packages = openssh-server, NetworkManager kernel-core\n\n[fedora]\nprotected_packages=ignored\n";
+        assert_eq!(
+            parse_protected_packages_option(conf),
+            ["openssh-server", "NetworkManager", "kernel-core"]
+        );
+    }
+
     #[test]
     fn test_dnf_backend_creation() {
         let _backend = DnfBackend::new();
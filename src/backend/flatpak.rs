@@ -1,4 +1,5 @@
-use super::streaming::{run_streaming, StreamLine};
+use super::streaming::{output_cancellable, run_streaming, CommandFailure, StreamLine};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{
     FlatpakMetadata, FlatpakPermission, FlatpakRuntime, InstallationType, Package, PackageSource,
@@ -373,10 +374,8 @@ impl FlatpakBackend {
                 let value = value.trim();
 
                 match key {
-                    "Ref" | "ID" => {
-                        if metadata.app_id.is_empty() {
-                            metadata.app_id = value.to_string();
-                        }
+                    "Ref" | "ID" if metadata.app_id.is_empty() => {
+                        metadata.app_id = value.to_string();
                     }
                     "Origin" => metadata.remote = Some(value.to_string()),
                     "Commit" => metadata.commit = Some(value.to_string()),
@@ -441,6 +440,7 @@ impl FlatpakBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -528,6 +528,7 @@ impl PackageBackend for FlatpakBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -560,16 +561,15 @@ impl PackageBackend for FlatpakBackend {
     }
 
     async fn install(&self, name: &str) -> Result<()> {
-        let status = Command::new("flatpak")
-            .args(["install", "-y", name])
-            .status()
+        let output = output_cancellable(Command::new("flatpak").args(["install", "-y", name]))
             .await
             .context("Failed to install flatpak")?;
 
-        if status.success() {
+        if output.status.success() {
             Ok(())
         } else {
-            anyhow::bail!("Failed to install flatpak {}", name)
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to install flatpak {}", name)))
         }
     }
 
@@ -587,7 +587,8 @@ impl PackageBackend for FlatpakBackend {
                 if output.success {
                     Ok(())
                 } else {
-                    anyhow::bail!("Failed to install flatpak {}", name)
+                    Err(CommandFailure::new("flatpak", output.exit_code, "")
+                        .with_message(format!("Failed to install flatpak {}", name)))
                 }
             }
             None => self.install(name).await,
@@ -595,16 +596,15 @@ impl PackageBackend for FlatpakBackend {
     }
 
     async fn remove(&self, name: &str) -> Result<()> {
-        let status = Command::new("flatpak")
-            .args(["uninstall", "-y", name])
-            .status()
+        let output = output_cancellable(Command::new("flatpak").args(["uninstall", "-y", name]))
             .await
             .context("Failed to remove flatpak")?;
 
-        if status.success() {
+        if output.status.success() {
             Ok(())
         } else {
-            anyhow::bail!("Failed to remove flatpak {}", name)
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to remove flatpak {}", name)))
         }
     }
 
@@ -622,7 +622,8 @@ impl PackageBackend for FlatpakBackend {
                 if output.success {
                     Ok(())
                 } else {
-                    anyhow::bail!("Failed to remove flatpak {}", name)
+                    Err(CommandFailure::new("flatpak", output.exit_code, "")
+                        .with_message(format!("Failed to remove flatpak {}", name)))
                 }
             }
             None => self.remove(name).await,
@@ -630,16 +631,15 @@ impl PackageBackend for FlatpakBackend {
     }
 
     async fn update(&self, name: &str) -> Result<()> {
-        let status = Command::new("flatpak")
-            .args(["update", "-y", name])
-            .status()
+        let output = output_cancellable(Command::new("flatpak").args(["update", "-y", name]))
             .await
             .context("Failed to update flatpak")?;
 
-        if status.success() {
+        if output.status.success() {
             Ok(())
         } else {
-            anyhow::bail!("Failed to update flatpak {}", name)
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to update flatpak {}", name)))
         }
     }
 
@@ -657,13 +657,58 @@ impl PackageBackend for FlatpakBackend {
                 if output.success {
                     Ok(())
                 } else {
-                    anyhow::bail!("Failed to update flatpak {}", name)
+                    Err(CommandFailure::new("flatpak", output.exit_code, "")
+                        .with_message(format!("Failed to update flatpak {}", name)))
                 }
             }
             None => self.update(name).await,
         }
     }
 
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        let output =
+            output_cancellable(Command::new("flatpak").args(["install", "-y"]).args(names))
+                .await
+                .context("Failed to install flatpaks")?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to install flatpaks {}", names.join(", "))))
+        }
+    }
+
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        let output = output_cancellable(
+            Command::new("flatpak")
+                .args(["uninstall", "-y"])
+                .args(names),
+        )
+        .await
+        .context("Failed to remove flatpaks")?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to remove flatpaks {}", names.join(", "))))
+        }
+    }
+
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        let output = output_cancellable(Command::new("flatpak").args(["update", "-y"]).args(names))
+            .await
+            .context("Failed to update flatpaks")?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(CommandFailure::from_output("flatpak", &output)
+                .with_message(format!("Failed to update flatpaks {}", names.join(", "))))
+        }
+    }
+
     async fn list_repositories(&self) -> Result<Vec<Repository>> {
         // flatpak remotes lists all configured remotes
         let output = Command::new("flatpak")
@@ -784,6 +829,7 @@ impl PackageBackend for FlatpakBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -876,6 +922,7 @@ impl PackageBackend for FlatpakBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -929,6 +976,35 @@ impl PackageBackend for FlatpakBackend {
         PackageSource::Flatpak
     }
 
+    async fn hold(&self, name: &str) -> Result<()> {
+        run_flatpak_mask(&[name], &format!("Failed to mask flatpak {}", name)).await
+    }
+
+    async fn unhold(&self, name: &str) -> Result<()> {
+        run_flatpak_mask(
+            &["--remove", name],
+            &format!("Failed to unmask flatpak {}", name),
+        )
+        .await
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        let output = Command::new("flatpak")
+            .arg("mask")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list masked flatpaks")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "flatpak mask failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(parse_mask_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     async fn get_package_commands(&self, name: &str) -> Result<Vec<(String, std::path::PathBuf)>> {
         let mut commands = Vec::new();
 
@@ -962,6 +1038,141 @@ impl PackageBackend for FlatpakBackend {
     }
 }
 
+async fn run_flatpak_mask(args: &[&str], context_msg: &str) -> Result<()> {
+    let output = Command::new("flatpak")
+        .arg("mask")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .with_context(|| context_msg.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "{}: {}",
+            context_msg,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+}
+
+/// Classify a flatpak failure from its `error:` lines.
+///
+/// Errors from the system helper carry their GError domain as a D-Bus error
+/// name (`org.freedesktop.Flatpak.Error.NotInstalled`); those decide first.
+/// Otherwise the CLI prints the message alone, and transfer failures quote
+/// the curl error number (`[6]`, `[28]`), which is checked before the
+/// "Unable to load summary" wrapper around it.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let errors: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("error: "))
+        .collect();
+    if errors.is_empty() {
+        return None;
+    }
+    let code = errors
+        .iter()
+        .find_map(|error| flatpak_error_domain(error))
+        .or_else(|| classify_error_message(&errors))
+        .unwrap_or(ProviderErrorCode::Unknown);
+    Some(code.into())
+}
+
+fn flatpak_error_domain(error: &str) -> Option<ProviderErrorCode> {
+    if let Some((_, rest)) = error.split_once("org.freedesktop.Flatpak.Error.") {
+        let name = rest.split(|c: char| !c.is_ascii_alphanumeric()).next()?;
+        return match name {
+            "NotInstalled" | "RefNotFound" | "InvalidRef" | "InvalidName" => {
+                Some(ProviderErrorCode::NotFound)
+            }
+            "RuntimeNotFound" | "NeedNewFlatpak" | "RuntimeUsed" => {
+                Some(ProviderErrorCode::DependencyConflict)
+            }
+            "RemoteNotFound" => Some(ProviderErrorCode::RepositoryUnavailable),
+            "Untrusted" => Some(ProviderErrorCode::SignatureInvalid),
+            "OutOfSpace" => Some(ProviderErrorCode::DiskFull),
+            "PermissionDenied" | "NotAuthorized" | "AuthenticationFailed" | "WrongUser" => {
+                Some(ProviderErrorCode::AuthorizationDenied)
+            }
+            "Aborted" => Some(ProviderErrorCode::Interrupted),
+            _ => None,
+        };
+    }
+    if let Some((_, rest)) = error.split_once("org.freedesktop.PolicyKit1.Error.") {
+        return match rest.split(|c: char| !c.is_ascii_alphanumeric()).next()? {
+            "Cancelled" => Some(ProviderErrorCode::AuthorizationCancelled),
+            "NotAuthorized" => Some(ProviderErrorCode::AuthorizationDenied),
+            _ => None,
+        };
+    }
+    None
+}
+
+fn classify_error_message(errors: &[&str]) -> Option<ProviderErrorCode> {
+    let any = |matches: &dyn Fn(&str) -> bool| errors.iter().any(|error| matches(error));
+    let code = if any(&|error| error.contains("No authentication agent found")) {
+        ProviderErrorCode::NoPrivilegeAgent
+    } else if any(&|error| error.contains("not allowed for user")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|error| error.starts_with("Locking repo") || error.starts_with("Unable to lock"))
+    {
+        ProviderErrorCode::LockBusy
+    } else if any(&|error| error.contains("[28]") || error.contains("Timeout was reached")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|error| {
+        error.contains("[6]")
+            || error.contains("[7]")
+            || error.contains("Could not resolve hostname")
+            || error.contains("Could not connect")
+    }) {
+        ProviderErrorCode::Network
+    } else if any(&|error| {
+        error.starts_with("GPG verification enabled, but no signatures found")
+            || error.contains("none are in trusted keyring")
+            || error.starts_with("Signature verification failed")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|error| {
+        error.starts_with("Unable to load summary from remote")
+            || (error.starts_with("Remote") && error.ends_with("not found"))
+    }) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|error| {
+        error.contains("No space left on device") || error.starts_with("Not enough disk space")
+    }) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|error| {
+        error.contains("requires the runtime") && error.contains("which was not found")
+    }) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|error| {
+        error.starts_with("Nothing matches")
+            || error.starts_with("No remote refs found")
+            || error.ends_with(" not installed")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if any(&|error| error.starts_with("Aborted")) {
+        ProviderErrorCode::Interrupted
+    } else {
+        return None;
+    };
+    Some(code)
+}
+
+/// Patterns printed by `flatpak mask` with no arguments: an optional
+/// "Masked patterns:" header followed by one indented pattern per line.
+fn parse_mask_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':') && !line.starts_with("No "))
+        .map(ToString::to_string)
+        .collect()
+}
+
 /// Builds update `Package` rows from cached or freshly-parsed
 /// (app_id, new_version, display_name) findings.
 fn update_packages_from(findings: Vec<(String, String, String)>) -> Vec<Package> {
@@ -983,6 +1194,7 @@ fn update_packages_from(findings: Vec<(String, String, String)>) -> Vec<Package>
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             };
             pkg.update_category = Some(pkg.detect_update_category());
             pkg
@@ -1179,6 +1391,15 @@ impl FlatpakBackend {
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn mask_list_skips_header_and_empty_state() {
+        assert_eq!(
+            parse_mask_list("Masked patterns:\n  org.mozilla.firefox\n  org.gimp.GIMP\n"),
+            vec!["org.mozilla.firefox", "org.gimp.GIMP"]
+        );
+        assert!(parse_mask_list("No masked patterns\n").is_empty());
+    }
     use crate::models::{PermissionCategory, PrivacyLevel, SandboxRating};
 
     #[test]
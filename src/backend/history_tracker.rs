@@ -1,10 +1,15 @@
 #![allow(dead_code)]
 
+use crate::backend::transaction::{
+    supports_batch, ExecutionLane, OperationAction, OperationRecord, OperationState, ProviderPlan,
+    STABLE_PROVIDERS,
+};
 use crate::models::history::{TaskQueueEntry, TaskQueueStatus};
 use crate::models::{
     HistoryEntry, HistoryOperation, OperationHistory, Package, PackageSnapshot, PackageSource,
 };
 use anyhow::{Context, Result};
+use std::collections::HashSet;
 use std::path::PathBuf;
 use tokio::fs;
 use tracing::{debug, info, warn};
@@ -16,7 +21,7 @@ const SNAPSHOT_FILE: &str = "snapshot.json";
 ///
 /// `kill(pid, 0)` performs the permission and existence checks without
 /// delivering a signal, which is the standard way to ask.
-fn process_is_alive(pid: u32) -> bool {
+pub(crate) fn process_is_alive(pid: u32) -> bool {
     if pid == 0 {
         return false;
     }
@@ -106,6 +111,67 @@ impl HistoryTracker {
         reclaimed
     }
 
+    /// Give tasks whose transaction the engine recovered on startup the
+    /// engine's verdict, replacing the generic "may or may not have
+    /// completed" failure with what the provider inventory actually shows.
+    pub async fn reconcile_recovered_operations(
+        &mut self,
+        records: &[OperationRecord],
+    ) -> Result<usize> {
+        let settled = Self::settle_recovered_tasks(&mut self.history, records);
+        if settled > 0 {
+            info!(count = settled, "Settled tasks from recovered transactions");
+            self.save()
+                .await
+                .context("Failed to save recovered task state")?;
+        }
+        Ok(settled)
+    }
+
+    fn settle_recovered_tasks(
+        history: &mut OperationHistory,
+        records: &[OperationRecord],
+    ) -> usize {
+        let mut settled = 0;
+        for record in records
+            .iter()
+            .filter(|record| record.recovered_at.is_some())
+        {
+            let Some(entry) = history.task_queue.entries.iter_mut().find(|entry| {
+                entry.reviewed_operation_id.as_deref() == Some(record.operation_id.as_str())
+                    && entry.verification_receipt_json.is_none()
+                    && matches!(
+                        entry.status,
+                        TaskQueueStatus::Running | TaskQueueStatus::Failed
+                    )
+            }) else {
+                continue;
+            };
+            match record.state {
+                OperationState::Succeeded => {
+                    entry.mark_completed();
+                    entry.error = None;
+                }
+                OperationState::Partial => entry.mark_failed(
+                    "Interrupted — LinGet exited while this task was running. Verification on \
+                     restart found it only partly applied; review the receipt before retrying."
+                        .to_string(),
+                ),
+                _ => entry.mark_failed(
+                    "Interrupted — LinGet exited while this task was running, and verification \
+                     on restart could not confirm any of its changes."
+                        .to_string(),
+                ),
+            }
+            entry.verification_receipt_json = record
+                .receipt
+                .as_ref()
+                .and_then(|receipt| serde_json::to_string(receipt).ok());
+            settled += 1;
+        }
+        settled
+    }
+
     pub fn history(&self) -> &OperationHistory {
         &self.history
     }
@@ -206,24 +272,77 @@ impl HistoryTracker {
             .context("Failed to save history after enqueueing task")
     }
 
-    pub async fn claim_next_task(&mut self) -> Result<Option<TaskQueueEntry>> {
-        let entry = self
-            .history
-            .task_queue
-            .entries
-            .iter_mut()
-            .find(|entry| entry.status == TaskQueueStatus::Queued);
+    /// Claim the next queued task whose execution lane is not `busy`,
+    /// together with the queued tasks behind it that can share its provider
+    /// invocation: reviewed single-provider plans for the same provider and
+    /// action. Tasks in other lanes are stepped over; claiming stops at the
+    /// first task in the same lane that cannot join, so lane order holds.
+    /// A bundle needs every lane, so nothing queued behind it overtakes it.
+    pub async fn claim_next_batch(
+        &mut self,
+        busy: &HashSet<ExecutionLane>,
+    ) -> Result<Vec<TaskQueueEntry>> {
+        let batch = Self::next_batch(&self.history.task_queue.entries, busy);
+        if batch.is_empty() {
+            return Ok(Vec::new());
+        }
+        let entries = &mut self.history.task_queue.entries;
+        let claimed = batch
+            .into_iter()
+            .map(|index| {
+                entries[index].mark_running();
+                entries[index].clone()
+            })
+            .collect();
+        self.save()
+            .await
+            .context("Failed to save history after starting tasks")?;
+        Ok(claimed)
+    }
 
-        let Some(entry) = entry else {
-            return Ok(None);
+    fn next_batch(entries: &[TaskQueueEntry], busy: &HashSet<ExecutionLane>) -> Vec<usize> {
+        if busy.contains(&ExecutionLane::Exclusive) {
+            return Vec::new();
+        }
+        let mut queued = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.status == TaskQueueStatus::Queued);
+        let Some((start, first)) = queued.find(|(_, entry)| {
+            let lane = ExecutionLane::for_entry(entry);
+            lane == ExecutionLane::Exclusive || !busy.contains(&lane)
+        }) else {
+            return Vec::new();
         };
+        let lane = ExecutionLane::for_entry(first);
+        if lane == ExecutionLane::Exclusive {
+            return if busy.is_empty() {
+                vec![start]
+            } else {
+                Vec::new()
+            };
+        }
 
-        entry.mark_running();
-        let entry_clone = entry.clone();
-        self.save()
-            .await
-            .context("Failed to save history after starting task")?;
-        Ok(Some(entry_clone))
+        let mut batch = vec![start];
+        if coalescible(first) {
+            for (index, entry) in queued {
+                let other = ExecutionLane::for_entry(entry);
+                if other == ExecutionLane::Exclusive {
+                    break;
+                }
+                if other != lane {
+                    continue;
+                }
+                if entry.package_source != first.package_source
+                    || entry.action != first.action
+                    || !coalescible(entry)
+                {
+                    break;
+                }
+                batch.push(index);
+            }
+        }
+        batch
     }
 
     pub async fn mark_task_completed(&mut self, entry_id: &str) -> Result<Option<TaskQueueEntry>> {
@@ -257,6 +376,80 @@ impl HistoryTracker {
         Ok(Some(entry_clone))
     }
 
+    pub async fn attach_task_snapshot(
+        &mut self,
+        entry_id: &str,
+        snapshot_json: String,
+    ) -> Result<Option<TaskQueueEntry>> {
+        let entry = self.history.task_queue.get_mut(entry_id);
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        entry.snapshot_json = Some(snapshot_json);
+        let entry_clone = entry.clone();
+        self.save().await.context("Failed to save task snapshot")?;
+        Ok(Some(entry_clone))
+    }
+
+    pub async fn attach_task_bundle_summary(
+        &mut self,
+        entry_id: &str,
+        summary_json: String,
+    ) -> Result<Option<TaskQueueEntry>> {
+        let entry = self.history.task_queue.get_mut(entry_id);
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        entry.bundle_summary_json = Some(summary_json);
+        let entry_clone = entry.clone();
+        self.save()
+            .await
+            .context("Failed to save task bundle summary")?;
+        Ok(Some(entry_clone))
+    }
+
+    pub async fn attach_task_reviewed_bundle(
+        &mut self,
+        entry_id: &str,
+        bundle_id: String,
+        bundle_json: String,
+    ) -> Result<Option<TaskQueueEntry>> {
+        let entry = self.history.task_queue.get_mut(entry_id);
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        entry.reviewed_operation_id = Some(bundle_id);
+        entry.reviewed_bundle_json = Some(bundle_json);
+        let entry_clone = entry.clone();
+        self.save()
+            .await
+            .context("Failed to save refreshed retry bundle")?;
+        Ok(Some(entry_clone))
+    }
+
+    /// Keep the re-plan of a retried bundle that moved since it was
+    /// reviewed, with what moved, so a further retry can approve it.
+    pub async fn attach_task_stale_bundle(
+        &mut self,
+        entry_id: &str,
+        bundle_id: String,
+        bundle_json: String,
+        diff_json: String,
+    ) -> Result<Option<TaskQueueEntry>> {
+        let entry = self.history.task_queue.get_mut(entry_id);
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        entry.reviewed_operation_id = Some(bundle_id);
+        entry.reviewed_bundle_json = Some(bundle_json);
+        entry.stale_plan_diff_json = Some(diff_json);
+        let entry_clone = entry.clone();
+        self.save()
+            .await
+            .context("Failed to save refreshed stale bundle")?;
+        Ok(Some(entry_clone))
+    }
+
     pub async fn attach_task_reviewed_plan(
         &mut self,
         entry_id: &str,
@@ -276,6 +469,29 @@ impl HistoryTracker {
         Ok(Some(entry_clone))
     }
 
+    /// Replace a task's stale reviewed plan with its re-plan and record what
+    /// moved, so a retry approves exactly the refreshed plan.
+    pub async fn attach_task_stale_plan(
+        &mut self,
+        entry_id: &str,
+        operation_id: String,
+        plan_json: String,
+        diff_json: String,
+    ) -> Result<Option<TaskQueueEntry>> {
+        let entry = self.history.task_queue.get_mut(entry_id);
+        let Some(entry) = entry else {
+            return Ok(None);
+        };
+        entry.reviewed_operation_id = Some(operation_id);
+        entry.reviewed_plan_json = Some(plan_json);
+        entry.stale_plan_diff_json = Some(diff_json);
+        let entry_clone = entry.clone();
+        self.save()
+            .await
+            .context("Failed to save refreshed stale plan")?;
+        Ok(Some(entry_clone))
+    }
+
     pub async fn mark_task_failed(
         &mut self,
         entry_id: &str,
@@ -459,6 +675,25 @@ async fn save_snapshot(snapshot: &PackageSnapshot) -> Result<()> {
         .context("Failed to write snapshot file")
 }
 
+/// Whether a queued task may share one provider invocation with its
+/// neighbours. Retries and stale re-plans are refreshed one at a time, and
+/// rollbacks restore each package individually.
+fn coalescible(entry: &TaskQueueEntry) -> bool {
+    if entry.reviewed_bundle_json.is_some()
+        || entry.retry_of.is_some()
+        || entry.stale_plan_diff_json.is_some()
+        || !STABLE_PROVIDERS.contains(&entry.package_source)
+        || !supports_batch(entry.package_source)
+    {
+        return false;
+    }
+    entry
+        .reviewed_plan_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<ProviderPlan>(json).ok())
+        .is_some_and(|plan| plan.action != OperationAction::Rollback && plan.rollback_of.is_none())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -506,6 +741,150 @@ mod tests {
         );
     }
 
+    #[test]
+    fn recovered_transactions_settle_their_reclaimed_tasks() {
+        use crate::backend::transaction::{
+            OperationAction, OperationRequest, RequestedBy, RiskAssessment,
+        };
+
+        let mut history = OperationHistory::default();
+        let mut records = Vec::new();
+        for (name, state) in [
+            ("vim", OperationState::Succeeded),
+            ("curl", OperationState::Partial),
+            ("git", OperationState::Interrupted),
+        ] {
+            let request = OperationRequest::new(
+                OperationAction::Install,
+                vec![crate::backend::transaction::PackageRef {
+                    name: name.to_string(),
+                    source: PackageSource::Apt,
+                    installed_version: None,
+                    available_version: Some("1.0".to_string()),
+                    pinned: false,
+                }],
+                RequestedBy::Tui,
+            );
+            let mut entry = running_entry(name);
+            entry.owner_pid = None;
+            entry.reviewed_operation_id = Some(request.id.clone());
+            history.task_queue.enqueue(entry);
+
+            let plan = crate::backend::transaction::build_plan(&request, &[], None);
+            records.push(OperationRecord {
+                operation_id: request.id,
+                state,
+                risk: RiskAssessment::for_plan(&plan),
+                plan,
+                receipt: None,
+                error: None,
+                updated_at: chrono::Utc::now(),
+                owner_pid: None,
+                recovered_at: Some(chrono::Utc::now()),
+                snapshot: None,
+            });
+        }
+        HistoryTracker::reclaim_interrupted_tasks(&mut history);
+
+        assert_eq!(
+            HistoryTracker::settle_recovered_tasks(&mut history, &records),
+            3
+        );
+        let entries = &history.task_queue.entries;
+        assert_eq!(entries[0].status, TaskQueueStatus::Completed);
+        assert_eq!(entries[0].error, None);
+        assert_eq!(entries[1].status, TaskQueueStatus::Failed);
+        assert!(entries[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("partly applied"));
+        assert_eq!(entries[2].status, TaskQueueStatus::Failed);
+        assert!(entries[2]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("could not confirm"));
+    }
+
+    #[test]
+    fn queued_tasks_are_claimed_by_lane_and_coalesced_per_provider() {
+        use crate::backend::transaction::{
+            OperationAction, OperationRequest, PackageRef, RequestedBy,
+        };
+
+        let reviewed = |name: &str, source: PackageSource| {
+            let request = OperationRequest::new(
+                OperationAction::Install,
+                vec![PackageRef {
+                    name: name.to_string(),
+                    source,
+                    installed_version: None,
+                    available_version: Some("1.0".to_string()),
+                    pinned: false,
+                }],
+                RequestedBy::Tui,
+            );
+            let plan = crate::backend::transaction::build_plan(&request, &[], None);
+            let mut entry = TaskQueueEntry::new(
+                TaskQueueAction::Install,
+                format!("{source:?}:{name}"),
+                name.to_string(),
+                source,
+            );
+            entry.reviewed_operation_id = Some(plan.operation_id.clone());
+            entry.reviewed_plan_json = Some(serde_json::to_string(&plan).unwrap());
+            entry
+        };
+        let mut done = reviewed("htop", PackageSource::Apt);
+        done.mark_completed();
+        let entries = vec![
+            done,
+            reviewed("vim", PackageSource::Apt),
+            reviewed("curl", PackageSource::Apt),
+            reviewed("typescript", PackageSource::Npm),
+            reviewed("git", PackageSource::Apt),
+        ];
+
+        let idle = HashSet::new();
+        // npm runs in its own lane, so it does not split the APT batch.
+        assert_eq!(HistoryTracker::next_batch(&entries, &idle), vec![1, 2, 4]);
+
+        // A retry is re-planned on its own, so it neither joins nor lets
+        // later tasks of its lane overtake it.
+        let mut retried = entries.clone();
+        retried[2].retry_of = Some("earlier".to_string());
+        assert_eq!(HistoryTracker::next_batch(&retried, &idle), vec![1]);
+
+        // While the system lane is busy, the npm task runs beside it.
+        let system = HashSet::from([ExecutionLane::System]);
+        assert_eq!(HistoryTracker::next_batch(&entries, &system), vec![3]);
+
+        // A bundle needs every lane: it waits for running lanes, and nothing
+        // queued behind it overtakes it.
+        let mut bundled = entries.clone();
+        bundled[1].reviewed_bundle_json = Some("{}".to_string());
+        assert!(HistoryTracker::next_batch(&bundled, &system).is_empty());
+        assert_eq!(HistoryTracker::next_batch(&bundled, &idle), vec![1]);
+        let exclusive = HashSet::from([ExecutionLane::Exclusive]);
+        assert!(HistoryTracker::next_batch(&entries, &exclusive).is_empty());
+
+        // Tasks from providers without a reviewed plan run one at a time.
+        let unreviewed: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                TaskQueueEntry::new(
+                    TaskQueueAction::Install,
+                    format!("pip:{name}"),
+                    name.to_string(),
+                    PackageSource::Pip,
+                )
+            })
+            .collect();
+        assert_eq!(HistoryTracker::next_batch(&unreviewed, &idle), vec![0]);
+        assert!(HistoryTracker::next_batch(&entries[..1], &idle).is_empty());
+    }
+
     #[test]
     fn queued_and_finished_tasks_are_left_alone() {
         let mut history = OperationHistory::default();
@@ -0,0 +1,476 @@
+//! User hooks run around the operations the transaction engine executes.
+//!
+//! A hook is a shell command from `config.toml` matched on provider, action
+//! and package name. It gets the plan, and after the operation the receipt,
+//! as JSON on stdin, and its output goes to the task log of the operation.
+//! A pre hook that exits non-zero cancels the operation.
+
+use super::streaming::{
+    log_lines, signal_group, strip_ansi, supervise, ProcessCancelled, StreamLine,
+};
+use super::transaction::{OperationAction, ProviderError, ProviderPlan, VerificationReceipt};
+use crate::models::{HookConfig, HookStage};
+use serde::Serialize;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// How long a post hook may run once its operation was cancelled.
+pub const CANCELLED_POST_HOOK_SECS: u64 = 10;
+
+/// What a hook reads on stdin.
+#[derive(Serialize)]
+struct HookInput<'a> {
+    stage: HookStage,
+    plan: &'a ProviderPlan,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    receipt: Option<&'a VerificationReceipt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a ProviderError>,
+}
+
+fn action_name(action: OperationAction) -> &'static str {
+    match action {
+        OperationAction::Install => "install",
+        OperationAction::Remove => "remove",
+        OperationAction::Update => "update",
+        OperationAction::Rollback => "rollback",
+    }
+}
+
+/// Whether `hook` runs at `stage` of `plan`.
+pub fn matches(hook: &HookConfig, stage: HookStage, plan: &ProviderPlan) -> bool {
+    let provider = plan.provider.source.to_string();
+    hook.stage == stage
+        && (hook.providers.is_empty()
+            || hook
+                .providers
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&provider)))
+        && (hook.actions.is_empty()
+            || hook
+                .actions
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(action_name(plan.action))))
+        && (hook.packages.is_empty()
+            || plan.targets.iter().any(|target| {
+                hook.packages
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &target.name))
+            }))
+}
+
+/// Shell-style match of `name` against `pattern`, where `*` matches any run
+/// of characters and `?` exactly one.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the name position it was tried at.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, tried)) => {
+                    p = star + 1;
+                    n = tried + 1;
+                    backtrack = Some((star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Run `hook` for `plan`, streaming its output to the task log. Returns why
+/// the hook failed: a non-zero exit, a signal, running past its timeout, or
+/// the cancellation in scope. The hook runs in its own process group, so
+/// whatever it started is stopped along with it.
+pub async fn run(
+    hook: &HookConfig,
+    stage: HookStage,
+    plan: &ProviderPlan,
+    outcome: Option<&Result<VerificationReceipt, ProviderError>>,
+) -> Result<(), String> {
+    let input = serde_json::to_vec(&HookInput {
+        stage,
+        plan,
+        receipt: outcome.and_then(|outcome| outcome.as_ref().ok()),
+        error: outcome.and_then(|outcome| outcome.as_ref().err()),
+    })
+    .map_err(|error| format!("Could not encode the hook input: {}", error))?;
+    let packages: Vec<&str> = plan
+        .targets
+        .iter()
+        .map(|target| target.name.as_str())
+        .collect();
+
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&hook.command)
+        .env("LINGET_HOOK_STAGE", stage.to_string())
+        .env("LINGET_PROVIDER", plan.provider.source.to_string())
+        .env("LINGET_ACTION", action_name(plan.action))
+        .env("LINGET_PACKAGES", packages.join(" "))
+        .env("LINGET_OPERATION_ID", &plan.operation_id)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|error| format!("Could not start the hook: {}", error))?;
+
+    let mut stdin = child.stdin.take();
+    let writer = tokio::spawn(async move {
+        if let Some(stdin) = stdin.as_mut() {
+            // A hook that ignores its input closes the pipe early.
+            let _ = stdin.write_all(&input).await;
+        }
+    });
+    let label = hook.label().to_string();
+    // Task-locals do not follow spawned tasks, so the sink is taken here.
+    let sink = log_lines();
+    let mut readers = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        readers.push(tokio::spawn(forward(
+            BufReader::new(stdout),
+            label.clone(),
+            sink.clone(),
+            false,
+        )));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        readers.push(tokio::spawn(forward(
+            BufReader::new(stderr),
+            label.clone(),
+            sink,
+            true,
+        )));
+    }
+
+    let pid = child.id();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(hook.timeout_secs);
+    let status = {
+        let wait = child.wait();
+        tokio::pin!(wait);
+        tokio::time::timeout_at(deadline, supervise(pid, wait)).await
+    };
+    let failure = match status {
+        Ok(Ok(status)) => Ok(status),
+        Ok(Err(error)) if error.is::<ProcessCancelled>() => {
+            Err(format!("{} hook '{}' was cancelled", stage, label))
+        }
+        Ok(Err(error)) => Err(format!("Could not wait for the hook: {}", error)),
+        Err(_) => {
+            if let Some(pid) = pid {
+                signal_group(pid, libc::SIGKILL);
+            }
+            let _ = child.kill().await;
+            Err(format!(
+                "{} hook '{}' did not finish within {}s",
+                stage, label, hook.timeout_secs
+            ))
+        }
+    };
+    let status = match failure {
+        Ok(status) => status,
+        Err(reason) => {
+            writer.abort();
+            for reader in readers {
+                reader.abort();
+            }
+            return Err(reason);
+        }
+    };
+    // Anything the hook left running in the background would hold its
+    // output open; it ends with the hook. Whatever escaped the process group
+    // gets the rest of the timeout to let go of the pipes.
+    if let Some(pid) = pid {
+        signal_group(pid, libc::SIGKILL);
+    }
+    let pending: Vec<_> = readers
+        .iter()
+        .chain([&writer])
+        .map(JoinHandle::abort_handle)
+        .collect();
+    let drained = tokio::time::timeout_at(deadline, async move {
+        let _ = writer.await;
+        for reader in readers {
+            let _ = reader.await;
+        }
+    })
+    .await;
+    if drained.is_err() {
+        for task in pending {
+            task.abort();
+        }
+    }
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(format!(
+            "{} hook '{}' exited with status {}",
+            stage, label, code
+        )),
+        None => Err(format!(
+            "{} hook '{}' was terminated by a signal",
+            stage, label
+        )),
+    }
+}
+
+async fn forward<R>(
+    reader: BufReader<R>,
+    label: String,
+    sink: Option<mpsc::Sender<StreamLine>>,
+    stderr: bool,
+) where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let line = strip_ansi(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line = format!("[hook {}] {}", label, line);
+        match &sink {
+            Some(sink) => {
+                let line = if stderr {
+                    StreamLine::Stderr(line)
+                } else {
+                    StreamLine::Stdout(line)
+                };
+                if sink.send(line).await.is_err() {
+                    break;
+                }
+            }
+            None => tracing::info!("{}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::streaming::{with_cancellation, with_log_lines};
+    use crate::backend::transaction::{
+        build_plan, CancellationFlag, OperationRequest, PackageRef, RequestedBy,
+    };
+    use crate::models::PackageSource;
+
+    fn hook(stage: HookStage, command: &str) -> HookConfig {
+        HookConfig {
+            name: "test".to_string(),
+            stage,
+            command: command.to_string(),
+            providers: Vec::new(),
+            actions: Vec::new(),
+            packages: Vec::new(),
+            timeout_secs: 10,
+        }
+    }
+
+    fn plan(source: PackageSource, action: OperationAction, names: &[&str]) -> ProviderPlan {
+        let request = OperationRequest::new(
+            action,
+            names
+                .iter()
+                .map(|name| PackageRef {
+                    name: name.to_string(),
+                    source,
+                    installed_version: Some("1.0".to_string()),
+                    available_version: Some("2.0".to_string()),
+                    pinned: false,
+                })
+                .collect(),
+            RequestedBy::Cli,
+        );
+        build_plan(&request, &[], None)
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        assert!(glob_match("nginx*", "nginx-common"));
+        assert!(glob_match(
+            "linux-image-*-generic",
+            "linux-image-6.8.0-generic"
+        ));
+        assert!(glob_match("lib?", "libc"));
+        assert!(glob_match("*", "anything"));
+        assert!(!glob_match("nginx", "nginx-common"));
+        assert!(!glob_match("lib?", "libssl"));
+        assert!(!glob_match("*-dev", "libssl-dev-doc"));
+    }
+
+    #[test]
+    fn hooks_match_on_stage_provider_action_and_package() {
+        let update = plan(
+            PackageSource::Apt,
+            OperationAction::Update,
+            &["vim", "nginx-core"],
+        );
+        let mut nginx = hook(HookStage::Pre, "true");
+        nginx.providers = vec!["apt".to_string()];
+        nginx.actions = vec!["update".to_string()];
+        nginx.packages = vec!["nginx*".to_string()];
+        assert!(matches(&nginx, HookStage::Pre, &update));
+        assert!(!matches(&nginx, HookStage::Post, &update));
+
+        let remove = plan(PackageSource::Apt, OperationAction::Remove, &["nginx-core"]);
+        assert!(!matches(&nginx, HookStage::Pre, &remove));
+        let flatpak = plan(PackageSource::Flatpak, OperationAction::Update, &["nginx"]);
+        assert!(!matches(&nginx, HookStage::Pre, &flatpak));
+        let vim = plan(PackageSource::Apt, OperationAction::Update, &["vim"]);
+        assert!(!matches(&nginx, HookStage::Pre, &vim));
+        assert!(matches(&hook(HookStage::Pre, "true"), HookStage::Pre, &vim));
+    }
+
+    #[tokio::test]
+    async fn hooks_read_the_plan_and_stream_their_output() {
+        let update = plan(PackageSource::Npm, OperationAction::Update, &["typescript"]);
+        let (sender, mut lines) = mpsc::channel(16);
+        let result = with_log_lines(
+            sender,
+            run(
+                &hook(
+                    HookStage::Pre,
+                    "grep -q '\"name\":\"typescript\"' && echo read-plan && echo \"$LINGET_ACTION $LINGET_PACKAGES\" >&2",
+                ),
+                HookStage::Pre,
+                &update,
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+
+        let mut output = Vec::new();
+        while let Ok(line) = lines.try_recv() {
+            output.push(match line {
+                StreamLine::Stdout(line) => format!("out {}", line),
+                StreamLine::Stderr(line) => format!("err {}", line),
+            });
+        }
+        output.sort();
+        assert_eq!(
+            output,
+            [
+                "err [hook test] update typescript",
+                "out [hook test] read-plan",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failing_and_hung_hooks_report_why() {
+        let update = plan(PackageSource::Npm, OperationAction::Update, &["typescript"]);
+        assert_eq!(
+            run(
+                &hook(HookStage::Pre, "exit 3"),
+                HookStage::Pre,
+                &update,
+                None
+            )
+            .await,
+            Err("pre hook 'test' exited with status 3".to_string())
+        );
+        let mut slow = hook(HookStage::Pre, "sleep 30");
+        slow.timeout_secs = 0;
+        assert!(run(&slow, HookStage::Pre, &update, None)
+            .await
+            .expect_err("a hung hook must fail")
+            .contains("did not finish within 0s"));
+    }
+
+    #[tokio::test]
+    async fn timeouts_and_cancellation_stop_everything_a_hook_started() {
+        let update = plan(PackageSource::Npm, OperationAction::Update, &["typescript"]);
+        let pid_file = std::env::temp_dir().join(format!("linget-hook-{}", uuid::Uuid::new_v4()));
+        let mut hung = hook(
+            HookStage::Pre,
+            &format!("sleep 30 & echo $! > '{}'; wait", pid_file.display()),
+        );
+        hung.timeout_secs = 1;
+        assert!(run(&hung, HookStage::Pre, &update, None)
+            .await
+            .expect_err("a hung hook must fail")
+            .contains("did not finish within 1s"));
+        let sleeper = std::fs::read_to_string(&pid_file).expect("hook wrote its child's pid");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        // Gone, or a zombie waiting to be reaped by init.
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", sleeper.trim()));
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+        std::fs::remove_file(&pid_file).ok();
+
+        let flag = CancellationFlag::default();
+        let trigger = flag.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            trigger.request();
+        });
+        let started = std::time::Instant::now();
+        let result = with_cancellation(
+            flag,
+            run(
+                &hook(HookStage::Pre, "sleep 30"),
+                HookStage::Pre,
+                &update,
+                None,
+            ),
+        )
+        .await;
+        assert_eq!(result, Err("pre hook 'test' was cancelled".to_string()));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn background_processes_do_not_keep_a_finished_hook_running() {
+        let update = plan(PackageSource::Npm, OperationAction::Update, &["typescript"]);
+        let pid_file = std::env::temp_dir().join(format!("linget-hook-{}", uuid::Uuid::new_v4()));
+        let started = std::time::Instant::now();
+        let backgrounded = hook(
+            HookStage::Post,
+            &format!("sleep 30 & echo $! > '{}'", pid_file.display()),
+        );
+        assert_eq!(
+            run(&backgrounded, HookStage::Post, &update, None).await,
+            Ok(())
+        );
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let sleeper = std::fs::read_to_string(&pid_file).expect("hook wrote its child's pid");
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", sleeper.trim()));
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+
+        // A process in its own session survives, but only holds the hook up
+        // until the timeout.
+        let mut escaped = hook(
+            HookStage::Post,
+            &format!("setsid sleep 30 & echo $! > '{}'", pid_file.display()),
+        );
+        escaped.timeout_secs = 1;
+        let started = std::time::Instant::now();
+        assert_eq!(run(&escaped, HookStage::Post, &update, None).await, Ok(()));
+        assert!(started.elapsed() < Duration::from_secs(10));
+        if let Ok(pid) = std::fs::read_to_string(&pid_file) {
+            if let Ok(pid) = pid.trim().parse() {
+                // Safety: only signals the sleep this test started.
+                unsafe { libc::kill(pid, libc::SIGKILL) };
+            }
+        }
+        std::fs::remove_file(&pid_file).ok();
+    }
+}
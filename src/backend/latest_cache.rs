@@ -7,6 +7,7 @@
 //! data dir next to the catalog cache.
 
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -16,7 +17,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 const TTL_SECS: u64 = 60 * 60;
 const CACHE_FILE: &str = "latest-versions.json";
-const CACHE_VERSION: u32 = 1;
+const CACHE_VERSION: u32 = 2;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct LatestCache {
@@ -27,6 +28,9 @@ struct LatestCache {
 #[derive(Debug, Serialize, Deserialize)]
 struct CachedEntry {
     version: String,
+    /// When `version` was published, for registries that say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    released_at: Option<DateTime<Utc>>,
     fetched_at: u64,
 }
 
@@ -69,9 +73,14 @@ fn cache() -> &'static Mutex<LatestCache> {
 
 /// Returns the cached latest version for `key` if it is still within TTL.
 pub fn get(key: &str) -> Option<String> {
+    get_release(key).map(|(version, _)| version)
+}
+
+/// Like `get`, with the publish time recorded by `put_release`.
+pub fn get_release(key: &str) -> Option<(String, Option<DateTime<Utc>>)> {
     let cache = cache().lock();
     let entry = cache.entries.get(key)?;
-    is_fresh(entry.fetched_at).then(|| entry.version.clone())
+    is_fresh(entry.fetched_at).then(|| (entry.version.clone(), entry.released_at))
 }
 
 fn is_fresh(fetched_at: u64) -> bool {
@@ -80,12 +89,18 @@ fn is_fresh(fetched_at: u64) -> bool {
 
 /// Records a fresh latest-version lookup and schedules a disk persist.
 pub fn put(key: String, version: String) {
+    put_release(key, version, None);
+}
+
+/// Records a fresh lookup together with when that version was published.
+pub fn put_release(key: String, version: String, released_at: Option<DateTime<Utc>>) {
     {
         let mut cache = cache().lock();
         cache.entries.insert(
             key,
             CachedEntry {
                 version,
+                released_at,
                 fetched_at: now_secs(),
             },
         );
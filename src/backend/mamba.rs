@@ -1,3 +1,5 @@
+use super::streaming::CommandFailure;
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -124,6 +126,7 @@ impl PackageBackend for MambaBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
         Ok(packages)
@@ -193,6 +196,7 @@ impl PackageBackend for MambaBackend {
                                 install_date: None,
                                 update_category: None,
                                 enrichment: None,
+                                released_at: None,
                             });
                         }
                     }
@@ -204,42 +208,48 @@ impl PackageBackend for MambaBackend {
     }
 
     async fn install(&self, name: &str) -> Result<()> {
-        let status = Command::new("mamba")
+        let output = Command::new("mamba")
             .args(["install", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install mamba package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install mamba package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("mamba", &output)
+            .with_message(format!("Failed to install mamba package {}", name)))
     }
 
     async fn remove(&self, name: &str) -> Result<()> {
-        let status = Command::new("mamba")
+        let output = Command::new("mamba")
             .args(["remove", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to remove mamba package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to remove mamba package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("mamba", &output)
+            .with_message(format!("Failed to remove mamba package {}", name)))
     }
 
     async fn update(&self, name: &str) -> Result<()> {
-        let status = Command::new("mamba")
+        let output = Command::new("mamba")
             .args(["update", "-n", "base", "-y", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to update mamba package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to update mamba package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("mamba", &output)
+            .with_message(format!("Failed to update mamba package {}", name)))
     }
 
     async fn downgrade_to(&self, name: &str, version: &str) -> Result<()> {
@@ -247,16 +257,18 @@ impl PackageBackend for MambaBackend {
             anyhow::bail!("Version is required");
         }
         let spec = format!("{}={}", name, version);
-        let status = Command::new("mamba")
+        let output = Command::new("mamba")
             .args(["install", "-n", "base", "-y", &spec])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install a specific mamba package version")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install {} via mamba", spec)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("mamba", &output)
+            .with_message(format!("Failed to install {} via mamba", spec)))
     }
 
     async fn search(&self, query: &str) -> Result<Vec<Package>> {
@@ -308,6 +320,7 @@ impl PackageBackend for MambaBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -404,6 +417,37 @@ impl PackageBackend for MambaBackend {
     }
 }
 
+/// Classify a mamba failure.
+///
+/// mamba reports solver and download failures through libmamba's own log
+/// lines; other failures are raised as the conda exceptions it shares.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.contains("does not exist (perhaps a typo")) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| line.contains("Could not solve for environment specs")) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| line.contains("Download error (28)")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| {
+        line.contains("Download error (6)") || line.contains("Download error (7)")
+    }) {
+        ProviderErrorCode::Network
+    } else if any(&|line| line.contains("Transfer finalized, status:")) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| line.contains("LockFile acquisition failed")) {
+        ProviderErrorCode::LockBusy
+    } else if let Some(class) = super::conda::classify_failure(exit_code, output) {
+        return Some(class);
+    } else if any(&|line| line.contains("libmamba")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -6,10 +6,12 @@ mod cargo;
 mod conda;
 mod dart;
 mod deb;
+pub mod dependency_graph;
 mod dnf;
 pub mod exec;
 mod flatpak;
 pub mod history_tracker;
+pub mod hooks;
 pub(crate) mod latest_cache;
 mod mamba;
 mod npm;
@@ -17,8 +19,11 @@ mod pacman;
 mod pip;
 mod pipx;
 mod pkexec;
+pub mod plugin;
 mod providers;
+mod registry;
 mod snap;
+pub mod snapshot;
 pub mod streaming;
 mod traits;
 pub mod transaction;
@@ -32,6 +37,9 @@ pub use cargo::CargoBackend;
 pub use conda::CondaBackend;
 pub use dart::DartBackend;
 pub use deb::DebBackend;
+pub use dependency_graph::{
+    DependencyNode, DependencyTree, ProtectedPackage, ProtectionReason, RemovalImpact, WhyInstalled,
+};
 pub use dnf::DnfBackend;
 pub use flatpak::FlatpakBackend;
 pub use history_tracker::HistoryTracker;
@@ -44,21 +52,22 @@ pub use pkexec::{
     graphical_prompt_available, install_terminal_handover, run_pkexec, run_pkexec_with_logs,
     Suggest, TerminalHandover, SUGGEST_PREFIX,
 };
+pub use plugin::PluginBackend;
 pub use providers::{
     detect_available_providers, detect_providers, detect_providers_fast, ProviderStatus,
 };
 pub use snap::SnapBackend;
+pub use snapshot::{FilesystemSnapshot, SnapshotDriver};
 pub use traits::*;
 pub use zypper::ZypperBackend;
 
 use crate::backend::streaming::StreamLine;
 use crate::models::history::{TaskQueueAction, TaskQueueEntry};
 use crate::models::{
-    normalize_name_for_dedup, FlatpakMetadata, FlatpakPermission, Package, PackageSource,
-    PackageStatus, Repository,
+    normalize_name_for_dedup, FlatpakMetadata, FlatpakPermission, HookConfig, Package,
+    PackageSource, PackageStatus, Repository, SnapshotConfig,
 };
 use anyhow::{Context, Result};
-#[cfg(test)]
 use once_cell::sync::Lazy;
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
@@ -71,6 +80,27 @@ use tracing::{debug, error, info, instrument, warn};
 pub(crate) static TEST_PATH_ENV_LOCK: Lazy<tokio::sync::Mutex<()>> =
     Lazy::new(|| tokio::sync::Mutex::new(()));
 
+/// Cancellation handles for queue tasks executing in this process, by entry id.
+static RUNNING_TASKS: Lazy<std::sync::Mutex<HashMap<String, transaction::CancellationFlag>>> =
+    Lazy::new(Default::default);
+
+/// Ask a queue task running in this process to stop. Providers that support
+/// cooperative cancellation have their running command terminated; the rest
+/// stop at the next package step. Returns false when the task is not running
+/// here.
+pub fn request_task_cancellation(entry_id: &str) -> bool {
+    let running = RUNNING_TASKS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    match running.get(entry_id) {
+        Some(flag) => {
+            flag.request();
+            true
+        }
+        None => false,
+    }
+}
+
 const BACKEND_LIST_TIMEOUT: Duration = Duration::from_secs(8);
 const BACKEND_UPDATE_TIMEOUT: Duration = Duration::from_secs(15);
 /// A lock check reads a lockfile; it should never take seconds.
@@ -115,10 +145,15 @@ pub enum PackageLoadProgress {
     },
 }
 
+type EngineCell = tokio::sync::OnceCell<transaction::TransactionEngine>;
+
 #[derive(Clone)]
 pub struct TaskQueueExecutor {
     package_manager: Arc<RwLock<PackageManager>>,
     history_tracker: Arc<Mutex<Option<HistoryTracker>>>,
+    concurrency: usize,
+    snapshots: SnapshotConfig,
+    hooks: Vec<HookConfig>,
 }
 
 impl TaskQueueExecutor {
@@ -129,37 +164,241 @@ impl TaskQueueExecutor {
         Self {
             package_manager,
             history_tracker,
+            concurrency: 1,
+            snapshots: SnapshotConfig::default(),
+            hooks: Vec::new(),
         }
     }
 
+    /// Run up to `limit` execution lanes at once (at least one).
+    pub fn with_concurrency(mut self, limit: usize) -> Self {
+        self.concurrency = limit.max(1);
+        self
+    }
+
+    /// Snapshot the filesystem before the plans `config` covers.
+    pub fn with_snapshots(mut self, config: SnapshotConfig) -> Self {
+        self.snapshots = config;
+        self
+    }
+
+    /// Run the matching `hooks` around the plans the engine executes.
+    pub fn with_hooks(mut self, hooks: Vec<HookConfig>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Settle transactions a previous LinGet process left running and give
+    /// their queue entries the verified outcome. Loading the engine performs
+    /// the recovery; this copies the verdict onto the tasks.
+    pub async fn recover_interrupted_transactions(&self) -> Result<usize> {
+        use transaction::TransactionEngine;
+
+        let engine =
+            TransactionEngine::load(self.package_manager.clone(), transaction_store_path())
+                .await
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        let records = engine.records().await;
+        let mut guard = self.history_tracker.lock().await;
+        match guard.as_mut() {
+            Some(tracker) => tracker.reconcile_recovered_operations(&records).await,
+            None => Ok(0),
+        }
+    }
+
+    /// Drain the queue. Tasks in different execution lanes run in parallel,
+    /// up to the concurrency limit; tasks in one lane run in queue order.
     pub async fn run(&self, event_sender: Option<mpsc::Sender<TaskQueueEvent>>) -> Result<()> {
+        use transaction::ExecutionLane;
+
+        // Lanes share one engine so their store updates and per-lane
+        // execution locks are shared. It is loaded when first needed.
+        let engine: Arc<EngineCell> = Arc::default();
+        let mut lanes = tokio::task::JoinSet::new();
+        let mut busy: HashSet<ExecutionLane> = HashSet::new();
+        let mut failure = None;
         loop {
-            let entry = {
-                let mut guard = self.history_tracker.lock().await;
-                let tracker = guard
-                    .as_mut()
-                    .context("History tracker must be initialized to run task queue")?;
-                tracker.claim_next_task().await?
-            };
+            while failure.is_none() && lanes.len() < self.concurrency {
+                let claimed = {
+                    let mut guard = self.history_tracker.lock().await;
+                    match guard.as_mut() {
+                        Some(tracker) => tracker.claim_next_batch(&busy).await,
+                        None => Err(anyhow::anyhow!(
+                            "History tracker must be initialized to run task queue"
+                        )),
+                    }
+                };
+                let batch = match claimed {
+                    Ok(batch) => batch,
+                    Err(error) => {
+                        failure = Some(error);
+                        break;
+                    }
+                };
+                let Some(first) = batch.first() else {
+                    break;
+                };
+                let lane = ExecutionLane::for_entry(first);
+                busy.insert(lane);
+                let executor = self.clone();
+                let engine = Arc::clone(&engine);
+                let event_sender = event_sender.clone();
+                lanes.spawn(async move {
+                    let result = executor.run_batch(batch, &engine, &event_sender).await;
+                    (lane, result)
+                });
+            }
 
-            let Some(entry) = entry else {
+            // A failing lane stops new work, but lanes already running are
+            // allowed to finish rather than being abandoned mid-operation.
+            let Some(joined) = lanes.join_next().await else {
                 break;
             };
+            match joined {
+                Ok((lane, result)) => {
+                    busy.remove(&lane);
+                    if let Err(error) = result {
+                        failure.get_or_insert(error);
+                    }
+                }
+                Err(error) => {
+                    failure.get_or_insert(anyhow::anyhow!("Queue lane stopped: {error}"));
+                }
+            }
+        }
 
-            if let Some(sender) = &event_sender {
+        match failure {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    async fn run_batch(
+        &self,
+        batch: Vec<TaskQueueEntry>,
+        engine: &EngineCell,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+    ) -> Result<()> {
+        if let Some(sender) = event_sender {
+            for entry in &batch {
                 let _ = sender.send(TaskQueueEvent::Started(entry.clone())).await;
             }
+        }
 
-            let result = if matches!(
-                entry.package_source,
-                PackageSource::Apt | PackageSource::Flatpak | PackageSource::Npm
-            ) {
-                self.run_verified_transaction(&entry, &event_sender).await
+        // A coalesced batch shares one provider invocation, so cancelling
+        // any of its tasks stops all of them.
+        let cancellation = transaction::CancellationFlag::default();
+        {
+            let mut running = RUNNING_TASKS
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for entry in &batch {
+                running.insert(entry.id.clone(), cancellation.clone());
+            }
+        }
+        let coalesced = if batch.len() > 1 {
+            Self::with_task_log(
+                event_sender,
+                &batch,
+                self.run_coalesced(&batch, engine, event_sender, cancellation.clone()),
+            )
+            .await?
+        } else {
+            None
+        };
+        match coalesced {
+            Some(results) => {
+                for (entry, result) in batch.iter().zip(results) {
+                    self.finish_entry(entry, result, event_sender).await?;
+                }
+            }
+            None => {
+                for entry in &batch {
+                    // Run one by one, each task stays separately cancellable.
+                    let cancellation = if cancellation.is_requested() {
+                        cancellation.clone()
+                    } else {
+                        let own = transaction::CancellationFlag::default();
+                        RUNNING_TASKS
+                            .lock()
+                            .unwrap_or_else(std::sync::PoisonError::into_inner)
+                            .insert(entry.id.clone(), own.clone());
+                        own
+                    };
+                    let result = Self::with_task_log(
+                        event_sender,
+                        std::slice::from_ref(entry),
+                        self.run_entry(entry, engine, event_sender, cancellation),
+                    )
+                    .await;
+                    self.finish_entry(entry, result, event_sender).await?;
+                }
+            }
+        }
+        {
+            let mut running = RUNNING_TASKS
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            for entry in &batch {
+                running.remove(&entry.id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn load_engine<'a>(
+        &self,
+        engine: &'a EngineCell,
+    ) -> Result<&'a transaction::TransactionEngine> {
+        engine
+            .get_or_try_init(|| async {
+                transaction::TransactionEngine::load(
+                    self.package_manager.clone(),
+                    transaction_store_path(),
+                )
+                .await
+                .map(|engine| {
+                    engine
+                        .with_snapshots(self.snapshots.clone())
+                        .with_hooks(self.hooks.clone())
+                })
+                .map_err(|error| anyhow::anyhow!(error.to_string()))
+            })
+            .await
+    }
+
+    async fn run_entry(
+        &self,
+        entry: &TaskQueueEntry,
+        engine: &EngineCell,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+        cancellation: transaction::CancellationFlag,
+    ) -> Result<()> {
+        if entry.reviewed_bundle_json.is_some() {
+            self.run_verified_bundle(entry, engine, event_sender, cancellation)
+                .await
+        } else if transaction::STABLE_PROVIDERS.contains(&entry.package_source) {
+            self.run_verified_transaction(entry, engine, event_sender, cancellation)
+                .await
+        } else {
+            let before = if transaction::verifies_unreviewed(entry.package_source) {
+                self.package_manager
+                    .read()
+                    .await
+                    .list_installed_for_source(entry.package_source)
+                    .await
+                    .map_err(|error| {
+                        tracing::warn!(error = %error, "Inventory unavailable; task will not be verified");
+                    })
+                    .ok()
             } else {
-                let (log_sender, log_task) = Self::spawn_log_forwarder(&event_sender, &entry);
-                let result = {
-                    let manager = self.package_manager.read().await;
-                    let pkg = Self::package_from_entry(&entry);
+                None
+            };
+            let (log_sender, log_task) = Self::spawn_log_forwarder(event_sender, entry);
+            let result = {
+                let manager = self.package_manager.read().await;
+                let pkg = Self::package_from_entry(entry);
+                let step = async {
                     match entry.action {
                         TaskQueueAction::Install => {
                             manager.install_streaming(&pkg, log_sender).await
@@ -168,68 +407,242 @@ impl TaskQueueExecutor {
                         TaskQueueAction::Update => manager.update_streaming(&pkg, log_sender).await,
                     }
                 };
-                if let Some(task) = log_task {
-                    let _ = task.await;
+                if transaction::ProviderDescriptor::for_source(entry.package_source).cancellation
+                    == transaction::CancellationSupport::Cooperative
+                {
+                    streaming::with_cancellation(cancellation, step).await
+                } else {
+                    step.await
                 }
-                result
             };
+            if let Some(task) = log_task {
+                let _ = task.await;
+            }
+            result?;
+            match before {
+                Some(before) => {
+                    self.verify_unreviewed_entry(entry, &before, event_sender)
+                        .await
+                }
+                None => Ok(()),
+            }
+        }
+    }
 
-            match result {
-                Ok(()) => {
-                    let updated = {
-                        let mut guard = self.history_tracker.lock().await;
-                        let tracker = guard
-                            .as_mut()
-                            .context("History tracker missing after task completion")?;
-                        tracker.mark_task_completed(&entry.id).await?
-                    };
+    /// Re-list a verified Beta provider after a queued mutation and attach
+    /// the receipt. Only a mismatch fails the task: Beta providers often
+    /// cannot name a target version up front, so an inconclusive receipt is
+    /// reported rather than treated as a failure.
+    async fn verify_unreviewed_entry(
+        &self,
+        entry: &TaskQueueEntry,
+        before: &[Package],
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+    ) -> Result<()> {
+        use transaction::{OperationAction, VerificationOutcome};
 
-                    let completed_entry = updated.unwrap_or_else(|| {
-                        let mut fallback = entry.clone();
-                        fallback.mark_completed();
-                        fallback
-                    });
+        let after = self
+            .package_manager
+            .read()
+            .await
+            .list_installed_for_source(entry.package_source)
+            .await;
+        let after = match after {
+            Ok(after) => after,
+            Err(error) => {
+                self.send_transaction_log(
+                    event_sender,
+                    entry,
+                    format!("Verification skipped: inventory unavailable ({error})"),
+                )
+                .await;
+                return Ok(());
+            }
+        };
+        let action = match entry.action {
+            TaskQueueAction::Install => OperationAction::Install,
+            TaskQueueAction::Remove => OperationAction::Remove,
+            TaskQueueAction::Update => OperationAction::Update,
+        };
+        let receipt = transaction::verify_unreviewed(
+            action,
+            &entry.package_name,
+            entry.package_source,
+            entry.available_version.as_deref(),
+            before,
+            &after,
+            chrono::Utc::now(),
+        );
+        self.send_transaction_log(
+            event_sender,
+            entry,
+            format!("Verification receipt: {:?}", receipt.outcome),
+        )
+        .await;
+        for warning in &receipt.warnings {
+            self.send_transaction_log(event_sender, entry, warning.clone())
+                .await;
+        }
+        let receipt_json = serde_json::to_string(&receipt)
+            .context("Verification receipt could not be serialized")?;
+        {
+            let mut guard = self.history_tracker.lock().await;
+            let tracker = guard
+                .as_mut()
+                .context("History tracker missing while saving verification receipt")?;
+            tracker
+                .attach_task_verification_receipt(&entry.id, receipt_json)
+                .await?;
+        }
+        if receipt.outcome == VerificationOutcome::Mismatch {
+            anyhow::bail!("Post-operation verification did not find the expected change");
+        }
+        Ok(())
+    }
 
-                    if let Some(sender) = &event_sender {
-                        let _ = sender
-                            .send(TaskQueueEvent::Completed(completed_entry))
-                            .await;
-                    }
+    async fn finish_entry(
+        &self,
+        entry: &TaskQueueEntry,
+        result: Result<()>,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+    ) -> Result<()> {
+        match result {
+            Ok(()) => {
+                let updated = {
+                    let mut guard = self.history_tracker.lock().await;
+                    let tracker = guard
+                        .as_mut()
+                        .context("History tracker missing after task completion")?;
+                    tracker.mark_task_completed(&entry.id).await?
+                };
+
+                let completed_entry = updated.unwrap_or_else(|| {
+                    let mut fallback = entry.clone();
+                    fallback.mark_completed();
+                    fallback
+                });
+
+                if let Some(sender) = event_sender {
+                    let _ = sender
+                        .send(TaskQueueEvent::Completed(completed_entry))
+                        .await;
                 }
-                Err(e) => {
-                    let error = e.to_string();
-                    let updated = {
-                        let mut guard = self.history_tracker.lock().await;
-                        let tracker = guard
-                            .as_mut()
-                            .context("History tracker missing after task failure")?;
-                        tracker.mark_task_failed(&entry.id, error.clone()).await?
-                    };
+            }
+            Err(e) => {
+                let error = e.to_string();
+                let updated = {
+                    let mut guard = self.history_tracker.lock().await;
+                    let tracker = guard
+                        .as_mut()
+                        .context("History tracker missing after task failure")?;
+                    tracker.mark_task_failed(&entry.id, error.clone()).await?
+                };
 
-                    let failed_entry = updated.unwrap_or_else(|| {
-                        let mut fallback = entry.clone();
-                        fallback.mark_failed(error.clone());
-                        fallback
-                    });
+                let failed_entry = updated.unwrap_or_else(|| {
+                    let mut fallback = entry.clone();
+                    fallback.mark_failed(error.clone());
+                    fallback
+                });
 
-                    if let Some(sender) = &event_sender {
-                        let _ = sender.send(TaskQueueEvent::Failed(failed_entry)).await;
-                    }
+                if let Some(sender) = event_sender {
+                    let _ = sender.send(TaskQueueEvent::Failed(failed_entry)).await;
                 }
             }
         }
-
         Ok(())
     }
 
+    /// Run a claimed batch of same-provider, same-action tasks as one
+    /// provider invocation and attribute each task's receipt back to it.
+    /// Returns `None` when the batch has to run task by task instead, e.g.
+    /// because a plan went stale or the combined plan reaches further than
+    /// the reviewed ones.
+    async fn run_coalesced(
+        &self,
+        batch: &[TaskQueueEntry],
+        engine: &EngineCell,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+        cancellation: transaction::CancellationFlag,
+    ) -> Result<Option<Vec<Result<()>>>> {
+        use transaction::{ProviderPlan, RequestedBy};
+
+        let mut plans = Vec::new();
+        for entry in batch {
+            let Some(plan) = entry
+                .reviewed_plan_json
+                .as_deref()
+                .and_then(|json| serde_json::from_str::<ProviderPlan>(json).ok())
+                .filter(|plan| {
+                    entry.reviewed_operation_id.as_deref() == Some(plan.operation_id.as_str())
+                })
+            else {
+                return Ok(None);
+            };
+            plans.push(plan);
+        }
+        let engine = self.load_engine(engine).await?;
+        for plan in &plans {
+            if engine.resume_reviewed_plan(plan).await.is_err() {
+                return Ok(None);
+            }
+        }
+
+        let names: Vec<&str> = batch
+            .iter()
+            .map(|entry| entry.package_name.as_str())
+            .collect();
+        for entry in batch {
+            self.send_transaction_log(
+                event_sender,
+                entry,
+                format!(
+                    "Coalesced {} queued {:?} tasks into one {} invocation: {}",
+                    batch.len(),
+                    entry.action,
+                    entry.package_source,
+                    names.join(", ")
+                ),
+            )
+            .await;
+        }
+
+        let result = engine
+            .execute_coalesced(&plans, RequestedBy::Tui, cancellation)
+            .await;
+        for (entry, plan) in batch.iter().zip(&plans) {
+            self.record_snapshot(entry, engine, &plan.operation_id, event_sender)
+                .await?;
+        }
+        let receipts = match result {
+            Ok(Some(receipts)) => receipts,
+            Ok(None) => return Ok(None),
+            Err(error) => {
+                let message = error.to_string();
+                return Ok(Some(
+                    batch
+                        .iter()
+                        .map(|_| Err(anyhow::anyhow!(message.clone())))
+                        .collect(),
+                ));
+            }
+        };
+        let mut results = Vec::new();
+        for (entry, receipt) in batch.iter().zip(receipts) {
+            results.push(self.settle_receipt(entry, receipt, event_sender).await);
+        }
+        Ok(Some(results))
+    }
+
     async fn run_verified_transaction(
         &self,
         entry: &TaskQueueEntry,
+        engine: &EngineCell,
         event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+        cancellation: transaction::CancellationFlag,
     ) -> Result<()> {
         use transaction::{
-            CancellationFlag, OperationRequest, ProviderPlan, RequestedBy, RiskAssessment,
-            RiskLevel, TransactionEngine, VerificationOutcome,
+            OperationRequest, ProviderErrorCode, ProviderPlan, RequestedBy, RiskAssessment,
+            RiskLevel,
         };
 
         let plan_json = entry.reviewed_plan_json.as_deref().ok_or_else(|| {
@@ -240,24 +653,25 @@ impl TaskQueueExecutor {
         if entry.reviewed_operation_id.as_deref() != Some(reviewed_plan.operation_id.as_str()) {
             anyhow::bail!("Queued task does not match the reviewed operation ID");
         }
-        let engine =
-            TransactionEngine::load(self.package_manager.clone(), transaction_store_path())
-                .await
-                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        let engine = self.load_engine(engine).await?;
 
         // A retry is a new transaction, not permission to resume a terminal
         // record. Re-plan against the current inventory, persist that exact
-        // plan on the queue entry, then execute it normally.
-        let plan = if entry.retry_of.is_some() {
-            let request = OperationRequest::new(
-                reviewed_plan.action,
-                reviewed_plan.targets.clone(),
-                RequestedBy::Tui,
-            );
-            let (fresh_plan, fresh_risk) = engine
-                .plan(request)
-                .await
-                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        // plan on the queue entry, then execute it normally. A retry that
+        // approves a stale task's refreshed plan runs that plan instead.
+        let plan = if replans_on_retry(entry, &reviewed_plan.operation_id)? {
+            let (fresh_plan, fresh_risk) = match &reviewed_plan.rollback_of {
+                Some(operation_id) => engine.plan_rollback(operation_id).await,
+                None => {
+                    let request = OperationRequest::new(
+                        reviewed_plan.action,
+                        reviewed_plan.targets.clone(),
+                        RequestedBy::Tui,
+                    );
+                    engine.plan(request).await
+                }
+            }
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
             if fresh_risk.level == RiskLevel::Blocked {
                 anyhow::bail!("The refreshed provider transaction plan is blocked");
             }
@@ -307,10 +721,109 @@ impl TaskQueueExecutor {
             anyhow::bail!("The provider transaction plan is blocked");
         }
 
-        let receipt = engine
-            .execute(plan, CancellationFlag::default())
-            .await
-            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        let mut executed = plan.operation_id.clone();
+        let result = match engine.execute(plan.clone(), cancellation.clone()).await {
+            Err(error)
+                if matches!(
+                    error.code,
+                    ProviderErrorCode::PlanExpired | ProviderErrorCode::PlanChanged
+                ) =>
+            {
+                // The plan went stale in the queue. Re-plan and keep the
+                // user's approval only if nothing they approved moved.
+                let stale = engine
+                    .replan_stale(&plan, error.code, RequestedBy::Tui)
+                    .await
+                    .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+                let summary = stale.diff.summary(stale.fresh.action);
+                let fresh_json = serde_json::to_string(&stale.fresh)
+                    .context("Refreshed provider plan could not be serialized")?;
+                let operation_id = stale.fresh.operation_id.clone();
+                {
+                    let mut guard = self.history_tracker.lock().await;
+                    let tracker = guard
+                        .as_mut()
+                        .context("History tracker missing while saving refreshed plan")?;
+                    if stale.needs_approval() {
+                        let diff_json = serde_json::to_string(&stale.diff)
+                            .context("Plan difference could not be serialized")?;
+                        tracker
+                            .attach_task_stale_plan(&entry.id, operation_id, fresh_json, diff_json)
+                            .await?;
+                    } else {
+                        tracker
+                            .attach_task_reviewed_plan(&entry.id, operation_id, fresh_json)
+                            .await?;
+                    }
+                }
+                if stale.needs_approval() {
+                    anyhow::bail!(
+                        "{}; re-planned: {}. Retry to approve the refreshed plan",
+                        error.safe_message,
+                        summary
+                    );
+                }
+                self.send_transaction_log(
+                    event_sender,
+                    entry,
+                    format!("{}; re-planned and {}", error.safe_message, summary),
+                )
+                .await;
+                executed = stale.fresh.operation_id.clone();
+                engine.execute(stale.fresh, cancellation).await
+            }
+            result => result,
+        };
+        self.record_snapshot(entry, engine, &executed, event_sender)
+            .await?;
+        let receipt = result.map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        self.settle_receipt(entry, receipt, event_sender).await
+    }
+
+    /// Log the snapshot taken before `operation_id` and keep it on the task,
+    /// with the command that restores it.
+    async fn record_snapshot(
+        &self,
+        entry: &TaskQueueEntry,
+        engine: &transaction::TransactionEngine,
+        operation_id: &str,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+    ) -> Result<()> {
+        let Some(snapshot) = engine.snapshot(operation_id).await else {
+            return Ok(());
+        };
+        self.send_transaction_log(
+            event_sender,
+            entry,
+            format!(
+                "Snapshot {} taken first; restore with: {}",
+                snapshot.label(),
+                snapshot.restore_hint()
+            ),
+        )
+        .await;
+        let snapshot_json =
+            serde_json::to_string(&snapshot).context("Snapshot could not be serialized")?;
+        let mut guard = self.history_tracker.lock().await;
+        let tracker = guard
+            .as_mut()
+            .context("History tracker missing while saving the snapshot")?;
+        tracker
+            .attach_task_snapshot(&entry.id, snapshot_json)
+            .await?;
+        Ok(())
+    }
+
+    /// Attach a verification receipt to its queue task and turn its outcome
+    /// into the task's result.
+    async fn settle_receipt(
+        &self,
+        entry: &TaskQueueEntry,
+        receipt: transaction::VerificationReceipt,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+    ) -> Result<()> {
+        use transaction::VerificationOutcome;
+
         self.send_transaction_log(
             event_sender,
             entry,
@@ -339,6 +852,164 @@ impl TaskQueueExecutor {
         }
     }
 
+    async fn run_verified_bundle(
+        &self,
+        entry: &TaskQueueEntry,
+        engine: &EngineCell,
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+        cancellation: transaction::CancellationFlag,
+    ) -> Result<()> {
+        use transaction::{
+            OperationRequest, PlanDiff, RequestedBy, RiskLevel, TransactionBundle,
+            VerificationOutcome,
+        };
+
+        let bundle_json = entry
+            .reviewed_bundle_json
+            .as_deref()
+            .context("Queued bundle task has no reviewed bundle")?;
+        let reviewed: TransactionBundle = serde_json::from_str(bundle_json)
+            .context("Reviewed transaction bundle could not be decoded")?;
+        if entry.reviewed_operation_id.as_deref() != Some(reviewed.id.as_str()) {
+            anyhow::bail!("Queued task does not match the reviewed bundle ID");
+        }
+        let engine = self.load_engine(engine).await?;
+
+        // Same retry rule as single plans: re-plan every provider against the
+        // current inventory and persist the fresh bundle before running it. A
+        // retry that approves a refreshed bundle runs that bundle instead.
+        let bundle = if replans_on_retry(entry, &reviewed.id)? {
+            let request = OperationRequest::new(
+                reviewed.action,
+                reviewed.targets().cloned().collect(),
+                RequestedBy::Tui,
+            );
+            let fresh = engine
+                .plan_bundle(request)
+                .await
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+            let fresh_json = serde_json::to_string(&fresh)
+                .context("Refreshed transaction bundle could not be serialized")?;
+            // Nobody reviewed the fresh bundle: it runs only if nothing the
+            // user approved moved, as for a stale single plan.
+            let diff = PlanDiff::between_bundles(&reviewed, &fresh);
+            let needs_approval = !diff.is_empty() || fresh.risk.level > reviewed.risk.level;
+            {
+                let mut guard = self.history_tracker.lock().await;
+                let tracker = guard
+                    .as_mut()
+                    .context("History tracker missing while saving refreshed retry bundle")?;
+                if needs_approval {
+                    let diff_json = serde_json::to_string(&diff)
+                        .context("Bundle difference could not be serialized")?;
+                    tracker
+                        .attach_task_stale_bundle(
+                            &entry.id,
+                            fresh.id.clone(),
+                            fresh_json,
+                            diff_json,
+                        )
+                        .await?;
+                } else {
+                    tracker
+                        .attach_task_reviewed_bundle(&entry.id, fresh.id.clone(), fresh_json)
+                        .await?;
+                }
+            }
+            if needs_approval {
+                anyhow::bail!(
+                    "The bundle changed since it was reviewed; re-planned at {:?} risk: {}. Retry to approve the refreshed bundle",
+                    fresh.risk.level,
+                    diff.summary(fresh.action)
+                );
+            }
+            fresh
+        } else {
+            engine
+                .resume_reviewed_bundle(&reviewed)
+                .await
+                .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+            reviewed
+        };
+
+        self.send_transaction_log(
+            event_sender,
+            entry,
+            format!(
+                "Reviewed {:?} bundle across {} provider(s)",
+                bundle.risk.level,
+                bundle.plans.len()
+            ),
+        )
+        .await;
+        for plan in &bundle.plans {
+            for command in &plan.exact_commands {
+                self.send_transaction_log(
+                    event_sender,
+                    entry,
+                    format!(
+                        "{} command: {} argv={:?}",
+                        plan.provider.source, command.program, command.args
+                    ),
+                )
+                .await;
+            }
+        }
+        if bundle.risk.level == RiskLevel::Blocked {
+            anyhow::bail!("The transaction bundle is blocked");
+        }
+
+        let operation_ids: Vec<String> = bundle
+            .plans
+            .iter()
+            .map(|plan| plan.operation_id.clone())
+            .collect();
+        let result = engine.execute_bundle(bundle, cancellation).await;
+        for operation_id in &operation_ids {
+            self.record_snapshot(entry, engine, operation_id, event_sender)
+                .await?;
+        }
+        let summary = result.map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        for receipt in &summary.receipts {
+            self.send_transaction_log(
+                event_sender,
+                entry,
+                format!(
+                    "{} verification receipt: {:?}",
+                    receipt.provider, receipt.outcome
+                ),
+            )
+            .await;
+        }
+        for error in &summary.failed {
+            self.send_transaction_log(event_sender, entry, format!("Failed: {}", error))
+                .await;
+        }
+        let summary_json =
+            serde_json::to_string(&summary).context("Bundle summary could not be serialized")?;
+        {
+            let mut guard = self.history_tracker.lock().await;
+            let tracker = guard
+                .as_mut()
+                .context("History tracker missing while saving bundle summary")?;
+            tracker
+                .attach_task_bundle_summary(&entry.id, summary_json)
+                .await?;
+        }
+        match summary.outcome {
+            VerificationOutcome::Verified => Ok(()),
+            VerificationOutcome::Inconclusive => {
+                anyhow::bail!("Bundle verification was inconclusive; review the summary")
+            }
+            VerificationOutcome::Mismatch => match summary.failed.first() {
+                Some(error) => anyhow::bail!(error.to_string()),
+                None => {
+                    anyhow::bail!("Post-operation verification did not match the reviewed bundle")
+                }
+            },
+        }
+    }
+
     async fn send_transaction_log(
         &self,
         event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
@@ -382,6 +1053,36 @@ impl TaskQueueExecutor {
         (Some(log_tx), Some(log_task))
     }
 
+    /// Run `future` with hook output going to the task log of every entry
+    /// in `entries`.
+    async fn with_task_log<F: std::future::Future>(
+        event_sender: &Option<mpsc::Sender<TaskQueueEvent>>,
+        entries: &[TaskQueueEntry],
+        future: F,
+    ) -> F::Output {
+        let Some(sender) = event_sender.as_ref().cloned() else {
+            return future.await;
+        };
+
+        let (log_tx, mut log_rx) = mpsc::channel::<StreamLine>(200);
+        let entry_ids: Vec<String> = entries.iter().map(|entry| entry.id.clone()).collect();
+        let log_task = tokio::spawn(async move {
+            while let Some(line) = log_rx.recv().await {
+                for entry_id in &entry_ids {
+                    let _ = sender
+                        .send(TaskQueueEvent::Log {
+                            entry_id: entry_id.clone(),
+                            line: line.clone(),
+                        })
+                        .await;
+                }
+            }
+        });
+        let output = streaming::with_log_lines(log_tx, future).await;
+        let _ = log_task.await;
+        output
+    }
+
     fn package_from_entry(entry: &TaskQueueEntry) -> Package {
         let status = match entry.action {
             TaskQueueAction::Install => PackageStatus::NotInstalled,
@@ -404,11 +1105,13 @@ impl TaskQueueExecutor {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 }
 
-fn transaction_store_path() -> PathBuf {
+/// Location of the transaction journal shared by the queue, TUI, web and CLI.
+pub fn transaction_store_path() -> PathBuf {
     dirs::data_dir()
         .unwrap_or_else(|| PathBuf::from("."))
         .join("linget")
@@ -539,6 +1242,17 @@ impl PackageManager {
             AppImageBackend::is_available(),
             Box::<AppImageBackend>::default(),
         );
+        for plugin in plugin::discover() {
+            let backend = plugin.backend();
+            check_backend(
+                PackageSource::Plugin(plugin.id),
+                backend.is_some(),
+                match backend {
+                    Some(backend) => Box::new(backend),
+                    None => continue,
+                },
+            );
+        }
 
         let enabled_sources = backends.keys().copied().collect();
         info!(
@@ -697,6 +1411,19 @@ impl PackageManager {
         Ok(())
     }
 
+    fn validate_version(version: &str) -> Result<()> {
+        if version.is_empty()
+            || version.starts_with('-')
+            || version.len() > 128
+            || !version.chars().all(|c| {
+                c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '~' | ':' | '_')
+            })
+        {
+            anyhow::bail!("Invalid package version '{}'", version);
+        }
+        Ok(())
+    }
+
     fn enabled_backends(&self) -> impl Iterator<Item = (&PackageSource, &Box<dyn PackageBackend>)> {
         self.backends
             .iter()
@@ -1057,6 +1784,58 @@ impl PackageManager {
         }
     }
 
+    /// Install several packages from one source in a single provider
+    /// invocation.
+    #[instrument(skip(self, packages), fields(count = packages.len()))]
+    pub async fn install_batch(&self, packages: &[Package]) -> Result<()> {
+        let (backend, names) = self.batch_backend(packages, BackendCapability::Install)?;
+        info!(packages = %names.join(" "), "Installing packages");
+        backend.install_batch(&names).await
+    }
+
+    #[instrument(skip(self, packages), fields(count = packages.len()))]
+    pub async fn remove_batch(&self, packages: &[Package]) -> Result<()> {
+        let (backend, names) = self.batch_backend(packages, BackendCapability::Remove)?;
+        info!(packages = %names.join(" "), "Removing packages");
+        backend.remove_batch(&names).await
+    }
+
+    #[instrument(skip(self, packages), fields(count = packages.len()))]
+    pub async fn update_batch(&self, packages: &[Package]) -> Result<()> {
+        let (backend, names) = self.batch_backend(packages, BackendCapability::Update)?;
+        info!(packages = %names.join(" "), "Updating packages");
+        backend.update_batch(&names).await
+    }
+
+    fn batch_backend(
+        &self,
+        packages: &[Package],
+        capability: BackendCapability,
+    ) -> Result<(&dyn PackageBackend, Vec<String>)> {
+        let source = packages
+            .first()
+            .map(|package| package.source)
+            .context("No packages were given")?;
+        for package in packages {
+            if package.source != source {
+                anyhow::bail!("A batched provider invocation cannot mix package sources");
+            }
+            Self::validate_package_name(&package.name)?;
+            self.ensure_package_capability(package, capability)?;
+        }
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Capability check should guarantee backend availability")?;
+        Ok((
+            backend.as_ref(),
+            packages
+                .iter()
+                .map(|package| package.name.clone())
+                .collect(),
+        ))
+    }
+
     pub async fn downgrade(&self, package: &Package) -> Result<()> {
         Self::validate_package_name(&package.name)?;
         self.ensure_package_capability(package, BackendCapability::Downgrade)?;
@@ -1068,7 +1847,6 @@ impl PackageManager {
         backend.downgrade(&package.name).await
     }
 
-    #[allow(dead_code)]
     pub async fn downgrade_to(&self, package: &Package, version: &str) -> Result<()> {
         Self::validate_package_name(&package.name)?;
         self.ensure_package_capability(package, BackendCapability::DowngradeToVersion)?;
@@ -1080,6 +1858,39 @@ impl PackageManager {
         backend.downgrade_to(&package.name, version).await
     }
 
+    /// Install a package that is not installed yet at `version`.
+    pub async fn install_version(&self, package: &Package, version: &str) -> Result<()> {
+        Self::validate_package_name(&package.name)?;
+        Self::validate_version(version)?;
+        self.ensure_package_capability(package, BackendCapability::InstallVersion)?;
+
+        let backend = self
+            .backends
+            .get(&package.source)
+            .context("Versioned install capability check should guarantee backend availability")?;
+        info!(package = %package.name, source = ?package.source, version, "Installing package version");
+        backend.downgrade_to(&package.name, version).await
+    }
+
+    /// Versions the provider can install, newest first, whether or not the
+    /// package is installed. Empty when the provider cannot list them.
+    pub async fn available_versions(&self, package: &Package) -> Result<Vec<String>> {
+        Self::validate_package_name(&package.name)?;
+        let status = self.source_capability_status(
+            package.source,
+            BackendCapability::AvailableDowngradeVersions,
+        );
+        if !status.is_supported() {
+            return Ok(Vec::new());
+        }
+
+        let backend = self
+            .backends
+            .get(&package.source)
+            .context("Version history capability check should guarantee backend availability")?;
+        backend.available_downgrade_versions(&package.name).await
+    }
+
     #[allow(dead_code)]
     pub async fn available_downgrade_versions(&self, package: &Package) -> Result<Vec<String>> {
         Self::validate_package_name(&package.name)?;
@@ -1114,6 +1925,136 @@ impl PackageManager {
         backend.get_reverse_dependencies(&package.name).await
     }
 
+    /// Everything removing `names` from `source` would break or take with it.
+    pub async fn removal_impact(
+        &self,
+        source: PackageSource,
+        names: &[String],
+    ) -> Result<RemovalImpact> {
+        for name in names {
+            Self::validate_package_name(name)?;
+        }
+        self.ensure_source_capability(source, BackendCapability::ReverseDependencies)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Reverse dependency capability check should guarantee backend availability")?;
+        Ok(dependency_graph::removal_impact(backend.as_ref(), names).await)
+    }
+
+    /// Which of `names`, installed from `source`, pruning may remove; see
+    /// `dependency_graph::prunable`.
+    pub async fn prunable(&self, source: PackageSource, names: &[String]) -> Result<Vec<String>> {
+        for name in names {
+            Self::validate_package_name(name)?;
+        }
+        let backend = self
+            .backends
+            .get(&source)
+            .with_context(|| format!("{} is not available", source))?;
+        dependency_graph::prunable(backend.as_ref(), names)
+            .await
+            .with_context(|| format!("{} could not say what the other packages need", source))
+    }
+
+    /// The chains of installed packages that pull `name` in.
+    pub async fn why_installed(&self, source: PackageSource, name: &str) -> Result<WhyInstalled> {
+        Self::validate_package_name(name)?;
+        self.ensure_source_capability(source, BackendCapability::DependencyTree)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Dependency tree capability check should guarantee backend availability")?;
+        Ok(dependency_graph::why_installed(backend.as_ref(), name).await)
+    }
+
+    /// What `name` depends on, down to `max_depth` levels.
+    pub async fn dependency_tree(
+        &self,
+        source: PackageSource,
+        name: &str,
+        max_depth: usize,
+    ) -> Result<DependencyTree> {
+        Self::validate_package_name(name)?;
+        self.ensure_source_capability(source, BackendCapability::DependencyTree)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Dependency tree capability check should guarantee backend availability")?;
+        Ok(dependency_graph::dependency_tree(backend.as_ref(), name, max_depth).await)
+    }
+
+    pub async fn hold(&self, source: PackageSource, name: &str) -> Result<()> {
+        Self::validate_package_name(name)?;
+        self.ensure_source_capability(source, BackendCapability::Hold)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Hold capability check should guarantee backend availability")?;
+        backend.hold(name).await
+    }
+
+    pub async fn unhold(&self, source: PackageSource, name: &str) -> Result<()> {
+        Self::validate_package_name(name)?;
+        self.ensure_source_capability(source, BackendCapability::Hold)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Hold capability check should guarantee backend availability")?;
+        backend.unhold(name).await
+    }
+
+    pub async fn list_holds(&self, source: PackageSource) -> Result<Vec<String>> {
+        self.ensure_source_capability(source, BackendCapability::Hold)?;
+
+        let backend = self
+            .backends
+            .get(&source)
+            .context("Hold capability check should guarantee backend availability")?;
+        backend.list_holds().await
+    }
+
+    /// Held packages across every enabled source that supports holds, keyed
+    /// by `Package::id()`. Sources whose hold listing fails are skipped so one
+    /// missing plugin (e.g. dnf versionlock) does not hide the others.
+    pub async fn list_all_holds(&self) -> HashSet<String> {
+        use futures::future::join_all;
+
+        let futures: Vec<_> = self
+            .enabled_backends()
+            .filter(|(_, backend)| {
+                backend
+                    .capabilities()
+                    .status(BackendCapability::Hold)
+                    .is_supported()
+            })
+            .map(|(source, backend)| {
+                let source = *source;
+                async move {
+                    let holds = timeout(BACKEND_LIST_TIMEOUT, backend.list_holds()).await;
+                    match holds {
+                        Ok(Ok(names)) => names
+                            .into_iter()
+                            .map(|name| format!("{}:{}", source, name))
+                            .collect(),
+                        Ok(Err(error)) => {
+                            debug!(%source, %error, "Failed to list held packages");
+                            Vec::new()
+                        }
+                        Err(_) => Vec::new(),
+                    }
+                }
+            })
+            .collect();
+
+        join_all(futures).await.into_iter().flatten().collect()
+    }
+
     #[allow(dead_code)]
     pub async fn list_repositories(&self, source: PackageSource) -> Result<Vec<Repository>> {
         self.ensure_source_capability(source, BackendCapability::ListRepositories)?;
@@ -1521,6 +2462,22 @@ impl Default for PackageManager {
     }
 }
 
+/// Whether a queued task is planned afresh instead of running its reviewed
+/// plan or bundle, `reviewed_id`. A plain retry is a new transaction and
+/// re-plans. A retry that approves the refreshed plan of a task whose plan
+/// went stale runs exactly that plan, so what the user approved is what
+/// runs; if it drifts again the stale-plan check fails it again.
+fn replans_on_retry(entry: &TaskQueueEntry, reviewed_id: &str) -> Result<bool> {
+    if entry.retry_of.is_none() {
+        return Ok(false);
+    }
+    match entry.approved_stale_operation_id.as_deref() {
+        None => Ok(true),
+        Some(approved) if approved == reviewed_id => Ok(false),
+        Some(_) => anyhow::bail!("The retried task does not carry the plan it approved"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1609,6 +2566,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -1736,6 +2694,44 @@ mod tests {
                 && provider.error.as_deref() == Some("backend unavailable")));
     }
 
+    #[test]
+    fn only_a_stale_plan_approval_retries_its_stored_plan() {
+        use transaction::{OperationAction, OperationRequest, PackageRef, RequestedBy};
+
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![PackageRef {
+                name: "vim".to_string(),
+                source: PackageSource::Apt,
+                installed_version: Some("9.0".to_string()),
+                available_version: Some("9.1".to_string()),
+                pinned: false,
+            }],
+            RequestedBy::Tui,
+        );
+        let refreshed = transaction::build_plan(&request, &[], None);
+        let mut failed = TaskQueueEntry::new(
+            TaskQueueAction::Update,
+            "APT:vim".to_string(),
+            "vim".to_string(),
+            PackageSource::Apt,
+        );
+        failed.reviewed_operation_id = Some(refreshed.operation_id.clone());
+        assert!(!replans_on_retry(&failed, &refreshed.operation_id).unwrap());
+
+        let mut retry = failed.clone();
+        retry.retry_of = Some(failed.id.clone());
+        retry.approved_stale_operation_id = failed.stale_plan_approval();
+        assert!(replans_on_retry(&retry, &refreshed.operation_id).unwrap());
+
+        failed.stale_plan_diff_json = Some("{}".to_string());
+        retry.approved_stale_operation_id = failed.stale_plan_approval();
+        assert!(!replans_on_retry(&retry, &refreshed.operation_id).unwrap());
+
+        retry.approved_stale_operation_id = Some("another-operation".to_string());
+        assert!(replans_on_retry(&retry, &refreshed.operation_id).is_err());
+    }
+
     /// The manager is shared behind an RwLock precisely so that its read-only
     /// operations — listing, update checks, search, transaction planning — can
     /// overlap. Under the exclusive Mutex this used to be, the second reader
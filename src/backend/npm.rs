@@ -1,3 +1,6 @@
+use super::registry::{Registry, Release};
+use super::streaming::{output_cancellable, CommandFailure};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::backend::SUGGEST_PREFIX;
 use crate::models::{Package, PackageEnrichment, PackageSource, PackageStatus};
@@ -5,13 +8,57 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use futures::StreamExt;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::process::Stdio;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::process::Command;
 
+/// How long one `npm ls --all` answer serves dependency queries. A tree walk
+/// asks about every node, and npm takes about a second per listing.
+const DEPENDENCY_GRAPH_TTL: Duration = Duration::from_secs(30);
+
 /// npm backend for managing Node.js packages installed globally via `npm install -g`
 pub struct NpmBackend {
     client: reqwest::Client,
+    graph: tokio::sync::Mutex<Option<(Instant, Arc<NpmGraph>)>>,
+}
+
+/// The global dependency tree flattened by name. npm nests a private copy of
+/// a dependency under each package that needs it, so edges from every copy
+/// are merged.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct NpmGraph {
+    depends: HashMap<String, Vec<String>>,
+    top_level: Vec<String>,
+}
+
+impl NpmGraph {
+    fn from_list(list: &NpmListOutput) -> Self {
+        let mut graph = Self::default();
+        if let Some(dependencies) = &list.dependencies {
+            graph.top_level = dependencies.keys().cloned().collect();
+            graph.top_level.sort();
+            graph.add(dependencies);
+        }
+        graph
+    }
+
+    fn add(&mut self, dependencies: &HashMap<String, NpmPackageInfo>) {
+        for (name, info) in dependencies {
+            let children = info.dependencies.as_ref();
+            let edges = self.depends.entry(name.clone()).or_default();
+            for child in children.into_iter().flat_map(HashMap::keys) {
+                if !edges.contains(child) {
+                    edges.push(child.clone());
+                }
+            }
+            edges.sort();
+            if let Some(children) = children {
+                self.add(children);
+            }
+        }
+    }
 }
 
 impl NpmBackend {
@@ -23,7 +70,40 @@ impl NpmBackend {
             .build()
             .unwrap_or_else(|_| reqwest::Client::new());
 
-        Self { client }
+        Self {
+            client,
+            graph: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    /// The global tree from `npm ls -g --all --json`, reused for
+    /// `DEPENDENCY_GRAPH_TTL`.
+    async fn dependency_graph(&self) -> Result<Arc<NpmGraph>> {
+        let mut cached = self.graph.lock().await;
+        if let Some((fetched, graph)) = cached.as_ref() {
+            if fetched.elapsed() < DEPENDENCY_GRAPH_TTL {
+                return Ok(Arc::clone(graph));
+            }
+        }
+        let output = Command::new("npm")
+            .args(["ls", "-g", "--all", "--json"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list the npm dependency tree")?;
+        // Like `npm list -g --depth=0`, a non-zero exit can still carry a
+        // usable tree.
+        let list: NpmListOutput = serde_json::from_slice(&output.stdout).map_err(|e| {
+            anyhow::anyhow!(
+                "Could not read the global npm dependency tree: {}\n{}",
+                e,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+        })?;
+        let graph = Arc::new(NpmGraph::from_list(&list));
+        *cached = Some((Instant::now(), Arc::clone(&graph)));
+        Ok(graph)
     }
 
     /// Locate npm's content-addressable cache directory.
@@ -63,10 +143,7 @@ impl NpmBackend {
     /// latest-version document is a couple of kilobytes and carries every field
     /// the package list needs.
     async fn fetch_latest_version(&self, name: &str) -> Option<NpmVersionDoc> {
-        let url = format!(
-            "https://registry.npmjs.org/{}/latest",
-            Self::registry_path(name)
-        );
+        let url = Registry::npm().url(&format!("{}/latest", Self::registry_path(name)));
         let resp = self.client.get(&url).send().await.ok()?;
 
         if !resp.status().is_success() {
@@ -81,7 +158,7 @@ impl NpmBackend {
     /// Only used for on-demand, single-package lookups (the changelog view) —
     /// never in a loop over installed packages. See [`Self::fetch_latest_version`].
     async fn fetch_package_info(&self, name: &str) -> Option<NpmRegistryPackage> {
-        let url = format!("https://registry.npmjs.org/{}", Self::registry_path(name));
+        let url = Registry::npm().url(&Self::registry_path(name));
         let resp = self.client.get(&url).send().await.ok()?;
 
         if !resp.status().is_success() {
@@ -100,7 +177,7 @@ impl NpmBackend {
         let docs =
             futures::future::join_all(names.iter().map(|n| self.fetch_latest_version(n))).await;
 
-        for (pkg, doc) in packages.iter_mut().zip(docs.into_iter()) {
+        for (pkg, doc) in packages.iter_mut().zip(docs) {
             if let Some(doc) = doc {
                 Self::apply_metadata(pkg, &doc);
             }
@@ -161,7 +238,7 @@ impl Default for NpmBackend {
 
 #[derive(Debug, Deserialize)]
 struct NpmListOutput {
-    dependencies: Option<std::collections::HashMap<String, NpmPackageInfo>>,
+    dependencies: Option<HashMap<String, NpmPackageInfo>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -169,6 +246,8 @@ struct NpmPackageInfo {
     version: Option<String>,
     #[serde(rename = "resolved")]
     _resolved: Option<String>,
+    /// Present with `--all`.
+    dependencies: Option<HashMap<String, NpmPackageInfo>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -221,7 +300,7 @@ struct NpmDistTags {
 #[derive(Debug, Deserialize)]
 struct NpmTimeInfo {
     #[serde(flatten)]
-    versions: std::collections::HashMap<String, String>,
+    versions: HashMap<String, String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -352,6 +431,7 @@ impl PackageBackend for NpmBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             })
             .collect();
 
@@ -373,34 +453,17 @@ impl PackageBackend for NpmBackend {
             .context("failed to build npm registry client")?;
 
         const CONCURRENCY: usize = 32;
-        let findings: Vec<Option<(String, String, Option<String>)>> = futures::stream::iter(
+        let registry = Registry::npm();
+        let findings: Vec<Option<(String, String, Release)>> = futures::stream::iter(
             installed
                 .into_iter()
                 .filter(|package| !package.name.is_empty()),
         )
         .map(|package| {
-            let client = &client;
-            let name = package.name;
-            let current = package.version;
-            let cache_key = format!("npm:{name}");
+            let (client, registry) = (&client, &registry);
             async move {
-                let latest = if let Some(cached) = super::latest_cache::get(&cache_key) {
-                    cached
-                } else {
-                    let response = client
-                        .get(format!("https://registry.npmjs.org/{name}"))
-                        .send()
-                        .await
-                        .ok()?;
-                    if !response.status().is_success() {
-                        return None;
-                    }
-                    let payload: serde_json::Value = response.json().await.ok()?;
-                    let latest = payload.pointer("/dist-tags/latest")?.as_str()?.to_string();
-                    super::latest_cache::put(cache_key, latest.clone());
-                    latest
-                };
-                Some((name, current, Some(latest)))
+                let release = registry.latest_release(client, &package.name).await?;
+                Some((package.name, package.version, release))
             }
         })
         .buffer_unordered(CONCURRENCY)
@@ -408,17 +471,14 @@ impl PackageBackend for NpmBackend {
         .await;
 
         let mut packages = Vec::new();
-        for (name, current, latest) in findings.into_iter().flatten() {
-            let Some(latest) = latest else {
-                continue;
-            };
-            if latest.is_empty() || latest == current {
+        for (name, current, release) in findings.into_iter().flatten() {
+            if release.version.is_empty() || release.version == current {
                 continue;
             }
             packages.push(Package {
                 name,
                 version: current,
-                available_version: Some(latest),
+                available_version: Some(release.version),
                 description: String::new(),
                 source: PackageSource::Npm,
                 status: PackageStatus::UpdateAvailable,
@@ -430,6 +490,7 @@ impl PackageBackend for NpmBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: release.released_at,
             });
         }
 
@@ -439,11 +500,7 @@ impl PackageBackend for NpmBackend {
     }
 
     async fn install(&self, name: &str) -> Result<()> {
-        let output = Command::new("npm")
-            .args(["install", "-g", name])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
+        let output = output_cancellable(Command::new("npm").args(["install", "-g", name]))
             .await
             .context("Failed to run npm install")?;
 
@@ -457,48 +514,37 @@ impl PackageBackend for NpmBackend {
         // Package not found. Checked before the permission case because npm words
         // a private/unauthorized package as "you do not have access to it", which
         // is not something sudo can fix.
-        if lowered.contains("404") || lowered.contains("e404") {
-            anyhow::bail!(
+        let message = if lowered.contains("404") || lowered.contains("e404") {
+            format!(
                 "Package '{}' not found on npm registry. Check the name and try again.",
                 name
-            );
-        }
-
-        // Permission errors
-        if is_permission_error(&lowered) {
-            anyhow::bail!(
+            )
+        } else if is_permission_error(&lowered) {
+            format!(
                 "Failed to install npm package '{}'.\n\n{} sudo npm install -g {}\n",
-                name,
-                SUGGEST_PREFIX,
-                name
-            );
-        }
-
-        // Network errors
-        if lowered.contains("network")
+                name, SUGGEST_PREFIX, name
+            )
+        } else if lowered.contains("network")
             || lowered.contains("enotfound")
             || lowered.contains("etimedout")
         {
-            anyhow::bail!(
+            format!(
                 "Network error while installing '{}'. Check your internet connection and try again.\n\n{}",
                 name,
                 stderr.lines().take(5).collect::<Vec<_>>().join("\n")
-            );
-        }
-
-        anyhow::bail!(
-            "Failed to install npm package '{}': {}",
-            name,
-            stderr.trim()
-        )
+            )
+        } else {
+            format!(
+                "Failed to install npm package '{}': {}",
+                name,
+                stderr.trim()
+            )
+        };
+        Err(CommandFailure::from_output("npm", &output).with_message(message))
     }
 
     async fn remove(&self, name: &str) -> Result<()> {
-        let output = Command::new("npm")
-            .args(["uninstall", "-g", name])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
+        let output = output_cancellable(Command::new("npm").args(["uninstall", "-g", name]))
             .await
             .context("Failed to run npm uninstall")?;
 
@@ -509,53 +555,60 @@ impl PackageBackend for NpmBackend {
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
         let lowered = stderr.to_lowercase();
 
-        // Permission errors
-        if is_permission_error(&lowered) {
-            anyhow::bail!(
+        let message = if is_permission_error(&lowered) {
+            format!(
                 "Failed to remove npm package '{}'.\n\n{} sudo npm uninstall -g {}\n",
-                name,
-                SUGGEST_PREFIX,
-                name
-            );
-        }
-
-        // Package not installed
-        if lowered.contains("not installed") {
-            anyhow::bail!("Package '{}' is not installed globally.", name);
-        }
-
-        anyhow::bail!("Failed to remove npm package '{}': {}", name, stderr.trim())
+                name, SUGGEST_PREFIX, name
+            )
+        } else if lowered.contains("not installed") {
+            format!("Package '{}' is not installed globally.", name)
+        } else {
+            format!("Failed to remove npm package '{}': {}", name, stderr.trim())
+        };
+        Err(CommandFailure::from_output("npm", &output).with_message(message))
     }
 
     async fn update(&self, name: &str) -> Result<()> {
         // npm update -g doesn't work well for specific packages
         // Use install -g to get the latest version
-        let output = Command::new("npm")
-            .args(["install", "-g", &format!("{}@latest", name)])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .await
-            .context("Failed to run npm install for update")?;
+        let output = output_cancellable(Command::new("npm").args([
+            "install",
+            "-g",
+            &format!("{}@latest", name),
+        ]))
+        .await
+        .context("Failed to run npm install for update")?;
 
         if output.status.success() {
             return Ok(());
         }
 
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        let lowered = stderr.to_lowercase();
-
-        // Permission errors
-        if is_permission_error(&lowered) {
-            anyhow::bail!(
+        let message = if is_permission_error(&stderr.to_lowercase()) {
+            format!(
                 "Failed to update npm package '{}'.\n\n{} sudo npm install -g {}@latest\n",
-                name,
-                SUGGEST_PREFIX,
-                name
-            );
-        }
+                name, SUGGEST_PREFIX, name
+            )
+        } else {
+            format!("Failed to update npm package '{}': {}", name, stderr.trim())
+        };
+        Err(CommandFailure::from_output("npm", &output).with_message(message))
+    }
+
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        run_npm_batch("install", names.to_vec(), "install").await
+    }
+
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        run_npm_batch("uninstall", names.to_vec(), "remove").await
+    }
 
-        anyhow::bail!("Failed to update npm package '{}': {}", name, stderr.trim())
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        let specs = names
+            .iter()
+            .map(|name| format!("{}@latest", name))
+            .collect();
+        run_npm_batch("install", specs, "update").await
     }
 
     async fn downgrade_to(&self, name: &str, version: &str) -> Result<()> {
@@ -573,17 +626,15 @@ impl PackageBackend for NpmBackend {
         }
 
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        let lowered = stderr.to_lowercase();
-        if is_permission_error(&lowered) {
-            anyhow::bail!(
+        let message = if is_permission_error(&stderr.to_lowercase()) {
+            format!(
                 "Failed to install {}.\n\n{} sudo npm install -g {}\n",
-                spec,
-                SUGGEST_PREFIX,
-                spec
-            );
-        }
-
-        anyhow::bail!("Failed to install {}: {}", spec, stderr);
+                spec, SUGGEST_PREFIX, spec
+            )
+        } else {
+            format!("Failed to install {}: {}", spec, stderr)
+        };
+        Err(CommandFailure::from_output("npm", &output).with_message(message))
     }
 
     async fn available_downgrade_versions(&self, name: &str) -> Result<Vec<String>> {
@@ -753,6 +804,7 @@ impl PackageBackend for NpmBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             })
             .collect();
 
@@ -815,6 +867,35 @@ impl PackageBackend for NpmBackend {
         Ok(before.saturating_sub(after))
     }
 
+    async fn get_dependencies(&self, name: &str) -> Result<Vec<String>> {
+        let graph = self.dependency_graph().await?;
+        Ok(graph.depends.get(name).cloned().unwrap_or_default())
+    }
+
+    async fn get_reverse_dependencies(&self, name: &str) -> Result<Vec<String>> {
+        let graph = self.dependency_graph().await?;
+        let mut users: Vec<String> = graph
+            .depends
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|dep| dep == name))
+            .map(|(user, _)| user.clone())
+            .collect();
+        users.sort();
+        Ok(users)
+    }
+
+    /// Everything below the top level of the global tree was installed for
+    /// a global package rather than asked for.
+    async fn list_auto_installed(&self) -> Result<Vec<String>> {
+        let graph = self.dependency_graph().await?;
+        Ok(graph
+            .depends
+            .keys()
+            .filter(|name| !graph.top_level.contains(name))
+            .cloned()
+            .collect())
+    }
+
     fn source(&self) -> PackageSource {
         PackageSource::Npm
     }
@@ -885,6 +966,37 @@ impl PackageBackend for NpmBackend {
 // Helper functions
 // ============================================================================
 
+/// Run one global npm command over several package specs.
+async fn run_npm_batch(subcommand: &str, specs: Vec<String>, verb: &str) -> Result<()> {
+    let output = output_cancellable(Command::new("npm").args([subcommand, "-g"]).args(&specs))
+        .await
+        .with_context(|| format!("Failed to run npm {}", subcommand))?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    let message = if is_permission_error(&stderr.to_lowercase()) {
+        format!(
+            "Failed to {} npm packages {}.\n\n{} sudo npm {} -g {}\n",
+            verb,
+            specs.join(", "),
+            SUGGEST_PREFIX,
+            subcommand,
+            specs.join(" ")
+        )
+    } else {
+        format!(
+            "Failed to {} npm packages {}: {}",
+            verb,
+            specs.join(", "),
+            stderr.trim()
+        )
+    };
+    Err(CommandFailure::from_output("npm", &output).with_message(message))
+}
+
 /// Decide whether npm's stderr describes a filesystem permission problem.
 ///
 /// Matches on npm's error codes and the exact phrasings the OS produces, so
@@ -901,6 +1013,66 @@ fn is_permission_error(lowered_stderr: &str) -> bool {
     .any(|needle| lowered_stderr.contains(needle))
 }
 
+/// Classify an npm failure by its error code.
+///
+/// npm names every failure with a code: the `code` field of the error object
+/// under `--json`, and an `npm ERR! code` (npm 10: `npm error code`) line
+/// otherwise. npm never escalates privileges or takes a lock, so those
+/// classes do not arise here.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let code = npm_error_code(output)?;
+    let class = match code.as_str() {
+        "EACCES" | "EPERM" | "E401" | "E403" | "ENEEDAUTH" | "EOTP" => {
+            ProviderErrorCode::AuthorizationDenied.into()
+        }
+        "E404" | "ETARGET" | "ENOVERSIONS" => ProviderErrorCode::NotFound.into(),
+        "ERESOLVE" | "EOVERRIDE" => ProviderErrorCode::DependencyConflict.into(),
+        "EBADENGINE" | "ENOTSUP" | "EBADPLATFORM" => FailureClass::RUNTIME_MISMATCH,
+        "ENOTFOUND" | "EAI_AGAIN" | "ECONNREFUSED" | "ECONNRESET" | "ENETUNREACH"
+        | "EHOSTUNREACH" => ProviderErrorCode::Network.into(),
+        "ETIMEDOUT" | "ESOCKETTIMEDOUT" | "ERR_SOCKET_TIMEOUT" => ProviderErrorCode::Timeout.into(),
+        "EINTEGRITY" => ProviderErrorCode::SignatureInvalid.into(),
+        "ENOSPC" => ProviderErrorCode::DiskFull.into(),
+        "ABORT_ERR" => ProviderErrorCode::Interrupted.into(),
+        server if server.len() == 4 && server.starts_with("E5") => {
+            ProviderErrorCode::RepositoryUnavailable.into()
+        }
+        // A lifecycle script killed by a signal reports ELIFECYCLE and the
+        // signal on a line of its own.
+        _ if npm_error_field(output, "signal").is_some() => ProviderErrorCode::Interrupted.into(),
+        _ => ProviderErrorCode::Unknown.into(),
+    };
+    Some(class)
+}
+
+/// The npm error code, from `--json` error output or from npm's log lines.
+fn npm_error_code(output: &str) -> Option<String> {
+    let json = output
+        .find('{')
+        .zip(output.rfind('}'))
+        .and_then(|(start, end)| output.get(start..=end))
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok());
+    if let Some(code) = json
+        .as_ref()
+        .and_then(|value| value.pointer("/error/code"))
+        .and_then(serde_json::Value::as_str)
+    {
+        return Some(code.to_string());
+    }
+    npm_error_field(output, "code").map(str::to_string)
+}
+
+fn npm_error_field<'a>(output: &'a str, field: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let rest = line
+            .trim()
+            .strip_prefix("npm ERR! ")
+            .or_else(|| line.trim().strip_prefix("npm error "))?;
+        let value = rest.strip_prefix(field)?.strip_prefix(' ')?.trim();
+        (!value.is_empty()).then_some(value)
+    })
+}
+
 /// Format download count for display
 #[allow(dead_code)]
 fn format_downloads(count: u64) -> String {
@@ -921,6 +1093,28 @@ fn format_downloads(count: u64) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn npm_ls_all_output_flattens_into_a_graph() {
+        let list: NpmListOutput = serde_json::from_str(
+            r#"{"dependencies": {
+                "eslint": {"version": "9.0.0", "dependencies": {
+                    "debug": {"version": "4.3.4", "dependencies": {"ms": {"version": "2.1.2"}}},
+                    "chalk": {"version": "4.1.2"}
+                }},
+                "nodemon": {"version": "3.1.0", "dependencies": {
+                    "debug": {"version": "4.3.4", "dependencies": {"ms": {"version": "2.1.3"}}}
+                }}
+            }}"#,
+        )
+        .expect("npm ls output");
+        let graph = NpmGraph::from_list(&list);
+
+        assert_eq!(graph.top_level, ["eslint", "nodemon"]);
+        assert_eq!(graph.depends["eslint"], ["chalk", "debug"]);
+        assert_eq!(graph.depends["debug"], ["ms"]);
+        assert!(graph.depends["ms"].is_empty());
+    }
+
     #[test]
     fn test_npm_author_parsing() {
         // Object format
@@ -1040,6 +1234,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         };
 
         NpmBackend::apply_metadata(&mut pkg, &doc);
@@ -1,3 +1,5 @@
+use super::dependency_graph::{ProtectedPackage, ProtectionReason};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::{run_pkexec, Suggest};
 use crate::models::{Package, PackageSource, PackageStatus};
@@ -6,6 +8,8 @@ use async_trait::async_trait;
 use std::process::Stdio;
 use tokio::process::Command;
 
+const PACMAN_CONF: &str = "/etc/pacman.conf";
+
 /// Parse human-readable size strings like "1.2 GiB", "500 MiB", "100 KiB"
 fn parse_human_size(s: &str) -> Option<u64> {
     let s = s.trim();
@@ -30,6 +34,225 @@ fn parse_human_size(s: &str) -> Option<u64> {
     Some((num * multiplier as f64) as u64)
 }
 
+/// Names on the `Depends On` line of `pacman -Qi`, without version
+/// constraints (`libcap>=2.0`) or descriptions.
+fn depends_on(info: &str) -> Vec<String> {
+    let Some(value) = info.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        (key.trim() == "Depends On").then_some(value.trim())
+    }) else {
+        return Vec::new();
+    };
+    if value == "None" {
+        return Vec::new();
+    }
+    value
+        .split_whitespace()
+        .map(|dep| dep.split(['<', '>', '=']).next().unwrap_or(dep).to_string())
+        .filter(|dep| !dep.is_empty())
+        .collect()
+}
+
+fn is_options_header(line: &str) -> bool {
+    line.trim().eq_ignore_ascii_case("[options]")
+}
+
+fn ignore_pkg_values(line: &str) -> Option<Vec<&str>> {
+    option_values(line, "IgnorePkg")
+}
+
+fn option_values<'a>(line: &'a str, option: &str) -> Option<Vec<&'a str>> {
+    let (key, value) = line.split_once('=')?;
+    (key.trim() == option).then(|| value.split_whitespace().collect())
+}
+
+/// Packages listed on active `IgnorePkg` lines of the `[options]` section.
+fn ignored_packages(conf: &str) -> Vec<String> {
+    options_list(conf, "IgnorePkg")
+}
+
+/// Packages listed on active `HoldPkg` lines, which pacman refuses to remove
+/// without a second confirmation.
+fn held_packages(conf: &str) -> Vec<String> {
+    options_list(conf, "HoldPkg")
+}
+
+/// Values of every active `option` line in the `[options]` section.
+fn options_list(conf: &str, option: &str) -> Vec<String> {
+    let mut in_options = false;
+    let mut names = Vec::new();
+    for line in conf.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_options = is_options_header(trimmed);
+            continue;
+        }
+        if !in_options {
+            continue;
+        }
+        if let Some(values) = option_values(trimmed, option) {
+            names.extend(values.into_iter().map(ToString::to_string));
+        }
+    }
+    names
+}
+
+/// One line of `pacman.conf` that holding or releasing a package changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConfEdit {
+    /// Replace the `IgnorePkg` line `old`, or delete it when `new` is `None`.
+    Replace { old: String, new: Option<String> },
+    /// Insert `line` right after the `[options]` header `header`.
+    Insert { header: String, line: String },
+}
+
+/// The line edits that make `name` ignored (or no longer ignored). Adding
+/// extends the first active `IgnorePkg` line, or inserts one right after
+/// `[options]`; removing drops the name from every line and deletes lines
+/// left empty. `None` when there is no `[options]` section to edit.
+fn ignore_edits(conf: &str, name: &str, ignored: bool) -> Option<Vec<ConfEdit>> {
+    let mut in_options = false;
+    let mut header = None;
+    let mut edits = Vec::new();
+    for line in conf.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_options = is_options_header(trimmed);
+            if in_options && header.is_none() {
+                header = Some(line.to_string());
+            }
+            continue;
+        }
+        let Some(values) = ignore_pkg_values(trimmed).filter(|_| in_options) else {
+            continue;
+        };
+        if ignored {
+            if edits.is_empty() {
+                edits.push(ConfEdit::Replace {
+                    old: line.to_string(),
+                    new: Some(format!("{} {}", line.trim_end(), name)),
+                });
+            }
+        } else if values.contains(&name) {
+            let rest: Vec<&str> = values.into_iter().filter(|value| *value != name).collect();
+            edits.push(ConfEdit::Replace {
+                old: line.to_string(),
+                new: (!rest.is_empty()).then(|| format!("IgnorePkg = {}", rest.join(" "))),
+            });
+        }
+    }
+    let header = header?;
+    if ignored && edits.is_empty() {
+        edits.push(ConfEdit::Insert {
+            header,
+            line: format!("IgnorePkg = {}", name),
+        });
+    }
+    Some(edits)
+}
+
+/// `sed` expressions applying `edits` to the `[options]` section. Each one
+/// matches its line by content, so lines edited by someone else since
+/// `pacman.conf` was read are left alone.
+fn sed_expressions(edits: &[ConfEdit]) -> Vec<String> {
+    fn pattern(line: &str) -> String {
+        let mut escaped = String::from("^");
+        for character in line.chars() {
+            if matches!(character, '\\' | '/' | '.' | '*' | '[' | ']' | '^' | '$') {
+                escaped.push('\\');
+            }
+            escaped.push(character);
+        }
+        escaped.push('$');
+        escaped
+    }
+    fn replacement(line: &str) -> String {
+        line.replace('\\', "\\\\")
+            .replace('/', "\\/")
+            .replace('&', "\\&")
+    }
+    const OPTIONS: &str = "/^[[:space:]]*\\[options\\]/,/^[[:space:]]*\\[/";
+    edits
+        .iter()
+        .map(|edit| match edit {
+            ConfEdit::Replace {
+                old,
+                new: Some(new),
+            } => {
+                format!("{}s/{}/{}/", OPTIONS, pattern(old), replacement(new))
+            }
+            ConfEdit::Replace { old, new: None } => format!("{}{{/{}/d}}", OPTIONS, pattern(old)),
+            ConfEdit::Insert { header, line } => {
+                format!("/{}/a {}", pattern(header), line)
+            }
+        })
+        .collect()
+}
+
+/// Apply `edits` to `path` in place. `sed -i` writes a sibling file and
+/// renames it over the original, so readers never see a partial file and
+/// only the edited lines differ from what is on disk at that moment.
+async fn edit_pacman_conf(path: &str, edits: &[ConfEdit], privileged: bool) -> Result<()> {
+    let mut args = vec!["sed".to_string(), "-i".to_string()];
+    for expression in sed_expressions(edits) {
+        args.push("-e".to_string());
+        args.push(expression);
+    }
+    args.push(path.to_string());
+    let (program, args) = if privileged {
+        ("pkexec", &args[..])
+    } else {
+        ("sed", &args[1..])
+    };
+    let output = Command::new(program)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .with_context(|| format!("Failed to spawn {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to update {}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+async fn set_pacman_hold(name: &str, held: bool) -> Result<()> {
+    let conf = tokio::fs::read_to_string(PACMAN_CONF)
+        .await
+        .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+    if ignored_packages(&conf)
+        .iter()
+        .any(|ignored| ignored == name)
+        == held
+    {
+        return Ok(());
+    }
+    let Some(edits) = ignore_edits(&conf, name, held) else {
+        anyhow::bail!("{} has no [options] section", PACMAN_CONF);
+    };
+    edit_pacman_conf(PACMAN_CONF, &edits, true).await?;
+
+    let conf = tokio::fs::read_to_string(PACMAN_CONF)
+        .await
+        .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+    if ignored_packages(&conf)
+        .iter()
+        .any(|ignored| ignored == name)
+        != held
+    {
+        anyhow::bail!(
+            "{} changed while LinGet was editing it; try again",
+            PACMAN_CONF
+        );
+    }
+    Ok(())
+}
+
 pub struct PacmanBackend;
 
 impl PacmanBackend {
@@ -84,6 +307,7 @@ impl PackageBackend for PacmanBackend {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         };
 
         let mut has_data = false;
@@ -108,6 +332,7 @@ impl PackageBackend for PacmanBackend {
                         install_date: None,
                         update_category: None,
                         enrichment: None,
+                        released_at: None,
                     };
                 }
                 continue;
@@ -200,6 +425,7 @@ impl PackageBackend for PacmanBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -288,6 +514,7 @@ impl PackageBackend for PacmanBackend {
                         install_date: None,
                         update_category: None,
                         enrichment: None,
+                        released_at: None,
                     });
                     i += 2;
                     continue;
@@ -353,6 +580,7 @@ impl PackageBackend for PacmanBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -421,9 +649,140 @@ impl PackageBackend for PacmanBackend {
         Ok(deps)
     }
 
+    async fn get_dependencies(&self, name: &str) -> Result<Vec<String>> {
+        let output = Command::new("pacman")
+            .args(["-Qi", name])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to get package info")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "pacman -Qi failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(depends_on(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    async fn list_auto_installed(&self) -> Result<Vec<String>> {
+        let output = Command::new("pacman")
+            .arg("-Qqd")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list dependency packages")?;
+        // pacman exits 1 when no package matches the filter.
+        if !output.status.success() && !output.stderr.is_empty() {
+            anyhow::bail!(
+                "pacman -Qqd failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    async fn list_protected(&self) -> Result<Vec<ProtectedPackage>> {
+        let conf = tokio::fs::read_to_string(PACMAN_CONF)
+            .await
+            .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+        Ok(held_packages(&conf)
+            .into_iter()
+            .map(|name| ProtectedPackage {
+                name,
+                reason: ProtectionReason::Protected,
+            })
+            .collect())
+    }
+
     fn source(&self) -> PackageSource {
         PackageSource::Pacman
     }
+
+    async fn hold(&self, name: &str) -> Result<()> {
+        set_pacman_hold(name, true).await
+    }
+
+    async fn unhold(&self, name: &str) -> Result<()> {
+        set_pacman_hold(name, false).await
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        let conf = tokio::fs::read_to_string(PACMAN_CONF)
+            .await
+            .with_context(|| format!("Failed to read {}", PACMAN_CONF))?;
+        Ok(ignored_packages(&conf))
+    }
+}
+
+/// Classify a pacman failure from its `error:` lines.
+///
+/// pacman exits 1 for every error, so only the messages say what went wrong.
+/// Causes are checked most specific first: a download failure is reported
+/// above the "failed to synchronize all databases" or "failed to commit
+/// transaction" summary it leads to.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.contains("Interrupt signal received")) {
+        ProviderErrorCode::Interrupted
+    } else if any(&|line| line.contains("you cannot perform this operation unless you are root")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.contains("(unable to lock database)")
+            || line.starts_with("error: could not lock database")
+    }) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| line.starts_with("error: target not found:")) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| {
+        line.contains("(could not satisfy dependencies)")
+            || line.contains("(conflicting dependencies)")
+            || line.contains("(conflicting files)")
+            || line.starts_with(":: unable to satisfy dependency")
+    }) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.contains("not enough free disk space")
+            || (line.starts_with("error: Partition") && line.contains("too full"))
+            || line.contains("No space left on device")
+    }) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| {
+        (line.contains("signature from")
+            && (line.contains("is invalid") || line.contains("is unknown trust")))
+            || line.contains("(PGP signature)")
+            || line.contains("(invalid or corrupted package")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| {
+        line.starts_with("error: failed retrieving file")
+            && (line.contains("Operation too slow") || line.contains("timed out"))
+    }) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| {
+        line.starts_with("error: failed retrieving file")
+            && (line.contains("Could not resolve host") || line.contains("Failed to connect"))
+    }) {
+        ProviderErrorCode::Network
+    } else if any(&|line| {
+        line.contains("The requested URL returned error")
+            || line.starts_with("error: failed to synchronize all databases")
+    }) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| line.starts_with("error:")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
 }
 
 #[cfg(test)]
@@ -437,6 +796,67 @@ mod tests {
         assert_eq!(parse_human_size("1 GiB"), Some(1_073_741_824));
     }
 
+    #[tokio::test]
+    async fn ignore_pkg_edits_only_touch_the_options_section() {
+        let conf = "[options]\n#IgnorePkg   =\nHoldPkg = pacman glibc\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+        let path = std::env::temp_dir().join(format!("linget-pacman-{}.conf", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, conf).unwrap();
+        let edit = |name: &'static str, held: bool| async move {
+            let current = std::fs::read_to_string(path_str).unwrap();
+            let edits = ignore_edits(&current, name, held).unwrap();
+            edit_pacman_conf(path_str, &edits, false).await.unwrap();
+            std::fs::read_to_string(path_str).unwrap()
+        };
+
+        let held = edit("linux", true).await;
+        assert_eq!(
+            held,
+            "[options]\nIgnorePkg = linux\n#IgnorePkg   =\nHoldPkg = pacman glibc\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n"
+        );
+        assert_eq!(ignored_packages(&held), vec!["linux"]);
+
+        let both = edit("mesa", true).await;
+        assert_eq!(ignored_packages(&both), vec!["linux", "mesa"]);
+        assert_eq!(
+            ignore_edits(&both, "linux", false),
+            Some(vec![ConfEdit::Replace {
+                old: "IgnorePkg = linux mesa".to_string(),
+                new: Some("IgnorePkg = mesa".to_string()),
+            }])
+        );
+
+        // A line edited since the conf was read is not overwritten.
+        let stale = ignore_edits(&both, "linux", false).unwrap();
+        std::fs::write(&path, both.replace("linux mesa", "linux mesa vim")).unwrap();
+        edit_pacman_conf(path_str, &stale, false).await.unwrap();
+        assert_eq!(
+            ignored_packages(&std::fs::read_to_string(&path).unwrap()),
+            vec!["linux", "mesa", "vim"]
+        );
+        std::fs::write(&path, &both).unwrap();
+
+        let released = edit("linux", false).await;
+        assert_eq!(ignored_packages(&released), vec!["mesa"]);
+        assert_eq!(edit("mesa", false).await, conf);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(ignore_edits("[core]\n", "linux", true), None);
+    }
+
+    #[test]
+    fn depends_on_strips_version_constraints() {
+        let info = "Name            : git\nDepends On      : curl  expat  perl-error>=0.17  zlib-ng=2.2\nRequired By     : git-lfs\n";
+        assert_eq!(depends_on(info), ["curl", "expat", "perl-error", "zlib-ng"]);
+        assert!(depends_on("Depends On      : None\n").is_empty());
+    }
+
+    #[test]
+    fn hold_pkg_lines_name_protected_packages() {
+        let conf =
+            "[options]\nHoldPkg     = pacman glibc\n#HoldPkg = ignored\n[core]\nHoldPkg = nope\n";
+        assert_eq!(held_packages(conf), ["pacman", "glibc"]);
+    }
+
     #[test]
     fn parse_human_size_rejects_unknown_units() {
         assert_eq!(parse_human_size("42"), None);
@@ -1,4 +1,6 @@
-use super::streaming::{run_streaming, StreamLine};
+use super::registry::{Registry, Release};
+use super::streaming::{output_cancellable, run_streaming, StreamLine};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::backend::SUGGEST_PREFIX;
 use crate::models::{Package, PackageSource, PackageStatus};
@@ -29,11 +31,7 @@ impl PipBackend {
 
     async fn run_pip_action(args: &[&str], context_msg: &str) -> Result<()> {
         let pip = Self::get_pip_command();
-        let output = Command::new(pip)
-            .args(args)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
+        let output = output_cancellable(Command::new(pip).args(args))
             .await
             .with_context(|| context_msg.to_string())?;
 
@@ -158,6 +156,7 @@ impl PackageBackend for PipBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -191,7 +190,8 @@ impl PackageBackend for PipBackend {
         // connection burst gets CDN-throttled and is slower. Over HTTP/2
         // (one multiplexed connection) a high stream count is cheap.
         const CONCURRENCY: usize = 64;
-        let findings: Vec<Option<(String, String, String, String)>> =
+        let registry = Registry::pypi();
+        let findings: Vec<Option<(String, String, Release)>> =
             futures::stream::iter(installed.into_iter().filter_map(|package| {
                 if package.version.is_empty() {
                     return None;
@@ -199,29 +199,10 @@ impl PackageBackend for PipBackend {
                 Some((package.name, package.version))
             }))
             .map(|(name, version)| {
-                let client = &client;
-                let cache_key = format!("pypi:{name}");
+                let (client, registry) = (&client, &registry);
                 async move {
-                    if let Some(latest) = super::latest_cache::get(&cache_key) {
-                        return Some((name, version, latest, String::new()));
-                    }
-                    let response = client
-                        .get(format!("https://pypi.org/pypi/{name}/json"))
-                        .send()
-                        .await
-                        .ok()?;
-                    if !response.status().is_success() {
-                        return None;
-                    }
-                    let payload: serde_json::Value = response.json().await.ok()?;
-                    let latest = payload.pointer("/info/version")?.as_str()?.to_string();
-                    let summary = payload
-                        .pointer("/info/summary")
-                        .and_then(|value| value.as_str())
-                        .unwrap_or("")
-                        .to_string();
-                    super::latest_cache::put(cache_key, latest.clone());
-                    Some((name, version, latest, summary))
+                    let release = registry.latest_release(client, &name).await?;
+                    Some((name, version, release))
                 }
             })
             .buffer_unordered(CONCURRENCY)
@@ -229,15 +210,15 @@ impl PackageBackend for PipBackend {
             .await;
 
         let mut packages = Vec::new();
-        for (name, version, latest, summary) in findings.into_iter().flatten() {
-            if version == latest {
+        for (name, version, release) in findings.into_iter().flatten() {
+            if version == release.version {
                 continue;
             }
             packages.push(Package {
                 name,
                 version,
-                available_version: Some(latest),
-                description: summary,
+                available_version: Some(release.version),
+                description: release.summary.unwrap_or_default(),
                 source: PackageSource::Pip,
                 status: PackageStatus::UpdateAvailable,
                 size: None,
@@ -248,6 +229,7 @@ impl PackageBackend for PipBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: release.released_at,
             });
         }
         Ok(packages)
@@ -370,7 +352,7 @@ impl PackageBackend for PipBackend {
             .build()
             .context("Failed to create HTTP client")?;
 
-        let url = format!("https://pypi.org/pypi/{}/json", name);
+        let url = Registry::pypi().url(&format!("pypi/{}/json", name));
         let resp = client.get(&url).send().await;
 
         match resp {
@@ -430,7 +412,7 @@ impl PackageBackend for PipBackend {
             .build()
             .context("Failed to create HTTP client")?;
 
-        let url = format!("https://pypi.org/pypi/{}/json", query);
+        let url = Registry::pypi().url(&format!("pypi/{}/json", query));
         if let Ok(resp) = client.get(&url).send().await {
             if resp.status().is_success() {
                 if let Ok(json) = resp.json::<serde_json::Value>().await {
@@ -482,6 +464,7 @@ impl PackageBackend for PipBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         });
                     }
                 }
@@ -491,7 +474,7 @@ impl PackageBackend for PipBackend {
         if packages.is_empty() {
             let query_lower = query.to_lowercase();
             if query_lower != query {
-                let url = format!("https://pypi.org/pypi/{}/json", query_lower);
+                let url = Registry::pypi().url(&format!("pypi/{}/json", query_lower));
                 if let Ok(resp) = client.get(&url).send().await {
                     if resp.status().is_success() {
                         if let Ok(json) = resp.json::<serde_json::Value>().await {
@@ -527,6 +510,7 @@ impl PackageBackend for PipBackend {
                                     install_date: None,
                                     update_category: None,
                                     enrichment: None,
+                                    released_at: None,
                                 });
                             }
                         }
@@ -694,6 +678,59 @@ impl PipBackend {
     }
 }
 
+/// Classify a pip failure from its `ERROR:` lines and the exceptions it
+/// reports.
+///
+/// pip exits 1 for nearly every error. When the index cannot be reached it
+/// retries, then reports "No matching distribution found" as if the package
+/// did not exist, so network causes are checked before a missing package.
+/// pip runs as the user and takes no lock, so those classes do not arise.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let class = if any(&|line| line.starts_with("ERROR: Operation cancelled by user")) {
+        ProviderErrorCode::Interrupted.into()
+    } else if any(&|line| {
+        line.contains("ReadTimeoutError")
+            || line.contains("ConnectTimeoutError")
+            || line.contains("Read timed out")
+    }) {
+        ProviderErrorCode::Timeout.into()
+    } else if any(&|line| {
+        line.contains("NewConnectionError")
+            || line.contains("Failed to establish a new connection")
+            || line.contains("Temporary failure in name resolution")
+    }) {
+        ProviderErrorCode::Network.into()
+    } else if any(&|line| {
+        line.contains("DO NOT MATCH THE HASHES") || line.contains("CERTIFICATE_VERIFY_FAILED")
+    }) {
+        ProviderErrorCode::SignatureInvalid.into()
+    } else if any(&|line| line.contains("HTTP error 5") || line.contains("Server Error:")) {
+        ProviderErrorCode::RepositoryUnavailable.into()
+    } else if any(&|line| line.contains("[Errno 28]")) {
+        ProviderErrorCode::DiskFull.into()
+    } else if any(&|line| line.contains("[Errno 13]")) {
+        ProviderErrorCode::AuthorizationDenied.into()
+    } else if any(&|line| {
+        line.contains("ResolutionImpossible") || line.contains("conflicting dependencies")
+    }) {
+        ProviderErrorCode::DependencyConflict.into()
+    } else if any(&|line| line.contains("require a different python version")) {
+        FailureClass::RUNTIME_MISMATCH
+    } else if any(&|line| {
+        line.starts_with("ERROR: No matching distribution found")
+            || line.starts_with("ERROR: Could not find a version that satisfies")
+    }) {
+        ProviderErrorCode::NotFound.into()
+    } else if any(&|line| line.starts_with("ERROR:") || line.starts_with("error:")) {
+        ProviderErrorCode::Unknown.into()
+    } else {
+        return None;
+    };
+    Some(class)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,3 +1,6 @@
+use super::registry::Registry;
+use super::streaming::CommandFailure;
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
@@ -91,6 +94,7 @@ impl PackageBackend for PipxBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -117,47 +121,37 @@ impl PackageBackend for PipxBackend {
             .context("Failed to create HTTP client")?;
 
         let mut packages_with_updates = Vec::new();
+        let registry = Registry::pypi();
 
         // Check each package against PyPI API
         for pkg in installed {
-            let url = format!("https://pypi.org/pypi/{}/json", pkg.name);
-            match client.get(&url).send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    if let Ok(json) = resp.json::<serde_json::Value>().await {
-                        if let Some(latest_version) = json
-                            .get("info")
-                            .and_then(|i| i.get("version"))
-                            .and_then(|v| v.as_str())
-                        {
-                            // Compare versions - if different and newer, there's an update
-                            if !pkg.version.is_empty()
-                                && latest_version != pkg.version
-                                && Self::is_newer_version(latest_version, &pkg.version)
-                            {
-                                packages_with_updates.push(Package {
-                                    name: pkg.name,
-                                    version: pkg.version,
-                                    available_version: Some(latest_version.to_string()),
-                                    description: String::new(),
-                                    source: PackageSource::Pipx,
-                                    status: PackageStatus::UpdateAvailable,
-                                    size: None,
-                                    homepage: None,
-                                    license: None,
-                                    maintainer: None,
-                                    dependencies: Vec::new(),
-                                    install_date: None,
-                                    update_category: None,
-                                    enrichment: None,
-                                });
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    // Skip packages that fail to fetch - might be private or renamed
-                    tracing::debug!("Failed to check updates for pipx package: {}", pkg.name);
-                }
+            let Some(release) = registry.latest_release(&client, &pkg.name).await else {
+                // Skip packages that fail to fetch - might be private or renamed
+                tracing::debug!("Failed to check updates for pipx package: {}", pkg.name);
+                continue;
+            };
+            // Compare versions - if different and newer, there's an update
+            if !pkg.version.is_empty()
+                && release.version != pkg.version
+                && Self::is_newer_version(&release.version, &pkg.version)
+            {
+                packages_with_updates.push(Package {
+                    name: pkg.name,
+                    version: pkg.version,
+                    available_version: Some(release.version),
+                    description: String::new(),
+                    source: PackageSource::Pipx,
+                    status: PackageStatus::UpdateAvailable,
+                    size: None,
+                    homepage: None,
+                    license: None,
+                    maintainer: None,
+                    dependencies: Vec::new(),
+                    install_date: None,
+                    update_category: None,
+                    enrichment: None,
+                    released_at: release.released_at,
+                });
             }
         }
 
@@ -165,56 +159,64 @@ impl PackageBackend for PipxBackend {
     }
 
     async fn install(&self, name: &str) -> Result<()> {
-        let status = Command::new("pipx")
+        let output = Command::new("pipx")
             .args(["install", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install pipx package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install pipx package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("pipx", &output)
+            .with_message(format!("Failed to install pipx package {}", name)))
     }
 
     async fn remove(&self, name: &str) -> Result<()> {
-        let status = Command::new("pipx")
+        let output = Command::new("pipx")
             .args(["uninstall", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to uninstall pipx package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to uninstall pipx package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("pipx", &output)
+            .with_message(format!("Failed to uninstall pipx package {}", name)))
     }
 
     async fn update(&self, name: &str) -> Result<()> {
-        let status = Command::new("pipx")
+        let output = Command::new("pipx")
             .args(["upgrade", name])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to update pipx package")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to update pipx package {}", name)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("pipx", &output)
+            .with_message(format!("Failed to update pipx package {}", name)))
     }
 
     async fn downgrade_to(&self, name: &str, version: &str) -> Result<()> {
         let spec = format!("{}=={}", name, version);
-        let status = Command::new("pipx")
+        let output = Command::new("pipx")
             .args(["install", "--force", &spec])
-            .status()
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
             .await
             .context("Failed to install a specific pipx package version")?;
-        if status.success() {
-            Ok(())
-        } else {
-            anyhow::bail!("Failed to install {} via pipx", spec)
+        if output.status.success() {
+            return Ok(());
         }
+        Err(CommandFailure::from_output("pipx", &output)
+            .with_message(format!("Failed to install {} via pipx", spec)))
     }
 
     async fn search(&self, query: &str) -> Result<Vec<Package>> {
@@ -223,7 +225,7 @@ impl PackageBackend for PipxBackend {
             .build()
             .context("Failed to create HTTP client")?;
 
-        let url = format!("https://pypi.org/pypi/{}/json", query);
+        let url = Registry::pypi().url(&format!("pypi/{}/json", query));
         let resp = client.get(&url).send().await;
 
         match resp {
@@ -273,6 +275,7 @@ impl PackageBackend for PipxBackend {
                             install_date: None,
                             update_category: None,
                             enrichment: None,
+                            released_at: None,
                         }]);
                     }
                 }
@@ -307,6 +310,22 @@ impl PackageBackend for PipxBackend {
     }
 }
 
+/// Classify a pipx failure.
+///
+/// pipx reports a package it does not manage in its own words; anything else
+/// failed inside the pip run it wraps, whose output pipx passes on.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let not_installed = output.lines().map(str::trim).any(|line| {
+        line.starts_with("Nothing to uninstall for")
+            || line.starts_with("Package is not installed.")
+            || (line.starts_with("Package ") && line.ends_with("is not installed."))
+    });
+    if not_installed {
+        return Some(ProviderErrorCode::NotFound.into());
+    }
+    super::pip::classify_failure(exit_code, output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
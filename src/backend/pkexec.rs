@@ -1,4 +1,5 @@
-use super::streaming::{run_streaming, StreamLine};
+use super::streaming::{run_streaming, CommandFailure, StreamLine};
+use super::transaction::{FailureClass, ProviderErrorCode};
 use anyhow::{Context, Result};
 use once_cell::sync::OnceCell;
 use std::io::{self, IsTerminal};
@@ -225,6 +226,28 @@ fn detect_auth_error(stderr: &str, exit_code: Option<i32>) -> AuthErrorKind {
     AuthErrorKind::Unknown
 }
 
+/// Recognise failures reported by pkexec itself rather than by the program it
+/// ran. pkexec exits 126 when the authorization dialog is dismissed and 127
+/// when authorization fails; any other status belongs to the program.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let pkexec_error = output
+        .lines()
+        .map(str::trim)
+        .find_map(|line| line.strip_prefix("Error executing command as another user:"));
+    let code = match (exit_code, pkexec_error) {
+        (_, Some(error)) if error.contains("No authentication agent found") => {
+            ProviderErrorCode::NoPrivilegeAgent
+        }
+        (Some(126), _) => ProviderErrorCode::AuthorizationCancelled,
+        (_, Some(error)) if error.contains("Request dismissed") => {
+            ProviderErrorCode::AuthorizationCancelled
+        }
+        (Some(127), _) | (_, Some(_)) => ProviderErrorCode::AuthorizationDenied,
+        _ => return None,
+    };
+    Some(code.into())
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum AuthErrorKind {
     Cancelled,
@@ -373,7 +396,29 @@ pub async fn run_pkexec(
         }
     }
 
-    anyhow::bail!("{}\n\n{} {}\n", msg, SUGGEST_PREFIX, suggest.command);
+    Err(
+        CommandFailure::new(program, exit_code, stderr).with_message(format!(
+            "{}\n\n{} {}\n",
+            msg, SUGGEST_PREFIX, suggest.command
+        )),
+    )
+}
+
+/// Run a command with pkexec and capture its output, for privileged helpers
+/// whose stdout LinGet needs to read.
+pub async fn pkexec_output(program: &str, args: &[&str]) -> io::Result<std::process::Output> {
+    debug!(command = %program, args = ?args, "Executing privileged command for output");
+    let mut tty_agent = TtyAgent::register().await;
+    let output = Command::new("pkexec")
+        .arg(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .await;
+    if let Some(agent) = tty_agent.as_mut() {
+        agent.stop().await;
+    }
+    output
 }
 
 pub async fn run_pkexec_with_logs(
@@ -547,7 +592,12 @@ pub async fn run_pkexec_with_logs(
         }
     }
 
-    anyhow::bail!("{}\n\n{} {}\n", msg, SUGGEST_PREFIX, suggest.command);
+    Err(
+        CommandFailure::new(program, exit_code, stderr).with_message(format!(
+            "{}\n\n{} {}\n",
+            msg, SUGGEST_PREFIX, suggest.command
+        )),
+    )
 }
 
 #[cfg(test)]
@@ -0,0 +1,1011 @@
+//! External providers that speak LinGet's plugin protocol.
+//!
+//! A plugin is any executable named `linget-provider-<name>` in the plugins
+//! directory or on PATH. LinGet starts it once per request, writes a single
+//! JSON request line to its stdin and reads JSON lines from its stdout until
+//! the plugin answers with `{"result": ...}` or `{"error": ...}`. Earlier
+//! `{"log": "..."}` lines, and anything on stderr, go to the task log.
+//!
+//! Discovery sends `handshake` first. The answer names the protocol version
+//! the plugin speaks, the methods it implements and how LinGet should treat
+//! its operations, so a plugin shows up in the catalog and the queue with
+//! the same plan fidelity and cancellation labels as a built-in provider.
+
+use super::providers::ProviderStatus;
+use super::streaming::{
+    isolate_when_cancellable, strip_ansi, supervise, CommandFailure, StreamLine,
+};
+use super::traits::{BackendCapability, CapabilityStatus};
+use super::transaction::{
+    CancellationSupport, OperationAction, PackageChange, PackageRef, PlanFidelity,
+    PrivilegeRequirement, ProviderDescriptor, ProviderError, ProviderErrorCode, ProviderTier,
+    RollbackCapability,
+};
+use super::PackageBackend;
+use crate::models::{Package, PackageSource, PackageStatus, PluginId};
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// The protocol version this LinGet speaks. A plugin answering the handshake
+/// with another version is listed as unavailable.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Executables named with this prefix are plugins; the rest is their name.
+pub const EXECUTABLE_PREFIX: &str = "linget-provider-";
+
+/// A plugin that does not finish its handshake in time is not loaded.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The requests a plugin can declare in its handshake.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginMethod {
+    ListInstalled,
+    CheckUpdates,
+    Search,
+    Plan,
+    Install,
+    Remove,
+    Update,
+}
+
+impl PluginMethod {
+    fn for_action(action: OperationAction) -> Option<Self> {
+        match action {
+            OperationAction::Install => Some(Self::Install),
+            OperationAction::Remove => Some(Self::Remove),
+            OperationAction::Update => Some(Self::Update),
+            OperationAction::Rollback => None,
+        }
+    }
+}
+
+/// How the plugin asks LinGet to treat its operations. Plugins are always
+/// Beta: LinGet cannot contract-test them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginDescriptor {
+    #[serde(default = "default_fidelity")]
+    pub fidelity: PlanFidelity,
+    #[serde(default = "default_privilege")]
+    pub privilege: PrivilegeRequirement,
+    #[serde(default = "default_cancellation")]
+    pub cancellation: CancellationSupport,
+}
+
+fn default_fidelity() -> PlanFidelity {
+    PlanFidelity::BestEffort
+}
+
+fn default_privilege() -> PrivilegeRequirement {
+    PrivilegeRequirement::MayRequire
+}
+
+fn default_cancellation() -> CancellationSupport {
+    CancellationSupport::BetweenStepsOnly
+}
+
+impl Default for PluginDescriptor {
+    fn default() -> Self {
+        Self {
+            fidelity: default_fidelity(),
+            privilege: default_privilege(),
+            cancellation: default_cancellation(),
+        }
+    }
+}
+
+/// The plugin's answer to `handshake`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub protocol: u32,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    pub capabilities: Vec<PluginMethod>,
+    #[serde(default)]
+    pub descriptor: PluginDescriptor,
+}
+
+/// A plugin found on disk, with its handshake or the reason it has none.
+#[derive(Debug, Clone)]
+pub struct DiscoveredPlugin {
+    pub id: PluginId,
+    pub path: PathBuf,
+    pub handshake: Result<Handshake, String>,
+}
+
+impl DiscoveredPlugin {
+    pub fn status(&self) -> ProviderStatus {
+        let source = PackageSource::Plugin(self.id);
+        let handshake = self.handshake.as_ref().ok();
+        let reason = match &self.handshake {
+            Ok(handshake) if handshake.protocol != PROTOCOL_VERSION => Some(format!(
+                "Speaks plugin protocol {}; this LinGet speaks {}",
+                handshake.protocol, PROTOCOL_VERSION
+            )),
+            Ok(_) => None,
+            Err(error) => Some(format!("Handshake failed: {}", error)),
+        };
+        ProviderStatus {
+            source,
+            display_name: handshake
+                .and_then(|handshake| handshake.display_name.clone())
+                .unwrap_or_else(|| source.to_string()),
+            available: reason.is_none(),
+            list_cmds: vec![format!("{}{}", EXECUTABLE_PREFIX, self.id.name())],
+            privileged_cmds: Vec::new(),
+            found_paths: vec![self.path.clone()],
+            version: handshake.and_then(|handshake| handshake.version.clone()),
+            reason,
+        }
+    }
+
+    /// The backend for this plugin, if its handshake succeeded.
+    pub fn backend(&self) -> Option<PluginBackend> {
+        let handshake = self.handshake.as_ref().ok()?;
+        (handshake.protocol == PROTOCOL_VERSION).then(|| PluginBackend {
+            id: self.id,
+            path: self.path.clone(),
+        })
+    }
+}
+
+struct RegisteredPlugin {
+    path: PathBuf,
+    handshake: Handshake,
+}
+
+/// Handshakes of the plugins loaded by this process, so descriptors and
+/// capabilities can be looked up by source like the built-in ones.
+static REGISTRY: RwLock<Option<HashMap<PluginId, RegisteredPlugin>>> = RwLock::new(None);
+
+fn registered<T>(id: PluginId, read: impl FnOnce(&RegisteredPlugin) -> T) -> Option<T> {
+    REGISTRY
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()?
+        .get(&id)
+        .map(read)
+}
+
+fn register(id: PluginId, path: &Path, handshake: &Handshake) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get_or_insert_with(HashMap::new)
+        .insert(
+            id,
+            RegisteredPlugin {
+                path: path.to_path_buf(),
+                handshake: handshake.clone(),
+            },
+        );
+}
+
+/// The last handshake with the executable at each path, failed ones
+/// included, keyed with the modification time it was made at. Detection runs
+/// on every catalog refresh, and a plugin that hangs would otherwise cost
+/// `HANDSHAKE_TIMEOUT` each time until it is replaced.
+type HandshakeCache = HashMap<PathBuf, (Option<SystemTime>, Result<Handshake, String>)>;
+
+static HANDSHAKES: RwLock<Option<HandshakeCache>> = RwLock::new(None);
+
+/// The handshake with `path`, made again only when the file changed.
+fn cached_handshake(path: &Path) -> Result<Handshake, String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    let cached = HANDSHAKES
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .as_ref()
+        .and_then(|cache| cache.get(path))
+        .filter(|(at, _)| modified.is_some() && *at == modified)
+        .map(|(_, handshake)| handshake.clone());
+    if let Some(handshake) = cached {
+        return handshake;
+    }
+    let handshake = handshake(path);
+    HANDSHAKES
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .get_or_insert_with(HashMap::new)
+        .insert(path.to_path_buf(), (modified, handshake.clone()));
+    handshake
+}
+
+/// Where plugins are installed for the current user.
+pub fn plugins_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("linget")
+        .join("plugins")
+}
+
+/// Find and handshake every plugin in the plugins directory and on PATH.
+pub fn discover() -> Vec<DiscoveredPlugin> {
+    let mut dirs = vec![plugins_dir()];
+    dirs.extend(
+        std::env::var_os("PATH")
+            .iter()
+            .flat_map(std::env::split_paths),
+    );
+    discover_in(&dirs)
+}
+
+/// Plugins in `dirs`; when a name appears twice, the earlier directory wins.
+pub fn discover_in(dirs: &[PathBuf]) -> Vec<DiscoveredPlugin> {
+    let mut found: Vec<DiscoveredPlugin> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            let Some(name) = plugin_name(&path) else {
+                continue;
+            };
+            let Some(id) = PluginId::intern(name) else {
+                tracing::warn!("Ignoring plugin {}: too many plugin names", path.display());
+                continue;
+            };
+            if found.iter().any(|plugin| plugin.id == id) {
+                continue;
+            }
+            let handshake = cached_handshake(&path);
+            if let Ok(handshake) = &handshake {
+                if handshake.protocol == PROTOCOL_VERSION {
+                    register(id, &path, handshake);
+                }
+            }
+            found.push(DiscoveredPlugin {
+                id,
+                path,
+                handshake,
+            });
+        }
+    }
+    found
+}
+
+/// The `<name>` of an executable `linget-provider-<name>`.
+fn plugin_name(path: &Path) -> Option<&str> {
+    let name = path
+        .file_name()?
+        .to_str()?
+        .strip_prefix(EXECUTABLE_PREFIX)?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    let executable = std::fs::metadata(path)
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false);
+    (valid && executable).then_some(name)
+}
+
+/// Ask the plugin at `path` what it is. Runs synchronously because provider
+/// detection does; a plugin that hangs is killed after `HANDSHAKE_TIMEOUT`.
+fn handshake(path: &Path) -> Result<Handshake, String> {
+    let mut child = std::process::Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|error| error.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = writeln!(stdin, "{}", request_line("handshake", Value::Null));
+    }
+    let stdout = child.stdout.take().ok_or("no stdout")?;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let answer = std::io::BufReader::new(stdout)
+            .lines()
+            .map_while(Result::ok)
+            .find_map(|line| match serde_json::from_str::<Reply>(&line) {
+                Ok(Reply {
+                    result: Some(result),
+                    ..
+                }) => Some(serde_json::from_value::<Handshake>(result).map_err(|e| e.to_string())),
+                Ok(Reply {
+                    error: Some(error), ..
+                }) => Some(Err(error.message)),
+                _ => None,
+            });
+        let _ = sender.send(answer);
+    });
+    let answer = receiver.recv_timeout(HANDSHAKE_TIMEOUT);
+    let _ = child.kill();
+    let _ = child.wait();
+    match answer {
+        Ok(Some(answer)) => answer,
+        Ok(None) => Err("the plugin exited without answering".to_string()),
+        Err(_) => Err(format!("no answer within {}s", HANDSHAKE_TIMEOUT.as_secs())),
+    }
+}
+
+/// How LinGet treats the operations of plugin `id`.
+pub fn descriptor(id: PluginId) -> ProviderDescriptor {
+    let declared = registered(id, |plugin| {
+        let handshake = &plugin.handshake;
+        let mut descriptor = handshake.descriptor;
+        // An exact plan needs the plugin to resolve it.
+        if !handshake.capabilities.contains(&PluginMethod::Plan) {
+            descriptor.fidelity = PlanFidelity::BestEffort;
+        }
+        descriptor
+    })
+    .unwrap_or_default();
+    ProviderDescriptor {
+        source: PackageSource::Plugin(id),
+        tier: ProviderTier::Beta,
+        fidelity: declared.fidelity,
+        privilege: declared.privilege,
+        cancellation: declared.cancellation,
+        rollback: RollbackCapability::Unsupported,
+    }
+}
+
+pub fn supports(id: PluginId, method: PluginMethod) -> bool {
+    registered(id, |plugin| plugin.handshake.capabilities.contains(&method)).unwrap_or(false)
+}
+
+/// Whether plugin `id` declared what `capability` needs.
+pub fn capability_status(id: PluginId, capability: BackendCapability) -> CapabilityStatus {
+    let method = match capability {
+        BackendCapability::Install => PluginMethod::Install,
+        BackendCapability::Remove => PluginMethod::Remove,
+        BackendCapability::Update => PluginMethod::Update,
+        BackendCapability::Search => PluginMethod::Search,
+        _ => {
+            return CapabilityStatus::unsupported(
+                "The plugin protocol does not cover this action yet",
+            )
+        }
+    };
+    if supports(id, method) {
+        CapabilityStatus::supported()
+    } else {
+        CapabilityStatus::unsupported(format!("The {} plugin does not support this", id.name()))
+    }
+}
+
+/// The plugin's changes for `targets`, from its `plan` method.
+pub async fn plan_changes(
+    id: PluginId,
+    action: OperationAction,
+    targets: &[PackageRef],
+) -> Result<Vec<PackageChange>, ProviderError> {
+    let source = PackageSource::Plugin(id);
+    let path = registered(id, |plugin| plugin.path.clone()).ok_or_else(|| {
+        ProviderError::protocol(
+            Some(source),
+            format!("The {} plugin is not loaded", id.name()),
+        )
+    })?;
+    let Some(method) = PluginMethod::for_action(action) else {
+        return Err(ProviderError::protocol(
+            Some(source),
+            "Plugins cannot plan rollbacks",
+        ));
+    };
+    let result = call(
+        &path,
+        "plan",
+        json!({ "action": method, "packages": names(targets) }),
+        None,
+    )
+    .await
+    .map_err(|error| ProviderError::from_error(source, &error))?;
+    #[derive(Deserialize)]
+    struct Plan {
+        changes: Vec<PackageChange>,
+    }
+    serde_json::from_value::<Plan>(result)
+        .map(|plan| plan.changes)
+        .map_err(|error| {
+            ProviderError::protocol(
+                Some(source),
+                format!("Malformed plan from {}: {}", id.name(), error),
+            )
+        })
+}
+
+fn names(targets: &[PackageRef]) -> Vec<&str> {
+    targets.iter().map(|target| target.name.as_str()).collect()
+}
+
+/// An error the plugin reported in its answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginFailure {
+    /// The plugin's own classification, when it used a code LinGet knows.
+    pub code: Option<ProviderErrorCode>,
+    pub message: String,
+}
+
+impl std::fmt::Display for PluginFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PluginFailure {}
+
+/// Error codes a plugin can report, named like the failure they describe.
+fn error_code(code: &str) -> Option<ProviderErrorCode> {
+    Some(match code {
+        "authorization_cancelled" => ProviderErrorCode::AuthorizationCancelled,
+        "authorization_denied" => ProviderErrorCode::AuthorizationDenied,
+        "lock_busy" => ProviderErrorCode::LockBusy,
+        "dependency_conflict" => ProviderErrorCode::DependencyConflict,
+        "network" => ProviderErrorCode::Network,
+        "not_found" => ProviderErrorCode::NotFound,
+        "repository_unavailable" => ProviderErrorCode::RepositoryUnavailable,
+        "signature_invalid" => ProviderErrorCode::SignatureInvalid,
+        "disk_full" => ProviderErrorCode::DiskFull,
+        "timeout" => ProviderErrorCode::Timeout,
+        _ => return None,
+    })
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    protocol: u32,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    params: Value,
+}
+
+fn request_line(method: &str, params: Value) -> String {
+    serde_json::to_string(&Request {
+        protocol: PROTOCOL_VERSION,
+        method,
+        params,
+    })
+    .expect("plugin requests serialize")
+}
+
+/// One line a plugin writes to stdout.
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    log: Option<String>,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ReplyError>,
+}
+
+#[derive(Deserialize)]
+struct ReplyError {
+    #[serde(default)]
+    code: Option<String>,
+    message: String,
+}
+
+/// Send `method` to the plugin at `path` and wait for its answer, passing
+/// its progress to `log_sender`. A cancellation in scope terminates it.
+async fn call(
+    path: &Path,
+    method: &str,
+    params: Value,
+    log_sender: Option<mpsc::Sender<StreamLine>>,
+) -> Result<Value> {
+    let program = path.display().to_string();
+    let mut child = isolate_when_cancellable(&mut Command::new(path))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let mut line = request_line(method, params);
+        line.push('\n');
+        // A plugin that exits before reading closes the pipe early.
+        let _ = stdin.write_all(line.as_bytes()).await;
+    }
+
+    let stdout = child.stdout.take().context("Failed to capture stdout")?;
+    let stderr = child.stderr.take().context("Failed to capture stderr")?;
+    let stderr_sender = log_sender.clone();
+    let stderr_task = tokio::spawn(async move {
+        let mut collected = String::new();
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = strip_ansi(&line);
+            collected.push_str(&line);
+            collected.push('\n');
+            if let Some(sender) = &stderr_sender {
+                let _ = sender.send(StreamLine::Stderr(line)).await;
+            }
+        }
+        collected
+    });
+
+    let pid = child.id();
+    let exchange = async {
+        let mut answer = None;
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            let log = match serde_json::from_str::<Reply>(&line) {
+                Ok(Reply {
+                    error: Some(error), ..
+                }) => {
+                    answer = Some(Err(PluginFailure {
+                        code: error.code.as_deref().and_then(error_code),
+                        message: error.message,
+                    }));
+                    continue;
+                }
+                Ok(Reply {
+                    result: Some(result),
+                    ..
+                }) => {
+                    answer = Some(Ok(result));
+                    continue;
+                }
+                Ok(Reply { log: Some(log), .. }) => log,
+                // Output that is not a protocol message is still progress.
+                _ => line,
+            };
+            let log = strip_ansi(&log);
+            if let Some(sender) = &log_sender {
+                if !log.trim().is_empty() {
+                    let _ = sender.send(StreamLine::Stdout(log)).await;
+                }
+            }
+        }
+        let status = child.wait().await?;
+        Ok((status, answer))
+    };
+    tokio::pin!(exchange);
+    let outcome = supervise(pid, exchange).await;
+    if outcome.is_err() {
+        stderr_task.abort();
+    }
+    let (status, answer) = outcome?;
+    let stderr = stderr_task.await.unwrap_or_default();
+
+    match answer {
+        Some(Ok(result)) => Ok(result),
+        Some(Err(failure)) => {
+            let message = format!("{} failed: {}", program, failure.message);
+            Err(anyhow::Error::new(failure).context(message))
+        }
+        None if !status.success() => {
+            let failure = CommandFailure::new(&program, status.code(), stderr);
+            let message = format!("{} {} failed without answering", program, method);
+            Err(failure.with_message(message))
+        }
+        None => Err(anyhow::anyhow!(
+            "{} exited without answering {}",
+            program,
+            method
+        )),
+    }
+}
+
+/// A package as a plugin reports it.
+#[derive(Deserialize)]
+struct PluginPackage {
+    name: String,
+    #[serde(default)]
+    version: String,
+    #[serde(default)]
+    available_version: Option<String>,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    homepage: Option<String>,
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    maintainer: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Adapter that runs a plugin as a `PackageBackend`.
+pub struct PluginBackend {
+    id: PluginId,
+    path: PathBuf,
+}
+
+impl PluginBackend {
+    async fn packages(&self, method: &str, params: Value) -> Result<Vec<Package>> {
+        #[derive(Deserialize)]
+        struct Packages {
+            packages: Vec<PluginPackage>,
+        }
+        let result = call(&self.path, method, params, None).await?;
+        let packages: Packages = serde_json::from_value(result).with_context(|| {
+            format!(
+                "Malformed {} answer from the {} plugin",
+                method,
+                self.id.name()
+            )
+        })?;
+        Ok(packages
+            .packages
+            .into_iter()
+            .map(|package| {
+                let status = match method {
+                    "search" => PackageStatus::NotInstalled,
+                    "check_updates" => PackageStatus::UpdateAvailable,
+                    _ if package
+                        .available_version
+                        .as_ref()
+                        .is_some_and(|available| *available != package.version) =>
+                    {
+                        PackageStatus::UpdateAvailable
+                    }
+                    _ => PackageStatus::Installed,
+                };
+                Package {
+                    name: package.name,
+                    version: package.version,
+                    available_version: package.available_version,
+                    description: package.description,
+                    source: PackageSource::Plugin(self.id),
+                    status,
+                    size: package.size,
+                    homepage: package.homepage,
+                    license: package.license,
+                    maintainer: package.maintainer,
+                    dependencies: package.dependencies,
+                    install_date: None,
+                    update_category: None,
+                    enrichment: None,
+                    released_at: None,
+                }
+            })
+            .collect())
+    }
+
+    async fn mutate(
+        &self,
+        method: PluginMethod,
+        names: &[String],
+        log_sender: Option<mpsc::Sender<StreamLine>>,
+    ) -> Result<()> {
+        let name = serde_json::to_value(method)?;
+        call(
+            &self.path,
+            name.as_str().unwrap_or_default(),
+            json!({ "packages": names }),
+            log_sender,
+        )
+        .await
+        .map(drop)
+    }
+}
+
+#[async_trait]
+impl PackageBackend for PluginBackend {
+    /// Each plugin is probed by its own handshake during discovery.
+    fn is_available() -> bool {
+        true
+    }
+
+    async fn list_installed(&self) -> Result<Vec<Package>> {
+        self.packages("list_installed", Value::Null).await
+    }
+
+    async fn check_updates(&self) -> Result<Vec<Package>> {
+        if !supports(self.id, PluginMethod::CheckUpdates) {
+            return Ok(Vec::new());
+        }
+        self.packages("check_updates", Value::Null).await
+    }
+
+    async fn install(&self, name: &str) -> Result<()> {
+        self.install_streaming(name, None).await
+    }
+
+    async fn install_streaming(
+        &self,
+        name: &str,
+        log_sender: Option<mpsc::Sender<StreamLine>>,
+    ) -> Result<()> {
+        self.mutate(PluginMethod::Install, &[name.to_string()], log_sender)
+            .await
+    }
+
+    async fn remove(&self, name: &str) -> Result<()> {
+        self.remove_streaming(name, None).await
+    }
+
+    async fn remove_streaming(
+        &self,
+        name: &str,
+        log_sender: Option<mpsc::Sender<StreamLine>>,
+    ) -> Result<()> {
+        self.mutate(PluginMethod::Remove, &[name.to_string()], log_sender)
+            .await
+    }
+
+    async fn update(&self, name: &str) -> Result<()> {
+        self.update_streaming(name, None).await
+    }
+
+    async fn update_streaming(
+        &self,
+        name: &str,
+        log_sender: Option<mpsc::Sender<StreamLine>>,
+    ) -> Result<()> {
+        self.mutate(PluginMethod::Update, &[name.to_string()], log_sender)
+            .await
+    }
+
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        self.mutate(PluginMethod::Install, names, None).await
+    }
+
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        self.mutate(PluginMethod::Remove, names, None).await
+    }
+
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        self.mutate(PluginMethod::Update, names, None).await
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<Package>> {
+        if !supports(self.id, PluginMethod::Search) {
+            return Ok(Vec::new());
+        }
+        self.packages("search", json!({ "query": query })).await
+    }
+
+    fn source(&self) -> PackageSource {
+        PackageSource::Plugin(self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::transaction::{
+        CancellationFlag, OperationRequest, RequestedBy, TransactionEngine, VerificationOutcome,
+    };
+    use crate::backend::PackageManager;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use tokio::sync::RwLock as AsyncRwLock;
+
+    /// A fresh copy of the toolbox fixture under a unique name, so tests
+    /// running in parallel do not share a plugin or its state.
+    fn toolbox() -> (PathBuf, PluginId) {
+        let dir = std::env::temp_dir().join(format!("linget-plugin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create plugin dir");
+        let name = format!("toolbox{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+        let path = dir.join(format!("{}{}", EXECUTABLE_PREFIX, name));
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/linget-provider-toolbox"
+            ),
+            &path,
+        )
+        .expect("copy plugin fixture");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod plugin");
+        (dir, PluginId::intern(&name).expect("plugin id"))
+    }
+
+    fn loaded(dir: &Path, id: PluginId) -> PluginBackend {
+        discover_in(&[dir.to_path_buf()])
+            .into_iter()
+            .find(|plugin| plugin.id == id)
+            .and_then(|plugin| plugin.backend())
+            .expect("plugin loads")
+    }
+
+    #[test]
+    fn failed_handshakes_are_retried_only_once_the_plugin_changes() {
+        let dir = std::env::temp_dir().join(format!("linget-plugin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create plugin dir");
+        let runs = dir.join("runs");
+        let broken = dir.join("linget-provider-broken");
+        let write_broken = |script: &str| {
+            std::fs::write(&broken, script).expect("write broken plugin");
+            std::fs::set_permissions(&broken, std::fs::Permissions::from_mode(0o755))
+                .expect("chmod broken plugin");
+        };
+        write_broken(&format!(
+            "#!/bin/sh
+echo run >> '{}'
+",
+            runs.display()
+        ));
+        let handshakes = || {
+            std::fs::read_to_string(&runs)
+                .map(|runs| runs.lines().count())
+                .unwrap_or(0)
+        };
+
+        for _ in 0..3 {
+            let found = discover_in(std::slice::from_ref(&dir));
+            assert!(found[0].handshake.is_err());
+        }
+        assert_eq!(handshakes(), 1);
+
+        let modified = std::fs::metadata(&broken)
+            .and_then(|m| m.modified())
+            .expect("mtime");
+        write_broken(&format!(
+            "#!/bin/sh
+echo run >> '{}'
+exit 1
+",
+            runs.display()
+        ));
+        std::fs::File::options()
+            .write(true)
+            .open(&broken)
+            .and_then(|file| file.set_modified(modified + Duration::from_secs(1)))
+            .expect("touch broken plugin");
+        discover_in(std::slice::from_ref(&dir));
+        assert_eq!(handshakes(), 2);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn the_handshake_declares_descriptor_and_capabilities() {
+        let (dir, id) = toolbox();
+        let future = dir.join("linget-provider-future");
+        std::fs::write(
+            &future,
+            "#!/bin/sh\necho '{\"result\":{\"protocol\":2,\"capabilities\":[]}}'\n",
+        )
+        .expect("write future plugin");
+        std::fs::set_permissions(&future, std::fs::Permissions::from_mode(0o755))
+            .expect("chmod future plugin");
+        std::fs::write(dir.join("linget-provider-notes"), "not executable").expect("write notes");
+
+        let found = discover_in(std::slice::from_ref(&dir));
+        assert_eq!(found.len(), 2);
+        let toolbox = found
+            .iter()
+            .find(|plugin| plugin.id == id)
+            .expect("toolbox found");
+        let status = toolbox.status();
+        assert!(status.available);
+        assert_eq!(status.display_name, "Toolbox");
+        assert_eq!(status.version.as_deref(), Some("0.3.0"));
+
+        let descriptor = descriptor(id);
+        assert_eq!(descriptor.tier, ProviderTier::Beta);
+        assert_eq!(descriptor.fidelity, PlanFidelity::Exact);
+        assert_eq!(descriptor.privilege, PrivilegeRequirement::None);
+        assert_eq!(descriptor.cancellation, CancellationSupport::Cooperative);
+        assert!(capability_status(id, BackendCapability::Install).is_supported());
+        assert!(!capability_status(id, BackendCapability::Update).is_supported());
+        assert!(!capability_status(id, BackendCapability::Changelog).is_supported());
+
+        let future = found
+            .iter()
+            .find(|plugin| plugin.id.name() == "future")
+            .expect("future plugin found");
+        assert!(future.backend().is_none());
+        assert_eq!(
+            future.status().reason.as_deref(),
+            Some("Speaks plugin protocol 2; this LinGet speaks 1")
+        );
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn plugin_backends_run_requests_and_stream_progress() {
+        let (dir, id) = toolbox();
+        let backend = loaded(&dir, id);
+        let (sender, mut lines) = mpsc::channel(16);
+
+        backend
+            .install_streaming("hammer", Some(sender))
+            .await
+            .expect("install");
+        match lines.try_recv() {
+            Ok(StreamLine::Stdout(line)) => assert_eq!(line, "Installing hammer"),
+            other => panic!("expected progress, got {:?}", other),
+        }
+        let installed = backend.list_installed().await.expect("list");
+        assert_eq!(installed.len(), 1);
+        assert_eq!(installed[0].name, "hammer");
+        assert_eq!(installed[0].version, "1.0");
+        assert_eq!(installed[0].source, PackageSource::Plugin(id));
+        let found = backend.search("ham").await.expect("search");
+        assert_eq!(found[0].status, PackageStatus::NotInstalled);
+        assert!(backend.check_updates().await.expect("updates").is_empty());
+
+        backend
+            .remove_batch(&["hammer".to_string()])
+            .await
+            .expect("remove");
+        assert!(backend.list_installed().await.expect("list").is_empty());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn plugin_errors_keep_the_code_the_plugin_reported() {
+        let (dir, id) = toolbox();
+        let error = loaded(&dir, id)
+            .install("broken")
+            .await
+            .expect_err("broken cannot be installed");
+        let error = ProviderError::from_error(PackageSource::Plugin(id), &error);
+        assert_eq!(error.code, ProviderErrorCode::Network);
+        assert!(error.diagnostic.contains("mirror unreachable"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn the_engine_plans_and_verifies_plugin_operations() {
+        let (dir, id) = toolbox();
+        let source = PackageSource::Plugin(id);
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(source, Box::new(loaded(&dir, id)));
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+        let engine = TransactionEngine::load(
+            Arc::new(AsyncRwLock::new(manager)),
+            dir.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+
+        let (plan, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Install,
+                vec![PackageRef {
+                    name: "hammer".to_string(),
+                    source,
+                    installed_version: None,
+                    available_version: None,
+                    pinned: false,
+                }],
+                RequestedBy::Cli,
+            ))
+            .await
+            .expect("plan");
+        assert_eq!(plan.provider.fidelity, PlanFidelity::Exact);
+        assert_eq!(
+            plan.expected_changes,
+            [PackageChange {
+                name: "hammer".to_string(),
+                before: None,
+                after: Some("1.0".to_string()),
+            }]
+        );
+        assert_eq!(
+            plan.exact_commands[0].program,
+            format!("{}{}", EXECUTABLE_PREFIX, id.name())
+        );
+
+        let receipt = engine
+            .execute(plan, CancellationFlag::default())
+            .await
+            .expect("execute");
+        assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -161,6 +161,11 @@ fn provider_row(source: PackageSource, include_version: bool) -> ProviderStatus
             list_cmds: &["scoop"],
             version_cmd: Some(("scoop", &["--version"])),
         },
+        // Plugins are probed by their handshake instead.
+        PackageSource::Plugin(_) => ProviderProbe {
+            list_cmds: &[],
+            version_cmd: None,
+        },
     };
 
     let privileged_cmds = source.privileged_probe_commands();
@@ -249,6 +254,11 @@ fn detect_providers_with_options(include_versions: bool) -> Vec<ProviderStatus>
         .iter()
         .map(|&source| provider_row(source, include_versions))
         .collect();
+    rows.extend(
+        super::plugin::discover()
+            .iter()
+            .map(|plugin| plugin.status()),
+    );
 
     rows.sort_by(|a, b| {
         let a_key = (!a.available, a.display_name.to_lowercase());
@@ -0,0 +1,265 @@
+//! The language registries LinGet asks for latest versions: npm, PyPI and
+//! crates.io. Besides the version, a lookup reports when that version was
+//! published, which the update policy's release cooldown waits on.
+//!
+//! Each base URL can be pointed at a mirror, or at a stand-in server in
+//! tests, with `LINGET_NPM_REGISTRY`, `LINGET_PYPI_URL` or
+//! `LINGET_CRATES_IO_URL`.
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryKind {
+    Npm,
+    PyPi,
+    CratesIo,
+}
+
+#[derive(Debug, Clone)]
+pub struct Registry {
+    kind: RegistryKind,
+    base: String,
+}
+
+/// A registry's latest release of a package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    pub released_at: Option<DateTime<Utc>>,
+    /// The package summary, when the registry sends one along (PyPI).
+    pub summary: Option<String>,
+}
+
+impl Registry {
+    pub fn npm() -> Self {
+        Self::from_env(
+            RegistryKind::Npm,
+            "LINGET_NPM_REGISTRY",
+            "https://registry.npmjs.org",
+        )
+    }
+
+    pub fn pypi() -> Self {
+        Self::from_env(RegistryKind::PyPi, "LINGET_PYPI_URL", "https://pypi.org")
+    }
+
+    pub fn crates_io() -> Self {
+        Self::from_env(
+            RegistryKind::CratesIo,
+            "LINGET_CRATES_IO_URL",
+            "https://crates.io",
+        )
+    }
+
+    pub fn with_base(kind: RegistryKind, base: &str) -> Self {
+        Self {
+            kind,
+            base: base.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn from_env(kind: RegistryKind, variable: &str, default: &str) -> Self {
+        let base = std::env::var(variable)
+            .ok()
+            .filter(|base| !base.is_empty())
+            .unwrap_or_else(|| default.to_string());
+        Self::with_base(kind, &base)
+    }
+
+    /// `path` under the registry's base URL.
+    pub fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base, path.trim_start_matches('/'))
+    }
+
+    /// The document that names a package's latest release and its time.
+    fn release_url(&self, name: &str) -> String {
+        match self.kind {
+            // Scoped names keep their `/` escaped, as the npm CLI sends them.
+            RegistryKind::Npm => self.url(&name.replace('/', "%2f")),
+            RegistryKind::PyPi => self.url(&format!("pypi/{}/json", name)),
+            RegistryKind::CratesIo => self.url(&format!("api/v1/crates/{}", name)),
+        }
+    }
+
+    fn cache_key(&self, name: &str) -> String {
+        match self.kind {
+            RegistryKind::Npm => format!("npm:{}", name),
+            RegistryKind::PyPi => format!("pypi:{}", name),
+            RegistryKind::CratesIo => format!("crates:{}", name),
+        }
+    }
+
+    /// The latest release of `name`, from the short-TTL cache when it is
+    /// fresh and from the registry otherwise.
+    pub async fn latest_release(&self, client: &reqwest::Client, name: &str) -> Option<Release> {
+        let key = self.cache_key(name);
+        if let Some((version, released_at)) = super::latest_cache::get_release(&key) {
+            return Some(Release {
+                version,
+                released_at,
+                summary: None,
+            });
+        }
+        let release = self.fetch_release(client, name).await?;
+        super::latest_cache::put_release(key, release.version.clone(), release.released_at);
+        Some(release)
+    }
+
+    /// Ask the registry for the latest release of `name`, bypassing the cache.
+    pub async fn fetch_release(&self, client: &reqwest::Client, name: &str) -> Option<Release> {
+        let response = client.get(self.release_url(name)).send().await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let document: Value = response.json().await.ok()?;
+        self.parse_release(&document)
+    }
+
+    /// Read the latest release from the document at `release_url`.
+    pub fn parse_release(&self, document: &Value) -> Option<Release> {
+        match self.kind {
+            RegistryKind::Npm => {
+                let version = document.pointer("/dist-tags/latest")?.as_str()?;
+                Some(Release {
+                    version: version.to_string(),
+                    released_at: document
+                        .get("time")
+                        .and_then(|times| times.get(version))
+                        .and_then(parse_time),
+                    summary: None,
+                })
+            }
+            RegistryKind::PyPi => {
+                let version = document.pointer("/info/version")?.as_str()?;
+                // `urls` lists the files of the latest release; the first
+                // upload is when it went public.
+                let released_at = document
+                    .get("urls")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|file| file.get("upload_time_iso_8601").and_then(parse_time))
+                    .min();
+                Some(Release {
+                    version: version.to_string(),
+                    released_at,
+                    summary: document
+                        .pointer("/info/summary")
+                        .and_then(Value::as_str)
+                        .filter(|summary| !summary.is_empty())
+                        .map(str::to_string),
+                })
+            }
+            RegistryKind::CratesIo => {
+                let version = document.pointer("/crate/max_version")?.as_str()?;
+                let released_at = document
+                    .get("versions")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .find(|entry| entry.get("num").and_then(Value::as_str) == Some(version))
+                    .and_then(|entry| entry.get("created_at"))
+                    .and_then(parse_time);
+                Some(Release {
+                    version: version.to_string(),
+                    released_at,
+                    summary: None,
+                })
+            }
+        }
+    }
+}
+
+fn parse_time(value: &Value) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.as_str()?)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::path::Path;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve the registry documents in `tests/fixtures/registry`, one per
+    /// request path, and answer 404 for anything else.
+    async fn stand_in_registry() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/registry");
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or("/");
+                let file = path.trim_start_matches('/').replace('/', "_") + ".json";
+                let response = match std::fs::read_to_string(fixtures.join(file)) {
+                    Ok(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    Err(_) => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                stream.write_all(response.as_bytes()).await.ok();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    fn at(text: &str) -> Option<DateTime<Utc>> {
+        Some(
+            DateTime::parse_from_rfc3339(text)
+                .unwrap()
+                .with_timezone(&Utc),
+        )
+    }
+
+    #[tokio::test]
+    async fn releases_carry_their_publish_time_from_each_registry() {
+        let base = stand_in_registry().await;
+        let client = reqwest::Client::new();
+
+        let npm = Registry::with_base(RegistryKind::Npm, &base);
+        assert_eq!(
+            npm.fetch_release(&client, "left-pad").await,
+            Some(Release {
+                version: "1.3.0".to_string(),
+                released_at: at("2026-10-15T08:30:00.000Z"),
+                summary: None,
+            })
+        );
+        let scoped = npm.fetch_release(&client, "@types/node").await.unwrap();
+        assert_eq!(scoped.version, "22.7.4");
+
+        let pypi = Registry::with_base(RegistryKind::PyPi, &base);
+        assert_eq!(
+            pypi.fetch_release(&client, "requests").await,
+            Some(Release {
+                version: "2.33.0".to_string(),
+                released_at: at("2026-10-14T16:02:11.000000Z"),
+                summary: Some("Python HTTP for Humans.".to_string()),
+            })
+        );
+
+        let crates = Registry::with_base(RegistryKind::CratesIo, &base);
+        assert_eq!(
+            crates.fetch_release(&client, "ripgrep").await,
+            Some(Release {
+                version: "15.1.0".to_string(),
+                released_at: Some(Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap()),
+                summary: None,
+            })
+        );
+
+        assert_eq!(crates.fetch_release(&client, "missing").await, None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::SUGGEST_PREFIX;
 use crate::models::{Package, PackageSource, PackageStatus};
@@ -160,6 +161,7 @@ impl PackageBackend for SnapBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -346,6 +348,7 @@ impl PackageBackend for SnapBackend {
                     install_date: None,
                     update_category: None,
                     enrichment: None,
+                    released_at: None,
                 });
             }
         }
@@ -388,6 +391,7 @@ impl PackageBackend for SnapBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -428,6 +432,43 @@ impl PackageBackend for SnapBackend {
         PackageSource::Snap
     }
 
+    async fn hold(&self, name: &str) -> Result<()> {
+        let (ok, stderr) = self
+            .run_pkexec_snap(
+                &["refresh", "--hold", name],
+                &format!("Failed to hold snap {}", name),
+            )
+            .await?;
+        if !ok {
+            anyhow::bail!("Failed to hold snap {}: {}", name, stderr.trim());
+        }
+        Ok(())
+    }
+
+    async fn unhold(&self, name: &str) -> Result<()> {
+        let (ok, stderr) = self
+            .run_pkexec_snap(
+                &["refresh", "--unhold", name],
+                &format!("Failed to release hold on snap {}", name),
+            )
+            .await?;
+        if !ok {
+            anyhow::bail!("Failed to release hold on snap {}: {}", name, stderr.trim());
+        }
+        Ok(())
+    }
+
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        let output = Command::new("snap")
+            .arg("list")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .context("Failed to list snaps")?;
+        Ok(parse_held_snaps(&String::from_utf8_lossy(&output.stdout)))
+    }
+
     async fn get_package_commands(&self, name: &str) -> Result<Vec<(String, std::path::PathBuf)>> {
         let mut commands = Vec::new();
         let snap_bin = std::path::PathBuf::from("/snap/bin");
@@ -449,6 +490,22 @@ impl PackageBackend for SnapBackend {
     }
 }
 
+/// Snaps whose `Notes` column (the last one in `snap list`) includes `held`.
+fn parse_held_snaps(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let notes = columns.last()?;
+            notes
+                .split(',')
+                .any(|note| note == "held")
+                .then(|| columns[0].to_string())
+        })
+        .collect()
+}
+
 /// Builds update `Package` rows from cached or freshly-parsed
 /// (name, new_version) findings.
 fn update_packages_from(findings: Vec<(String, String)>) -> Vec<Package> {
@@ -469,6 +526,7 @@ fn update_packages_from(findings: Vec<(String, String)>) -> Vec<Package> {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         })
         .collect()
 }
@@ -513,6 +571,54 @@ impl SnapBackend {
     }
 }
 
+/// Classify a snap failure from its `error:` message.
+///
+/// snap exits 1 for every error and reports the cause on the `error:` line,
+/// or on the task lines that follow "cannot perform the following tasks".
+/// Store failures quote the Go network error, so a timeout is checked before
+/// the dial error it is worded as.
+pub(crate) fn classify_failure(_exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.contains("access denied")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| line.contains("change in progress")) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| {
+        line.contains("i/o timeout")
+            || line.contains("Client.Timeout exceeded")
+            || line.contains("context deadline exceeded")
+    }) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| line.contains("dial tcp") || line.contains("network is unreachable")) {
+        ProviderErrorCode::Network
+    } else if any(&|line| line.contains("got unexpected HTTP status code 5")) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| {
+        line.contains("cannot verify snap") || line.contains("cannot find signatures")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| {
+        line.contains("insufficient space") || line.contains("no space left on device")
+    }) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| line.starts_with("- Ensure prerequisites for")) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| line.starts_with("- ") && line.ends_with("(cancelled)")) {
+        ProviderErrorCode::Interrupted
+    } else if any(&|line| {
+        line.starts_with("error:")
+            && (line.ends_with("not found") || line.ends_with("is not installed"))
+    }) {
+        ProviderErrorCode::NotFound
+    } else if any(&|line| line.starts_with("error:")) {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -525,6 +631,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn held_snaps_are_read_from_the_notes_column() {
+        let output = "Name    Version  Rev    Tracking       Publisher   Notes\n\
+                      core22  20240111 1122   latest/stable  canonical✓  base\n\
+                      firefox 125.0.2  4173   latest/stable  mozilla✓    held\n\
+                      code    1.88.1   157    latest/stable  vscode✓     classic,held\n";
+        assert_eq!(parse_held_snaps(output), vec!["firefox", "code"]);
+    }
+
     #[test]
     fn running_error_includes_pid_summary_when_present() {
         let error = SnapBackend::format_snap_running_error("code", "pids:\n15704\n15705");
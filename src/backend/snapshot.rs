@@ -0,0 +1,316 @@
+//! Filesystem snapshots taken before a plan runs.
+//!
+//! A bad kernel or libc update can leave a system that no package manager
+//! can repair from inside. When snapshots are enabled, the transaction
+//! engine asks one of these drivers for a snapshot first and records its id
+//! on the operation, so the user can boot or restore from it.
+
+use super::pkexec::pkexec_output;
+use super::streaming::CommandFailure;
+use crate::models::{SnapshotConfig, SnapshotDriverKind};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::process::Output;
+use tokio::process::Command;
+
+/// A snapshot taken before an operation ran.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilesystemSnapshot {
+    pub driver: SnapshotDriverKind,
+    /// Snapper snapshot number, Timeshift snapshot name or btrfs snapshot
+    /// path.
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FilesystemSnapshot {
+    pub fn label(&self) -> String {
+        match self.driver {
+            SnapshotDriverKind::Snapper => format!("snapper #{}", self.id),
+            SnapshotDriverKind::Timeshift | SnapshotDriverKind::Btrfs => {
+                format!("{} {}", self.driver, self.id)
+            }
+        }
+    }
+
+    /// How to put the system back to this snapshot.
+    pub fn restore_hint(&self) -> String {
+        match self.driver {
+            SnapshotDriverKind::Snapper => {
+                format!("sudo snapper rollback {} && reboot", self.id)
+            }
+            SnapshotDriverKind::Timeshift => {
+                format!("sudo timeshift --restore --snapshot '{}'", self.id)
+            }
+            SnapshotDriverKind::Btrfs => format!(
+                "sudo btrfs subvolume snapshot {} <new root>, then boot from <new root>",
+                self.id
+            ),
+        }
+    }
+}
+
+pub struct SnapshotDriver {
+    kind: SnapshotDriverKind,
+    config: SnapshotConfig,
+    /// Run through pkexec; false when LinGet already runs as root.
+    privileged: bool,
+}
+
+impl SnapshotDriver {
+    /// The configured driver, or the first one installed.
+    pub fn detect(config: &SnapshotConfig) -> Option<Self> {
+        let kind = match config.driver {
+            Some(kind) => kind,
+            None => SnapshotDriverKind::ALL
+                .into_iter()
+                .find(|kind| which::which(kind.program()).is_ok())?,
+        };
+        // Safety: geteuid has no preconditions and cannot fail.
+        let root = unsafe { libc::geteuid() } == 0;
+        Some(Self {
+            kind,
+            config: config.clone(),
+            privileged: !root,
+        })
+    }
+
+    pub fn kind(&self) -> SnapshotDriverKind {
+        self.kind
+    }
+
+    fn args(&self, description: &str, now: DateTime<Utc>) -> Vec<String> {
+        let args: Vec<&str> = match self.kind {
+            SnapshotDriverKind::Snapper => vec![
+                "-c",
+                &self.config.snapper_config,
+                "create",
+                "--type",
+                "single",
+                "--cleanup-algorithm",
+                "number",
+                "--print-number",
+                "--description",
+                description,
+            ],
+            SnapshotDriverKind::Timeshift => {
+                vec!["--create", "--scripted", "--comments", description]
+            }
+            SnapshotDriverKind::Btrfs => {
+                return vec![
+                    "subvolume".to_string(),
+                    "snapshot".to_string(),
+                    "-r".to_string(),
+                    self.config.btrfs_subvolume.to_string_lossy().into_owned(),
+                    self.btrfs_target(now),
+                ];
+            }
+        };
+        args.into_iter().map(str::to_string).collect()
+    }
+
+    fn btrfs_target(&self, now: DateTime<Utc>) -> String {
+        self.config
+            .btrfs_snapshot_dir
+            .join(format!("linget-{}", now.format("%Y%m%d-%H%M%S")))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Take a snapshot labelled with `description`.
+    pub async fn create(&self, description: &str) -> Result<FilesystemSnapshot> {
+        let now = Utc::now();
+        let args = self.args(description, now);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let program = self.kind.program();
+        let output: Output = if self.privileged {
+            pkexec_output(program, &args).await
+        } else {
+            Command::new(program).args(&args).output().await
+        }
+        .with_context(|| format!("Failed to run {}", program))?;
+        if !output.status.success() {
+            let failure = CommandFailure::from_output(program, &output);
+            let message = format!(
+                "{} could not take a snapshot: {}",
+                program,
+                failure.output().trim()
+            );
+            return Err(failure.with_message(message));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let id = match self.kind {
+            SnapshotDriverKind::Snapper => parse_snapper_number(&stdout),
+            SnapshotDriverKind::Timeshift => parse_timeshift_name(&stdout),
+            SnapshotDriverKind::Btrfs => Some(self.btrfs_target(now)),
+        }
+        .with_context(|| format!("{} did not report the snapshot it took", program))?;
+        Ok(FilesystemSnapshot {
+            driver: self.kind,
+            id,
+            created_at: now,
+        })
+    }
+}
+
+/// `snapper create --print-number` prints only the new number.
+fn parse_snapper_number(stdout: &str) -> Option<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty() && line.chars().all(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// Timeshift names the snapshot in its `Tagged snapshot '<name>': ondemand`
+/// line.
+fn parse_timeshift_name(stdout: &str) -> Option<String> {
+    stdout.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("Tagged snapshot '")?;
+        let end = rest.find('\'')?;
+        Some(rest[..end].to_string())
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::ffi::OsString;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    /// A directory of stand-in binaries prepended to PATH for one test.
+    pub(crate) struct FakeBin {
+        dir: PathBuf,
+        old_path: Option<OsString>,
+    }
+
+    impl FakeBin {
+        /// `name` records its arguments, then runs `script`. A passthrough
+        /// `pkexec` sits next to it, so drivers that escalate run it too.
+        pub(crate) fn new(name: &str, script: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("linget-snapshot-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).expect("create fake bin dir");
+            let path = dir.join(name);
+            std::fs::write(
+                &path,
+                format!(
+                    "#!/bin/sh\necho \"$*\" > '{}'\n{}",
+                    dir.join("args").display(),
+                    script
+                ),
+            )
+            .expect("write fake binary");
+            let pkexec = dir.join("pkexec");
+            std::fs::write(&pkexec, "#!/bin/sh\nexec \"$@\"\n").expect("write fake pkexec");
+            for binary in [&path, &pkexec] {
+                std::fs::set_permissions(binary, std::fs::Permissions::from_mode(0o755))
+                    .expect("chmod fake binary");
+            }
+            let old_path = std::env::var_os("PATH");
+            let mut paths = vec![dir.clone()];
+            paths.extend(old_path.iter().flat_map(std::env::split_paths));
+            std::env::set_var("PATH", std::env::join_paths(paths).expect("join PATH"));
+            Self { dir, old_path }
+        }
+
+        pub(crate) fn logged_args(&self) -> String {
+            std::fs::read_to_string(self.dir.join("args")).unwrap_or_default()
+        }
+    }
+
+    impl Drop for FakeBin {
+        fn drop(&mut self) {
+            match self.old_path.take() {
+                Some(path) => std::env::set_var("PATH", path),
+                None => std::env::remove_var("PATH"),
+            }
+            std::fs::remove_dir_all(&self.dir).ok();
+        }
+    }
+
+    fn driver(kind: SnapshotDriverKind, config: SnapshotConfig) -> SnapshotDriver {
+        SnapshotDriver {
+            kind,
+            config,
+            privileged: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn snapper_snapshots_are_numbered_from_print_number() {
+        let _path_env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let fake = FakeBin::new("snapper", "echo 42\n");
+        let snapshot = driver(SnapshotDriverKind::Snapper, SnapshotConfig::default())
+            .create("linget update linux-image-generic")
+            .await
+            .expect("snapper snapshot");
+        let args = fake.logged_args();
+
+        assert_eq!(snapshot.driver, SnapshotDriverKind::Snapper);
+        assert_eq!(snapshot.id, "42");
+        assert!(args.starts_with("-c root create --type single"));
+        assert!(args.contains("--print-number --description linget update linux-image-generic"));
+        assert_eq!(
+            snapshot.restore_hint(),
+            "sudo snapper rollback 42 && reboot"
+        );
+    }
+
+    #[tokio::test]
+    async fn timeshift_snapshots_are_named_from_the_tag_line() {
+        let _path_env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let fake = FakeBin::new(
+            "timeshift",
+            "echo 'Creating new snapshot...(BTRFS)'\necho \"Tagged snapshot '2026-10-17_03-00-01': ondemand\"\n",
+        );
+        let snapshot = driver(SnapshotDriverKind::Timeshift, SnapshotConfig::default())
+            .create("linget remove vim")
+            .await
+            .expect("timeshift snapshot");
+
+        assert_eq!(snapshot.id, "2026-10-17_03-00-01");
+        assert_eq!(
+            fake.logged_args().trim(),
+            "--create --scripted --comments linget remove vim"
+        );
+    }
+
+    #[tokio::test]
+    async fn btrfs_snapshots_are_read_only_and_named_by_time() {
+        let _path_env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let fake = FakeBin::new("btrfs", "");
+        let config = SnapshotConfig {
+            btrfs_subvolume: PathBuf::from("/"),
+            btrfs_snapshot_dir: PathBuf::from("/.snapshots"),
+            ..SnapshotConfig::default()
+        };
+        let snapshot = driver(SnapshotDriverKind::Btrfs, config)
+            .create("linget update")
+            .await
+            .expect("btrfs snapshot");
+
+        assert!(snapshot.id.starts_with("/.snapshots/linget-"));
+        assert_eq!(
+            fake.logged_args().trim(),
+            format!("subvolume snapshot -r / {}", snapshot.id)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failing_driver_reports_its_output() {
+        let _path_env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let _fake = FakeBin::new("snapper", "echo 'Unknown config.' >&2\nexit 1\n");
+        let error = driver(SnapshotDriverKind::Snapper, SnapshotConfig::default())
+            .create("linget update")
+            .await
+            .expect_err("a failed snapshot must not succeed");
+        let failure = error
+            .downcast_ref::<CommandFailure>()
+            .expect("command failure attached");
+        assert_eq!(failure.exit_code, Some(1));
+        assert!(failure.stderr.contains("Unknown config."));
+    }
+}
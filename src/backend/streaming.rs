@@ -1,9 +1,192 @@
+use super::transaction::CancellationFlag;
 use anyhow::{Context, Result};
-use std::process::Stdio;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::{Output, Stdio};
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::sync::mpsc;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// How long a signalled provider gets to stop on its own before the rest of
+/// its process group is killed.
+const TERMINATE_GRACE: Duration = Duration::from_secs(10);
+
+tokio::task_local! {
+    static CANCELLATION: CancellationFlag;
+    static LOG_LINES: mpsc::Sender<StreamLine>;
+}
+
+/// Run `future` with `flag` as the cancellation for every provider process it
+/// spawns through this module. Outside such a scope, processes run to
+/// completion as before.
+pub async fn with_cancellation<F: Future>(flag: CancellationFlag, future: F) -> F::Output {
+    CANCELLATION.scope(flag, future).await
+}
+
+/// Run `future` with `sender` receiving the output of hooks it runs, so the
+/// output lands in the task log of the operation that ran them.
+pub async fn with_log_lines<F: Future>(sender: mpsc::Sender<StreamLine>, future: F) -> F::Output {
+    LOG_LINES.scope(sender, future).await
+}
+
+/// Where output should go in the current scope, if anywhere.
+pub fn log_lines() -> Option<mpsc::Sender<StreamLine>> {
+    LOG_LINES.try_with(mpsc::Sender::clone).ok()
+}
+
+/// A provider process stopped because its operation was cancelled.
+///
+/// `graceful` is true when the process exited after `SIGTERM`, i.e. the
+/// provider reached a point where it could stop cleanly, and false when it
+/// had to be killed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessCancelled {
+    pub graceful: bool,
+}
+
+impl std::fmt::Display for ProcessCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.graceful {
+            write!(f, "Cancelled; the provider stopped at a safe point")
+        } else {
+            write!(
+                f,
+                "Cancelled; the provider did not stop in time and was killed"
+            )
+        }
+    }
+}
+
+impl std::error::Error for ProcessCancelled {}
+
+/// A provider process that ran to completion and reported failure.
+///
+/// Backends attach this beneath their user-facing message so the transaction
+/// engine can classify the failure from the exit status and the provider's
+/// own output instead of from the wording of that message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandFailure {
+    pub program: String,
+    /// `None` when the process was terminated by a signal.
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandFailure {
+    pub fn new(program: &str, exit_code: Option<i32>, stderr: impl Into<String>) -> Self {
+        Self {
+            program: program.to_string(),
+            exit_code,
+            stdout: String::new(),
+            stderr: stderr.into(),
+        }
+    }
+
+    pub fn from_output(program: &str, output: &Output) -> Self {
+        Self {
+            program: program.to_string(),
+            exit_code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    /// Everything the provider printed, stderr first.
+    pub fn output(&self) -> String {
+        match (self.stderr.trim().is_empty(), self.stdout.trim().is_empty()) {
+            (_, true) => self.stderr.clone(),
+            (true, false) => self.stdout.clone(),
+            (false, false) => format!("{}\n{}", self.stderr, self.stdout),
+        }
+    }
+
+    /// Wrap in `message`, which stays the error's displayed text.
+    pub fn with_message(self, message: impl Into<String>) -> anyhow::Error {
+        anyhow::Error::new(self).context(message.into())
+    }
+}
+
+impl std::fmt::Display for CommandFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.exit_code {
+            Some(code) => write!(f, "{} exited with status {}", self.program, code),
+            None => write!(f, "{} was terminated by a signal", self.program),
+        }
+    }
+}
+
+impl std::error::Error for CommandFailure {}
+
+/// Prepare a provider command for `supervise`. Inside a cancellation scope
+/// it gets its own process group, so it can be terminated together with its
+/// children. Outside one it stays in LinGet's group, where a Ctrl-C at the
+/// terminal reaches it too. Either way it is killed if LinGet drops it.
+pub(crate) fn isolate_when_cancellable(command: &mut Command) -> &mut Command {
+    if CANCELLATION.try_with(|_| ()).is_ok() {
+        command.process_group(0);
+    }
+    command.kill_on_drop(true)
+}
+
+/// Like `Command::output`, but a cancellation in scope terminates the
+/// process together with its children.
+pub async fn output_cancellable(command: &mut Command) -> Result<Output> {
+    isolate_when_cancellable(command)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let child = command.spawn()?;
+    let pid = child.id();
+    let output = child.wait_with_output();
+    tokio::pin!(output);
+    supervise(pid, output).await
+}
+
+/// Wait for a provider process, signalling its group if the cancellation in
+/// scope is requested first.
+///
+/// Privileged commands run under pkexec belong to root and cannot be
+/// signalled; those are waited on to completion and the engine stops at the
+/// next step boundary instead.
+pub(crate) async fn supervise<T>(
+    pid: Option<u32>,
+    mut wait: Pin<&mut impl Future<Output = std::io::Result<T>>>,
+) -> Result<T> {
+    let Some(flag) = CANCELLATION.try_with(CancellationFlag::clone).ok() else {
+        return Ok(wait.await?);
+    };
+    tokio::select! {
+        result = wait.as_mut() => return Ok(result?),
+        _ = flag.cancelled() => {}
+    }
+    let Some(pid) = pid else {
+        return Ok(wait.await?);
+    };
+    if !signal_group(pid, libc::SIGTERM) {
+        warn!(
+            pid,
+            "Provider process could not be signalled; waiting for it"
+        );
+        return Ok(wait.await?);
+    }
+    let graceful = match tokio::time::timeout(TERMINATE_GRACE, wait.as_mut()).await {
+        Ok(_) => true,
+        Err(_) => {
+            signal_group(pid, libc::SIGKILL);
+            let _ = wait.await;
+            false
+        }
+    };
+    Err(ProcessCancelled { graceful }.into())
+}
+
+pub(crate) fn signal_group(pgid: u32, signal: libc::c_int) -> bool {
+    // Safety: killpg only sends a signal; callers only pass the pid of a
+    // child spawned in its own process group, so its id is the child's pid.
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
+}
 
 /// Strips ANSI escape codes (colors, cursor movements) from terminal output.
 pub fn strip_ansi(s: &str) -> String {
@@ -65,8 +248,7 @@ pub async fn run_streaming(
         "Starting streaming command"
     );
 
-    let mut child = Command::new(program)
-        .args(args)
+    let mut child = isolate_when_cancellable(Command::new(program).args(args))
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
         .spawn()
@@ -110,7 +292,15 @@ pub async fn run_streaming(
         }
     });
 
-    let status = child.wait().await.context("Failed to wait for command")?;
+    let pid = child.id();
+    let wait = child.wait();
+    tokio::pin!(wait);
+    let status = supervise(pid, wait).await;
+    if status.is_err() {
+        stdout_task.abort();
+        stderr_task.abort();
+    }
+    let status = status.context("Failed to wait for command")?;
 
     let _ = stdout_task.await;
     let _ = stderr_task.await;
@@ -187,6 +377,59 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn cancellation_terminates_the_process_group() {
+        let flag = CancellationFlag::default();
+        let cancel = flag.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancel.request();
+        });
+
+        let started = std::time::Instant::now();
+        let result = with_cancellation(
+            flag,
+            output_cancellable(Command::new("sh").args(["-c", "sleep 30 & wait"])),
+        )
+        .await;
+
+        let error = result.expect_err("a cancelled process must not report success");
+        assert_eq!(
+            error.downcast_ref::<ProcessCancelled>(),
+            Some(&ProcessCancelled { graceful: true })
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn only_cancellable_processes_leave_the_terminal_process_group() {
+        async fn child_group() -> i32 {
+            let output =
+                output_cancellable(Command::new("sh").args(["-c", "cut -d' ' -f5 /proc/$$/stat"]))
+                    .await
+                    .unwrap();
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse()
+                .unwrap()
+        }
+        // Safety: getpgrp has no preconditions.
+        let own = unsafe { libc::getpgrp() };
+
+        assert_eq!(child_group().await, own);
+        let isolated = with_cancellation(CancellationFlag::default(), child_group()).await;
+        assert_ne!(isolated, own);
+    }
+
+    #[tokio::test]
+    async fn processes_outside_a_cancellation_scope_run_to_completion() {
+        let output = output_cancellable(Command::new("echo").arg("done"))
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "done");
+    }
+
     #[tokio::test]
     async fn test_run_streaming_stderr() {
         let (tx, mut rx) = mpsc::channel(100);
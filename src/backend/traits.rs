@@ -1,3 +1,4 @@
+use super::dependency_graph::ProtectedPackage;
 use super::streaming::StreamLine;
 use crate::models::{Package, PackageSource, PackageStatus, Repository};
 use anyhow::Result;
@@ -66,14 +67,18 @@ pub enum BackendCapability {
     Downgrade,
     DowngradeToVersion,
     AvailableDowngradeVersions,
+    /// Install a package that is not installed yet at a chosen version.
+    InstallVersion,
     ListRepositories,
     AddRepository,
     RemoveRepository,
     CleanupCache,
     ListOrphanedPackages,
     ReverseDependencies,
+    DependencyTree,
     PackageCommands,
     CheckLockStatus,
+    Hold,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -112,6 +117,9 @@ impl BackendCapabilities {
     }
 
     pub fn status(&self, capability: BackendCapability) -> CapabilityStatus {
+        if let PackageSource::Plugin(id) = self.source {
+            return super::plugin::capability_status(id, capability);
+        }
         let supported = match capability {
             BackendCapability::Install
             | BackendCapability::Remove
@@ -154,6 +162,16 @@ impl BackendCapabilities {
                     | PackageSource::Cargo
                     | PackageSource::Npm
             ),
+            BackendCapability::InstallVersion => matches!(
+                self.source,
+                PackageSource::Apt
+                    | PackageSource::Pip
+                    | PackageSource::Pipx
+                    | PackageSource::Cargo
+                    | PackageSource::Npm
+                    | PackageSource::Conda
+                    | PackageSource::Mamba
+            ),
             BackendCapability::ListRepositories
             | BackendCapability::AddRepository
             | BackendCapability::RemoveRepository => matches!(
@@ -178,6 +196,13 @@ impl BackendCapabilities {
                     | PackageSource::Pacman
                     | PackageSource::Zypper
             ),
+            BackendCapability::DependencyTree => matches!(
+                self.source,
+                PackageSource::Apt
+                    | PackageSource::Dnf
+                    | PackageSource::Pacman
+                    | PackageSource::Npm
+            ),
             BackendCapability::PackageCommands => matches!(
                 self.source,
                 PackageSource::Apt
@@ -193,6 +218,14 @@ impl BackendCapabilities {
             BackendCapability::CheckLockStatus => {
                 matches!(self.source, PackageSource::Apt | PackageSource::Dnf)
             }
+            BackendCapability::Hold => matches!(
+                self.source,
+                PackageSource::Apt
+                    | PackageSource::Dnf
+                    | PackageSource::Pacman
+                    | PackageSource::Flatpak
+                    | PackageSource::Snap
+            ),
         };
 
         if supported {
@@ -220,6 +253,9 @@ impl BackendCapabilities {
             BackendCapability::AvailableDowngradeVersions => {
                 "Version history is not available for this source".to_string()
             }
+            BackendCapability::InstallVersion => {
+                "Installing a specific version is not supported for this source".to_string()
+            }
             BackendCapability::ListRepositories => {
                 "Repository listing is not supported for this source".to_string()
             }
@@ -238,12 +274,24 @@ impl BackendCapabilities {
             BackendCapability::ReverseDependencies => {
                 "Reverse dependency inspection is not supported for this source".to_string()
             }
+            BackendCapability::DependencyTree => {
+                "Dependency trees are not supported for this source".to_string()
+            }
             BackendCapability::PackageCommands => {
                 "Executable command inspection is not supported for this source".to_string()
             }
             BackendCapability::CheckLockStatus => {
                 "Lock status inspection is not supported for this source".to_string()
             }
+            BackendCapability::Hold if self.source == PackageSource::Npm => {
+                "npm cannot hold global packages: `npm install -g` and `npm update -g` ignore version pins outside a project".to_string()
+            }
+            BackendCapability::Hold if self.source == PackageSource::Pip => {
+                "pip has no native hold: constraints apply only to the pip runs that are given them".to_string()
+            }
+            BackendCapability::Hold => {
+                "This source has no native way to hold a package".to_string()
+            }
         }
     }
 }
@@ -297,7 +345,7 @@ impl SourceCapabilityContext {
         }
 
         match capability {
-            BackendCapability::Install => {
+            BackendCapability::Install | BackendCapability::InstallVersion => {
                 if package.status == PackageStatus::NotInstalled {
                     CapabilityStatus::supported()
                 } else {
@@ -388,6 +436,32 @@ pub trait PackageBackend: Send + Sync {
         self.update(name).await
     }
 
+    /// Install several packages in one provider invocation. Backends whose
+    /// tool accepts multiple targets override this; the default installs
+    /// them one at a time.
+    async fn install_batch(&self, names: &[String]) -> Result<()> {
+        for name in names {
+            self.install(name).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove several packages in one provider invocation.
+    async fn remove_batch(&self, names: &[String]) -> Result<()> {
+        for name in names {
+            self.remove(name).await?;
+        }
+        Ok(())
+    }
+
+    /// Update several packages in one provider invocation.
+    async fn update_batch(&self, names: &[String]) -> Result<()> {
+        for name in names {
+            self.update(name).await?;
+        }
+        Ok(())
+    }
+
     /// Downgrade/revert a package by name (best-effort; optional per backend)
     async fn downgrade(&self, _name: &str) -> Result<()> {
         anyhow::bail!("Downgrade is not supported for this source")
@@ -447,6 +521,21 @@ pub trait PackageBackend: Send + Sync {
         Ok(Vec::new())
     }
 
+    /// Get the installed packages the given package depends on
+    async fn get_dependencies(&self, _name: &str) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Names of the packages installed only to satisfy a dependency
+    async fn list_auto_installed(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Packages the provider refuses to remove (essential or protected)
+    async fn list_protected(&self) -> Result<Vec<ProtectedPackage>> {
+        Ok(Vec::new())
+    }
+
     #[allow(dead_code)]
     fn source(&self) -> PackageSource;
 
@@ -461,6 +550,22 @@ pub trait PackageBackend: Send + Sync {
     async fn check_lock_status(&self) -> LockStatus {
         LockStatus::default()
     }
+
+    /// Pin a package at its installed version using the provider's own hold
+    /// mechanism, so upgrades run outside LinGet respect it too
+    async fn hold(&self, _name: &str) -> Result<()> {
+        anyhow::bail!("This source has no native way to hold a package")
+    }
+
+    /// Release a hold placed with `hold`
+    async fn unhold(&self, _name: &str) -> Result<()> {
+        anyhow::bail!("This source has no native way to hold a package")
+    }
+
+    /// Names of the packages the provider currently holds
+    async fn list_holds(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
 }
 
 // We need async_trait for async trait methods
@@ -492,6 +597,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -517,6 +623,35 @@ mod tests {
             .is_supported());
     }
 
+    #[test]
+    fn hold_support_is_limited_to_native_pinning_tools() {
+        for source in [
+            PackageSource::Apt,
+            PackageSource::Dnf,
+            PackageSource::Pacman,
+            PackageSource::Flatpak,
+            PackageSource::Snap,
+        ] {
+            assert!(BackendCapabilities::for_source(source)
+                .status(BackendCapability::Hold)
+                .is_supported());
+        }
+        assert!(BackendCapabilities::for_source(PackageSource::Npm)
+            .status(BackendCapability::Hold)
+            .reason()
+            .is_some_and(|reason| reason.starts_with("npm cannot hold global packages")));
+        assert!(BackendCapabilities::for_source(PackageSource::Pip)
+            .status(BackendCapability::Hold)
+            .reason()
+            .is_some_and(|reason| reason.starts_with("pip has no native hold")));
+        assert_eq!(
+            BackendCapabilities::for_source(PackageSource::Cargo)
+                .status(BackendCapability::Hold)
+                .reason(),
+            Some("This source has no native way to hold a package")
+        );
+    }
+
     #[test]
     fn package_capability_status_includes_package_state() {
         let context = SourceCapabilityContext::available(PackageSource::Apt);
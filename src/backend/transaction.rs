@@ -1,24 +1,62 @@
-use super::PackageManager;
-use crate::models::{Package, PackageSource, PackageStatus};
+use super::history_tracker::process_is_alive;
+use super::hooks;
+use super::plugin::{self, PluginFailure, PluginMethod};
+use super::snapshot::{FilesystemSnapshot, SnapshotDriver};
+use super::streaming::{with_cancellation, CommandFailure, ProcessCancelled};
+use super::{BackendCapabilities, BackendCapability, PackageManager, RemovalImpact};
+use crate::models::history::{TaskQueueAction, TaskQueueEntry, TaskQueueStatus};
+use crate::models::{HookConfig, HookStage, Package, PackageSource, PackageStatus, SnapshotConfig};
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::fs;
 use tokio::process::Command;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{Mutex, MutexGuard, Notify, RwLock};
 use uuid::Uuid;
 
 pub const TRANSACTION_SCHEMA_VERSION: u16 = 1;
 pub const PLAN_TTL_SECONDS: i64 = 120;
-pub const STABLE_PROVIDERS: [PackageSource; 3] = [
+pub const STABLE_PROVIDERS: [PackageSource; 4] = [
     PackageSource::Apt,
+    PackageSource::Dnf,
     PackageSource::Flatpak,
     PackageSource::Npm,
 ];
 
+/// Beta providers whose queued mutations are verified against a fresh
+/// inventory. They run without a reviewed plan, so the expected change is
+/// derived from the inventory listed just before the mutation.
+pub const VERIFIED_BETA_PROVIDERS: [PackageSource; 5] = [
+    PackageSource::Pip,
+    PackageSource::Pipx,
+    PackageSource::Cargo,
+    PackageSource::Snap,
+    PackageSource::Brew,
+];
+
+/// Whether queued tasks for `source` are verified without a reviewed plan:
+/// the `VERIFIED_BETA_PROVIDERS` and every plugin, which must be able to list
+/// its inventory.
+pub fn verifies_unreviewed(source: PackageSource) -> bool {
+    VERIFIED_BETA_PROVIDERS.contains(&source) || matches!(source, PackageSource::Plugin(_))
+}
+
+/// Operation a finished queue entry can be rolled back from: a completed,
+/// single-provider reviewed plan whose provider manages rollback.
+pub fn rollback_operation_id(entry: &TaskQueueEntry) -> Option<String> {
+    if entry.status != TaskQueueStatus::Completed || entry.reviewed_bundle_json.is_some() {
+        return None;
+    }
+    let plan: ProviderPlan = serde_json::from_str(entry.reviewed_plan_json.as_deref()?).ok()?;
+    (ProviderDescriptor::for_source(plan.provider.source).rollback
+        == RollbackCapability::ProviderManaged)
+        .then_some(plan.operation_id)
+}
+
 /// Read-only lookup used to restore actionable queue diagnostics after a
 /// restart. Queue entries historically persisted only `safe_message`, while
 /// the transaction journal retained the provider's actual diagnostic.
@@ -38,6 +76,9 @@ pub enum OperationAction {
     Install,
     Remove,
     Update,
+    /// Reverse a completed operation from its receipt. Each target is either
+    /// removed (no `available_version`) or put back at `available_version`.
+    Rollback,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -46,6 +87,10 @@ pub struct PackageRef {
     pub source: PackageSource,
     pub installed_version: Option<String>,
     pub available_version: Option<String>,
+    /// Install or update to exactly `available_version` with the provider's
+    /// versioned install, instead of letting it pick the newest version.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
 impl PackageRef {
@@ -60,9 +105,24 @@ impl PackageRef {
             source: package.source,
             installed_version,
             available_version: package.available_version.clone(),
+            pinned: false,
+        }
+    }
+
+    /// A target installed or updated to exactly `version`.
+    pub fn pinned(package: &Package, version: &str) -> Self {
+        Self {
+            available_version: Some(version.to_string()),
+            pinned: true,
+            ..Self::from_package(package)
         }
     }
 
+    /// The version a pinned target is installed at.
+    fn pin(&self) -> Option<&str> {
+        self.available_version.as_deref().filter(|_| self.pinned)
+    }
+
     fn as_package(&self, action: OperationAction) -> Package {
         Package {
             name: self.name.clone(),
@@ -74,6 +134,10 @@ impl PackageRef {
                 OperationAction::Install => PackageStatus::NotInstalled,
                 OperationAction::Remove => PackageStatus::Installed,
                 OperationAction::Update => PackageStatus::UpdateAvailable,
+                OperationAction::Rollback if self.installed_version.is_some() => {
+                    PackageStatus::Installed
+                }
+                OperationAction::Rollback => PackageStatus::NotInstalled,
             },
             size: None,
             homepage: None,
@@ -83,6 +147,7 @@ impl PackageRef {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 }
@@ -130,6 +195,27 @@ impl OperationRequest {
         }
         Ok(source)
     }
+
+    /// Split a request that may span several package sources into one
+    /// single-source request per provider, in execution order (system
+    /// providers first, following `PackageSource` ordering).
+    pub fn split_by_source(&self) -> Vec<OperationRequest> {
+        let mut groups: Vec<(PackageSource, Vec<PackageRef>)> = Vec::new();
+        for target in &self.targets {
+            match groups
+                .iter_mut()
+                .find(|(source, _)| *source == target.source)
+            {
+                Some((_, targets)) => targets.push(target.clone()),
+                None => groups.push((target.source, vec![target.clone()])),
+            }
+        }
+        groups.sort_by_key(|(source, _)| *source);
+        groups
+            .into_iter()
+            .map(|(_, targets)| OperationRequest::new(self.action, targets, self.requested_by))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -169,6 +255,8 @@ pub enum PrivilegeRequirement {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CancellationSupport {
+    /// A running provider command can be terminated: its downloads and
+    /// pulls are discarded without leaving the package half-installed.
     Cooperative,
     BetweenStepsOnly,
     NotSafe,
@@ -193,22 +281,47 @@ pub struct ProviderDescriptor {
 impl ProviderDescriptor {
     pub fn for_source(source: PackageSource) -> Self {
         match source {
-            PackageSource::Apt => Self {
+            PackageSource::Apt | PackageSource::Dnf => Self {
                 source,
                 tier: ProviderTier::Stable,
                 fidelity: PlanFidelity::Exact,
                 privilege: PrivilegeRequirement::Required,
                 cancellation: CancellationSupport::BetweenStepsOnly,
-                rollback: RollbackCapability::Unsupported,
+                rollback: RollbackCapability::ProviderManaged,
+            },
+            PackageSource::Flatpak => Self {
+                source,
+                tier: ProviderTier::Stable,
+                fidelity: PlanFidelity::BestEffort,
+                privilege: PrivilegeRequirement::MayRequire,
+                cancellation: CancellationSupport::Cooperative,
+                rollback: RollbackCapability::ProviderManaged,
             },
-            PackageSource::Flatpak | PackageSource::Npm => Self {
+            PackageSource::Npm => Self {
                 source,
                 tier: ProviderTier::Stable,
                 fidelity: PlanFidelity::BestEffort,
                 privilege: PrivilegeRequirement::MayRequire,
+                cancellation: CancellationSupport::Cooperative,
+                rollback: RollbackCapability::Unsupported,
+            },
+            PackageSource::Pip => Self {
+                source,
+                tier: ProviderTier::Beta,
+                fidelity: PlanFidelity::BestEffort,
+                privilege: PrivilegeRequirement::MayRequire,
+                cancellation: CancellationSupport::Cooperative,
+                rollback: RollbackCapability::Unsupported,
+            },
+            PackageSource::Pacman | PackageSource::Zypper => Self {
+                source,
+                tier: ProviderTier::Beta,
+                fidelity: PlanFidelity::Exact,
+                privilege: PrivilegeRequirement::Required,
                 cancellation: CancellationSupport::BetweenStepsOnly,
                 rollback: RollbackCapability::Unsupported,
             },
+            PackageSource::Plugin(id) => super::plugin::descriptor(id),
             PackageSource::Aur | PackageSource::AppImage => Self {
                 source,
                 tier: ProviderTier::DetectionOnly,
@@ -245,15 +358,230 @@ pub struct ProviderPlan {
     pub inventory_fingerprint: String,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    /// Operation this plan reverses, for `OperationAction::Rollback` plans.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<String>,
+    /// Provider-specific argument that restores a package to an earlier
+    /// state, keyed by package name: the version for APT and DNF, the
+    /// deployed commit for Flatpak. Update plans record the pre-update
+    /// Flatpak commit here so the operation can be rolled back later.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub restore_points: BTreeMap<String, String>,
+    /// What a removal breaks or takes with it beyond its targets, for
+    /// `OperationAction::Remove` plans on providers that can answer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub removal_impact: Option<RemovalImpact>,
 }
 
 impl ProviderPlan {
     pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
         now >= self.expires_at
     }
+
+    /// What to tell the user when this plan's risk is Blocked.
+    pub fn blocked_reason(&self) -> String {
+        match self
+            .removal_impact
+            .as_ref()
+            .filter(|impact| impact.is_blocked())
+        {
+            Some(impact) => impact.refusal(),
+            None if self.action == OperationAction::Rollback => {
+                format!("{} rollback plan is blocked", self.provider.source)
+            }
+            None => format!("{} provider plan is blocked", self.provider.source),
+        }
+    }
+
+    /// Queue entry for a reviewed rollback plan. The row is labelled with
+    /// the dominant step so the queue reads as what will actually happen.
+    pub fn rollback_queue_entry(&self) -> Result<TaskQueueEntry, serde_json::Error> {
+        let action = if self
+            .targets
+            .iter()
+            .all(|target| target.available_version.is_none())
+        {
+            TaskQueueAction::Remove
+        } else if self
+            .targets
+            .iter()
+            .all(|target| target.installed_version.is_none())
+        {
+            TaskQueueAction::Install
+        } else {
+            TaskQueueAction::Update
+        };
+        let package_name = if self.targets.len() == 1 {
+            format!("roll back {}", self.targets[0].name)
+        } else {
+            format!(
+                "roll back {} {} packages",
+                self.targets.len(),
+                self.provider.source
+            )
+        };
+        let mut entry = TaskQueueEntry::new(
+            action,
+            format!("rollback:{}", self.operation_id),
+            package_name,
+            self.provider.source,
+        );
+        entry.reviewed_operation_id = Some(self.operation_id.clone());
+        entry.reviewed_plan_json = Some(serde_json::to_string(self)?);
+        Ok(entry)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// A package whose expected change differs between the reviewed and the
+/// fresh plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeRevision {
+    pub reviewed: PackageChange,
+    pub fresh: PackageChange,
+}
+
+/// What moved between a reviewed plan and its re-plan, keyed by package name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanDiff {
+    /// Changes only the fresh plan makes, e.g. a newly pulled-in dependency.
+    pub added: Vec<PackageChange>,
+    /// Reviewed changes the fresh plan no longer makes.
+    pub removed: Vec<PackageChange>,
+    /// Packages both plans change, but from or to a different version.
+    pub reversioned: Vec<ChangeRevision>,
+}
+
+impl PlanDiff {
+    pub fn between(reviewed: &ProviderPlan, fresh: &ProviderPlan) -> Self {
+        let find = |plan: &ProviderPlan, name: &str| {
+            plan.expected_changes
+                .iter()
+                .find(|change| change.name == name)
+                .cloned()
+        };
+        let mut diff = Self::default();
+        for change in &fresh.expected_changes {
+            match find(reviewed, &change.name) {
+                None => diff.added.push(change.clone()),
+                Some(before) if before != *change => diff.reversioned.push(ChangeRevision {
+                    reviewed: before,
+                    fresh: change.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.removed = reviewed
+            .expected_changes
+            .iter()
+            .filter(|change| find(fresh, &change.name).is_none())
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// [`PlanDiff::between`] for every provider of two bundles. A provider
+    /// only one bundle plans counts as wholly added or removed.
+    pub fn between_bundles(reviewed: &TransactionBundle, fresh: &TransactionBundle) -> Self {
+        let plan_for = |bundle: &TransactionBundle, source: PackageSource| {
+            bundle
+                .plans
+                .iter()
+                .find(|plan| plan.provider.source == source)
+                .cloned()
+        };
+        let mut diff = Self::default();
+        for plan in &fresh.plans {
+            match plan_for(reviewed, plan.provider.source) {
+                Some(before) => {
+                    let moved = Self::between(&before, plan);
+                    diff.added.extend(moved.added);
+                    diff.removed.extend(moved.removed);
+                    diff.reversioned.extend(moved.reversioned);
+                }
+                None => diff.added.extend(plan.expected_changes.iter().cloned()),
+            }
+        }
+        for plan in &reviewed.plans {
+            if plan_for(fresh, plan.provider.source).is_none() {
+                diff.removed.extend(plan.expected_changes.iter().cloned());
+            }
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.reversioned.is_empty()
+    }
+
+    /// One line for queue rows and status bars, e.g.
+    /// `+libfoo (new 1.2); -bar; ~vim (9.0 → 9.1, was 9.0 → 9.0.2)`.
+    pub fn summary(&self, action: OperationAction) -> String {
+        if self.is_empty() {
+            return "no package changes moved".to_string();
+        }
+        let added = self
+            .added
+            .iter()
+            .map(|change| format!("+{} ({})", change.name, describe_change(action, change)));
+        let removed = self
+            .removed
+            .iter()
+            .map(|change| format!("-{}", change.name));
+        let reversioned = self.reversioned.iter().map(|revision| {
+            format!(
+                "~{} ({}, was {})",
+                revision.fresh.name,
+                describe_change(action, &revision.fresh),
+                describe_change(action, &revision.reviewed)
+            )
+        });
+        added
+            .chain(removed)
+            .chain(reversioned)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// Human description of one expected change, e.g. `1.0 → 2.0` or
+/// `new 1.2`. A missing target means removal only for actions that remove.
+pub fn describe_change(action: OperationAction, change: &PackageChange) -> String {
+    match (&change.before, &change.after) {
+        (Some(before), Some(after)) => format!("{before} → {after}"),
+        (None, Some(after)) => format!("new {after}"),
+        (Some(before), None)
+            if matches!(action, OperationAction::Remove | OperationAction::Rollback) =>
+        {
+            format!("{before} → removed")
+        }
+        (Some(before), None) => format!("{before} → latest"),
+        (None, None) => "version decided by provider".to_string(),
+    }
+}
+
+/// A reviewed plan that expired or whose inventory moved, re-planned against
+/// the current inventory. The user re-approves `diff` rather than the whole
+/// plan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StalePlan {
+    /// `PlanExpired` or `PlanChanged`.
+    pub reason: ProviderErrorCode,
+    pub reviewed_operation_id: String,
+    pub reviewed_risk: RiskLevel,
+    pub fresh: ProviderPlan,
+    pub risk: RiskAssessment,
+    pub diff: PlanDiff,
+}
+
+impl StalePlan {
+    /// Whether anything the user approved moved. A plan that merely aged out,
+    /// or whose inventory changed elsewhere, carries the same approval over.
+    pub fn needs_approval(&self) -> bool {
+        !self.diff.is_empty() || self.risk.level > self.reviewed_risk
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum RiskLevel {
     Low,
     Caution,
@@ -268,6 +596,14 @@ pub enum RiskReason {
     BestEffortPlan,
     ProviderNotStable,
     UnsupportedProvider,
+    /// Installed packages depend on a target, directly or transitively.
+    BreaksDependents,
+    /// Dependencies nothing else needs will be removed too, or left for
+    /// the provider's autoremove.
+    AutoRemovesPackages,
+    /// The removal reaches a package the provider marks essential or
+    /// protected. Such a plan is blocked.
+    RemovesProtectedPackage,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -280,7 +616,12 @@ pub struct RiskAssessment {
 impl RiskAssessment {
     pub fn for_plan(plan: &ProviderPlan) -> Self {
         let mut reasons = Vec::new();
-        if plan.action == OperationAction::Remove {
+        let rollback_removes = plan.action == OperationAction::Rollback
+            && plan
+                .expected_changes
+                .iter()
+                .any(|change| change.after.is_none());
+        if plan.action == OperationAction::Remove || rollback_removes {
             reasons.push(RiskReason::RemovesPackages);
         }
         if plan.provider.privilege == PrivilegeRequirement::Required {
@@ -295,8 +636,21 @@ impl RiskAssessment {
         if plan.provider.fidelity == PlanFidelity::Unsupported {
             reasons.push(RiskReason::UnsupportedProvider);
         }
+        if let Some(impact) = &plan.removal_impact {
+            if !impact.dependents.is_empty() {
+                reasons.push(RiskReason::BreaksDependents);
+            }
+            if !impact.auto_removed.is_empty() {
+                reasons.push(RiskReason::AutoRemovesPackages);
+            }
+            if impact.is_blocked() {
+                reasons.push(RiskReason::RemovesProtectedPackage);
+            }
+        }
 
-        let level = if reasons.contains(&RiskReason::UnsupportedProvider) {
+        let level = if reasons.contains(&RiskReason::UnsupportedProvider)
+            || reasons.contains(&RiskReason::RemovesProtectedPackage)
+        {
             RiskLevel::Blocked
         } else if reasons.contains(&RiskReason::RemovesPackages) {
             RiskLevel::High
@@ -311,6 +665,94 @@ impl RiskAssessment {
             requires_explicit_confirmation: matches!(level, RiskLevel::High | RiskLevel::Blocked),
         }
     }
+
+    /// The risk of a bundle is its riskiest plan; reasons are the union of
+    /// every plan's reasons so one review covers all of them.
+    pub fn aggregate<'a>(risks: impl IntoIterator<Item = &'a RiskAssessment>) -> Self {
+        let mut level = RiskLevel::Low;
+        let mut reasons = Vec::new();
+        let mut requires_explicit_confirmation = false;
+        for risk in risks {
+            level = level.max(risk.level);
+            requires_explicit_confirmation |= risk.requires_explicit_confirmation;
+            for reason in &risk.reasons {
+                if !reasons.contains(reason) {
+                    reasons.push(reason.clone());
+                }
+            }
+        }
+        Self {
+            level,
+            reasons,
+            requires_explicit_confirmation,
+        }
+    }
+}
+
+/// Per-provider plans reviewed and executed together as one change set, e.g.
+/// "update everything" across APT, Flatpak and npm.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionBundle {
+    pub id: String,
+    pub action: OperationAction,
+    pub plans: Vec<ProviderPlan>,
+    pub risk: RiskAssessment,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TransactionBundle {
+    pub fn target_count(&self) -> usize {
+        self.plans.iter().map(|plan| plan.targets.len()).sum()
+    }
+
+    pub fn targets(&self) -> impl Iterator<Item = &PackageRef> {
+        self.plans.iter().flat_map(|plan| plan.targets.iter())
+    }
+
+    /// A single queue entry carrying the whole reviewed bundle, so the queue
+    /// shows one change set instead of one row per provider.
+    pub fn queue_entry(&self) -> Result<TaskQueueEntry, serde_json::Error> {
+        let action = match self.action {
+            OperationAction::Install => TaskQueueAction::Install,
+            OperationAction::Remove => TaskQueueAction::Remove,
+            OperationAction::Update | OperationAction::Rollback => TaskQueueAction::Update,
+        };
+        let providers: Vec<String> = self
+            .plans
+            .iter()
+            .map(|plan| plan.provider.source.to_string())
+            .collect();
+        let source = self
+            .plans
+            .first()
+            .map(|plan| plan.provider.source)
+            .unwrap_or(PackageSource::Apt);
+        let mut entry = TaskQueueEntry::new(
+            action,
+            format!("bundle:{}", self.id),
+            format!(
+                "{} packages across {}",
+                self.target_count(),
+                providers.join(", ")
+            ),
+            source,
+        );
+        entry.reviewed_operation_id = Some(self.id.clone());
+        entry.reviewed_bundle_json = Some(serde_json::to_string(self)?);
+        Ok(entry)
+    }
+}
+
+/// Combined verification of a bundle: one receipt per provider plan that
+/// ran, plus the operations that failed or were skipped after a failure.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleSummary {
+    pub bundle_id: String,
+    pub outcome: VerificationOutcome,
+    pub receipts: Vec<VerificationReceipt>,
+    pub failed: Vec<ProviderError>,
+    pub skipped_operation_ids: Vec<String>,
+    pub verified_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -349,157 +791,295 @@ pub enum ProviderErrorCode {
     PlanExpired,
     PlanChanged,
     VerificationMismatch,
+    /// A filesystem snapshot was enabled for the plan but could not be
+    /// taken, so the plan did not run.
+    SnapshotFailed,
+    /// A pre hook exited non-zero, so the plan did not run.
+    HookVetoed,
     Protocol,
     Persistence,
     Unknown,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct ProviderError {
+/// How a failure is reported to the user: what went wrong, whether running
+/// the same operation again can help, and what to do first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureClass {
     pub code: ProviderErrorCode,
-    pub provider: Option<PackageSource>,
-    pub safe_message: String,
-    pub diagnostic: String,
+    pub safe_message: &'static str,
     pub retryable: bool,
-    pub recovery_actions: Vec<String>,
+    pub recovery: &'static str,
 }
 
-impl ProviderError {
-    pub fn protocol(provider: Option<PackageSource>, message: impl Into<String>) -> Self {
-        let message = message.into();
-        Self {
-            code: ProviderErrorCode::Protocol,
-            provider,
-            safe_message: message.clone(),
-            diagnostic: message,
-            retryable: false,
-            recovery_actions: vec!["Review the selected provider and packages".to_string()],
-        }
-    }
+impl FailureClass {
+    /// A dependency conflict caused by the runtime (node, python, ...) rather
+    /// than by other packages.
+    pub const RUNTIME_MISMATCH: Self = Self {
+        code: ProviderErrorCode::DependencyConflict,
+        safe_message: "The package runtime requirements are not satisfied",
+        retryable: false,
+        recovery: "Upgrade the required runtime or choose a compatible package version",
+    };
 
-    pub fn classify(provider: PackageSource, diagnostic: impl Into<String>) -> Self {
-        let diagnostic = diagnostic.into();
+    /// The generic keyword heuristic, for output no provider classifier
+    /// recognises. It matches words anywhere in the text, so a package name
+    /// can sway it; provider classifiers take precedence.
+    fn guess(diagnostic: &str) -> Self {
         let lower = diagnostic.to_ascii_lowercase();
-        let (code, safe_message, retryable, recovery) =
+        let code =
             if lower.contains("cancel") && (lower.contains("auth") || lower.contains("pkexec")) {
-                (
-                    ProviderErrorCode::AuthorizationCancelled,
-                    "Authorization was cancelled",
-                    true,
-                    "Approve the authorization prompt and retry",
-                )
+                ProviderErrorCode::AuthorizationCancelled
             } else if lower.contains("no authentication agent")
                 || lower.contains("no polkit agent")
                 || lower.contains("cannot open display")
             {
-                (
-                    ProviderErrorCode::NoPrivilegeAgent,
-                    "No authorization agent is available",
-                    true,
-                    "Start an authorization agent or use an interactive session",
-                )
+                ProviderErrorCode::NoPrivilegeAgent
             } else if lower.contains("permission")
                 || lower.contains("not authorized")
                 || lower.contains("eacces")
             {
-                (
-                    ProviderErrorCode::AuthorizationDenied,
-                    "The package manager denied authorization",
-                    true,
-                    "Check your privileges and retry",
-                )
+                ProviderErrorCode::AuthorizationDenied
             } else if lower.contains("lock") || lower.contains("another process") {
-                (
-                    ProviderErrorCode::LockBusy,
-                    "The package manager is busy",
-                    true,
-                    "Wait for the other package operation to finish",
-                )
+                ProviderErrorCode::LockBusy
             } else if lower.contains("ebadengine")
                 || lower.contains("unsupported engine")
                 || (lower.contains("notsup") && lower.contains("required:"))
             {
-                (
-                    ProviderErrorCode::DependencyConflict,
-                    "The package runtime requirements are not satisfied",
-                    false,
-                    "Upgrade the required runtime or choose a compatible package version",
-                )
+                return Self::RUNTIME_MISMATCH;
             } else if lower.contains("dependency") || lower.contains("conflict") {
-                (
-                    ProviderErrorCode::DependencyConflict,
-                    "Package dependencies conflict",
-                    false,
-                    "Inspect the provider output before changing dependencies",
-                )
+                ProviderErrorCode::DependencyConflict
             } else if lower.contains("signature")
                 && (lower.contains("invalid") || lower.contains("not verified"))
             {
-                (
-                    ProviderErrorCode::SignatureInvalid,
-                    "The repository signature could not be verified",
-                    false,
-                    "Refresh trusted repository keys before retrying",
-                )
+                ProviderErrorCode::SignatureInvalid
             } else if lower.contains("repository")
                 && (lower.contains("unavailable") || lower.contains("no release file"))
             {
-                (
-                    ProviderErrorCode::RepositoryUnavailable,
-                    "The configured repository is unavailable",
-                    true,
-                    "Check the repository configuration and refresh metadata",
-                )
+                ProviderErrorCode::RepositoryUnavailable
             } else if lower.contains("timeout") || lower.contains("timed out") {
-                (
-                    ProviderErrorCode::Timeout,
-                    "The provider operation timed out",
-                    true,
-                    "Check the network and retry",
-                )
+                ProviderErrorCode::Timeout
             } else if lower.contains("interrupted") || lower.contains("terminated by signal") {
-                (
-                    ProviderErrorCode::Interrupted,
-                    "The provider operation was interrupted",
-                    true,
-                    "Refresh package state before retrying",
-                )
+                ProviderErrorCode::Interrupted
             } else if lower.contains("network") || lower.contains("resolve") {
-                (
-                    ProviderErrorCode::Network,
-                    "The provider could not reach its repository",
-                    true,
-                    "Check the network and refresh provider metadata",
-                )
+                ProviderErrorCode::Network
             } else if lower.contains("not found") || lower.contains("404") {
-                (
-                    ProviderErrorCode::NotFound,
-                    "The package was not found",
-                    false,
-                    "Verify the package name and provider",
-                )
+                ProviderErrorCode::NotFound
             } else if lower.contains("no space") || lower.contains("disk full") {
-                (
-                    ProviderErrorCode::DiskFull,
-                    "There is not enough disk space",
-                    true,
-                    "Free disk space and retry",
-                )
+                ProviderErrorCode::DiskFull
             } else {
-                (
-                    ProviderErrorCode::Unknown,
-                    "The provider operation failed",
-                    true,
-                    "Review the provider output and retry",
-                )
+                ProviderErrorCode::Unknown
             };
+        Self::from(code)
+    }
+}
+
+impl From<ProviderErrorCode> for FailureClass {
+    fn from(code: ProviderErrorCode) -> Self {
+        let (safe_message, retryable, recovery) = match code {
+            ProviderErrorCode::AuthorizationCancelled => (
+                "Authorization was cancelled",
+                true,
+                "Approve the authorization prompt and retry",
+            ),
+            ProviderErrorCode::NoPrivilegeAgent => (
+                "No authorization agent is available",
+                true,
+                "Start an authorization agent or use an interactive session",
+            ),
+            ProviderErrorCode::AuthorizationDenied => (
+                "The package manager denied authorization",
+                true,
+                "Check your privileges and retry",
+            ),
+            ProviderErrorCode::LockBusy => (
+                "The package manager is busy",
+                true,
+                "Wait for the other package operation to finish",
+            ),
+            ProviderErrorCode::DependencyConflict => (
+                "Package dependencies conflict",
+                false,
+                "Inspect the provider output before changing dependencies",
+            ),
+            ProviderErrorCode::SignatureInvalid => (
+                "The repository signature could not be verified",
+                false,
+                "Refresh trusted repository keys before retrying",
+            ),
+            ProviderErrorCode::RepositoryUnavailable => (
+                "The configured repository is unavailable",
+                true,
+                "Check the repository configuration and refresh metadata",
+            ),
+            ProviderErrorCode::Timeout => (
+                "The provider operation timed out",
+                true,
+                "Check the network and retry",
+            ),
+            ProviderErrorCode::Interrupted => (
+                "The provider operation was interrupted",
+                true,
+                "Refresh package state before retrying",
+            ),
+            ProviderErrorCode::Network => (
+                "The provider could not reach its repository",
+                true,
+                "Check the network and refresh provider metadata",
+            ),
+            ProviderErrorCode::NotFound => (
+                "The package was not found",
+                false,
+                "Verify the package name and provider",
+            ),
+            ProviderErrorCode::DiskFull => (
+                "There is not enough disk space",
+                true,
+                "Free disk space and retry",
+            ),
+            ProviderErrorCode::HookVetoed => (
+                "A pre-operation hook cancelled the operation",
+                true,
+                "Check the hook's output in the task log",
+            ),
+            ProviderErrorCode::SnapshotFailed => (
+                "The pre-transaction snapshot could not be taken",
+                true,
+                "Check the snapshot tool, or disable snapshots in config.toml",
+            ),
+            _ => (
+                "The provider operation failed",
+                true,
+                "Review the provider output and retry",
+            ),
+        };
         Self {
             code,
+            safe_message,
+            retryable,
+            recovery,
+        }
+    }
+}
+
+/// Ask the provider's own classifier about a failure. `exit_code` is `None`
+/// when only the text is known.
+fn classify_provider_output(
+    provider: PackageSource,
+    exit_code: Option<i32>,
+    output: &str,
+) -> Option<FailureClass> {
+    match provider {
+        PackageSource::Apt => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::apt::classify_failure(exit_code, output)),
+        PackageSource::Dnf => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::dnf::classify_failure(exit_code, output)),
+        PackageSource::Pacman => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::pacman::classify_failure(exit_code, output)),
+        PackageSource::Zypper => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::zypper::classify_failure(exit_code, output)),
+        PackageSource::Snap => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::snap::classify_failure(exit_code, output)),
+        PackageSource::Aur => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::aur::classify_failure(exit_code, output)),
+        PackageSource::Deb => super::pkexec::classify_failure(exit_code, output)
+            .or_else(|| super::deb::classify_failure(exit_code, output)),
+        PackageSource::Flatpak => super::flatpak::classify_failure(exit_code, output),
+        PackageSource::Npm => super::npm::classify_failure(exit_code, output),
+        PackageSource::Pip => super::pip::classify_failure(exit_code, output),
+        PackageSource::Pipx => super::pipx::classify_failure(exit_code, output),
+        PackageSource::Cargo => super::cargo::classify_failure(exit_code, output),
+        PackageSource::Brew => super::brew::classify_failure(exit_code, output),
+        PackageSource::Conda => super::conda::classify_failure(exit_code, output),
+        PackageSource::Mamba => super::mamba::classify_failure(exit_code, output),
+        PackageSource::Dart => super::dart::classify_failure(exit_code, output),
+        PackageSource::AppImage => super::appimage::classify_failure(exit_code, output),
+        // No backend runs these here, and plugins report their own codes.
+        PackageSource::Winget
+        | PackageSource::Chocolatey
+        | PackageSource::Scoop
+        | PackageSource::Plugin(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderError {
+    pub code: ProviderErrorCode,
+    pub provider: Option<PackageSource>,
+    pub safe_message: String,
+    pub diagnostic: String,
+    pub retryable: bool,
+    pub recovery_actions: Vec<String>,
+}
+
+impl ProviderError {
+    pub fn protocol(provider: Option<PackageSource>, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self {
+            code: ProviderErrorCode::Protocol,
+            provider,
+            safe_message: message.clone(),
+            diagnostic: message,
+            retryable: false,
+            recovery_actions: vec!["Review the selected provider and packages".to_string()],
+        }
+    }
+
+    /// Classify a failure from its text alone.
+    ///
+    /// The provider's own classifier reads the text first; the generic
+    /// keyword heuristic only decides when it recognises nothing.
+    pub fn classify(provider: PackageSource, diagnostic: impl Into<String>) -> Self {
+        let diagnostic = diagnostic.into();
+        let class = classify_provider_output(provider, None, &diagnostic)
+            .unwrap_or_else(|| FailureClass::guess(&diagnostic));
+        Self::from_class(provider, class, diagnostic)
+    }
+
+    /// Classify a backend error, using the exit status and output of the
+    /// provider command when the backend attached them.
+    pub fn from_error(provider: PackageSource, error: &anyhow::Error) -> Self {
+        if let Some(PluginFailure {
+            code: Some(code), ..
+        }) = error.downcast_ref::<PluginFailure>()
+        {
+            return Self::from_class(provider, FailureClass::from(*code), error.to_string());
+        }
+        match error.downcast_ref::<CommandFailure>() {
+            Some(failure) => Self::from_failure(provider, failure, error.to_string()),
+            None => Self::classify(provider, error.to_string()),
+        }
+    }
+
+    pub fn from_failure(
+        provider: PackageSource,
+        failure: &CommandFailure,
+        diagnostic: impl Into<String>,
+    ) -> Self {
+        let diagnostic = diagnostic.into();
+        let class = match failure.exit_code {
+            None => FailureClass::from(ProviderErrorCode::Interrupted),
+            Some(code) => {
+                let output = failure.output();
+                classify_provider_output(provider, Some(code), &output).unwrap_or_else(|| {
+                    FailureClass::guess(if output.trim().is_empty() {
+                        &diagnostic
+                    } else {
+                        &output
+                    })
+                })
+            }
+        };
+        Self::from_class(provider, class, diagnostic)
+    }
+
+    fn from_class(provider: PackageSource, class: FailureClass, diagnostic: String) -> Self {
+        Self {
+            code: class.code,
             provider: Some(provider),
-            safe_message: safe_message.to_string(),
+            safe_message: class.safe_message.to_string(),
             diagnostic,
-            retryable,
-            recovery_actions: vec![recovery.to_string()],
+            retryable: class.retryable,
+            recovery_actions: vec![class.recovery.to_string()],
         }
     }
 
@@ -550,7 +1130,7 @@ impl OperationState {
                     Running,
                     Verifying | Partial | Failed | Cancelled | Interrupted
                 )
-                | (Verifying, Succeeded | Partial | Failed)
+                | (Verifying, Succeeded | Partial | Failed | Interrupted)
         )
     }
 }
@@ -564,19 +1144,54 @@ pub struct OperationRecord {
     pub receipt: Option<VerificationReceipt>,
     pub error: Option<ProviderError>,
     pub updated_at: DateTime<Utc>,
+    /// Process executing the plan while it is `Running` or `Verifying`, so a
+    /// second LinGet instance does not recover an operation that is still
+    /// in progress.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_pid: Option<u32>,
+    /// Set when the engine verified an operation orphaned by a LinGet
+    /// process that exited mid-run, instead of the operation finishing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovered_at: Option<DateTime<Utc>>,
+    /// Filesystem snapshot taken just before the plan ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<FilesystemSnapshot>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TransactionStore {
-    pub schema_version: u16,
-    pub operations: Vec<OperationRecord>,
+impl OperationRecord {
+    /// Running or verifying with no live process behind it.
+    fn is_orphaned(&self) -> bool {
+        matches!(
+            self.state,
+            OperationState::Running | OperationState::Verifying
+        ) && self.owner_pid.is_none_or(|pid| !process_is_alive(pid))
+    }
 }
 
-impl Default for TransactionStore {
-    fn default() -> Self {
-        Self {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleRecord {
+    pub bundle_id: String,
+    pub state: OperationState,
+    pub operation_ids: Vec<String>,
+    pub risk: RiskAssessment,
+    pub summary: Option<BundleSummary>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransactionStore {
+    pub schema_version: u16,
+    pub operations: Vec<OperationRecord>,
+    #[serde(default)]
+    pub bundles: Vec<BundleRecord>,
+}
+
+impl Default for TransactionStore {
+    fn default() -> Self {
+        Self {
             schema_version: TRANSACTION_SCHEMA_VERSION,
             operations: Vec::new(),
+            bundles: Vec::new(),
         }
     }
 }
@@ -608,6 +1223,7 @@ impl TransactionStore {
         Ok(Self {
             schema_version: TRANSACTION_SCHEMA_VERSION,
             operations,
+            bundles: Vec::new(),
         })
     }
 
@@ -630,15 +1246,97 @@ impl TransactionStore {
 }
 
 #[derive(Clone, Default)]
-pub struct CancellationFlag(Arc<AtomicBool>);
+pub struct CancellationFlag(Arc<CancellationState>);
+
+#[derive(Default)]
+struct CancellationState {
+    requested: AtomicBool,
+    notify: Notify,
+}
 
 impl CancellationFlag {
     pub fn request(&self) {
-        self.0.store(true, Ordering::Release);
+        self.0.requested.store(true, Ordering::Release);
+        self.0.notify.notify_waiters();
     }
 
     pub fn is_requested(&self) -> bool {
-        self.0.load(Ordering::Acquire)
+        self.0.requested.load(Ordering::Acquire)
+    }
+
+    /// Resolves once cancellation is requested.
+    pub async fn cancelled(&self) {
+        let notified = self.0.notify.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        if self.is_requested() {
+            return;
+        }
+        notified.await;
+    }
+}
+
+/// Where a queued task runs. Providers that mutate through the same
+/// privilege prompt or package database share a lane and run one task at a
+/// time; every other provider has a lane of its own. A multi-provider
+/// bundle needs every lane, so it runs alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExecutionLane {
+    /// APT, DNF, Pacman, Zypper, AUR, Snap and local `.deb` installs, which
+    /// all go through `pkexec` and most share the dpkg/rpm database.
+    System,
+    /// Conda and Mamba share environments and a package cache.
+    Conda,
+    Provider(PackageSource),
+    Exclusive,
+}
+
+impl ExecutionLane {
+    pub fn for_source(source: PackageSource) -> Self {
+        match source {
+            PackageSource::Apt
+            | PackageSource::Dnf
+            | PackageSource::Pacman
+            | PackageSource::Zypper
+            | PackageSource::Aur
+            | PackageSource::Snap
+            | PackageSource::Deb => Self::System,
+            PackageSource::Conda | PackageSource::Mamba => Self::Conda,
+            source => Self::Provider(source),
+        }
+    }
+
+    pub fn for_entry(entry: &TaskQueueEntry) -> Self {
+        if entry.reviewed_bundle_json.is_some() {
+            Self::Exclusive
+        } else {
+            Self::for_source(entry.package_source)
+        }
+    }
+}
+
+/// Serializes read-modify-write cycles on the transaction store within this
+/// process, so engines running in parallel queue lanes never overwrite each
+/// other's records.
+static STORE_WRITE_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// The engine's store, freshly reloaded from disk, held for one update.
+struct StoreGuard<'a> {
+    _write: MutexGuard<'static, ()>,
+    store: MutexGuard<'a, TransactionStore>,
+}
+
+impl Deref for StoreGuard<'_> {
+    type Target = TransactionStore;
+
+    fn deref(&self) -> &Self::Target {
+        &self.store
+    }
+}
+
+impl DerefMut for StoreGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.store
     }
 }
 
@@ -646,7 +1344,11 @@ pub struct TransactionEngine {
     package_manager: Arc<RwLock<PackageManager>>,
     store: Arc<Mutex<TransactionStore>>,
     store_path: PathBuf,
-    execution_lock: Mutex<()>,
+    /// One lock per execution lane: a plan executes exactly once, and plans
+    /// in the same lane never overlap.
+    execution_locks: Mutex<HashMap<ExecutionLane, Arc<Mutex<()>>>>,
+    snapshots: SnapshotConfig,
+    hooks: Vec<HookConfig>,
 }
 
 impl TransactionEngine {
@@ -654,24 +1356,117 @@ impl TransactionEngine {
         package_manager: Arc<RwLock<PackageManager>>,
         store_path: PathBuf,
     ) -> Result<Self, ProviderError> {
+        let write = STORE_WRITE_LOCK.lock().await;
         let mut store = TransactionStore::load(&store_path).await?;
+        let live: Vec<String> = store
+            .operations
+            .iter()
+            .filter(|record| {
+                matches!(
+                    record.state,
+                    OperationState::Running | OperationState::Verifying
+                ) && !record.is_orphaned()
+            })
+            .map(|record| record.operation_id.clone())
+            .collect();
         for record in &mut store.operations {
-            if record.state == OperationState::Running || record.state == OperationState::Verifying
-            {
-                record.state = OperationState::Interrupted;
-                record.updated_at = Utc::now();
-            } else if record.state == OperationState::Ready {
+            if record.state == OperationState::Ready {
                 record.state = OperationState::NeedsReview;
                 record.updated_at = Utc::now();
             }
         }
+        for bundle in &mut store.bundles {
+            let in_progress = bundle.operation_ids.iter().any(|id| live.contains(id));
+            if (bundle.state == OperationState::Running
+                || bundle.state == OperationState::Verifying)
+                && !in_progress
+            {
+                bundle.state = OperationState::Interrupted;
+                bundle.updated_at = Utc::now();
+            } else if bundle.state == OperationState::Ready {
+                bundle.state = OperationState::NeedsReview;
+                bundle.updated_at = Utc::now();
+            }
+        }
         store.save_atomic(&store_path).await?;
-        Ok(Self {
+        let engine = Self {
             package_manager,
             store: Arc::new(Mutex::new(store)),
             store_path,
-            execution_lock: Mutex::new(()),
-        })
+            execution_locks: Mutex::new(HashMap::new()),
+            snapshots: SnapshotConfig::default(),
+            hooks: Vec::new(),
+        };
+        drop(write);
+        engine.recover_orphaned().await?;
+        Ok(engine)
+    }
+
+    /// Take a filesystem snapshot before plans that are High risk or go
+    /// through the system lane, when `config` enables it.
+    pub fn with_snapshots(mut self, config: SnapshotConfig) -> Self {
+        self.snapshots = config;
+        self
+    }
+
+    /// Run the matching `hooks` before and after each plan. A failing pre
+    /// hook cancels the plan.
+    pub fn with_hooks(mut self, hooks: Vec<HookConfig>) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Settle operations a previous LinGet process left `Running` or
+    /// `Verifying` when it died. Each is verified against a fresh inventory
+    /// of its provider: every planned change present means `Succeeded`, some
+    /// means `Partial`, none (or no inventory at all) means `Interrupted`.
+    async fn recover_orphaned(&self) -> Result<(), ProviderError> {
+        let orphaned: Vec<ProviderPlan> = {
+            let store = self.store.lock().await;
+            store
+                .operations
+                .iter()
+                .filter(|record| record.is_orphaned())
+                .map(|record| record.plan.clone())
+                .collect()
+        };
+        for plan in orphaned {
+            let verified = self.verify(&plan).await;
+            let mut store = self.lock_store().await?;
+            let Some(record) = store
+                .operations
+                .iter_mut()
+                .find(|record| record.operation_id == plan.operation_id)
+            else {
+                continue;
+            };
+            let (state, error) = match verified {
+                Ok(receipt) => {
+                    let state = recovered_state(&receipt);
+                    let error = (state != OperationState::Succeeded).then(|| ProviderError {
+                        code: ProviderErrorCode::Interrupted,
+                        provider: Some(plan.provider.source),
+                        safe_message: "LinGet exited while this operation was running".to_string(),
+                        diagnostic: format!(
+                            "Recovered on startup: {:?} verification against the stored plan",
+                            receipt.outcome
+                        ),
+                        retryable: true,
+                        recovery_actions: vec!["Refresh and review the remaining work".to_string()],
+                    });
+                    record.receipt = Some(receipt);
+                    (state, error)
+                }
+                Err(error) => (OperationState::Interrupted, Some(error)),
+            };
+            record.state = state;
+            record.error = error;
+            record.owner_pid = None;
+            record.recovered_at = Some(Utc::now());
+            record.updated_at = Utc::now();
+            store.save_atomic(&self.store_path).await?;
+        }
+        Ok(())
     }
 
     pub async fn plan(
@@ -679,14 +1474,49 @@ impl TransactionEngine {
         request: OperationRequest,
     ) -> Result<(ProviderPlan, RiskAssessment), ProviderError> {
         let source = request.source()?;
+        if request.action == OperationAction::Rollback {
+            return Err(ProviderError::protocol(
+                Some(source),
+                "Rollback plans are built from a completed operation's receipt",
+            ));
+        }
         validate_targets(&request.targets, source)?;
         let inventory = self.inventory(source).await?;
-        let apt_changes = if source == PackageSource::Apt {
-            Some(probe_apt_changes(request.action, &request.targets).await?)
-        } else {
-            None
+        // Simulations resolve the newest version, so a plan with pinned
+        // targets expects the pinned versions instead.
+        let pinned = request.targets.iter().any(|target| target.pinned);
+        let simulated_changes = match source {
+            _ if pinned => None,
+            PackageSource::Apt => Some(probe_apt_changes(request.action, &request.targets).await?),
+            PackageSource::Dnf => {
+                match probe_dnf_changes(request.action, &request.targets, &inventory).await {
+                    Ok(changes) => Some(changes),
+                    Err(error) if dnf_probe_refused(&error) => None,
+                    Err(error) => return Err(error),
+                }
+            }
+            PackageSource::Pacman => {
+                Some(probe_pacman_changes(request.action, &request.targets, &inventory).await?)
+            }
+            PackageSource::Zypper => {
+                Some(probe_zypper_changes(request.action, &request.targets, &inventory).await?)
+            }
+            PackageSource::Plugin(id) if plugin::supports(id, PluginMethod::Plan) => {
+                Some(plugin::plan_changes(id, request.action, &request.targets).await?)
+            }
+            _ => None,
         };
-        let plan = build_plan(&request, &inventory, apt_changes);
+        let simulated = simulated_changes.is_some();
+        let mut plan = build_plan(&request, &inventory, simulated_changes);
+        if !simulated && plan.provider.fidelity == PlanFidelity::Exact {
+            plan.provider.fidelity = PlanFidelity::BestEffort;
+        }
+        if source == PackageSource::Flatpak && request.action == OperationAction::Update {
+            plan.restore_points = capture_flatpak_commits(&request.targets).await;
+        }
+        if request.action == OperationAction::Remove {
+            plan.removal_impact = self.removal_impact(source, &request.targets).await;
+        }
         let risk = RiskAssessment::for_plan(&plan);
         let record = OperationRecord {
             operation_id: request.id,
@@ -700,13 +1530,57 @@ impl TransactionEngine {
             receipt: None,
             error: None,
             updated_at: Utc::now(),
+            owner_pid: None,
+            recovered_at: None,
+            snapshot: None,
+        };
+        self.upsert_record(record).await?;
+        Ok((plan, risk))
+    }
+
+    /// Plan the reverse of a finished operation from its verification
+    /// receipt. The reverse plan is persisted as a new operation and goes
+    /// through the same review, execution and verification as any other.
+    pub async fn plan_rollback(
+        &self,
+        operation_id: &str,
+    ) -> Result<(ProviderPlan, RiskAssessment), ProviderError> {
+        let original = {
+            let store = self.store.lock().await;
+            store
+                .operations
+                .iter()
+                .find(|record| record.operation_id == operation_id)
+                .cloned()
+        }
+        .ok_or_else(|| {
+            ProviderError::protocol(None, format!("No transaction record for {operation_id}"))
+        })?;
+        let inventory = self.inventory(original.plan.provider.source).await?;
+        let plan = build_rollback_plan(&original, &inventory, Utc::now())?;
+        let risk = RiskAssessment::for_plan(&plan);
+        let record = OperationRecord {
+            operation_id: plan.operation_id.clone(),
+            state: if risk.level == RiskLevel::Blocked {
+                OperationState::Failed
+            } else {
+                OperationState::Ready
+            },
+            plan: plan.clone(),
+            risk: risk.clone(),
+            receipt: None,
+            error: None,
+            updated_at: Utc::now(),
+            owner_pid: None,
+            recovered_at: None,
+            snapshot: None,
         };
         self.upsert_record(record).await?;
         Ok((plan, risk))
     }
 
     pub async fn resume_reviewed_plan(&self, reviewed: &ProviderPlan) -> Result<(), ProviderError> {
-        let mut store = self.store.lock().await;
+        let mut store = self.lock_store().await?;
         let record = store
             .operations
             .iter_mut()
@@ -741,12 +1615,64 @@ impl TransactionEngine {
         store.save_atomic(&self.store_path).await
     }
 
+    /// Re-plan a reviewed plan that failed with `PlanExpired` or
+    /// `PlanChanged` and report what moved. The stale record is cancelled
+    /// and the fresh plan recorded `Ready` under a new operation id.
+    pub async fn replan_stale(
+        &self,
+        reviewed: &ProviderPlan,
+        reason: ProviderErrorCode,
+        requested_by: RequestedBy,
+    ) -> Result<StalePlan, ProviderError> {
+        let (fresh, risk) = match &reviewed.rollback_of {
+            Some(operation_id) => self.plan_rollback(operation_id).await?,
+            None => {
+                self.plan(OperationRequest::new(
+                    reviewed.action,
+                    reviewed.targets.clone(),
+                    requested_by,
+                ))
+                .await?
+            }
+        };
+        let stale_state = {
+            let store = self.store.lock().await;
+            store
+                .operations
+                .iter()
+                .find(|record| record.operation_id == reviewed.operation_id)
+                .map(|record| record.state)
+        };
+        if matches!(
+            stale_state,
+            Some(OperationState::Ready | OperationState::NeedsReview)
+        ) {
+            self.mark_state(&reviewed.operation_id, OperationState::Cancelled)
+                .await?;
+        }
+        Ok(StalePlan {
+            reason,
+            reviewed_operation_id: reviewed.operation_id.clone(),
+            reviewed_risk: RiskAssessment::for_plan(reviewed).level,
+            diff: PlanDiff::between(reviewed, &fresh),
+            fresh,
+            risk,
+        })
+    }
+
     pub async fn execute(
         &self,
         plan: ProviderPlan,
         cancellation: CancellationFlag,
     ) -> Result<VerificationReceipt, ProviderError> {
-        let _execution_guard = self.execution_lock.lock().await;
+        let lane_lock = Arc::clone(
+            self.execution_locks
+                .lock()
+                .await
+                .entry(ExecutionLane::for_source(plan.provider.source))
+                .or_default(),
+        );
+        let _execution_guard = lane_lock.lock().await;
         if plan.is_expired(Utc::now()) {
             self.mark_error(
                 &plan.operation_id,
@@ -804,48 +1730,141 @@ impl TransactionEngine {
             return Err(error);
         }
 
-        self.mark_state(&plan.operation_id, OperationState::Running)
-            .await?;
-        for (completed, target) in plan.targets.iter().enumerate() {
-            if cancellation.is_requested() && completed > 0 {
-                let error = ProviderError {
-                    code: ProviderErrorCode::Interrupted,
-                    provider: Some(plan.provider.source),
-                    safe_message: "The operation stopped between package steps".to_string(),
-                    diagnostic: format!("{} package step(s) completed", completed),
-                    retryable: true,
-                    recovery_actions: vec!["Refresh and review the remaining work".to_string()],
-                };
-                self.mark_error(&plan.operation_id, OperationState::Partial, error.clone())
+        // Once pre hooks have started, post hooks hear how the operation
+        // ended, including a veto by a later pre hook or a failed snapshot.
+        let result = match self.run_pre_hooks(&plan, &cancellation).await {
+            Ok(()) => self.snapshot_and_run(&plan, &cancellation).await,
+            Err(error) => self
+                .mark_error(&plan.operation_id, OperationState::Cancelled, error.clone())
+                .await
+                .and(Err(error)),
+        };
+        self.run_post_hooks(&plan, &result, &cancellation).await;
+        result
+    }
+
+    /// Take the snapshot `plan` calls for, then run and verify it.
+    async fn snapshot_and_run(
+        &self,
+        plan: &ProviderPlan,
+        cancellation: &CancellationFlag,
+    ) -> Result<VerificationReceipt, ProviderError> {
+        match self.take_snapshot(plan).await {
+            Ok(Some(snapshot)) => {
+                self.record_snapshot(&plan.operation_id, snapshot).await?;
+            }
+            Ok(None) => {}
+            Err(error) => {
+                self.mark_error(&plan.operation_id, OperationState::Failed, error.clone())
                     .await?;
                 return Err(error);
             }
-            let result = {
-                let manager = self.package_manager.read().await;
-                let package = target.as_package(plan.action);
-                match plan.action {
-                    OperationAction::Install => manager.install(&package).await,
-                    OperationAction::Remove => manager.remove(&package).await,
-                    OperationAction::Update => manager.update(&package).await,
+        }
+        self.run_and_verify(plan, cancellation).await
+    }
+
+    /// Run a checked plan's provider steps and verify the outcome.
+    async fn run_and_verify(
+        &self,
+        plan: &ProviderPlan,
+        cancellation: &CancellationFlag,
+    ) -> Result<VerificationReceipt, ProviderError> {
+        self.mark_state(&plan.operation_id, OperationState::Running)
+            .await?;
+        if runs_as_batch(plan.provider.source, plan.action, &plan.targets) {
+            self.run_batch_step(plan, cancellation).await?;
+        } else {
+            for (completed, target) in plan.targets.iter().enumerate() {
+                if cancellation.is_requested() && completed > 0 {
+                    let error = ProviderError {
+                        code: ProviderErrorCode::Interrupted,
+                        provider: Some(plan.provider.source),
+                        safe_message: "The operation stopped between package steps".to_string(),
+                        diagnostic: format!("{} package step(s) completed", completed),
+                        retryable: true,
+                        recovery_actions: vec!["Refresh and review the remaining work".to_string()],
+                    };
+                    self.mark_error(&plan.operation_id, OperationState::Partial, error.clone())
+                        .await?;
+                    return Err(error);
                 }
-            };
-            if let Err(error) = result {
-                let provider_error =
-                    ProviderError::classify(plan.provider.source, error.to_string());
-                let state = if completed == 0 {
-                    OperationState::Failed
-                } else {
-                    OperationState::Partial
+                let result = {
+                    let manager = self.package_manager.read().await;
+                    let package = target.as_package(plan.action);
+                    let step = async {
+                        match (plan.action, target.pin()) {
+                            (
+                                OperationAction::Install
+                                | OperationAction::Update
+                                | OperationAction::Rollback,
+                                Some(version),
+                            ) => manager.install_version(&package, version).await,
+                            (OperationAction::Install, None) => manager.install(&package).await,
+                            (OperationAction::Remove, _) => manager.remove(&package).await,
+                            (OperationAction::Update, None) => manager.update(&package).await,
+                            (OperationAction::Rollback, None) => match (
+                                &target.available_version,
+                                plan.restore_points.get(&target.name),
+                            ) {
+                                (None, _) => manager.remove(&package).await,
+                                (Some(_), Some(restore_point)) => {
+                                    manager.downgrade_to(&package, restore_point).await
+                                }
+                                (Some(_), None) => Err(anyhow::anyhow!(
+                                    "No version was recorded to restore {}",
+                                    target.name
+                                )),
+                            },
+                        }
+                    };
+                    if plan.provider.cancellation == CancellationSupport::Cooperative {
+                        with_cancellation(cancellation.clone(), step).await
+                    } else {
+                        step.await
+                    }
                 };
-                self.mark_error(&plan.operation_id, state, provider_error.clone())
-                    .await?;
-                return Err(provider_error);
+                if let Some(cancelled) = result
+                    .as_ref()
+                    .err()
+                    .and_then(|error| error.downcast_ref::<ProcessCancelled>())
+                {
+                    let error = ProviderError {
+                        code: ProviderErrorCode::Interrupted,
+                        provider: Some(plan.provider.source),
+                        safe_message: cancelled.to_string(),
+                        diagnostic: format!(
+                            "{} stopped while changing {}; {} earlier package step(s) completed",
+                            plan.provider.source, target.name, completed
+                        ),
+                        retryable: true,
+                        recovery_actions: vec!["Refresh and review the remaining work".to_string()],
+                    };
+                    let state = if completed == 0 && cancelled.graceful {
+                        OperationState::Cancelled
+                    } else {
+                        OperationState::Partial
+                    };
+                    self.mark_error(&plan.operation_id, state, error.clone())
+                        .await?;
+                    return Err(error);
+                }
+                if let Err(error) = result {
+                    let provider_error = ProviderError::from_error(plan.provider.source, &error);
+                    let state = if completed == 0 {
+                        OperationState::Failed
+                    } else {
+                        OperationState::Partial
+                    };
+                    self.mark_error(&plan.operation_id, state, provider_error.clone())
+                        .await?;
+                    return Err(provider_error);
+                }
             }
         }
 
         self.mark_state(&plan.operation_id, OperationState::Verifying)
             .await?;
-        let receipt = self.verify(&plan).await?;
+        let receipt = self.verify(plan).await?;
         let final_state = match receipt.outcome {
             VerificationOutcome::Verified => OperationState::Succeeded,
             VerificationOutcome::Mismatch => OperationState::Failed,
@@ -856,72 +1875,633 @@ impl TransactionEngine {
         Ok(receipt)
     }
 
-    pub async fn records(&self) -> Vec<OperationRecord> {
-        self.store.lock().await.operations.clone()
-    }
+    /// Execute reviewed plans queued back to back for the same provider and
+    /// action as one provider invocation. The union of their targets is
+    /// re-planned; if that plan would change anything the reviewed plans did
+    /// not, or at a different version, it is cancelled and `None` returned
+    /// so the caller runs the plans one by one. Otherwise every member plan
+    /// finishes with its own receipt, verified against one inventory read.
+    pub async fn execute_coalesced(
+        &self,
+        reviewed: &[ProviderPlan],
+        requested_by: RequestedBy,
+        cancellation: CancellationFlag,
+    ) -> Result<Option<Vec<VerificationReceipt>>, ProviderError> {
+        let Some(first) = reviewed.first() else {
+            return Ok(None);
+        };
+        let source = first.provider.source;
+        let coalescible = reviewed.len() > 1
+            && supports_batch(source)
+            && reviewed.iter().all(|plan| {
+                plan.provider.source == source
+                    && plan.action == first.action
+                    && plan.rollback_of.is_none()
+                    && plan.targets.iter().all(|target| !target.pinned)
+            });
+        if !coalescible {
+            return Ok(None);
+        }
+        let now = Utc::now();
+        let fingerprint = inventory_fingerprint(&self.inventory(source).await?);
+        if reviewed
+            .iter()
+            .any(|plan| plan.is_expired(now) || plan.inventory_fingerprint != fingerprint)
+        {
+            // Stale members are re-planned individually.
+            return Ok(None);
+        }
 
-    async fn inventory(&self, source: PackageSource) -> Result<Vec<Package>, ProviderError> {
-        let manager = self.package_manager.read().await;
-        manager
-            .list_installed_for_source(source)
-            .await
-            .map_err(|error| ProviderError::classify(source, error.to_string()))
-    }
+        let mut targets: Vec<PackageRef> = Vec::new();
+        let mut union = first.clone();
+        union.expected_changes.clear();
+        for plan in reviewed {
+            for target in &plan.targets {
+                if !targets.iter().any(|known| known.name == target.name) {
+                    targets.push(target.clone());
+                }
+            }
+            for change in &plan.expected_changes {
+                if !union.expected_changes.contains(change) {
+                    union.expected_changes.push(change.clone());
+                }
+            }
+        }
+        let (combined, risk) = self
+            .plan(OperationRequest::new(first.action, targets, requested_by))
+            .await?;
+        let reviewed_risk = reviewed
+            .iter()
+            .map(|plan| RiskAssessment::for_plan(plan).level)
+            .max()
+            .unwrap_or(RiskLevel::Low);
+        let diff = PlanDiff::between(&union, &combined);
+        if !diff.added.is_empty() || !diff.reversioned.is_empty() || risk.level > reviewed_risk {
+            if risk.level != RiskLevel::Blocked {
+                self.mark_state(&combined.operation_id, OperationState::Cancelled)
+                    .await?;
+            }
+            return Ok(None);
+        }
 
-    async fn verify(&self, plan: &ProviderPlan) -> Result<VerificationReceipt, ProviderError> {
-        let inventory = self.inventory(plan.provider.source).await?;
-        Ok(verify_inventory(plan, &inventory, Utc::now()))
-    }
+        for plan in reviewed {
+            self.mark_state(&plan.operation_id, OperationState::Running)
+                .await?;
+        }
+        let result = self.execute(combined.clone(), cancellation).await;
+        if let Some(snapshot) = self.snapshot(&combined.operation_id).await {
+            for plan in reviewed {
+                self.record_snapshot(&plan.operation_id, snapshot.clone())
+                    .await?;
+            }
+        }
+        if let Err(error) = result {
+            let state = {
+                let store = self.store.lock().await;
+                store
+                    .operations
+                    .iter()
+                    .find(|record| record.operation_id == combined.operation_id)
+                    .map(|record| record.state)
+                    .unwrap_or(OperationState::Failed)
+            };
+            for plan in reviewed {
+                self.mark_error(&plan.operation_id, state, error.clone())
+                    .await?;
+            }
+            return Err(error);
+        }
 
-    async fn upsert_record(&self, record: OperationRecord) -> Result<(), ProviderError> {
-        let mut store = self.store.lock().await;
-        if let Some(existing) = store
-            .operations
-            .iter_mut()
-            .find(|entry| entry.operation_id == record.operation_id)
-        {
-            *existing = record;
-        } else {
-            store.operations.push(record);
+        let inventory = self.inventory(source).await?;
+        let verified_at = Utc::now();
+        let mut receipts = Vec::new();
+        for plan in reviewed {
+            self.mark_state(&plan.operation_id, OperationState::Verifying)
+                .await?;
+            let receipt = verify_inventory(plan, &inventory, verified_at);
+            let final_state = match receipt.outcome {
+                VerificationOutcome::Verified => OperationState::Succeeded,
+                VerificationOutcome::Mismatch => OperationState::Failed,
+                VerificationOutcome::Inconclusive => OperationState::Partial,
+            };
+            self.mark_receipt(&plan.operation_id, final_state, receipt.clone())
+                .await?;
+            receipts.push(receipt);
         }
-        store.save_atomic(&self.store_path).await
+        Ok(Some(receipts))
     }
 
-    async fn mark_state(
+    /// Run every target of `plan` through one provider invocation. A failed
+    /// invocation is checked against the inventory, since providers that
+    /// are not transactional may have applied some targets before failing.
+    async fn run_batch_step(
         &self,
-        operation_id: &str,
-        next: OperationState,
+        plan: &ProviderPlan,
+        cancellation: &CancellationFlag,
     ) -> Result<(), ProviderError> {
-        let mut store = self.store.lock().await;
-        let record = store
-            .operations
-            .iter_mut()
-            .find(|record| record.operation_id == operation_id)
-            .ok_or_else(|| ProviderError::protocol(None, "Transaction record is missing"))?;
-        if !record.state.can_transition_to(next) {
-            return Err(ProviderError::protocol(
-                Some(record.plan.provider.source),
-                format!(
-                    "Invalid transaction transition {:?} -> {:?}",
-                    record.state, next
-                ),
-            ));
-        }
-        record.state = next;
-        record.updated_at = Utc::now();
-        store.save_atomic(&self.store_path).await
+        let result = {
+            let manager = self.package_manager.read().await;
+            let packages: Vec<Package> = plan
+                .targets
+                .iter()
+                .map(|target| target.as_package(plan.action))
+                .collect();
+            let step = async {
+                match plan.action {
+                    OperationAction::Install => manager.install_batch(&packages).await,
+                    OperationAction::Remove => manager.remove_batch(&packages).await,
+                    OperationAction::Update => manager.update_batch(&packages).await,
+                    OperationAction::Rollback => {
+                        anyhow::bail!("Rollback plans run one package at a time")
+                    }
+                }
+            };
+            if plan.provider.cancellation == CancellationSupport::Cooperative {
+                with_cancellation(cancellation.clone(), step).await
+            } else {
+                step.await
+            }
+        };
+        let Err(error) = result else {
+            return Ok(());
+        };
+        let applied_some = match self.verify(plan).await {
+            Ok(receipt) => recovered_state(&receipt) != OperationState::Interrupted,
+            Err(_) => false,
+        };
+        let (provider_error, state) = match error.downcast_ref::<ProcessCancelled>() {
+            Some(cancelled) => (
+                ProviderError {
+                    code: ProviderErrorCode::Interrupted,
+                    provider: Some(plan.provider.source),
+                    safe_message: cancelled.to_string(),
+                    diagnostic: format!(
+                        "{} stopped while changing {} package(s) in one invocation",
+                        plan.provider.source,
+                        plan.targets.len()
+                    ),
+                    retryable: true,
+                    recovery_actions: vec!["Refresh and review the remaining work".to_string()],
+                },
+                if cancelled.graceful && !applied_some {
+                    OperationState::Cancelled
+                } else {
+                    OperationState::Partial
+                },
+            ),
+            None => (
+                ProviderError::from_error(plan.provider.source, &error),
+                if applied_some {
+                    OperationState::Partial
+                } else {
+                    OperationState::Failed
+                },
+            ),
+        };
+        self.mark_error(&plan.operation_id, state, provider_error.clone())
+            .await?;
+        Err(provider_error)
     }
 
-    async fn mark_error(
+    /// Plan a request that may span several providers as one bundle: one
+    /// `ProviderPlan` per source under the request's id, with an aggregated
+    /// risk so the whole change set is reviewed and confirmed once.
+    pub async fn plan_bundle(
         &self,
-        operation_id: &str,
-        next: OperationState,
-        error: ProviderError,
-    ) -> Result<(), ProviderError> {
-        let mut store = self.store.lock().await;
-        let record = store
-            .operations
-            .iter_mut()
+        request: OperationRequest,
+    ) -> Result<TransactionBundle, ProviderError> {
+        if request.targets.is_empty() {
+            return Err(ProviderError::protocol(None, "No packages were selected"));
+        }
+        let mut plans = Vec::new();
+        let mut risks = Vec::new();
+        for provider_request in request.split_by_source() {
+            let (plan, risk) = self.plan(provider_request).await?;
+            plans.push(plan);
+            risks.push(risk);
+        }
+        let risk = RiskAssessment::aggregate(&risks);
+        let bundle = TransactionBundle {
+            id: request.id,
+            action: request.action,
+            plans,
+            risk,
+            created_at: Utc::now(),
+        };
+        let record = BundleRecord {
+            bundle_id: bundle.id.clone(),
+            state: if bundle.risk.level == RiskLevel::Blocked {
+                OperationState::Failed
+            } else {
+                OperationState::Ready
+            },
+            operation_ids: bundle
+                .plans
+                .iter()
+                .map(|plan| plan.operation_id.clone())
+                .collect(),
+            risk: bundle.risk.clone(),
+            summary: None,
+            updated_at: Utc::now(),
+        };
+        let mut store = self.lock_store().await?;
+        store
+            .bundles
+            .retain(|entry| entry.bundle_id != record.bundle_id);
+        store.bundles.push(record);
+        store.save_atomic(&self.store_path).await?;
+        Ok(bundle)
+    }
+
+    pub async fn resume_reviewed_bundle(
+        &self,
+        reviewed: &TransactionBundle,
+    ) -> Result<(), ProviderError> {
+        {
+            let mut store = self.lock_store().await?;
+            let record = store
+                .bundles
+                .iter_mut()
+                .find(|record| record.bundle_id == reviewed.id)
+                .ok_or_else(|| {
+                    ProviderError::protocol(None, "Reviewed transaction bundle is missing")
+                })?;
+            let operation_ids: Vec<&str> = reviewed
+                .plans
+                .iter()
+                .map(|plan| plan.operation_id.as_str())
+                .collect();
+            if record.operation_ids != operation_ids {
+                return Err(ProviderError {
+                    code: ProviderErrorCode::PlanChanged,
+                    provider: None,
+                    safe_message: "The queued bundle differs from the reviewed bundle".to_string(),
+                    diagnostic: "Persisted bundle operations did not match".to_string(),
+                    retryable: true,
+                    recovery_actions: vec!["Review a fresh plan".to_string()],
+                });
+            }
+            match record.state {
+                OperationState::NeedsReview => record.state = OperationState::Ready,
+                OperationState::Ready => {}
+                state => {
+                    return Err(ProviderError::protocol(
+                        None,
+                        format!("Reviewed bundle cannot resume from {state:?}"),
+                    ));
+                }
+            }
+            record.updated_at = Utc::now();
+            store.save_atomic(&self.store_path).await?;
+        }
+        for plan in &reviewed.plans {
+            self.resume_reviewed_plan(plan).await?;
+        }
+        Ok(())
+    }
+
+    /// Execute a reviewed bundle provider by provider. The first provider
+    /// failure stops the bundle; later plans are cancelled rather than run
+    /// against a system the reviewer did not see.
+    pub async fn execute_bundle(
+        &self,
+        bundle: TransactionBundle,
+        cancellation: CancellationFlag,
+    ) -> Result<BundleSummary, ProviderError> {
+        if bundle.risk.level == RiskLevel::Blocked {
+            return Err(ProviderError::protocol(
+                None,
+                "The transaction bundle is blocked",
+            ));
+        }
+        self.mark_bundle_state(&bundle.id, OperationState::Running, None)
+            .await?;
+
+        let mut receipts = Vec::new();
+        let mut failed = Vec::new();
+        let mut skipped_operation_ids = Vec::new();
+        for plan in bundle.plans {
+            if !failed.is_empty() || cancellation.is_requested() {
+                let error = ProviderError {
+                    code: ProviderErrorCode::Interrupted,
+                    provider: Some(plan.provider.source),
+                    safe_message: "Skipped after an earlier provider in the bundle stopped"
+                        .to_string(),
+                    diagnostic: "Bundle execution stopped before this provider".to_string(),
+                    retryable: true,
+                    recovery_actions: vec!["Review a fresh plan".to_string()],
+                };
+                self.mark_error(&plan.operation_id, OperationState::Cancelled, error)
+                    .await?;
+                skipped_operation_ids.push(plan.operation_id);
+                continue;
+            }
+            match self.execute(plan, cancellation.clone()).await {
+                Ok(receipt) => receipts.push(receipt),
+                Err(error) if error.code == ProviderErrorCode::Persistence => return Err(error),
+                Err(error) => failed.push(error),
+            }
+        }
+
+        self.mark_bundle_state(&bundle.id, OperationState::Verifying, None)
+            .await?;
+        let outcome = if !failed.is_empty()
+            || receipts
+                .iter()
+                .any(|receipt| receipt.outcome == VerificationOutcome::Mismatch)
+        {
+            VerificationOutcome::Mismatch
+        } else if receipts
+            .iter()
+            .all(|receipt| receipt.outcome == VerificationOutcome::Verified)
+        {
+            VerificationOutcome::Verified
+        } else {
+            VerificationOutcome::Inconclusive
+        };
+        let final_state = match outcome {
+            VerificationOutcome::Verified => OperationState::Succeeded,
+            VerificationOutcome::Mismatch if receipts.is_empty() => OperationState::Failed,
+            VerificationOutcome::Mismatch | VerificationOutcome::Inconclusive => {
+                OperationState::Partial
+            }
+        };
+        let summary = BundleSummary {
+            bundle_id: bundle.id.clone(),
+            outcome,
+            receipts,
+            failed,
+            skipped_operation_ids,
+            verified_at: Utc::now(),
+        };
+        self.mark_bundle_state(&bundle.id, final_state, Some(summary.clone()))
+            .await?;
+        Ok(summary)
+    }
+
+    /// Run the pre hooks matching `plan`; the first that fails cancels it.
+    async fn run_pre_hooks(
+        &self,
+        plan: &ProviderPlan,
+        cancellation: &CancellationFlag,
+    ) -> Result<(), ProviderError> {
+        for hook in self
+            .hooks
+            .iter()
+            .filter(|hook| hooks::matches(hook, HookStage::Pre, plan))
+        {
+            let run = hooks::run(hook, HookStage::Pre, plan, None);
+            if let Err(reason) = with_cancellation(cancellation.clone(), run).await {
+                let code = if cancellation.is_requested() {
+                    ProviderErrorCode::Interrupted
+                } else {
+                    ProviderErrorCode::HookVetoed
+                };
+                return Err(ProviderError::from_class(
+                    plan.provider.source,
+                    FailureClass::from(code),
+                    reason,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the post hooks matching `plan`. Their failures are logged; the
+    /// operation has already finished. After a cancellation they still run so
+    /// they can clean up, but only for a short grace period.
+    async fn run_post_hooks(
+        &self,
+        plan: &ProviderPlan,
+        outcome: &Result<VerificationReceipt, ProviderError>,
+        cancellation: &CancellationFlag,
+    ) {
+        for hook in self
+            .hooks
+            .iter()
+            .filter(|hook| hooks::matches(hook, HookStage::Post, plan))
+        {
+            let result = if cancellation.is_requested() {
+                let mut hook = hook.clone();
+                hook.timeout_secs = hook.timeout_secs.min(hooks::CANCELLED_POST_HOOK_SECS);
+                hooks::run(&hook, HookStage::Post, plan, Some(outcome)).await
+            } else {
+                let run = hooks::run(hook, HookStage::Post, plan, Some(outcome));
+                with_cancellation(cancellation.clone(), run).await
+            };
+            if let Err(reason) = result {
+                tracing::warn!(operation = %plan.operation_id, %reason, "Post hook failed");
+            }
+        }
+    }
+
+    /// The snapshot taken before `operation_id` ran, if any.
+    pub async fn snapshot(&self, operation_id: &str) -> Option<FilesystemSnapshot> {
+        self.store
+            .lock()
+            .await
+            .operations
+            .iter()
+            .find(|record| record.operation_id == operation_id)
+            .and_then(|record| record.snapshot.clone())
+    }
+
+    /// Snapshot the filesystem before `plan` runs, when snapshots are
+    /// enabled and the plan is High risk or changes system packages. A
+    /// snapshot that was asked for but could not be taken stops the plan.
+    async fn take_snapshot(
+        &self,
+        plan: &ProviderPlan,
+    ) -> Result<Option<FilesystemSnapshot>, ProviderError> {
+        if !self.snapshots.enabled
+            || (RiskAssessment::for_plan(plan).level < RiskLevel::High
+                && ExecutionLane::for_source(plan.provider.source) != ExecutionLane::System)
+        {
+            return Ok(None);
+        }
+        let failed = |diagnostic: String| {
+            ProviderError::from_class(
+                plan.provider.source,
+                FailureClass::from(ProviderErrorCode::SnapshotFailed),
+                diagnostic,
+            )
+        };
+        let driver = SnapshotDriver::detect(&self.snapshots).ok_or_else(|| {
+            failed("No snapshot tool (snapper, timeshift or btrfs) is installed".to_string())
+        })?;
+        let mut description = format!(
+            "linget {} {}",
+            format!("{:?}", plan.action).to_lowercase(),
+            plan.targets
+                .iter()
+                .take(5)
+                .map(|target| target.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        if plan.targets.len() > 5 {
+            description.push_str(&format!(" and {} more", plan.targets.len() - 5));
+        }
+        match driver.create(&description).await {
+            Ok(snapshot) => {
+                tracing::info!(snapshot = %snapshot.label(), operation = %plan.operation_id, "Took pre-transaction snapshot");
+                Ok(Some(snapshot))
+            }
+            Err(error) => Err(failed(format!("{:#}", error))),
+        }
+    }
+
+    async fn record_snapshot(
+        &self,
+        operation_id: &str,
+        snapshot: FilesystemSnapshot,
+    ) -> Result<(), ProviderError> {
+        let mut store = self.lock_store().await?;
+        let record = store
+            .operations
+            .iter_mut()
+            .find(|record| record.operation_id == operation_id)
+            .ok_or_else(|| ProviderError::protocol(None, "Transaction record is missing"))?;
+        record.snapshot = Some(snapshot);
+        record.updated_at = Utc::now();
+        store.save_atomic(&self.store_path).await
+    }
+
+    pub async fn records(&self) -> Vec<OperationRecord> {
+        self.store.lock().await.operations.clone()
+    }
+
+    pub async fn bundle_records(&self) -> Vec<BundleRecord> {
+        self.store.lock().await.bundles.clone()
+    }
+
+    async fn inventory(&self, source: PackageSource) -> Result<Vec<Package>, ProviderError> {
+        let manager = self.package_manager.read().await;
+        manager
+            .list_installed_for_source(source)
+            .await
+            .map_err(|error| ProviderError::from_error(source, &error))
+    }
+
+    /// The transitive impact of removing `targets`, or `None` when the
+    /// provider cannot report reverse dependencies. A failed analysis does
+    /// not fail the plan; it leaves the plan without an impact section.
+    async fn removal_impact(
+        &self,
+        source: PackageSource,
+        targets: &[PackageRef],
+    ) -> Option<RemovalImpact> {
+        let manager = self.package_manager.read().await;
+        if !manager
+            .source_capability_status(source, BackendCapability::ReverseDependencies)
+            .is_supported()
+        {
+            return None;
+        }
+        let names: Vec<String> = targets.iter().map(|target| target.name.clone()).collect();
+        match manager.removal_impact(source, &names).await {
+            Ok(impact) => Some(impact),
+            Err(error) => {
+                tracing::warn!(%source, %error, "Removal impact analysis failed");
+                None
+            }
+        }
+    }
+
+    async fn verify(&self, plan: &ProviderPlan) -> Result<VerificationReceipt, ProviderError> {
+        let inventory = self.inventory(plan.provider.source).await?;
+        Ok(verify_inventory(plan, &inventory, Utc::now()))
+    }
+
+    /// Lock the store for an update, first reloading it so records written
+    /// by other engines since this one loaded are kept.
+    async fn lock_store(&self) -> Result<StoreGuard<'_>, ProviderError> {
+        let write = STORE_WRITE_LOCK.lock().await;
+        let mut store = self.store.lock().await;
+        *store = TransactionStore::load(&self.store_path).await?;
+        Ok(StoreGuard {
+            _write: write,
+            store,
+        })
+    }
+
+    async fn upsert_record(&self, record: OperationRecord) -> Result<(), ProviderError> {
+        let mut store = self.lock_store().await?;
+        if let Some(existing) = store
+            .operations
+            .iter_mut()
+            .find(|entry| entry.operation_id == record.operation_id)
+        {
+            *existing = record;
+        } else {
+            store.operations.push(record);
+        }
+        store.save_atomic(&self.store_path).await
+    }
+
+    async fn mark_state(
+        &self,
+        operation_id: &str,
+        next: OperationState,
+    ) -> Result<(), ProviderError> {
+        let mut store = self.lock_store().await?;
+        let record = store
+            .operations
+            .iter_mut()
+            .find(|record| record.operation_id == operation_id)
+            .ok_or_else(|| ProviderError::protocol(None, "Transaction record is missing"))?;
+        if !record.state.can_transition_to(next) {
+            return Err(ProviderError::protocol(
+                Some(record.plan.provider.source),
+                format!(
+                    "Invalid transaction transition {:?} -> {:?}",
+                    record.state, next
+                ),
+            ));
+        }
+        record.state = next;
+        record.owner_pid = matches!(next, OperationState::Running | OperationState::Verifying)
+            .then(std::process::id);
+        record.updated_at = Utc::now();
+        store.save_atomic(&self.store_path).await
+    }
+
+    async fn mark_bundle_state(
+        &self,
+        bundle_id: &str,
+        next: OperationState,
+        summary: Option<BundleSummary>,
+    ) -> Result<(), ProviderError> {
+        let mut store = self.lock_store().await?;
+        let record = store
+            .bundles
+            .iter_mut()
+            .find(|record| record.bundle_id == bundle_id)
+            .ok_or_else(|| ProviderError::protocol(None, "Transaction bundle record is missing"))?;
+        if !record.state.can_transition_to(next) {
+            return Err(ProviderError::protocol(
+                None,
+                format!("Invalid bundle transition {:?} -> {:?}", record.state, next),
+            ));
+        }
+        record.state = next;
+        if summary.is_some() {
+            record.summary = summary;
+        }
+        record.updated_at = Utc::now();
+        store.save_atomic(&self.store_path).await
+    }
+
+    async fn mark_error(
+        &self,
+        operation_id: &str,
+        next: OperationState,
+        error: ProviderError,
+    ) -> Result<(), ProviderError> {
+        let mut store = self.lock_store().await?;
+        let record = store
+            .operations
+            .iter_mut()
             .find(|record| record.operation_id == operation_id)
             .ok_or_else(|| ProviderError::protocol(None, "Transaction record is missing"))?;
         record.state = next;
@@ -936,7 +2516,7 @@ impl TransactionEngine {
         next: OperationState,
         receipt: VerificationReceipt,
     ) -> Result<(), ProviderError> {
-        let mut store = self.store.lock().await;
+        let mut store = self.lock_store().await?;
         let record = store
             .operations
             .iter_mut()
@@ -949,6 +2529,24 @@ impl TransactionEngine {
     }
 }
 
+/// Final state of an orphaned operation from its recovery receipt.
+fn recovered_state(receipt: &VerificationReceipt) -> OperationState {
+    let applied = receipt
+        .expected
+        .iter()
+        .zip(&receipt.observed)
+        .filter(|(expected, observed)| {
+            expected.after.is_some() && expected.after == observed.after
+                || expected.after.is_none() && expected.before.is_some() && observed.after.is_none()
+        })
+        .count();
+    match receipt.outcome {
+        VerificationOutcome::Verified => OperationState::Succeeded,
+        _ if applied > 0 => OperationState::Partial,
+        _ => OperationState::Interrupted,
+    }
+}
+
 fn verify_inventory(
     plan: &ProviderPlan,
     inventory: &[Package],
@@ -962,18 +2560,35 @@ fn verify_inventory(
             .iter()
             .find(|package| package.name == expected.name);
         let observed_after = installed.map(|package| package.version.clone());
-        let target_matches = match plan.action {
-            OperationAction::Install => match (&expected.after, installed) {
-                (Some(version), Some(package)) => &package.version == version,
-                (None, Some(_)) => true,
-                _ => false,
-            },
-            OperationAction::Remove => installed.is_none(),
-            OperationAction::Update => match (&expected.after, installed) {
-                (Some(version), Some(package)) => &package.version == version,
-                (None, Some(_)) => false,
-                _ => false,
-            },
+        // Exact simulations list every package the transaction touches, so a
+        // change without an `after` version is a removal even inside an
+        // install or update (e.g. a conflicting package being replaced).
+        let removal = expected.after.is_none()
+            && (matches!(
+                plan.action,
+                OperationAction::Remove | OperationAction::Rollback
+            ) || plan.provider.fidelity == PlanFidelity::Exact);
+        let target_matches = if removal {
+            installed.is_none()
+        } else {
+            match plan.action {
+                OperationAction::Install => match (&expected.after, installed) {
+                    (Some(version), Some(package)) => &package.version == version,
+                    (None, Some(_)) => true,
+                    _ => false,
+                },
+                OperationAction::Remove => installed.is_none(),
+                OperationAction::Update => match (&expected.after, installed) {
+                    (Some(version), Some(package)) => &package.version == version,
+                    (None, Some(_)) => false,
+                    _ => false,
+                },
+                // Restore points and pinned reinstalls fix the version.
+                OperationAction::Rollback => match (&expected.after, installed) {
+                    (Some(version), Some(package)) => &package.version == version,
+                    _ => false,
+                },
+            }
         };
         if target_matches {
             matches += 1;
@@ -1012,6 +2627,83 @@ fn verify_inventory(
     }
 }
 
+/// Verify a mutation that ran without a reviewed plan, from the provider's
+/// inventory before and after it. The target version is rarely known up
+/// front, so an update is `Inconclusive` unless the package vanished, and a
+/// package listed without a version cannot prove an install either.
+pub fn verify_unreviewed(
+    action: OperationAction,
+    target: &str,
+    source: PackageSource,
+    available_version: Option<&str>,
+    before: &[Package],
+    after: &[Package],
+    verified_at: DateTime<Utc>,
+) -> VerificationReceipt {
+    // pip and pipx list a project under its declared name, which may differ
+    // in case and separators from the name it was requested by.
+    let canonical = |inventory: &[Package]| -> Vec<Package> {
+        inventory
+            .iter()
+            .cloned()
+            .map(|mut package| {
+                if same_package_name(source, &package.name, target) {
+                    package.name = target.to_string();
+                }
+                package
+            })
+            .collect()
+    };
+    let before = canonical(before);
+    let after = canonical(after);
+    let request = OperationRequest::new(
+        action,
+        vec![PackageRef {
+            name: target.to_string(),
+            source,
+            installed_version: before
+                .iter()
+                .find(|package| package.name == target)
+                .map(|package| package.version.clone()),
+            // An update queued while no newer version was pending expects the
+            // installed version to stay put.
+            available_version: available_version
+                .filter(|_| action == OperationAction::Update)
+                .map(str::to_string),
+            pinned: false,
+        }],
+        RequestedBy::Tui,
+    );
+    let plan = build_plan(&request, &before, None);
+    let mut receipt = verify_inventory(&plan, &after, verified_at);
+    let unversioned = receipt.observed.iter().any(|change| {
+        change
+            .after
+            .as_deref()
+            .is_some_and(|version| version.trim().is_empty() || version == "unknown")
+    });
+    if action == OperationAction::Update && receipt.observed.iter().any(|c| c.after.is_none()) {
+        receipt.outcome = VerificationOutcome::Mismatch;
+        receipt.warnings = vec![format!("{source} no longer lists {target}")];
+    } else if unversioned && receipt.outcome == VerificationOutcome::Verified {
+        receipt.outcome = VerificationOutcome::Inconclusive;
+        receipt
+            .warnings
+            .push(format!("{source} does not report a version for {target}"));
+    }
+    receipt
+}
+
+fn same_package_name(source: PackageSource, listed: &str, requested: &str) -> bool {
+    match source {
+        PackageSource::Pip | PackageSource::Pipx => {
+            let normalize = |name: &str| name.to_ascii_lowercase().replace(['_', '.'], "-");
+            normalize(listed) == normalize(requested)
+        }
+        _ => listed == requested,
+    }
+}
+
 fn validate_targets(targets: &[PackageRef], source: PackageSource) -> Result<(), ProviderError> {
     for target in targets {
         if target.name.is_empty()
@@ -1031,13 +2723,13 @@ fn validate_targets(targets: &[PackageRef], source: PackageSource) -> Result<(),
     Ok(())
 }
 
-fn build_plan(
+pub(crate) fn build_plan(
     request: &OperationRequest,
     inventory: &[Package],
-    apt_changes: Option<Vec<PackageChange>>,
+    simulated_changes: Option<Vec<PackageChange>>,
 ) -> ProviderPlan {
     let source = request.targets[0].source;
-    let expected_changes = apt_changes.unwrap_or_else(|| {
+    let expected_changes = simulated_changes.unwrap_or_else(|| {
         request
             .targets
             .iter()
@@ -1048,19 +2740,26 @@ fn build_plan(
                     before: installed.map(|package| package.version.clone()),
                     after: match request.action {
                         OperationAction::Remove => None,
-                        OperationAction::Install | OperationAction::Update => {
-                            target.available_version.clone()
-                        }
+                        OperationAction::Install
+                        | OperationAction::Update
+                        | OperationAction::Rollback => target.available_version.clone(),
                     },
                 }
             })
             .collect()
     });
-    let exact_commands = request
-        .targets
-        .iter()
-        .map(|target| command_for(source, request.action, &target.name))
-        .collect();
+    let exact_commands = if runs_as_batch(source, request.action, &request.targets) {
+        vec![batch_command_for(source, request.action, &request.targets)]
+    } else {
+        request
+            .targets
+            .iter()
+            .map(|target| match target.pin() {
+                Some(version) => versioned_command_for(source, &target.name, version),
+                None => command_for(source, request.action, &target.name),
+            })
+            .collect()
+    };
     let created_at = Utc::now();
     ProviderPlan {
         id: Uuid::new_v4().to_string(),
@@ -1073,24 +2772,257 @@ fn build_plan(
         inventory_fingerprint: inventory_fingerprint(inventory),
         created_at,
         expires_at: created_at + Duration::seconds(PLAN_TTL_SECONDS),
+        rollback_of: None,
+        restore_points: BTreeMap::new(),
+        removal_impact: None,
     }
 }
 
-fn command_for(source: PackageSource, action: OperationAction, name: &str) -> CommandSpec {
-    match (source, action) {
-        (PackageSource::Apt, OperationAction::Install) => CommandSpec {
-            program: "pkexec".to_string(),
-            args: vec!["apt", "install", "-y", "--", name]
-                .into_iter()
-                .map(ToString::to_string)
-                .collect(),
-            privileged: true,
-        },
-        (PackageSource::Apt, OperationAction::Remove) => CommandSpec {
-            program: "pkexec".to_string(),
-            args: vec!["apt", "remove", "-y", "--", name]
-                .into_iter()
-                .map(ToString::to_string)
+/// Reverse a finished operation using what its receipt observed: packages
+/// it installed are removed, packages it updated go back to their `before`
+/// version through the provider's restore point, and packages it removed
+/// are reinstalled. Every package must still be in the state the receipt
+/// recorded, otherwise the rollback would undo changes made since.
+fn build_rollback_plan(
+    original: &OperationRecord,
+    inventory: &[Package],
+    created_at: DateTime<Utc>,
+) -> Result<ProviderPlan, ProviderError> {
+    let source = original.plan.provider.source;
+    let provider = ProviderDescriptor::for_source(source);
+    if provider.rollback != RollbackCapability::ProviderManaged {
+        return Err(ProviderError::protocol(
+            Some(source),
+            format!("{source} operations cannot be rolled back by the provider"),
+        ));
+    }
+    let receipt = match (&original.state, &original.receipt) {
+        (OperationState::Succeeded | OperationState::Partial, Some(receipt)) => receipt,
+        (state, _) => {
+            return Err(ProviderError::protocol(
+                Some(source),
+                format!("Only verified operations can be rolled back; this one is {state:?}"),
+            ));
+        }
+    };
+
+    let mut targets = Vec::new();
+    let mut expected_changes = Vec::new();
+    let mut restore_points = BTreeMap::new();
+    for change in receipt.observed.iter().rev() {
+        if change.before == change.after {
+            continue;
+        }
+        let current = inventory
+            .iter()
+            .find(|package| package.name == change.name)
+            .map(|package| package.version.clone());
+        if current != change.after {
+            return Err(ProviderError {
+                code: ProviderErrorCode::PlanChanged,
+                provider: Some(source),
+                safe_message: format!("{} changed after the operation finished", change.name),
+                diagnostic: format!(
+                    "Receipt recorded {:?}, inventory has {:?}",
+                    change.after, current
+                ),
+                retryable: false,
+                recovery_actions: vec!["Review the package's current state instead".to_string()],
+            });
+        }
+        if let (Some(before), Some(_)) = (&change.before, &change.after) {
+            let restore_point = match source {
+                PackageSource::Flatpak => original
+                    .plan
+                    .restore_points
+                    .get(&change.name)
+                    .cloned()
+                    .ok_or_else(|| {
+                    ProviderError::protocol(
+                        Some(source),
+                        format!("No pre-update commit was recorded for {}", change.name),
+                    )
+                })?,
+                _ => before.clone(),
+            };
+            restore_points.insert(change.name.clone(), restore_point);
+        }
+        // A removed package has to come back at the version it had, which
+        // only providers that install a chosen version can promise.
+        let reinstall = change.before.is_some() && change.after.is_none();
+        if reinstall
+            && !BackendCapabilities::for_source(source)
+                .status(BackendCapability::InstallVersion)
+                .is_supported()
+        {
+            return Err(ProviderError::protocol(
+                Some(source),
+                format!(
+                    "{} was removed, and {source} cannot reinstall a chosen version to restore it",
+                    change.name
+                ),
+            ));
+        }
+        targets.push(PackageRef {
+            name: change.name.clone(),
+            source,
+            installed_version: change.after.clone(),
+            available_version: change.before.clone(),
+            pinned: reinstall,
+        });
+        expected_changes.push(PackageChange {
+            name: change.name.clone(),
+            before: change.after.clone(),
+            after: change.before.clone(),
+        });
+    }
+    if targets.is_empty() {
+        return Err(ProviderError::protocol(
+            Some(source),
+            "The operation left no package changes to roll back",
+        ));
+    }
+    validate_targets(&targets, source)?;
+
+    let exact_commands = targets
+        .iter()
+        .map(|target| {
+            match target
+                .pin()
+                .or_else(|| restore_points.get(&target.name).map(String::as_str))
+            {
+                Some(version) => versioned_command_for(source, &target.name, version),
+                None => command_for(source, OperationAction::Remove, &target.name),
+            }
+        })
+        .collect();
+    Ok(ProviderPlan {
+        id: Uuid::new_v4().to_string(),
+        operation_id: Uuid::new_v4().to_string(),
+        provider,
+        action: OperationAction::Rollback,
+        targets,
+        exact_commands,
+        expected_changes,
+        inventory_fingerprint: inventory_fingerprint(inventory),
+        created_at,
+        expires_at: created_at + Duration::seconds(PLAN_TTL_SECONDS),
+        rollback_of: Some(original.operation_id.clone()),
+        restore_points,
+        removal_impact: None,
+    })
+}
+
+/// The command `PackageManager::downgrade_to` runs for a restore point.
+/// The command that installs `name` at `version`: a pinned target, or a
+/// rollback to its restore point (a commit, for Flatpak).
+fn versioned_command_for(source: PackageSource, name: &str, version: &str) -> CommandSpec {
+    match source {
+        PackageSource::Apt => {
+            let target = format!("{name}={version}");
+            privileged_command(
+                "apt",
+                &["install", "-y", "--allow-downgrades", "--", &target],
+            )
+        }
+        PackageSource::Dnf => {
+            let target = format!("{name}-{version}");
+            privileged_command("dnf", &["downgrade", "-y", "--", &target])
+        }
+        PackageSource::Flatpak => {
+            let commit = format!("--commit={version}");
+            command("flatpak", &["update", "-y", &commit, name])
+        }
+        PackageSource::Npm => command("npm", &["install", "-g", &format!("{name}@{version}")]),
+        PackageSource::Pip => command("pip", &["install", "--user", &format!("{name}=={version}")]),
+        PackageSource::Pipx => command(
+            "pipx",
+            &["install", "--force", &format!("{name}=={version}")],
+        ),
+        PackageSource::Cargo => {
+            command("cargo", &["install", name, "--version", version, "--force"])
+        }
+        PackageSource::Conda => command(
+            "conda",
+            &["install", "-n", "base", "-y", &format!("{name}={version}")],
+        ),
+        _ => command_for(source, OperationAction::Rollback, name),
+    }
+}
+
+/// Commits currently deployed for `targets`, recorded before an update so
+/// `flatpak update --commit` can return to them. Apps whose commit cannot
+/// be read are left out; rolling those back is refused later.
+async fn capture_flatpak_commits(targets: &[PackageRef]) -> BTreeMap<String, String> {
+    let mut commits = BTreeMap::new();
+    for target in targets {
+        let Ok(output) = Command::new("flatpak")
+            .args(["info", "--show-commit", &target.name])
+            .output()
+            .await
+        else {
+            continue;
+        };
+        let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if output.status.success() && !commit.is_empty() {
+            commits.insert(target.name.clone(), commit);
+        }
+    }
+    commits
+}
+
+/// Providers whose tool changes several packages in one invocation, so a
+/// multi-target plan runs as a single command instead of one per package.
+pub fn supports_batch(source: PackageSource) -> bool {
+    matches!(
+        source,
+        PackageSource::Apt
+            | PackageSource::Dnf
+            | PackageSource::Flatpak
+            | PackageSource::Npm
+            | PackageSource::Plugin(_)
+    )
+}
+
+/// Pinned targets each need their own versioned install.
+fn runs_as_batch(source: PackageSource, action: OperationAction, targets: &[PackageRef]) -> bool {
+    targets.len() > 1
+        && action != OperationAction::Rollback
+        && supports_batch(source)
+        && targets.iter().all(|target| !target.pinned)
+}
+
+/// The single-target command with every other target appended; each
+/// provider's command ends with the package argument.
+fn batch_command_for(
+    source: PackageSource,
+    action: OperationAction,
+    targets: &[PackageRef],
+) -> CommandSpec {
+    let mut spec = command_for(source, action, &targets[0].name);
+    for target in &targets[1..] {
+        if let Some(argument) = command_for(source, action, &target.name).args.pop() {
+            spec.args.push(argument);
+        }
+    }
+    spec
+}
+
+fn command_for(source: PackageSource, action: OperationAction, name: &str) -> CommandSpec {
+    match (source, action) {
+        (PackageSource::Apt, OperationAction::Install) => CommandSpec {
+            program: "pkexec".to_string(),
+            args: vec!["apt", "install", "-y", "--", name]
+                .into_iter()
+                .map(ToString::to_string)
+                .collect(),
+            privileged: true,
+        },
+        (PackageSource::Apt, OperationAction::Remove) => CommandSpec {
+            program: "pkexec".to_string(),
+            args: vec!["apt", "remove", "-y", "--", name]
+                .into_iter()
+                .map(ToString::to_string)
                 .collect(),
             privileged: true,
         },
@@ -1102,6 +3034,31 @@ fn command_for(source: PackageSource, action: OperationAction, name: &str) -> Co
                 .collect(),
             privileged: true,
         },
+        (PackageSource::Dnf, OperationAction::Install) => {
+            privileged_command("dnf", &["install", "-y", "--", name])
+        }
+        (PackageSource::Dnf, OperationAction::Remove) => {
+            privileged_command("dnf", &["remove", "-y", "--", name])
+        }
+        (PackageSource::Dnf, OperationAction::Update) => {
+            privileged_command("dnf", &["upgrade", "-y", "--", name])
+        }
+        (PackageSource::Pacman, OperationAction::Install | OperationAction::Update) => {
+            privileged_command("pacman", &["-S", "--noconfirm", "--", name])
+        }
+        (PackageSource::Pacman, OperationAction::Remove) => {
+            privileged_command("pacman", &["-Rs", "--noconfirm", "--", name])
+        }
+        (PackageSource::Zypper, OperationAction::Install) => privileged_command(
+            "zypper",
+            &["--non-interactive", "install", "-y", "--", name],
+        ),
+        (PackageSource::Zypper, OperationAction::Remove) => {
+            privileged_command("zypper", &["--non-interactive", "remove", "-y", "--", name])
+        }
+        (PackageSource::Zypper, OperationAction::Update) => {
+            privileged_command("zypper", &["--non-interactive", "update", "-y", "--", name])
+        }
         (PackageSource::Flatpak, OperationAction::Install) => {
             command("flatpak", &["install", "-y", name])
         }
@@ -1117,6 +3074,10 @@ fn command_for(source: PackageSource, action: OperationAction, name: &str) -> Co
             let spec = format!("{}@latest", name);
             command("npm", &["install", "-g", &spec])
         }
+        (PackageSource::Plugin(id), _) => command(
+            &format!("{}{}", plugin::EXECUTABLE_PREFIX, id.name()),
+            &[&format!("{:?}", action).to_ascii_lowercase(), name],
+        ),
         (_, _) => CommandSpec {
             program: source.to_string().to_ascii_lowercase(),
             args: vec![
@@ -1139,6 +3100,16 @@ fn command(program: &str, args: &[&str]) -> CommandSpec {
     }
 }
 
+fn privileged_command(program: &str, args: &[&str]) -> CommandSpec {
+    let mut argv = vec![program.to_string()];
+    argv.extend(args.iter().map(|argument| (*argument).to_string()));
+    CommandSpec {
+        program: "pkexec".to_string(),
+        args: argv,
+        privileged: true,
+    }
+}
+
 fn inventory_fingerprint(inventory: &[Package]) -> String {
     let mut entries: Vec<_> = inventory
         .iter()
@@ -1158,6 +3129,12 @@ fn inventory_fingerprint(inventory: &[Package]) -> String {
     format!("fnv1a64:{:016x}", hash)
 }
 
+/// Rollback plans come from a finished operation's receipt, never from a
+/// provider simulation.
+fn rollback_not_simulated(source: PackageSource) -> ProviderError {
+    ProviderError::protocol(Some(source), "Rollback plans are not simulated")
+}
+
 async fn probe_apt_changes(
     action: OperationAction,
     targets: &[PackageRef],
@@ -1170,6 +3147,7 @@ async fn probe_apt_changes(
             args.push("install".to_string());
             args.push("--only-upgrade".to_string());
         }
+        OperationAction::Rollback => return Err(rollback_not_simulated(PackageSource::Apt)),
     }
     args.push("--".to_string());
     args.extend(targets.iter().map(|target| target.name.clone()));
@@ -1179,9 +3157,11 @@ async fn probe_apt_changes(
         .await
         .map_err(|error| ProviderError::classify(PackageSource::Apt, error.to_string()))?;
     if !output.status.success() {
-        return Err(ProviderError::classify(
+        let failure = CommandFailure::from_output("apt-get", &output);
+        return Err(ProviderError::from_failure(
             PackageSource::Apt,
-            String::from_utf8_lossy(&output.stderr),
+            &failure,
+            failure.stderr.clone(),
         ));
     }
     let text = format!(
@@ -1246,10 +3226,369 @@ pub fn parse_apt_simulation(output: &str) -> Vec<PackageChange> {
     changes
 }
 
+async fn probe_dnf_changes(
+    action: OperationAction,
+    targets: &[PackageRef],
+    inventory: &[Package],
+) -> Result<Vec<PackageChange>, ProviderError> {
+    let mut args = vec![match action {
+        OperationAction::Install => "install".to_string(),
+        OperationAction::Remove => "remove".to_string(),
+        OperationAction::Update => "upgrade".to_string(),
+        OperationAction::Rollback => return Err(rollback_not_simulated(PackageSource::Dnf)),
+    }];
+    args.push("--assumeno".to_string());
+    args.push("--".to_string());
+    args.extend(targets.iter().map(|target| target.name.clone()));
+    let output = Command::new("dnf")
+        .args(&args)
+        .output()
+        .await
+        .map_err(|error| ProviderError::classify(PackageSource::Dnf, error.to_string()))?;
+    // `--assumeno` answers the prompt with "no", so a resolvable transaction
+    // still exits non-zero; only the absence of a change set is a failure.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut changes = parse_dnf_simulation(&format!("{}\n{}", stdout, stderr));
+    if changes.is_empty() {
+        if stderr.trim().is_empty() || stdout.contains("Nothing to do") {
+            return Err(ProviderError::protocol(
+                Some(PackageSource::Dnf),
+                "DNF simulation returned no concrete package changes",
+            ));
+        }
+        return Err(ProviderError::from_failure(
+            PackageSource::Dnf,
+            &CommandFailure::from_output("dnf", &output),
+            stderr,
+        ));
+    }
+    fill_before_from_inventory(&mut changes, inventory);
+    Ok(changes)
+}
+
+/// dnf4 refuses even `--assumeno` to users other than root. Planning never
+/// asks for a password, so such a refusal falls back to a best-effort plan
+/// built from the inventory.
+fn dnf_probe_refused(error: &ProviderError) -> bool {
+    error.code == ProviderErrorCode::AuthorizationDenied
+}
+
+/// Parse the transaction table printed by `dnf <action> --assumeno` (dnf4 and
+/// dnf5). Section headers decide whether a row installs, upgrades or removes a
+/// package; `replacing` rows (dnf5) carry the version being upgraded from.
+/// Versions are `version-release` with any epoch dropped, matching the
+/// installed inventory.
+pub fn parse_dnf_simulation(output: &str) -> Vec<PackageChange> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Section {
+        None,
+        Install,
+        Upgrade,
+        Remove,
+    }
+
+    let mut changes: Vec<PackageChange> = Vec::new();
+    let mut section = Section::None;
+    // dnf4 moves the rest of a row to the next line when the name overflows
+    // its column.
+    let mut wrapped_name: Option<&str> = None;
+    for raw in output.lines() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('=') {
+            continue;
+        }
+        if line.ends_with(':') && !raw.starts_with(' ') {
+            let header = line.trim_end_matches(':').to_ascii_lowercase();
+            section = if header.starts_with("installing") || header.starts_with("reinstalling") {
+                Section::Install
+            } else if header.starts_with("upgrading") || header.starts_with("downgrading") {
+                Section::Upgrade
+            } else if header.starts_with("removing") {
+                Section::Remove
+            } else {
+                Section::None
+            };
+            continue;
+        }
+        if line.starts_with("Transaction Summary") || line.starts_with("Transaction summary") {
+            section = Section::None;
+            continue;
+        }
+        let mut columns: Vec<&str> = line.split_whitespace().collect();
+        if section != Section::None && columns.len() == 1 {
+            wrapped_name = Some(columns[0]);
+            continue;
+        }
+        if let Some(name) = wrapped_name.take() {
+            columns.insert(0, name);
+        }
+        if columns.first() == Some(&"replacing") {
+            // dnf5: `replacing <name> <arch> <version> ...`;
+            // dnf4: `replacing <name>.<arch> <version>`.
+            let replaced = match columns.len() {
+                3 => columns[1]
+                    .rsplit_once('.')
+                    .map(|(name, _arch)| (name, columns[2])),
+                len if len >= 4 => Some((columns[1], columns[3])),
+                _ => None,
+            };
+            if let Some((name, version)) = replaced {
+                let version = strip_epoch(version).to_string();
+                match changes.iter_mut().rev().find(|change| change.name == name) {
+                    Some(change) => change.before = Some(version),
+                    None => changes.push(PackageChange {
+                        name: name.to_string(),
+                        before: Some(version),
+                        after: None,
+                    }),
+                }
+            }
+            continue;
+        }
+        if section == Section::None || columns.len() < 3 {
+            continue;
+        }
+        let (name, version) = (columns[0], strip_epoch(columns[2]));
+        if changes.iter().any(|change| change.name == name) {
+            continue;
+        }
+        changes.push(match section {
+            Section::Remove => PackageChange {
+                name: name.to_string(),
+                before: Some(version.to_string()),
+                after: None,
+            },
+            Section::Install | Section::Upgrade | Section::None => PackageChange {
+                name: name.to_string(),
+                before: None,
+                after: Some(version.to_string()),
+            },
+        });
+    }
+    changes
+}
+
+fn strip_epoch(version: &str) -> &str {
+    match version.split_once(':') {
+        Some((epoch, rest)) if epoch.chars().all(|character| character.is_ascii_digit()) => rest,
+        _ => version,
+    }
+}
+
+async fn probe_pacman_changes(
+    action: OperationAction,
+    targets: &[PackageRef],
+    inventory: &[Package],
+) -> Result<Vec<PackageChange>, ProviderError> {
+    // `--print` resolves the transaction against the sync databases without
+    // taking the lock, so it runs unprivileged. Removal mirrors the backend's
+    // `-Rs` so unneeded dependencies show up in the plan.
+    if action == OperationAction::Rollback {
+        return Err(rollback_not_simulated(PackageSource::Pacman));
+    }
+    let mut args = vec![if action == OperationAction::Remove {
+        "-Rs".to_string()
+    } else {
+        "-S".to_string()
+    }];
+    args.push("--print".to_string());
+    args.push("--print-format".to_string());
+    args.push("%n %v".to_string());
+    args.push("--".to_string());
+    args.extend(targets.iter().map(|target| target.name.clone()));
+    let output = Command::new("pacman")
+        .args(&args)
+        .output()
+        .await
+        .map_err(|error| ProviderError::classify(PackageSource::Pacman, error.to_string()))?;
+    if !output.status.success() {
+        return Err(ProviderError::classify(
+            PackageSource::Pacman,
+            String::from_utf8_lossy(&output.stderr),
+        ));
+    }
+    let mut changes = parse_pacman_simulation(action, &String::from_utf8_lossy(&output.stdout));
+    if changes.is_empty() {
+        return Err(ProviderError::protocol(
+            Some(PackageSource::Pacman),
+            "Pacman simulation returned no concrete package changes",
+        ));
+    }
+    fill_before_from_inventory(&mut changes, inventory);
+    Ok(changes)
+}
+
+/// Parse `pacman <-S|-Rs> --print --print-format '%n %v'`. Pacman prints the
+/// resolved target list, one `name version` pair per line, so the action
+/// decides whether a row is the version being installed or removed.
+pub fn parse_pacman_simulation(action: OperationAction, output: &str) -> Vec<PackageChange> {
+    let mut changes: Vec<PackageChange> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let [name, version] = columns[..] else {
+            continue;
+        };
+        if name.ends_with(':') || name.starts_with("::") {
+            continue;
+        }
+        if changes.iter().any(|change| change.name == name) {
+            continue;
+        }
+        changes.push(if action == OperationAction::Remove {
+            PackageChange {
+                name: name.to_string(),
+                before: Some(version.to_string()),
+                after: None,
+            }
+        } else {
+            PackageChange {
+                name: name.to_string(),
+                before: None,
+                after: Some(version.to_string()),
+            }
+        });
+    }
+    changes
+}
+
+async fn probe_zypper_changes(
+    action: OperationAction,
+    targets: &[PackageRef],
+    inventory: &[Package],
+) -> Result<Vec<PackageChange>, ProviderError> {
+    let mut args = vec![
+        "--xmlout".to_string(),
+        "--non-interactive".to_string(),
+        match action {
+            OperationAction::Install => "install".to_string(),
+            OperationAction::Remove => "remove".to_string(),
+            OperationAction::Update => "update".to_string(),
+            OperationAction::Rollback => return Err(rollback_not_simulated(PackageSource::Zypper)),
+        },
+        "--dry-run".to_string(),
+        "--".to_string(),
+    ];
+    args.extend(targets.iter().map(|target| target.name.clone()));
+    let output = Command::new("zypper")
+        .args(&args)
+        .output()
+        .await
+        .map_err(|error| ProviderError::classify(PackageSource::Zypper, error.to_string()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        // Errors are reported as `<message type="error">` in XML mode.
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ProviderError::classify(
+            PackageSource::Zypper,
+            format!("{}\n{}", stderr, stdout),
+        ));
+    }
+    let mut changes = parse_zypper_simulation(&stdout);
+    if changes.is_empty() {
+        return Err(ProviderError::protocol(
+            Some(PackageSource::Zypper),
+            "Zypper simulation returned no concrete package changes",
+        ));
+    }
+    fill_before_from_inventory(&mut changes, inventory);
+    Ok(changes)
+}
+
+/// Parse the `<install-summary>` of `zypper --xmlout <action> --dry-run`.
+/// The enclosing `to-*` list decides the kind of change; upgrades and
+/// downgrades carry the installed version in `edition-old`. Only package
+/// solvables are kept (patches and patterns are not inventory entries), and
+/// epochs are dropped to match the rpm-based inventory.
+pub fn parse_zypper_simulation(output: &str) -> Vec<PackageChange> {
+    let mut changes: Vec<PackageChange> = Vec::new();
+    let mut removing: Option<bool> = None;
+    for tag in output.split('<').skip(1) {
+        let Some((tag, _text)) = tag.split_once('>') else {
+            continue;
+        };
+        let tag = tag.trim_end_matches('/').trim();
+        if let Some(list) = tag.strip_prefix("to-") {
+            removing = Some(list == "remove");
+            continue;
+        }
+        if tag.starts_with("/to-") {
+            removing = None;
+            continue;
+        }
+        let Some(attributes) = tag.strip_prefix("solvable") else {
+            continue;
+        };
+        let Some(removing) = removing else {
+            continue;
+        };
+        if xml_attribute(attributes, "type").is_some_and(|kind| kind != "package") {
+            continue;
+        }
+        let (Some(name), Some(edition)) = (
+            xml_attribute(attributes, "name"),
+            xml_attribute(attributes, "edition"),
+        ) else {
+            continue;
+        };
+        if changes.iter().any(|change| change.name == name) {
+            continue;
+        }
+        let edition = strip_epoch(&edition).to_string();
+        changes.push(if removing {
+            PackageChange {
+                name,
+                before: Some(edition),
+                after: None,
+            }
+        } else {
+            PackageChange {
+                name,
+                before: xml_attribute(attributes, "edition-old")
+                    .map(|old| strip_epoch(&old).to_string()),
+                after: Some(edition),
+            }
+        });
+    }
+    changes
+}
+
+fn xml_attribute(attributes: &str, key: &str) -> Option<String> {
+    let needle = format!(" {}=", key);
+    let start = attributes.find(&needle)? + needle.len();
+    let rest = &attributes[start..];
+    let quote = rest
+        .chars()
+        .next()
+        .filter(|quote| *quote == '"' || *quote == '\'')?;
+    let value = rest[1..].split(quote).next()?;
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn fill_before_from_inventory(changes: &mut [PackageChange], inventory: &[Package]) {
+    for change in changes {
+        if change.before.is_none() {
+            change.before = inventory
+                .iter()
+                .find(|package| package.name == change.name)
+                .map(|package| package.version.clone());
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::backend::PackageBackend;
+    use crate::backend::dependency_graph::tests::GraphBackend;
+    use crate::backend::dependency_graph::Dependent;
+    use crate::backend::{PackageBackend, ProtectedPackage, ProtectionReason};
     use anyhow::Result;
     use async_trait::async_trait;
     use serde_json::Value;
@@ -1276,6 +3615,11 @@ mod tests {
         }
 
         async fn install(&self, name: &str) -> Result<()> {
+            if name == "slow" {
+                // Stands in for a long download the provider can abandon.
+                crate::backend::streaming::output_cancellable(Command::new("sleep").arg("30"))
+                    .await?;
+            }
             let mut inventory = self.inventory.lock().await;
             inventory.retain(|package| package.name != name);
             inventory.push(package(name, self.source, "2.0"));
@@ -1294,6 +3638,13 @@ mod tests {
             self.install(name).await
         }
 
+        async fn downgrade_to(&self, name: &str, version: &str) -> Result<()> {
+            let mut inventory = self.inventory.lock().await;
+            inventory.retain(|package| package.name != name);
+            inventory.push(package(name, self.source, version));
+            Ok(())
+        }
+
         async fn search(&self, _query: &str) -> Result<Vec<Package>> {
             Ok(Vec::new())
         }
@@ -1309,6 +3660,7 @@ mod tests {
             source,
             installed_version: Some("1.0".to_string()),
             available_version: Some("2.0".to_string()),
+            pinned: false,
         }
     }
 
@@ -1328,14 +3680,29 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
     fn fixture_source(value: &str) -> PackageSource {
         match value {
             "Apt" => PackageSource::Apt,
+            "Dnf" => PackageSource::Dnf,
             "Flatpak" => PackageSource::Flatpak,
             "Npm" => PackageSource::Npm,
+            "Pacman" => PackageSource::Pacman,
+            "Zypper" => PackageSource::Zypper,
+            "Snap" => PackageSource::Snap,
+            "Pip" => PackageSource::Pip,
+            "Pipx" => PackageSource::Pipx,
+            "Cargo" => PackageSource::Cargo,
+            "Brew" => PackageSource::Brew,
+            "Aur" => PackageSource::Aur,
+            "Conda" => PackageSource::Conda,
+            "Mamba" => PackageSource::Mamba,
+            "Dart" => PackageSource::Dart,
+            "Deb" => PackageSource::Deb,
+            "AppImage" => PackageSource::AppImage,
             other => panic!("unknown fixture provider {other}"),
         }
     }
@@ -1351,10 +3718,11 @@ mod tests {
 
     #[test]
     fn stable_provider_descriptors_are_truthful() {
-        assert_eq!(
-            ProviderDescriptor::for_source(PackageSource::Apt).fidelity,
-            PlanFidelity::Exact
-        );
+        for source in [PackageSource::Apt, PackageSource::Dnf] {
+            let descriptor = ProviderDescriptor::for_source(source);
+            assert_eq!(descriptor.tier, ProviderTier::Stable);
+            assert_eq!(descriptor.fidelity, PlanFidelity::Exact);
+        }
         for source in [PackageSource::Flatpak, PackageSource::Npm] {
             let descriptor = ProviderDescriptor::for_source(source);
             assert_eq!(descriptor.tier, ProviderTier::Stable);
@@ -1389,6 +3757,11 @@ mod tests {
             assert_eq!(descriptor.tier, ProviderTier::Stable);
             assert_eq!(format!("{:?}", descriptor.tier), provider["tier"]);
             assert_eq!(format!("{:?}", descriptor.fidelity), provider["fidelity"]);
+            assert_eq!(format!("{:?}", descriptor.rollback), provider["rollback"]);
+            assert_eq!(
+                format!("{:?}", descriptor.cancellation),
+                provider["cancellation"]
+            );
 
             let operations = provider["operations"].as_array().expect("operations");
             assert_eq!(operations.len(), 3);
@@ -1403,7 +3776,10 @@ mod tests {
                     .collect::<Vec<_>>();
                 assert_eq!(actual.program, operation["program"]);
                 assert_eq!(actual.args, expected_args);
-                assert_eq!(actual.privileged, source == PackageSource::Apt);
+                assert_eq!(
+                    actual.privileged,
+                    matches!(source, PackageSource::Apt | PackageSource::Dnf)
+                );
             }
         }
     }
@@ -1467,150 +3843,1709 @@ mod tests {
         }
     }
 
-    #[test]
-    fn verification_rejects_wrong_installed_version() {
+    #[test]
+    fn verification_rejects_wrong_installed_version() {
+        let request = OperationRequest::new(
+            OperationAction::Install,
+            vec![package_ref("demo", PackageSource::Npm)],
+            RequestedBy::Tui,
+        );
+        let plan = build_plan(&request, &[], None);
+        let receipt = verify_inventory(
+            &plan,
+            &[package("demo", PackageSource::Npm, "1.5")],
+            Utc::now(),
+        );
+        assert_eq!(receipt.outcome, VerificationOutcome::Mismatch);
+    }
+
+    #[test]
+    fn provider_classifiers_cover_every_reported_error_code() {
+        let reported = [
+            ProviderErrorCode::AuthorizationCancelled,
+            ProviderErrorCode::AuthorizationDenied,
+            ProviderErrorCode::NoPrivilegeAgent,
+            ProviderErrorCode::LockBusy,
+            ProviderErrorCode::DependencyConflict,
+            ProviderErrorCode::Network,
+            ProviderErrorCode::NotFound,
+            ProviderErrorCode::RepositoryUnavailable,
+            ProviderErrorCode::SignatureInvalid,
+            ProviderErrorCode::DiskFull,
+            ProviderErrorCode::Timeout,
+            ProviderErrorCode::Interrupted,
+            ProviderErrorCode::Unknown,
+        ]
+        .map(|code| format!("{:?}", code));
+        let fixture: Value = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/provider-error-classification.json"
+        )))
+        .expect("valid classification fixture");
+
+        for provider in fixture["providers"].as_array().expect("provider array") {
+            let source = fixture_source(provider["source"].as_str().expect("source"));
+            let mut covered: HashSet<String> = HashSet::new();
+            for case in provider["cases"].as_array().expect("case array") {
+                let failure = CommandFailure {
+                    program: provider["program"].as_str().expect("program").to_string(),
+                    exit_code: case["exit_code"].as_i64().map(|code| code as i32),
+                    stdout: case["stdout"].as_str().unwrap_or_default().to_string(),
+                    stderr: case["stderr"].as_str().unwrap_or_default().to_string(),
+                };
+                let error = ProviderError::from_failure(source, &failure, "provider failed");
+                assert_eq!(
+                    format!("{:?}", error.code),
+                    case["code"],
+                    "{source:?} misclassified {}",
+                    failure.output()
+                );
+                covered.insert(case["code"].as_str().expect("code").to_string());
+            }
+            for code in provider["unreachable"]
+                .as_array()
+                .expect("unreachable array")
+            {
+                let code = code.as_str().expect("code");
+                assert!(
+                    covered.insert(code.to_string()),
+                    "{source:?} lists {code} as both reachable and unreachable"
+                );
+            }
+            for code in &reported {
+                assert!(covered.contains(code), "{source:?} has no {code} fixture");
+            }
+        }
+    }
+
+    #[test]
+    fn package_names_do_not_decide_the_error_class() {
+        let failure = CommandFailure::new(
+            "npm",
+            Some(1),
+            "npm error code ECONNRESET\nnpm error network request to https://registry.npmjs.org/lockfile failed, reason: socket hang up",
+        );
+        // The keyword heuristic alone reads the package name as a lock.
+        assert_eq!(
+            ProviderError::classify(PackageSource::Winget, failure.stderr.clone()).code,
+            ProviderErrorCode::LockBusy
+        );
+        let error = ProviderError::from_failure(PackageSource::Npm, &failure, "install failed");
+        assert_eq!(error.code, ProviderErrorCode::Network);
+        assert_eq!(
+            ProviderError::classify(PackageSource::Npm, failure.stderr.clone()).code,
+            ProviderErrorCode::Network
+        );
+
+        let terminated = CommandFailure::new("flatpak", None, "");
+        assert_eq!(
+            ProviderError::from_failure(PackageSource::Flatpak, &terminated, "update failed").code,
+            ProviderErrorCode::Interrupted
+        );
+    }
+
+    #[test]
+    fn backend_errors_carry_their_command_failure_to_the_classifier() {
+        let error = CommandFailure::new(
+            "apt",
+            Some(100),
+            "E: Could not get lock /var/lib/dpkg/lock-frontend. It is held by process 7 (apt)",
+        )
+        .with_message("Failed to install package lockfile-progs");
+        assert_eq!(
+            error.to_string(),
+            "Failed to install package lockfile-progs"
+        );
+        let classified = ProviderError::from_error(PackageSource::Apt, &error);
+        assert_eq!(classified.code, ProviderErrorCode::LockBusy);
+        assert_eq!(
+            classified.diagnostic,
+            "Failed to install package lockfile-progs"
+        );
+    }
+
+    #[test]
+    fn npm_engine_mismatch_is_actionable_and_not_blindly_retryable() {
+        let error = ProviderError::classify(
+            PackageSource::Npm,
+            "npm ERR! code EBADENGINE\nnpm ERR! engine Unsupported engine\nnpm ERR! notsup Required: node >=24.15.0",
+        );
+        assert_eq!(error.code, ProviderErrorCode::DependencyConflict);
+        assert_eq!(
+            error.safe_message,
+            "The package runtime requirements are not satisfied"
+        );
+        assert!(!error.retryable);
+        assert!(error.recovery_actions[0].contains("Upgrade"));
+    }
+
+    #[tokio::test]
+    async fn stable_provider_execution_and_receipt_matrix_is_complete() {
+        for source in STABLE_PROVIDERS {
+            for action in [
+                OperationAction::Install,
+                OperationAction::Remove,
+                OperationAction::Update,
+            ] {
+                let initial_inventory = if action == OperationAction::Install {
+                    Vec::new()
+                } else {
+                    vec![package("demo", source, "1.0")]
+                };
+                let inventory = Arc::new(Mutex::new(initial_inventory.clone()));
+                let backend = ContractBackend {
+                    source,
+                    inventory: Arc::clone(&inventory),
+                };
+                let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+                backends.insert(source, Box::new(backend));
+                let manager = PackageManager {
+                    backends,
+                    enabled_sources: HashSet::from([source]),
+                    provider_statuses: HashMap::new(),
+                };
+                let root = std::env::temp_dir().join(format!(
+                    "linget-provider-contract-{}-{}",
+                    source,
+                    Uuid::new_v4()
+                ));
+                let store_path = root.join("transactions.json");
+                let engine = TransactionEngine::load(Arc::new(RwLock::new(manager)), store_path)
+                    .await
+                    .expect("load contract engine");
+                let request = OperationRequest::new(
+                    action,
+                    vec![package_ref("demo", source)],
+                    RequestedBy::Tui,
+                );
+                let apt_changes = (source == PackageSource::Apt).then(|| {
+                    vec![PackageChange {
+                        name: "demo".to_string(),
+                        before: (action != OperationAction::Install).then(|| "1.0".to_string()),
+                        after: (action != OperationAction::Remove).then(|| "2.0".to_string()),
+                    }]
+                });
+                let plan = build_plan(&request, &initial_inventory, apt_changes);
+                engine
+                    .upsert_record(OperationRecord {
+                        operation_id: request.id,
+                        state: OperationState::Ready,
+                        risk: RiskAssessment::for_plan(&plan),
+                        plan: plan.clone(),
+                        receipt: None,
+                        error: None,
+                        updated_at: Utc::now(),
+                        owner_pid: None,
+                        recovered_at: None,
+                        snapshot: None,
+                    })
+                    .await
+                    .expect("persist reviewed plan");
+                let receipt = engine
+                    .execute(plan, CancellationFlag::default())
+                    .await
+                    .expect("execute contract operation");
+                assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+                assert_eq!(engine.records().await[0].state, OperationState::Succeeded);
+                fs::remove_dir_all(root)
+                    .await
+                    .expect("remove provider contract directory");
+            }
+        }
+    }
+
+    #[test]
+    fn mixed_source_requests_split_into_ordered_provider_requests() {
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![
+                package_ref("left-pad", PackageSource::Npm),
+                package_ref("curl", PackageSource::Apt),
+                package_ref("org.demo.App", PackageSource::Flatpak),
+                package_ref("vim", PackageSource::Apt),
+            ],
+            RequestedBy::Tui,
+        );
+        assert!(request.source().is_err());
+        let split = request.split_by_source();
+        let sources: Vec<_> = split
+            .iter()
+            .map(|request| request.source().expect("single source"))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                PackageSource::Apt,
+                PackageSource::Flatpak,
+                PackageSource::Npm
+            ]
+        );
+        assert_eq!(split[0].targets.len(), 2);
+        assert!(split.iter().all(|part| part.id != request.id));
+    }
+
+    #[test]
+    fn bundle_risk_is_the_riskiest_plan_with_all_reasons() {
+        let caution = RiskAssessment {
+            level: RiskLevel::Caution,
+            reasons: vec![RiskReason::BestEffortPlan],
+            requires_explicit_confirmation: false,
+        };
+        let high = RiskAssessment {
+            level: RiskLevel::High,
+            reasons: vec![RiskReason::RemovesPackages, RiskReason::BestEffortPlan],
+            requires_explicit_confirmation: true,
+        };
+        let risk = RiskAssessment::aggregate([&caution, &high]);
+        assert_eq!(risk.level, RiskLevel::High);
+        assert!(risk.requires_explicit_confirmation);
+        assert_eq!(
+            risk.reasons,
+            [RiskReason::BestEffortPlan, RiskReason::RemovesPackages]
+        );
+    }
+
+    #[tokio::test]
+    async fn pinned_targets_install_the_pinned_version_one_by_one() {
+        let manager = snapshot_engine_manager(PackageSource::Npm);
+        let root = std::env::temp_dir().join(format!("linget-pinned-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+        let target = |name: &str, version: &str| {
+            PackageRef::pinned(&package(name, PackageSource::Npm, ""), version)
+        };
+        let request = OperationRequest::new(
+            OperationAction::Install,
+            vec![target("eslint", "8.57.0"), target("prettier", "3.2.5")],
+            RequestedBy::Cli,
+        );
+
+        let (plan, _) = engine.plan(request).await.expect("plan pinned install");
+        assert_eq!(
+            plan.exact_commands
+                .iter()
+                .map(|command| command.args.join(" "))
+                .collect::<Vec<_>>(),
+            ["install -g eslint@8.57.0", "install -g prettier@3.2.5"]
+        );
+        let receipt = engine
+            .execute(plan, CancellationFlag::default())
+            .await
+            .expect("execute pinned install");
+        assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+        assert_eq!(
+            receipt.observed,
+            vec![
+                change("eslint", None, Some("8.57.0")),
+                change("prettier", None, Some("3.2.5"))
+            ]
+        );
+
+        fs::remove_dir_all(root).await.ok();
+    }
+
+    #[tokio::test]
+    async fn bundle_executes_every_provider_and_records_combined_summary() {
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        for source in [PackageSource::Flatpak, PackageSource::Npm] {
+            backends.insert(
+                source,
+                Box::new(ContractBackend {
+                    source,
+                    inventory: Arc::new(Mutex::new(vec![package("demo", source, "1.0")])),
+                }),
+            );
+        }
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([PackageSource::Flatpak, PackageSource::Npm]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-bundle-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load bundle engine");
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![
+                package_ref("demo", PackageSource::Npm),
+                package_ref("demo", PackageSource::Flatpak),
+            ],
+            RequestedBy::Tui,
+        );
+        let bundle_id = request.id.clone();
+        let bundle = engine.plan_bundle(request).await.expect("plan bundle");
+        assert_eq!(bundle.id, bundle_id);
+        assert_eq!(bundle.plans.len(), 2);
+        assert_eq!(bundle.plans[0].provider.source, PackageSource::Flatpak);
+        assert_eq!(bundle.target_count(), 2);
+
+        engine
+            .resume_reviewed_bundle(&bundle)
+            .await
+            .expect("resume reviewed bundle");
+        let summary = engine
+            .execute_bundle(bundle, CancellationFlag::default())
+            .await
+            .expect("execute bundle");
+        assert_eq!(summary.outcome, VerificationOutcome::Verified);
+        assert_eq!(summary.receipts.len(), 2);
+        assert!(summary.failed.is_empty());
+
+        let records = engine.bundle_records().await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].state, OperationState::Succeeded);
+        assert_eq!(records[0].operation_ids.len(), 2);
+        assert_eq!(
+            records[0].summary.as_ref().map(|summary| summary.outcome),
+            Some(VerificationOutcome::Verified)
+        );
+        fs::remove_dir_all(root)
+            .await
+            .expect("remove bundle directory");
+    }
+
+    #[tokio::test]
+    async fn bundle_diff_compares_each_provider_with_its_reviewed_plan() {
+        let npm = Arc::new(Mutex::new(vec![package("demo", PackageSource::Npm, "1.0")]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            PackageSource::Npm,
+            Box::new(ContractBackend {
+                source: PackageSource::Npm,
+                inventory: Arc::clone(&npm),
+            }),
+        );
+        backends.insert(
+            PackageSource::Flatpak,
+            Box::new(ContractBackend {
+                source: PackageSource::Flatpak,
+                inventory: Arc::new(Mutex::new(vec![package(
+                    "demo",
+                    PackageSource::Flatpak,
+                    "1.0",
+                )])),
+            }),
+        );
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([PackageSource::Flatpak, PackageSource::Npm]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-bundle-diff-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load bundle engine");
+        let request = || {
+            OperationRequest::new(
+                OperationAction::Update,
+                vec![
+                    package_ref("demo", PackageSource::Npm),
+                    package_ref("demo", PackageSource::Flatpak),
+                ],
+                RequestedBy::Tui,
+            )
+        };
+        let reviewed = engine.plan_bundle(request()).await.expect("plan bundle");
+
+        let replanned = engine.plan_bundle(request()).await.expect("re-plan bundle");
+        assert!(PlanDiff::between_bundles(&reviewed, &replanned).is_empty());
+
+        // Only the npm package moved; the Flatpak one of the same name did not.
+        npm.lock().await[0].version = "1.5".to_string();
+        let moved = engine.plan_bundle(request()).await.expect("re-plan bundle");
+        let diff = PlanDiff::between_bundles(&reviewed, &moved);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(
+            diff.reversioned,
+            vec![ChangeRevision {
+                reviewed: change("demo", Some("1.0"), Some("2.0")),
+                fresh: change("demo", Some("1.5"), Some("2.0")),
+            }]
+        );
+
+        let mut npm_only = moved.clone();
+        npm_only
+            .plans
+            .retain(|plan| plan.provider.source == PackageSource::Npm);
+        let diff = PlanDiff::between_bundles(&reviewed, &npm_only);
+        assert_eq!(diff.removed, vec![change("demo", Some("1.0"), Some("2.0"))]);
+
+        fs::remove_dir_all(root).await.ok();
+    }
+
+    #[test]
+    fn command_specs_keep_package_names_as_single_argv_values() {
+        let command = command_for(
+            PackageSource::Npm,
+            OperationAction::Install,
+            "@scope/example",
+        );
+        assert_eq!(command.program, "npm");
+        assert_eq!(command.args, ["install", "-g", "@scope/example"]);
+        assert!(!command
+            .args
+            .iter()
+            .any(|argument| argument.contains("sh -c")));
+    }
+
+    #[test]
+    fn multi_target_plans_run_as_one_provider_command() {
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![
+                package_ref("typescript", PackageSource::Npm),
+                package_ref("@scope/example", PackageSource::Npm),
+            ],
+            RequestedBy::Cli,
+        );
+        let plan = build_plan(&request, &[], None);
+        assert_eq!(plan.exact_commands.len(), 1);
+        assert_eq!(
+            plan.exact_commands[0].args,
+            [
+                "install",
+                "-g",
+                "typescript@latest",
+                "@scope/example@latest"
+            ]
+        );
+
+        let apt = batch_command_for(
+            PackageSource::Apt,
+            OperationAction::Remove,
+            &[
+                package_ref("vim", PackageSource::Apt),
+                package_ref("curl", PackageSource::Apt),
+            ],
+        );
+        assert_eq!(apt.args, ["apt", "remove", "-y", "--", "vim", "curl"]);
+
+        // Providers without a multi-target invocation keep one command per
+        // package.
+        let request = OperationRequest::new(
+            OperationAction::Install,
+            vec![
+                package_ref("vim", PackageSource::Pacman),
+                package_ref("curl", PackageSource::Pacman),
+            ],
+            RequestedBy::Cli,
+        );
+        assert_eq!(build_plan(&request, &[], None).exact_commands.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn coalesced_plans_run_once_and_keep_their_own_receipts() {
+        let source = PackageSource::Npm;
+        let inventory = Arc::new(Mutex::new(vec![
+            package("typescript", source, "1.0"),
+            package("eslint", source, "1.0"),
+        ]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::clone(&inventory),
+            }),
+        );
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-coalesce-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+        let mut reviewed = Vec::new();
+        for name in ["typescript", "eslint"] {
+            let (plan, _) = engine
+                .plan(OperationRequest::new(
+                    OperationAction::Update,
+                    vec![package_ref(name, source)],
+                    RequestedBy::Tui,
+                ))
+                .await
+                .expect("plan update");
+            reviewed.push(plan);
+        }
+
+        let receipts = engine
+            .execute_coalesced(&reviewed, RequestedBy::Tui, CancellationFlag::default())
+            .await
+            .expect("execute")
+            .expect("the combined plan matches what was reviewed");
+
+        assert_eq!(receipts.len(), 2);
+        for (plan, receipt) in reviewed.iter().zip(&receipts) {
+            assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+            assert_eq!(receipt.expected, plan.expected_changes);
+        }
+        let records = engine.records().await;
+        for plan in &reviewed {
+            let record = records
+                .iter()
+                .find(|record| record.operation_id == plan.operation_id)
+                .expect("member record");
+            assert_eq!(record.state, OperationState::Succeeded);
+            assert_eq!(
+                record.receipt.as_ref(),
+                receipts
+                    .iter()
+                    .find(|receipt| { receipt.expected == plan.expected_changes })
+            );
+        }
+        let combined = records
+            .iter()
+            .find(|record| record.plan.targets.len() == 2)
+            .expect("combined record");
+        assert_eq!(combined.state, OperationState::Succeeded);
+        assert_eq!(
+            combined.plan.exact_commands[0].args,
+            ["install", "-g", "typescript@latest", "eslint@latest"]
+        );
+
+        // Plans that went stale are left for the caller to re-plan one by one.
+        let (stale, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Remove,
+                vec![package_ref("typescript", source)],
+                RequestedBy::Tui,
+            ))
+            .await
+            .expect("plan remove");
+        let (other, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Remove,
+                vec![package_ref("eslint", source)],
+                RequestedBy::Tui,
+            ))
+            .await
+            .expect("plan remove");
+        inventory.lock().await[0].version = "3.0".to_string();
+        assert_eq!(
+            engine
+                .execute_coalesced(
+                    &[stale, other],
+                    RequestedBy::Tui,
+                    CancellationFlag::default()
+                )
+                .await
+                .expect("execute"),
+            None
+        );
+        fs::remove_dir_all(root).await.expect("remove temp store");
+    }
+
+    #[tokio::test]
+    async fn engines_in_parallel_lanes_keep_each_others_records() {
+        let root = std::env::temp_dir().join(format!("linget-lanes-{}", Uuid::new_v4()));
+        let path = root.join("transactions.json");
+        let mut engines = Vec::new();
+        for source in [PackageSource::Npm, PackageSource::Flatpak] {
+            let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+            backends.insert(
+                source,
+                Box::new(ContractBackend {
+                    source,
+                    inventory: Arc::new(Mutex::new(Vec::new())),
+                }),
+            );
+            let manager = PackageManager {
+                backends,
+                enabled_sources: HashSet::from([source]),
+                provider_statuses: HashMap::new(),
+            };
+            let engine = TransactionEngine::load(Arc::new(RwLock::new(manager)), path.clone())
+                .await
+                .expect("load engine");
+            engines.push((source, engine));
+        }
+
+        let mut planned = Vec::new();
+        for (source, engine) in &engines {
+            let (plan, _) = engine
+                .plan(OperationRequest::new(
+                    OperationAction::Install,
+                    vec![package_ref("demo", *source)],
+                    RequestedBy::Tui,
+                ))
+                .await
+                .expect("plan install");
+            planned.push(plan.operation_id);
+        }
+
+        let store = TransactionStore::load(&path).await.expect("reload store");
+        for operation_id in &planned {
+            assert!(
+                store
+                    .operations
+                    .iter()
+                    .any(|record| &record.operation_id == operation_id),
+                "a second engine must not drop the first engine's record"
+            );
+        }
+        assert_eq!(
+            ExecutionLane::for_source(PackageSource::Apt),
+            ExecutionLane::for_source(PackageSource::Snap)
+        );
+        assert_ne!(
+            ExecutionLane::for_source(PackageSource::Npm),
+            ExecutionLane::for_source(PackageSource::Flatpak)
+        );
+        fs::remove_dir_all(root).await.expect("remove temp store");
+    }
+
+    #[test]
+    fn beta_provider_mutations_are_verified_against_inventory() {
+        let now = Utc::now();
+        let receipt = verify_unreviewed(
+            OperationAction::Install,
+            "Django_Rest",
+            PackageSource::Pip,
+            None,
+            &[],
+            &[package("django-rest", PackageSource::Pip, "3.0")],
+            now,
+        );
+        assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+        assert_eq!(
+            receipt.observed,
+            vec![change("Django_Rest", None, Some("3.0"))]
+        );
+
+        let removed = verify_unreviewed(
+            OperationAction::Remove,
+            "ripgrep",
+            PackageSource::Cargo,
+            None,
+            &[package("ripgrep", PackageSource::Cargo, "14.1.0")],
+            &[],
+            now,
+        );
+        assert_eq!(removed.outcome, VerificationOutcome::Verified);
+
+        // Without a known target version an update cannot be proven, even
+        // when the version moved.
+        let updated = verify_unreviewed(
+            OperationAction::Update,
+            "httpie",
+            PackageSource::Pipx,
+            None,
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            &[package("httpie", PackageSource::Pipx, "3.2.2")],
+            now,
+        );
+        assert_eq!(updated.outcome, VerificationOutcome::Inconclusive);
+        assert_eq!(
+            updated.observed,
+            vec![change("httpie", Some("3.2.1"), Some("3.2.2"))]
+        );
+
+        // The version offered when the update was queued proves it either way.
+        let landed = verify_unreviewed(
+            OperationAction::Update,
+            "httpie",
+            PackageSource::Pipx,
+            Some("3.2.2"),
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            &[package("httpie", PackageSource::Pipx, "3.2.2")],
+            now,
+        );
+        assert_eq!(landed.outcome, VerificationOutcome::Verified);
+        let stuck = verify_unreviewed(
+            OperationAction::Update,
+            "httpie",
+            PackageSource::Pipx,
+            Some("3.2.2"),
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            now,
+        );
+        assert_eq!(stuck.outcome, VerificationOutcome::Mismatch);
+
+        // With no update pending, an unchanged version is the expected result.
+        let current = verify_unreviewed(
+            OperationAction::Update,
+            "httpie",
+            PackageSource::Pipx,
+            Some("3.2.1"),
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            &[package("httpie", PackageSource::Pipx, "3.2.1")],
+            now,
+        );
+        assert_eq!(current.outcome, VerificationOutcome::Verified);
+
+        let vanished = verify_unreviewed(
+            OperationAction::Update,
+            "wget",
+            PackageSource::Brew,
+            None,
+            &[package("wget", PackageSource::Brew, "1.24")],
+            &[],
+            now,
+        );
+        assert_eq!(vanished.outcome, VerificationOutcome::Mismatch);
+
+        let unversioned = verify_unreviewed(
+            OperationAction::Install,
+            "hello",
+            PackageSource::Snap,
+            None,
+            &[],
+            &[package("hello", PackageSource::Snap, "")],
+            now,
+        );
+        assert_eq!(unversioned.outcome, VerificationOutcome::Inconclusive);
+
+        let missing = verify_unreviewed(
+            OperationAction::Install,
+            "hello",
+            PackageSource::Snap,
+            None,
+            &[],
+            &[],
+            now,
+        );
+        assert_eq!(missing.outcome, VerificationOutcome::Mismatch);
+    }
+
+    #[test]
+    fn unsafe_targets_are_rejected_before_planning() {
+        for name in ["-rf", "bad\nname", ""] {
+            let error = validate_targets(
+                &[PackageRef {
+                    name: name.to_string(),
+                    source: PackageSource::Npm,
+                    installed_version: None,
+                    available_version: None,
+                    pinned: false,
+                }],
+                PackageSource::Npm,
+            )
+            .expect_err("unsafe name must fail");
+            assert_eq!(error.code, ProviderErrorCode::Protocol);
+        }
+    }
+
+    #[test]
+    fn apt_simulation_parser_captures_dependency_changes() {
+        let changes = parse_apt_simulation(
+            "Inst demo [1.0] (2.0 Ubuntu:stable [amd64])\nInst dependency (3.0 Ubuntu:stable [amd64])\nRemv old-lib [0.9]",
+        );
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].before.as_deref(), Some("1.0"));
+        assert_eq!(changes[0].after.as_deref(), Some("2.0"));
+        assert_eq!(changes[2].after, None);
+    }
+
+    #[test]
+    fn dnf4_simulation_parser_captures_dependencies_and_removals() {
+        let changes = parse_dnf_simulation(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/dnf4-install-assumeno.txt"
+        )));
+        assert_eq!(
+            changes,
+            vec![
+                PackageChange {
+                    name: "htop".to_string(),
+                    before: None,
+                    after: Some("3.3.0-1.fc40".to_string()),
+                },
+                PackageChange {
+                    name: "curl".to_string(),
+                    before: None,
+                    after: Some("8.6.0-8.fc40".to_string()),
+                },
+                PackageChange {
+                    name: "hwloc-libs".to_string(),
+                    before: None,
+                    after: Some("2.10.0-3.fc40".to_string()),
+                },
+                PackageChange {
+                    name: "libnl3".to_string(),
+                    before: None,
+                    after: Some("3.9.0-3.fc40".to_string()),
+                },
+                PackageChange {
+                    name: "oldtop".to_string(),
+                    before: Some("1.0-4.fc39".to_string()),
+                    after: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dnf4_simulation_parser_joins_rows_wrapped_after_long_names() {
+        let changes = parse_dnf_simulation(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/dnf4-install-wrapped-assumeno.txt"
+        )));
+        let rows: Vec<_> = changes
+            .iter()
+            .map(|change| (change.name.as_str(), change.after.as_deref()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    "texlive-collection-fontsrecommended",
+                    Some("20230311-85.fc40")
+                ),
+                ("texlive-tex-gyre", Some("20230311-85.fc40")),
+                ("google-noto-sans-cjk-vf-fonts", Some("2.004-6.fc40")),
+            ]
+        );
+    }
+
+    #[test]
+    fn dnf4_refusing_unprivileged_simulation_falls_back_to_best_effort() {
+        let failure = CommandFailure {
+            program: "dnf".to_string(),
+            exit_code: Some(1),
+            stdout: String::new(),
+            stderr: include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/dnf4-assumeno-nonroot.txt"
+            ))
+            .to_string(),
+        };
+        let error = ProviderError::from_failure(PackageSource::Dnf, &failure, "dnf failed");
+        assert_eq!(error.code, ProviderErrorCode::AuthorizationDenied);
+        assert!(dnf_probe_refused(&error));
+
+        let network = CommandFailure {
+            stderr: "Curl error (6): Couldn't resolve host name".to_string(),
+            ..failure
+        };
+        let error = ProviderError::from_failure(PackageSource::Dnf, &network, "dnf failed");
+        assert!(!dnf_probe_refused(&error));
+    }
+
+    #[test]
+    fn dnf5_simulation_parser_reads_replaced_versions_and_epochs() {
+        let changes = parse_dnf_simulation(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/dnf5-upgrade-assumeno.txt"
+        )));
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].name, "curl");
+        assert_eq!(changes[0].before.as_deref(), Some("8.6.0-7.fc40"));
+        assert_eq!(changes[0].after.as_deref(), Some("8.6.0-8.fc40"));
+        assert_eq!(changes[1].name, "vim-enhanced");
+        assert_eq!(changes[1].before.as_deref(), Some("9.1.158-1.fc40"));
+        assert_eq!(changes[1].after.as_deref(), Some("9.1.264-1.fc40"));
+        assert_eq!(changes[2].name, "vim-data");
+        assert_eq!(changes[2].after.as_deref(), Some("9.1.264-1.fc40"));
+    }
+
+    #[tokio::test]
+    async fn simulation_probes_refuse_rollback_instead_of_panicking() {
+        let targets = [PackageRef {
+            name: "curl".to_string(),
+            source: PackageSource::Apt,
+            installed_version: None,
+            available_version: None,
+            pinned: false,
+        }];
+        let errors = [
+            probe_apt_changes(OperationAction::Rollback, &targets).await,
+            probe_dnf_changes(OperationAction::Rollback, &targets, &[]).await,
+            probe_pacman_changes(OperationAction::Rollback, &targets, &[]).await,
+            probe_zypper_changes(OperationAction::Rollback, &targets, &[]).await,
+        ];
+        for error in errors {
+            assert_eq!(error.unwrap_err().code, ProviderErrorCode::Protocol);
+        }
+    }
+
+    #[test]
+    fn dnf_nothing_to_do_yields_no_changes() {
+        assert!(parse_dnf_simulation(
+            "Last metadata expiration check: 0:01:02 ago.\nDependencies resolved.\nNothing to do.\nComplete!"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn pacman_print_parser_lists_resolved_targets() {
+        let changes = parse_pacman_simulation(
+            OperationAction::Install,
+            include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/pacman-install-print.txt"
+            )),
+        );
+        let names: Vec<_> = changes.iter().map(|change| change.name.as_str()).collect();
+        assert_eq!(names, vec!["libnl", "hwloc", "htop"]);
+        assert!(changes.iter().all(|change| change.before.is_none()));
+        assert_eq!(changes[2].after.as_deref(), Some("3.3.0-2"));
+
+        let removals =
+            parse_pacman_simulation(OperationAction::Remove, "htop 3.3.0-2\nlibnl 3.9.0-1\n");
+        assert_eq!(removals[1].before.as_deref(), Some("3.9.0-1"));
+        assert_eq!(removals[1].after, None);
+    }
+
+    #[test]
+    fn zypper_dry_run_parser_reads_install_summary() {
+        let changes = parse_zypper_simulation(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/zypper-install-dry-run.xml"
+        )));
+        assert_eq!(
+            changes,
+            vec![
+                PackageChange {
+                    name: "htop".to_string(),
+                    before: None,
+                    after: Some("3.3.0-1.2".to_string()),
+                },
+                PackageChange {
+                    name: "libnl3-200".to_string(),
+                    before: None,
+                    after: Some("3.9.0-1.3".to_string()),
+                },
+                PackageChange {
+                    name: "libncurses6".to_string(),
+                    before: Some("6.4.20231209-5.2".to_string()),
+                    after: Some("6.4.20240224-9.1".to_string()),
+                },
+                PackageChange {
+                    name: "terminfo-base".to_string(),
+                    before: Some("6.4.20231209-5.2".to_string()),
+                    after: Some("6.4.20240224-9.1".to_string()),
+                },
+                PackageChange {
+                    name: "oldtop".to_string(),
+                    before: Some("1.0-4.1".to_string()),
+                    after: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn pacman_plan_is_exact_and_verifies_against_inventory() {
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![package_ref("htop", PackageSource::Pacman)],
+            RequestedBy::Cli,
+        );
+        let inventory = vec![package("htop", PackageSource::Pacman, "3.3.0-1")];
+        let mut changes = parse_pacman_simulation(OperationAction::Update, "htop 3.3.0-2\n");
+        fill_before_from_inventory(&mut changes, &inventory);
+        let plan = build_plan(&request, &inventory, Some(changes));
+        assert_eq!(plan.provider.fidelity, PlanFidelity::Exact);
+        assert_eq!(plan.expected_changes[0].before.as_deref(), Some("3.3.0-1"));
+        assert_eq!(plan.exact_commands[0].program, "pkexec");
+
+        let upgraded = vec![package("htop", PackageSource::Pacman, "3.3.0-2")];
+        assert_eq!(
+            verify_inventory(&plan, &upgraded, Utc::now()).outcome,
+            VerificationOutcome::Verified
+        );
+        assert_eq!(
+            verify_inventory(&plan, &inventory, Utc::now()).outcome,
+            VerificationOutcome::Mismatch
+        );
+    }
+
+    fn finished_record(
+        source: PackageSource,
+        state: OperationState,
+        observed: Vec<PackageChange>,
+    ) -> OperationRecord {
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![package_ref("vim", source)],
+            RequestedBy::Cli,
+        );
+        let plan = build_plan(&request, &[], Some(observed.clone()));
+        OperationRecord {
+            operation_id: request.id,
+            state,
+            risk: RiskAssessment::for_plan(&plan),
+            receipt: Some(VerificationReceipt {
+                operation_id: plan.operation_id.clone(),
+                plan_id: plan.id.clone(),
+                provider: source,
+                expected: observed.clone(),
+                observed,
+                outcome: VerificationOutcome::Verified,
+                warnings: Vec::new(),
+                verified_at: Utc::now(),
+            }),
+            plan,
+            error: None,
+            updated_at: Utc::now(),
+            owner_pid: None,
+            recovered_at: None,
+            snapshot: None,
+        }
+    }
+
+    fn change(name: &str, before: Option<&str>, after: Option<&str>) -> PackageChange {
+        PackageChange {
+            name: name.to_string(),
+            before: before.map(ToString::to_string),
+            after: after.map(ToString::to_string),
+        }
+    }
+
+    #[test]
+    fn rollback_plan_reverses_each_receipt_change() {
+        let record = finished_record(
+            PackageSource::Apt,
+            OperationState::Succeeded,
+            vec![
+                change("vim", Some("9.0"), Some("9.1")),
+                change("libfoo", None, Some("1.0")),
+                change("oldpkg", Some("2.0"), None),
+                change("vim-common", Some("9.1"), Some("9.1")),
+            ],
+        );
+        let inventory = [
+            package("vim", PackageSource::Apt, "9.1"),
+            package("libfoo", PackageSource::Apt, "1.0"),
+            package("vim-common", PackageSource::Apt, "9.1"),
+        ];
+        let plan = build_rollback_plan(&record, &inventory, Utc::now()).expect("rollback plan");
+
+        assert_eq!(plan.action, OperationAction::Rollback);
+        assert_eq!(
+            plan.rollback_of.as_deref(),
+            Some(record.operation_id.as_str())
+        );
+        assert_ne!(plan.operation_id, record.operation_id);
+        assert_eq!(
+            plan.expected_changes,
+            vec![
+                change("oldpkg", None, Some("2.0")),
+                change("libfoo", Some("1.0"), None),
+                change("vim", Some("9.1"), Some("9.0")),
+            ]
+        );
+        assert_eq!(
+            plan.restore_points,
+            BTreeMap::from([("vim".to_string(), "9.0".to_string())])
+        );
+        let argv: Vec<Vec<String>> = plan
+            .exact_commands
+            .iter()
+            .map(|command| command.args.clone())
+            .collect();
+        assert_eq!(
+            argv[0],
+            [
+                "apt",
+                "install",
+                "-y",
+                "--allow-downgrades",
+                "--",
+                "oldpkg=2.0"
+            ]
+        );
+        assert_eq!(argv[1], ["apt", "remove", "-y", "--", "libfoo"]);
+        assert_eq!(
+            argv[2],
+            [
+                "apt",
+                "install",
+                "-y",
+                "--allow-downgrades",
+                "--",
+                "vim=9.0"
+            ]
+        );
+        assert!(RiskAssessment::for_plan(&plan)
+            .reasons
+            .contains(&RiskReason::RemovesPackages));
+
+        // A removed package comes back at the version it had.
+        let receipt = verify_inventory(
+            &plan,
+            &[
+                package("vim", PackageSource::Apt, "9.0"),
+                package("oldpkg", PackageSource::Apt, "2.0"),
+            ],
+            Utc::now(),
+        );
+        assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+        let receipt = verify_inventory(
+            &plan,
+            &[
+                package("vim", PackageSource::Apt, "9.0"),
+                package("oldpkg", PackageSource::Apt, "2.1"),
+            ],
+            Utc::now(),
+        );
+        assert_eq!(receipt.outcome, VerificationOutcome::Mismatch);
+
+        let receipt = verify_inventory(
+            &plan,
+            &[
+                package("vim", PackageSource::Apt, "9.1"),
+                package("oldpkg", PackageSource::Apt, "2.0"),
+            ],
+            Utc::now(),
+        );
+        assert_eq!(receipt.outcome, VerificationOutcome::Mismatch);
+    }
+
+    #[test]
+    fn removal_impact_adds_risk_reasons() {
+        let request = OperationRequest::new(
+            OperationAction::Remove,
+            vec![package_ref("libssl3", PackageSource::Apt)],
+            RequestedBy::Cli,
+        );
+        let mut plan = build_plan(&request, &[], None);
+        plan.removal_impact = Some(RemovalImpact {
+            dependents: vec![Dependent {
+                name: "curl".to_string(),
+                requires: "libssl3".to_string(),
+            }],
+            auto_removed: vec!["ca-certificates".to_string()],
+            ..RemovalImpact::default()
+        });
+        let risk = RiskAssessment::for_plan(&plan);
+        assert_eq!(risk.level, RiskLevel::High);
+        assert!(risk.reasons.contains(&RiskReason::BreaksDependents));
+        assert!(risk.reasons.contains(&RiskReason::AutoRemovesPackages));
+
+        plan.removal_impact = Some(RemovalImpact {
+            protected: vec![ProtectedPackage {
+                name: "curl".to_string(),
+                reason: ProtectionReason::Protected,
+            }],
+            ..RemovalImpact::default()
+        });
+        let risk = RiskAssessment::for_plan(&plan);
+        assert_eq!(risk.level, RiskLevel::Blocked);
+        assert!(risk.reasons.contains(&RiskReason::RemovesProtectedPackage));
+        assert!(plan.blocked_reason().contains("curl (protected)"));
+    }
+
+    #[tokio::test]
+    async fn removals_reaching_a_protected_package_are_planned_as_blocked() {
+        let mut backend = GraphBackend::with_edges(&[
+            ("org.gnome.Platform", &[]),
+            ("org.gnome.Maps", &["org.gnome.Platform"]),
+        ]);
+        backend.source = Some(PackageSource::Flatpak);
+        backend.installed = vec![
+            package("org.gnome.Platform", PackageSource::Flatpak, "46"),
+            package("org.gnome.Maps", PackageSource::Flatpak, "46.1"),
+        ];
+        backend.protected = vec![ProtectedPackage {
+            name: "org.gnome.Maps".to_string(),
+            reason: ProtectionReason::Protected,
+        }];
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(PackageSource::Flatpak, Box::new(backend));
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([PackageSource::Flatpak]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-removal-impact-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+
+        let request = OperationRequest::new(
+            OperationAction::Remove,
+            vec![package_ref("org.gnome.Platform", PackageSource::Flatpak)],
+            RequestedBy::Cli,
+        );
+        let (plan, risk) = engine.plan(request).await.expect("plan removal");
+        let impact = plan.removal_impact.as_ref().expect("removal impact");
+        assert_eq!(impact.dependents.len(), 1);
+        assert_eq!(impact.dependents[0].name, "org.gnome.Maps");
+        assert_eq!(risk.level, RiskLevel::Blocked);
+        assert!(risk.reasons.contains(&RiskReason::BreaksDependents));
+        assert_eq!(engine.records().await[0].state, OperationState::Failed);
+        fs::remove_dir_all(root).await.ok();
+    }
+
+    #[test]
+    fn rollback_is_refused_when_it_would_undo_later_changes() {
+        let updated = vec![change("vim", Some("9.0"), Some("9.1"))];
+        let record = finished_record(
+            PackageSource::Apt,
+            OperationState::Succeeded,
+            updated.clone(),
+        );
+        let error = build_rollback_plan(
+            &record,
+            &[package("vim", PackageSource::Apt, "9.2")],
+            Utc::now(),
+        )
+        .expect_err("drifted package");
+        assert_eq!(error.code, ProviderErrorCode::PlanChanged);
+
+        let failed = finished_record(PackageSource::Apt, OperationState::Failed, updated.clone());
+        assert!(build_rollback_plan(
+            &failed,
+            &[package("vim", PackageSource::Apt, "9.1")],
+            Utc::now()
+        )
+        .is_err());
+
+        // DNF cannot reinstall a chosen version, so a removal stays undone.
+        let removed = finished_record(
+            PackageSource::Dnf,
+            OperationState::Succeeded,
+            vec![change("vim", Some("9.1"), None)],
+        );
+        assert!(build_rollback_plan(&removed, &[], Utc::now())
+            .expect_err("unversioned reinstall")
+            .safe_message
+            .contains("cannot reinstall a chosen version"));
+
+        let npm = finished_record(PackageSource::Npm, OperationState::Succeeded, updated);
+        assert!(build_rollback_plan(
+            &npm,
+            &[package("vim", PackageSource::Npm, "9.1")],
+            Utc::now()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn flatpak_rollback_restores_the_recorded_commit() {
+        let mut record = finished_record(
+            PackageSource::Flatpak,
+            OperationState::Succeeded,
+            vec![change("org.example.App", Some("1.0"), Some("1.1"))],
+        );
+        let inventory = [package("org.example.App", PackageSource::Flatpak, "1.1")];
+        assert!(
+            build_rollback_plan(&record, &inventory, Utc::now()).is_err(),
+            "an update without a recorded commit cannot be restored"
+        );
+
+        record
+            .plan
+            .restore_points
+            .insert("org.example.App".to_string(), "abc123".to_string());
+        let plan = build_rollback_plan(&record, &inventory, Utc::now()).expect("rollback plan");
+        assert_eq!(
+            plan.exact_commands[0].args,
+            ["update", "-y", "--commit=abc123", "org.example.App"]
+        );
+        assert_eq!(plan.expected_changes[0].after.as_deref(), Some("1.0"));
+    }
+
+    #[test]
+    fn plan_diff_reports_added_removed_and_reversioned_changes() {
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![package_ref("vim", PackageSource::Apt)],
+            RequestedBy::Cli,
+        );
+        let reviewed = build_plan(
+            &request,
+            &[],
+            Some(vec![
+                change("vim", Some("9.0"), Some("9.0.2")),
+                change("vim-common", Some("9.0"), Some("9.0.2")),
+            ]),
+        );
+        let fresh = build_plan(
+            &request,
+            &[],
+            Some(vec![
+                change("vim", Some("9.0"), Some("9.1")),
+                change("libsodium23", None, Some("1.0.18")),
+            ]),
+        );
+
+        let diff = PlanDiff::between(&reviewed, &fresh);
+
+        assert_eq!(
+            diff.added,
+            vec![change("libsodium23", None, Some("1.0.18"))]
+        );
+        assert_eq!(
+            diff.removed,
+            vec![change("vim-common", Some("9.0"), Some("9.0.2"))]
+        );
+        assert_eq!(diff.reversioned.len(), 1);
+        assert_eq!(diff.reversioned[0].fresh.after.as_deref(), Some("9.1"));
+        assert_eq!(
+            diff.summary(OperationAction::Update),
+            "+libsodium23 (new 1.0.18); -vim-common; ~vim (9.0 → 9.1, was 9.0 → 9.0.2)"
+        );
+        assert!(PlanDiff::between(&reviewed, &reviewed).is_empty());
+    }
+
+    #[tokio::test]
+    async fn stale_plan_is_replanned_with_the_versions_that_moved() {
+        let source = PackageSource::Npm;
+        let inventory = Arc::new(Mutex::new(vec![package("demo", source, "1.0")]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::clone(&inventory),
+            }),
+        );
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-stale-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+        let (reviewed, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Update,
+                vec![package_ref("demo", source)],
+                RequestedBy::Tui,
+            ))
+            .await
+            .expect("plan update");
+
+        // Something else moved the package between review and execution.
+        inventory.lock().await[0].version = "1.5".to_string();
+        let error = engine
+            .execute(reviewed.clone(), CancellationFlag::default())
+            .await
+            .expect_err("the fingerprint moved");
+        assert_eq!(error.code, ProviderErrorCode::PlanChanged);
+
+        let stale = engine
+            .replan_stale(&reviewed, error.code, RequestedBy::Tui)
+            .await
+            .expect("re-plan");
+
+        assert!(stale.needs_approval());
+        assert!(stale.diff.added.is_empty() && stale.diff.removed.is_empty());
+        assert_eq!(
+            stale.diff.reversioned,
+            vec![ChangeRevision {
+                reviewed: change("demo", Some("1.0"), Some("2.0")),
+                fresh: change("demo", Some("1.5"), Some("2.0")),
+            }]
+        );
+        let records = engine.records().await;
+        let state_of = |operation_id: &str| {
+            records
+                .iter()
+                .find(|record| record.operation_id == operation_id)
+                .map(|record| record.state)
+        };
+        assert_eq!(
+            state_of(&reviewed.operation_id),
+            Some(OperationState::Cancelled)
+        );
+        assert_eq!(
+            state_of(&stale.fresh.operation_id),
+            Some(OperationState::Ready)
+        );
+        fs::remove_dir_all(root).await.expect("remove temp store");
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_cooperative_step_terminates_the_provider() {
+        let source = PackageSource::Npm;
+        let inventory = Arc::new(Mutex::new(vec![package("slow", source, "1.0")]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::clone(&inventory),
+            }),
+        );
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-cancel-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
+
         let request = OperationRequest::new(
-            OperationAction::Install,
-            vec![package_ref("demo", PackageSource::Npm)],
+            OperationAction::Update,
+            vec![package_ref("slow", source)],
             RequestedBy::Tui,
         );
-        let plan = build_plan(&request, &[], None);
-        let receipt = verify_inventory(
-            &plan,
-            &[package("demo", PackageSource::Npm, "1.5")],
-            Utc::now(),
+        let plan = build_plan(
+            &request,
+            &[package("slow", source, "1.0")],
+            Some(vec![change("slow", Some("1.0"), Some("2.0"))]),
         );
-        assert_eq!(receipt.outcome, VerificationOutcome::Mismatch);
+        assert_eq!(plan.provider.cancellation, CancellationSupport::Cooperative);
+        engine
+            .upsert_record(OperationRecord {
+                operation_id: request.id.clone(),
+                state: OperationState::Ready,
+                risk: RiskAssessment::for_plan(&plan),
+                plan: plan.clone(),
+                receipt: None,
+                error: None,
+                updated_at: Utc::now(),
+                owner_pid: None,
+                recovered_at: None,
+                snapshot: None,
+            })
+            .await
+            .expect("persist plan");
+
+        let cancellation = CancellationFlag::default();
+        let trigger = cancellation.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            trigger.request();
+        });
+        let error = engine
+            .execute(plan, cancellation)
+            .await
+            .expect_err("the cancelled step must not succeed");
+
+        assert_eq!(error.code, ProviderErrorCode::Interrupted);
+        assert!(error.safe_message.contains("safe point"), "{error:?}");
+        let records = engine.records().await;
+        assert_eq!(records[0].state, OperationState::Cancelled);
+        assert_eq!(inventory.lock().await[0].version, "1.0");
+        fs::remove_dir_all(root).await.expect("remove temp store");
     }
 
-    #[test]
-    fn npm_engine_mismatch_is_actionable_and_not_blindly_retryable() {
-        let error = ProviderError::classify(
-            PackageSource::Npm,
-            "npm ERR! code EBADENGINE\nnpm ERR! engine Unsupported engine\nnpm ERR! notsup Required: node >=24.15.0",
+    /// A session killed mid-update leaves records `Running`. Reloading must
+    /// verify each orphan against the provider instead of guessing, and must
+    /// not touch an operation another live process is still executing.
+    #[tokio::test]
+    async fn orphaned_operations_are_verified_on_load() {
+        let source = PackageSource::Apt;
+        let inventory = Arc::new(Mutex::new(vec![
+            package("applied", source, "2.0"),
+            package("untouched", source, "1.0"),
+        ]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::clone(&inventory),
+            }),
         );
-        assert_eq!(error.code, ProviderErrorCode::DependencyConflict);
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+
+        let running = |name: &str, owner_pid: u32| {
+            let request = OperationRequest::new(
+                OperationAction::Update,
+                vec![package_ref(name, source)],
+                RequestedBy::Tui,
+            );
+            let plan = build_plan(
+                &request,
+                &[package(name, source, "1.0")],
+                Some(vec![change(name, Some("1.0"), Some("2.0"))]),
+            );
+            OperationRecord {
+                operation_id: request.id,
+                state: OperationState::Running,
+                risk: RiskAssessment::for_plan(&plan),
+                plan,
+                receipt: None,
+                error: None,
+                updated_at: Utc::now(),
+                owner_pid: Some(owner_pid),
+                recovered_at: None,
+                snapshot: None,
+            }
+        };
+        let dead = u32::MAX - 1; // a process that cannot exist
+        let store = TransactionStore {
+            operations: vec![
+                running("applied", dead),
+                running("untouched", dead),
+                running("applied", std::process::id()),
+            ],
+            ..TransactionStore::default()
+        };
+        let root = std::env::temp_dir().join(format!("linget-recovery-{}", Uuid::new_v4()));
+        let path = root.join("transactions.json");
+        store.save_atomic(&path).await.expect("write store");
+
+        let engine = TransactionEngine::load(Arc::new(RwLock::new(manager)), path)
+            .await
+            .expect("load engine");
+        let records = engine.records().await;
+
+        assert_eq!(records[0].state, OperationState::Succeeded);
+        assert!(records[0].recovered_at.is_some());
         assert_eq!(
-            error.safe_message,
-            "The package runtime requirements are not satisfied"
+            records[0].receipt.as_ref().map(|receipt| receipt.outcome),
+            Some(VerificationOutcome::Verified)
         );
-        assert!(!error.retryable);
-        assert!(error.recovery_actions[0].contains("Upgrade"));
+        assert_eq!(records[1].state, OperationState::Interrupted);
+        assert_eq!(
+            records[1].error.as_ref().map(|error| error.code),
+            Some(ProviderErrorCode::Interrupted)
+        );
+        assert_eq!(records[2].state, OperationState::Running);
+        assert!(records[2].recovered_at.is_none());
+        fs::remove_dir_all(root).await.expect("remove temp store");
     }
 
     #[tokio::test]
-    async fn stable_provider_execution_and_receipt_matrix_is_complete() {
-        for source in STABLE_PROVIDERS {
-            for action in [
-                OperationAction::Install,
-                OperationAction::Remove,
-                OperationAction::Update,
-            ] {
-                let initial_inventory = if action == OperationAction::Install {
-                    Vec::new()
-                } else {
-                    vec![package("demo", source, "1.0")]
-                };
-                let inventory = Arc::new(Mutex::new(initial_inventory.clone()));
-                let backend = ContractBackend {
-                    source,
-                    inventory: Arc::clone(&inventory),
-                };
-                let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
-                backends.insert(source, Box::new(backend));
-                let manager = PackageManager {
-                    backends,
-                    enabled_sources: HashSet::from([source]),
-                    provider_statuses: HashMap::new(),
-                };
-                let root = std::env::temp_dir().join(format!(
-                    "linget-provider-contract-{}-{}",
-                    source,
-                    Uuid::new_v4()
-                ));
-                let store_path = root.join("transactions.json");
-                let engine = TransactionEngine::load(Arc::new(RwLock::new(manager)), store_path)
-                    .await
-                    .expect("load contract engine");
-                let request = OperationRequest::new(
-                    action,
-                    vec![package_ref("demo", source)],
-                    RequestedBy::Tui,
-                );
-                let apt_changes = (source == PackageSource::Apt).then(|| {
-                    vec![PackageChange {
-                        name: "demo".to_string(),
-                        before: (action != OperationAction::Install).then(|| "1.0".to_string()),
-                        after: (action != OperationAction::Remove).then(|| "2.0".to_string()),
-                    }]
-                });
-                let plan = build_plan(&request, &initial_inventory, apt_changes);
-                engine
-                    .upsert_record(OperationRecord {
-                        operation_id: request.id,
-                        state: OperationState::Ready,
-                        risk: RiskAssessment::for_plan(&plan),
-                        plan: plan.clone(),
-                        receipt: None,
-                        error: None,
-                        updated_at: Utc::now(),
-                    })
-                    .await
-                    .expect("persist reviewed plan");
-                let receipt = engine
-                    .execute(plan, CancellationFlag::default())
-                    .await
-                    .expect("execute contract operation");
-                assert_eq!(receipt.outcome, VerificationOutcome::Verified);
-                assert_eq!(engine.records().await[0].state, OperationState::Succeeded);
-                fs::remove_dir_all(root)
-                    .await
-                    .expect("remove provider contract directory");
-            }
-        }
-    }
+    async fn rolled_back_update_restores_previous_version_and_is_recorded() {
+        let source = PackageSource::Apt;
+        let inventory = Arc::new(Mutex::new(vec![package("demo", source, "1.0")]));
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::clone(&inventory),
+            }),
+        );
+        let manager = PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        };
+        let root = std::env::temp_dir().join(format!("linget-rollback-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(manager)),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine");
 
-    #[test]
-    fn command_specs_keep_package_names_as_single_argv_values() {
-        let command = command_for(
-            PackageSource::Npm,
-            OperationAction::Install,
-            "@scope/example",
+        let request = OperationRequest::new(
+            OperationAction::Update,
+            vec![package_ref("demo", source)],
+            RequestedBy::Cli,
         );
-        assert_eq!(command.program, "npm");
-        assert_eq!(command.args, ["install", "-g", "@scope/example"]);
-        assert!(!command
-            .args
-            .iter()
-            .any(|argument| argument.contains("sh -c")));
-    }
+        let plan = build_plan(
+            &request,
+            &[package("demo", source, "1.0")],
+            Some(vec![change("demo", Some("1.0"), Some("2.0"))]),
+        );
+        engine
+            .upsert_record(OperationRecord {
+                operation_id: request.id.clone(),
+                state: OperationState::Ready,
+                risk: RiskAssessment::for_plan(&plan),
+                plan: plan.clone(),
+                receipt: None,
+                error: None,
+                updated_at: Utc::now(),
+                owner_pid: None,
+                recovered_at: None,
+                snapshot: None,
+            })
+            .await
+            .expect("persist plan");
+        engine
+            .execute(plan, CancellationFlag::default())
+            .await
+            .expect("execute update");
 
-    #[test]
-    fn unsafe_targets_are_rejected_before_planning() {
-        for name in ["-rf", "bad\nname", ""] {
-            let error = validate_targets(
-                &[PackageRef {
-                    name: name.to_string(),
-                    source: PackageSource::Npm,
-                    installed_version: None,
-                    available_version: None,
-                }],
-                PackageSource::Npm,
-            )
-            .expect_err("unsafe name must fail");
-            assert_eq!(error.code, ProviderErrorCode::Protocol);
-        }
+        let (rollback, risk) = engine
+            .plan_rollback(&request.id)
+            .await
+            .expect("plan rollback");
+        assert_ne!(risk.level, RiskLevel::Blocked);
+        let receipt = engine
+            .execute(rollback.clone(), CancellationFlag::default())
+            .await
+            .expect("execute rollback");
+        assert_eq!(receipt.outcome, VerificationOutcome::Verified);
+        assert_eq!(inventory.lock().await[0].version, "1.0");
+        let records = engine.records().await;
+        let record = records
+            .iter()
+            .find(|record| record.operation_id == rollback.operation_id)
+            .expect("rollback record");
+        assert_eq!(record.state, OperationState::Succeeded);
+        assert_eq!(
+            record.plan.rollback_of.as_deref(),
+            Some(request.id.as_str())
+        );
+        fs::remove_dir_all(root)
+            .await
+            .expect("remove rollback store");
     }
 
     #[test]
-    fn apt_simulation_parser_captures_dependency_changes() {
-        let changes = parse_apt_simulation(
-            "Inst demo [1.0] (2.0 Ubuntu:stable [amd64])\nInst dependency (3.0 Ubuntu:stable [amd64])\nRemv old-lib [0.9]",
+    fn exact_plan_verification_checks_pulled_in_removals() {
+        let request = OperationRequest::new(
+            OperationAction::Install,
+            vec![package_ref("htop", PackageSource::Dnf)],
+            RequestedBy::Cli,
         );
-        assert_eq!(changes.len(), 3);
-        assert_eq!(changes[0].before.as_deref(), Some("1.0"));
-        assert_eq!(changes[0].after.as_deref(), Some("2.0"));
-        assert_eq!(changes[2].after, None);
+        let plan = build_plan(
+            &request,
+            &[package("oldtop", PackageSource::Dnf, "1.0-4.fc39")],
+            Some(vec![
+                PackageChange {
+                    name: "htop".to_string(),
+                    before: None,
+                    after: Some("3.3.0-1.fc40".to_string()),
+                },
+                PackageChange {
+                    name: "oldtop".to_string(),
+                    before: Some("1.0-4.fc39".to_string()),
+                    after: None,
+                },
+            ]),
+        );
+        assert_eq!(plan.provider.fidelity, PlanFidelity::Exact);
+        let verified = verify_inventory(
+            &plan,
+            &[package("htop", PackageSource::Dnf, "3.3.0-1.fc40")],
+            Utc::now(),
+        );
+        assert_eq!(verified.outcome, VerificationOutcome::Verified);
+        let stale = verify_inventory(
+            &plan,
+            &[
+                package("htop", PackageSource::Dnf, "3.3.0-1.fc40"),
+                package("oldtop", PackageSource::Dnf, "1.0-4.fc39"),
+            ],
+            Utc::now(),
+        );
+        assert_eq!(stale.outcome, VerificationOutcome::Mismatch);
     }
 
     #[test]
@@ -1663,10 +5598,14 @@ mod tests {
             receipt: None,
             error: None,
             updated_at: Utc::now(),
+            owner_pid: None,
+            recovered_at: None,
+            snapshot: None,
         };
         let store = TransactionStore {
             schema_version: TRANSACTION_SCHEMA_VERSION,
             operations: vec![record.clone()],
+            bundles: Vec::new(),
         };
         store.save_atomic(&path).await.expect("atomic save");
         let loaded = TransactionStore::load(&path).await.expect("load store");
@@ -1719,6 +5658,7 @@ mod tests {
                 source: PackageSource::Npm,
                 installed_version: None,
                 available_version: Some("2.0".to_string()),
+                pinned: false,
             }],
             RequestedBy::Tui,
         );
@@ -1748,6 +5688,215 @@ mod tests {
         std::fs::remove_dir_all(root).expect("remove fake npm directory");
     }
 
+    fn snapshot_engine_manager(source: PackageSource) -> PackageManager {
+        let mut backends: HashMap<PackageSource, Box<dyn PackageBackend>> = HashMap::new();
+        backends.insert(
+            source,
+            Box::new(ContractBackend {
+                source,
+                inventory: Arc::new(Mutex::new(vec![package("demo", source, "1.0")])),
+            }),
+        );
+        PackageManager {
+            backends,
+            enabled_sources: HashSet::from([source]),
+            provider_statuses: HashMap::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn high_risk_plans_record_the_snapshot_taken_before_them() {
+        let _env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let fake = crate::backend::snapshot::tests::FakeBin::new("snapper", "echo 7\n");
+        let root = std::env::temp_dir().join(format!("linget-snapshot-engine-{}", Uuid::new_v4()));
+        let engine = TransactionEngine::load(
+            Arc::new(RwLock::new(snapshot_engine_manager(PackageSource::Npm))),
+            root.join("transactions.json"),
+        )
+        .await
+        .expect("load engine")
+        .with_snapshots(SnapshotConfig {
+            enabled: true,
+            driver: Some(crate::models::SnapshotDriverKind::Snapper),
+            ..SnapshotConfig::default()
+        });
+
+        let (install, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Install,
+                vec![package_ref("extra", PackageSource::Npm)],
+                RequestedBy::Cli,
+            ))
+            .await
+            .expect("plan install");
+        engine
+            .execute(install.clone(), CancellationFlag::default())
+            .await
+            .expect("execute install");
+        assert_eq!(engine.snapshot(&install.operation_id).await, None);
+        assert_eq!(fake.logged_args(), "");
+
+        let (remove, risk) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Remove,
+                vec![package_ref("demo", PackageSource::Npm)],
+                RequestedBy::Cli,
+            ))
+            .await
+            .expect("plan removal");
+        assert_eq!(risk.level, RiskLevel::High);
+        engine
+            .execute(remove.clone(), CancellationFlag::default())
+            .await
+            .expect("execute removal");
+        let snapshot = engine
+            .snapshot(&remove.operation_id)
+            .await
+            .expect("snapshot recorded on the operation");
+        assert_eq!(snapshot.id, "7");
+        assert!(fake
+            .logged_args()
+            .contains("--description linget remove demo"));
+
+        drop(fake);
+        fs::remove_dir_all(root).await.ok();
+    }
+
+    #[tokio::test]
+    async fn a_failed_snapshot_stops_the_plan_before_the_provider_runs() {
+        let _env_guard = crate::backend::TEST_PATH_ENV_LOCK.lock().await;
+        let _fake = crate::backend::snapshot::tests::FakeBin::new(
+            "timeshift",
+            "echo 'E: Selected snapshot device is not mounted' >&2\nexit 1\n",
+        );
+        let root = std::env::temp_dir().join(format!("linget-snapshot-engine-{}", Uuid::new_v4()));
+        let manager = Arc::new(RwLock::new(snapshot_engine_manager(PackageSource::Npm)));
+        let engine = TransactionEngine::load(manager.clone(), root.join("transactions.json"))
+            .await
+            .expect("load engine")
+            .with_snapshots(SnapshotConfig {
+                enabled: true,
+                driver: Some(crate::models::SnapshotDriverKind::Timeshift),
+                ..SnapshotConfig::default()
+            });
+        let (remove, _) = engine
+            .plan(OperationRequest::new(
+                OperationAction::Remove,
+                vec![package_ref("demo", PackageSource::Npm)],
+                RequestedBy::Cli,
+            ))
+            .await
+            .expect("plan removal");
+
+        let error = engine
+            .execute(remove.clone(), CancellationFlag::default())
+            .await
+            .expect_err("the removal must not run without its snapshot");
+        assert_eq!(error.code, ProviderErrorCode::SnapshotFailed);
+        assert!(error.diagnostic.contains("not mounted"));
+        let inventory = manager
+            .read()
+            .await
+            .list_installed_for_source(PackageSource::Npm)
+            .await
+            .expect("inventory");
+        assert_eq!(inventory.len(), 1);
+        let record = engine
+            .records()
+            .await
+            .into_iter()
+            .find(|record| record.operation_id == remove.operation_id)
+            .expect("removal record");
+        assert_eq!(record.state, OperationState::Failed);
+
+        fs::remove_dir_all(root).await.ok();
+    }
+
+    #[tokio::test]
+    async fn pre_hooks_can_veto_a_plan_and_post_hooks_read_its_receipt() {
+        let root = std::env::temp_dir().join(format!("linget-hook-engine-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&root).expect("create hook dir");
+        let received = root.join("post.json");
+        let hook = |stage, command: String| HookConfig {
+            name: format!("{}-check", stage),
+            stage,
+            command,
+            providers: vec!["npm".to_string()],
+            actions: vec!["remove".to_string()],
+            packages: vec!["de*".to_string()],
+            timeout_secs: 10,
+        };
+        let manager = Arc::new(RwLock::new(snapshot_engine_manager(PackageSource::Npm)));
+        let engine = TransactionEngine::load(manager.clone(), root.join("transactions.json"))
+            .await
+            .expect("load engine")
+            .with_hooks(vec![
+                hook(
+                    HookStage::Pre,
+                    "test \"$LINGET_PACKAGES\" != demo".to_string(),
+                ),
+                hook(HookStage::Post, format!("cat > '{}'", received.display())),
+            ]);
+        let remove_demo = || {
+            OperationRequest::new(
+                OperationAction::Remove,
+                vec![package_ref("demo", PackageSource::Npm)],
+                RequestedBy::Cli,
+            )
+        };
+
+        let (vetoed, _) = engine.plan(remove_demo()).await.expect("plan removal");
+        let error = engine
+            .execute(vetoed.clone(), CancellationFlag::default())
+            .await
+            .expect_err("the pre hook vetoes the removal");
+        assert_eq!(error.code, ProviderErrorCode::HookVetoed);
+        assert!(error.diagnostic.contains("pre hook 'pre-check' exited"));
+        let record = engine
+            .records()
+            .await
+            .into_iter()
+            .find(|record| record.operation_id == vetoed.operation_id)
+            .expect("vetoed record");
+        assert_eq!(record.state, OperationState::Cancelled);
+        // The post hook still hears about the veto, so it can undo whatever
+        // earlier pre hooks did.
+        let input: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&received).expect("post hook input"))
+                .expect("hook input is JSON");
+        assert_eq!(input["error"]["code"], "HookVetoed");
+        assert!(input.get("receipt").is_none());
+        std::fs::remove_file(&received).expect("reset post hook input");
+        let inventory = manager
+            .read()
+            .await
+            .list_installed_for_source(PackageSource::Npm)
+            .await
+            .expect("inventory");
+        assert_eq!(inventory.len(), 1);
+
+        let engine = engine.with_hooks(vec![hook(
+            HookStage::Post,
+            format!("cat > '{}'", received.display()),
+        )]);
+        let (remove, _) = engine.plan(remove_demo()).await.expect("plan removal");
+        engine
+            .execute(remove.clone(), CancellationFlag::default())
+            .await
+            .expect("execute removal");
+        let input: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&received).expect("post hook input"))
+                .expect("hook input is JSON");
+        assert_eq!(input["stage"], "post");
+        assert_eq!(input["plan"]["operation_id"], remove.operation_id.as_str());
+        assert_eq!(
+            input["receipt"]["operation_id"],
+            remove.operation_id.as_str()
+        );
+
+        fs::remove_dir_all(root).await.ok();
+    }
+
     #[test]
     fn error_classifier_maps_common_recovery_categories() {
         assert_eq!(
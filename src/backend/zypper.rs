@@ -1,3 +1,4 @@
+use super::transaction::{FailureClass, ProviderErrorCode};
 use super::PackageBackend;
 use super::{run_pkexec, Suggest};
 use crate::models::{Package, PackageSource, PackageStatus};
@@ -74,6 +75,7 @@ impl PackageBackend for ZypperBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -126,6 +128,7 @@ impl PackageBackend for ZypperBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
         }
 
@@ -211,6 +214,7 @@ impl PackageBackend for ZypperBackend {
                 install_date: None,
                 update_category: None,
                 enrichment: None,
+                released_at: None,
             });
 
             if packages.len() >= 50 {
@@ -252,6 +256,67 @@ impl PackageBackend for ZypperBackend {
     }
 }
 
+/// Classify a zypper failure from its exit status and messages.
+///
+/// zypper gives most failures an exit status of their own: 5 for missing
+/// privileges, 7 for a held lock, 104 for a package it cannot find and 105
+/// when a signal stopped it. Status 4 (a libzypp error) and 8 (a failed
+/// commit) cover everything else, so the messages are read for a cause. 6
+/// and 106 mean no usable repository, and only decide once the messages name
+/// nothing more specific.
+pub(crate) fn classify_failure(exit_code: Option<i32>, output: &str) -> Option<FailureClass> {
+    match exit_code {
+        Some(5) => return Some(ProviderErrorCode::AuthorizationDenied.into()),
+        Some(7) => return Some(ProviderErrorCode::LockBusy.into()),
+        Some(104) => return Some(ProviderErrorCode::NotFound.into()),
+        Some(105) => return Some(ProviderErrorCode::Interrupted.into()),
+        _ => {}
+    }
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let any = |matches: &dyn Fn(&str) -> bool| lines.iter().any(|line| matches(line));
+    let code = if any(&|line| line.starts_with("System management is locked by the application")) {
+        ProviderErrorCode::LockBusy
+    } else if any(&|line| line.starts_with("Root privileges are required")) {
+        ProviderErrorCode::AuthorizationDenied
+    } else if any(&|line| {
+        line.starts_with("Signature verification failed")
+            || line.contains("is signed with an unknown key")
+    }) {
+        ProviderErrorCode::SignatureInvalid
+    } else if any(&|line| line.contains("Error code: Timeout exceeded")) {
+        ProviderErrorCode::Timeout
+    } else if any(&|line| {
+        line.contains("Error code: Connection failed") || line.contains("Could not resolve host")
+    }) {
+        ProviderErrorCode::Network
+    } else if any(&|line| {
+        line.starts_with("Valid metadata not found at specified URL")
+            || line.contains("Error code: HTTP response: 404")
+            || line.contains("Error code: Bad URL")
+    }) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if any(&|line| {
+        (line.contains(" needs ") && line.contains("filesystem"))
+            || line.contains("No space left on device")
+    }) {
+        ProviderErrorCode::DiskFull
+    } else if any(&|line| line.starts_with("Problem:")) {
+        ProviderErrorCode::DependencyConflict
+    } else if any(&|line| {
+        line.starts_with("No provider of")
+            || line.ends_with("not found in package names. Trying capabilities.")
+    }) {
+        ProviderErrorCode::NotFound
+    } else if matches!(exit_code, Some(6 | 106)) {
+        ProviderErrorCode::RepositoryUnavailable
+    } else if exit_code.is_some() {
+        ProviderErrorCode::Unknown
+    } else {
+        return None;
+    };
+    Some(code.into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
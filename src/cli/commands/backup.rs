@@ -1,14 +1,50 @@
-use crate::backend::PackageManager;
+use super::transaction::{self, TransactionReport};
+use crate::backend::transaction::{OperationAction, PackageRef, VerificationOutcome};
+use crate::backend::{BackendCapability, PackageManager};
 use crate::cli::{BackupAction, OutputWriter};
-use crate::models::{Config, PackageListExport};
+use crate::models::package_list::RestoreChoice;
+use crate::models::{Config, ExportedPackage, PackageListExport, PackageSource};
 use anyhow::{Context, Result};
-use std::collections::BTreeMap;
+use console::style;
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
 const DEFAULT_BACKUP_FILE: &str = "linget-backup.json";
 
+/// How many providers are asked for version lists at once.
+const VERSION_QUERY_CONCURRENCY: usize = 8;
+
+/// One package of an exact restore: what is installed now, how it will be
+/// installed, and how that went.
+#[derive(Debug, Serialize)]
+struct RestoreStep<'a> {
+    name: &'a str,
+    source: PackageSource,
+    /// The version the backup recorded.
+    #[serde(skip_serializing_if = "str::is_empty")]
+    recorded: &'a str,
+    /// Set when the package is already installed; nothing is done then.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    installed: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    choice: Option<RestoreChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestoreOutput<'a> {
+    steps: &'a [RestoreStep<'a>],
+    executed: bool,
+    installed: usize,
+    failed: usize,
+}
+
 pub async fn run(
     pm: Arc<RwLock<PackageManager>>,
     action: BackupAction,
@@ -16,7 +52,9 @@ pub async fn run(
 ) -> Result<()> {
     match action {
         BackupAction::Create { output } => create_backup(pm, output, writer).await,
-        BackupAction::Restore { file, yes } => restore_backup(pm, &file, yes, writer).await,
+        BackupAction::Restore { file, exact, yes } => {
+            restore_backup(pm, &file, exact, yes, writer).await
+        }
     }
 }
 
@@ -52,6 +90,7 @@ async fn create_backup(
 async fn restore_backup(
     pm: Arc<RwLock<PackageManager>>,
     file: &str,
+    exact: bool,
     skip_confirm: bool,
     writer: &OutputWriter,
 ) -> Result<()> {
@@ -77,6 +116,10 @@ async fn restore_backup(
         backup.source_count()
     ));
 
+    if exact {
+        return restore_exact(pm, &backup, skip_confirm, writer).await;
+    }
+
     if !skip_confirm {
         writer.warning("This will attempt to install all packages from the backup.");
         writer.message("Run with --yes to skip this prompt.");
@@ -130,3 +173,232 @@ async fn restore_backup(
 
     Ok(())
 }
+
+/// Restore the recorded versions. Every package gets a step first: already
+/// installed, the exact version, the nearest substitute when the recorded one
+/// is gone upstream, or the latest when the provider cannot install versions.
+/// The plan is shown before anything runs, and only `--yes` runs it.
+async fn restore_exact(
+    pm: Arc<RwLock<PackageManager>>,
+    backup: &PackageListExport,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let spinner = if !writer.is_quiet() && !writer.is_json() {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.cyan} {msg}")
+                .expect("hardcoded progress template should be valid"),
+        );
+        pb.set_message("Checking which recorded versions are still available...");
+        pb.enable_steady_tick(std::time::Duration::from_millis(80));
+        Some(pb)
+    } else {
+        None
+    };
+
+    let manager = pm.read().await;
+    let installed: HashMap<(PackageSource, String), String> = manager
+        .list_all_installed()
+        .await?
+        .into_iter()
+        .map(|package| ((package.source, package.name), package.version))
+        .collect();
+    let manager_ref = &*manager;
+    let installed_ref = &installed;
+    let mut steps: Vec<RestoreStep> = stream::iter(&backup.packages)
+        .map(|package: &ExportedPackage| async move {
+            if let Some(version) = installed_ref.get(&(package.source, package.name.clone())) {
+                return RestoreStep {
+                    name: &package.name,
+                    source: package.source,
+                    recorded: &package.version,
+                    installed: Some(version.clone()),
+                    choice: None,
+                    error: None,
+                };
+            }
+            let installs_versions = manager_ref
+                .source_capability_status(package.source, BackendCapability::InstallVersion)
+                .is_supported();
+            let available = if installs_versions && !package.version.is_empty() {
+                manager_ref
+                    .available_versions(&package.to_install_stub())
+                    .await
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            RestoreStep {
+                name: &package.name,
+                source: package.source,
+                recorded: &package.version,
+                installed: None,
+                choice: Some(package.restore_choice(&available, installs_versions)),
+                error: None,
+            }
+        })
+        .buffered(VERSION_QUERY_CONCURRENCY)
+        .collect()
+        .await;
+
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    print_restore_plan(writer, &steps);
+
+    if !skip_confirm {
+        if writer.is_json() {
+            print_restore_output(&steps, false)?;
+        } else {
+            writer.message("Run with --yes to restore with this plan.");
+        }
+        return Ok(());
+    }
+
+    drop(manager);
+    for step in &mut steps {
+        let Some(choice) = &step.choice else {
+            continue;
+        };
+        let stub = ExportedPackage {
+            name: step.name.to_string(),
+            source: step.source,
+            version: String::new(),
+        }
+        .to_install_stub();
+        let target = match choice {
+            RestoreChoice::Exact { version, .. } | RestoreChoice::Substitute { version, .. } => {
+                PackageRef::pinned(&stub, version)
+            }
+            RestoreChoice::Latest { .. } => PackageRef::from_package(&stub),
+        };
+        // Each package is its own reviewed plan, so one that cannot be
+        // planned or installed does not stop the rest of the restore.
+        let result = transaction::review_and_execute_targets(
+            pm.clone(),
+            OperationAction::Install,
+            vec![target],
+            skip_confirm,
+            writer,
+        )
+        .await;
+        let error = match result {
+            Ok(reports) => reports.into_iter().find_map(|report| match report {
+                TransactionReport {
+                    error: Some(error), ..
+                } => Some(error.safe_message),
+                TransactionReport {
+                    receipt: Some(receipt),
+                    ..
+                } if receipt.outcome == VerificationOutcome::Mismatch => {
+                    Some("The installed version does not match the plan".to_string())
+                }
+                _ => None,
+            }),
+            Err(error) => Some(error.to_string()),
+        };
+        if let Some(error) = error {
+            writer.warning(&format!("Failed to install {}: {}", step.name, error));
+            step.error = Some(error);
+        }
+    }
+
+    if let Some(saved_config) = backup.config.as_ref() {
+        let mut config = Config::load();
+        saved_config.apply_preferences(&mut config);
+        config.save()?;
+    }
+
+    if writer.is_json() {
+        print_restore_output(&steps, true)?;
+    } else {
+        let (installed, failed) = restore_counts(&steps);
+        writer.success(&format!(
+            "Restore complete: {} installed, {} failed",
+            installed, failed
+        ));
+    }
+    Ok(())
+}
+
+fn restore_counts(steps: &[RestoreStep]) -> (usize, usize) {
+    let attempted = steps.iter().filter(|step| step.choice.is_some());
+    let failed = attempted
+        .clone()
+        .filter(|step| step.error.is_some())
+        .count();
+    (attempted.count() - failed, failed)
+}
+
+fn print_restore_output(steps: &[RestoreStep], executed: bool) -> Result<()> {
+    let (installed, failed) = if executed {
+        restore_counts(steps)
+    } else {
+        (0, 0)
+    };
+    let output = RestoreOutput {
+        steps,
+        executed,
+        installed,
+        failed,
+    };
+    println!("{}", serde_json::to_string_pretty(&output)?);
+    Ok(())
+}
+
+fn print_restore_plan(writer: &OutputWriter, steps: &[RestoreStep]) {
+    if writer.is_json() || writer.is_quiet() {
+        return;
+    }
+    let mut exact = Vec::new();
+    let mut substituted = Vec::new();
+    let mut latest = Vec::new();
+    let mut present = Vec::new();
+    for step in steps {
+        let label = format!("{} ({})", style(step.name).cyan(), step.source);
+        match (&step.choice, &step.installed) {
+            (Some(RestoreChoice::Exact { version, verified }), _) => exact.push(format!(
+                "{} {}{}",
+                label,
+                version,
+                if *verified {
+                    String::new()
+                } else {
+                    style(" (availability unknown)").dim().to_string()
+                }
+            )),
+            (Some(RestoreChoice::Substitute { recorded, version }), _) => substituted.push(
+                format!("{} {} is gone; nearest is {}", label, recorded, version),
+            ),
+            (Some(RestoreChoice::Latest { reason }), _) => {
+                latest.push(format!("{} {}", label, style(reason).dim()))
+            }
+            (None, Some(version)) if step.recorded.is_empty() || step.recorded == version => {
+                present.push(format!("{} {}", label, version))
+            }
+            (None, Some(version)) => present.push(format!(
+                "{} {} {}",
+                label,
+                version,
+                style(format!("(backup recorded {})", step.recorded)).dim()
+            )),
+            (None, None) => {}
+        }
+    }
+    for (title, lines) in [
+        ("Exact versions", exact),
+        ("Substituted versions", substituted),
+        ("Latest versions", latest),
+        ("Already installed", present),
+    ] {
+        if lines.is_empty() {
+            continue;
+        }
+        writer.header(&format!("{} ({})", title, lines.len()));
+        for line in lines {
+            println!("  {}", line);
+        }
+    }
+}
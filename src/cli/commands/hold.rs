@@ -0,0 +1,183 @@
+use crate::backend::{BackendCapability, PackageManager};
+use crate::cli::{HoldAction, OutputWriter};
+use crate::models::PackageSource;
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Serialize)]
+struct HoldJson {
+    name: String,
+    source: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HoldsOutput {
+    count: usize,
+    holds: Vec<HoldJson>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<String>,
+}
+
+pub async fn run(
+    pm: Arc<RwLock<PackageManager>>,
+    action: HoldAction,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let manager = pm.read().await;
+
+    match action {
+        HoldAction::List { source } => {
+            let sources = match source {
+                Some(source) => vec![source.into()],
+                None => hold_sources(&manager),
+            };
+            let mut holds = Vec::new();
+            let mut errors = Vec::new();
+            for source in sources {
+                match manager.list_holds(source).await {
+                    Ok(names) => holds.extend(names.into_iter().map(|name| (source, name))),
+                    Err(error) => errors.push(format!("{}: {}", source, error)),
+                }
+            }
+
+            if writer.is_json() {
+                let output = HoldsOutput {
+                    count: holds.len(),
+                    holds: holds
+                        .iter()
+                        .map(|(source, name)| HoldJson {
+                            name: name.clone(),
+                            source: format!("{:?}", source).to_lowercase(),
+                        })
+                        .collect(),
+                    errors,
+                };
+                println!("{}", serde_json::to_string_pretty(&output)?);
+                return Ok(());
+            }
+
+            for error in &errors {
+                writer.warning(&format!("Could not read holds for {}", error));
+            }
+            if holds.is_empty() {
+                writer.message("No packages are held.");
+            } else {
+                writer.header("Held Packages");
+                for (source, name) in &holds {
+                    writer.message(&format!("  • {} ({})", name, source));
+                }
+            }
+        }
+        HoldAction::Add { package, source } => {
+            let source = match source {
+                Some(source) => source.into(),
+                None => installed_source(&manager, &package).await?,
+            };
+            manager.hold(source, &package).await?;
+            print_change(writer, &package, source, true);
+        }
+        HoldAction::Remove { package, source } => {
+            let source = match source {
+                Some(source) => source.into(),
+                None => held_source(&manager, &package).await?,
+            };
+            manager.unhold(source, &package).await?;
+            print_change(writer, &package, source, false);
+        }
+    }
+
+    Ok(())
+}
+
+fn hold_sources(manager: &PackageManager) -> Vec<PackageSource> {
+    let mut sources: Vec<PackageSource> = manager
+        .available_sources()
+        .into_iter()
+        .filter(|source| {
+            manager
+                .source_capability_status(*source, BackendCapability::Hold)
+                .is_supported()
+        })
+        .collect();
+    sources.sort();
+    sources
+}
+
+pub(super) fn single_source(
+    package: &str,
+    mut sources: Vec<PackageSource>,
+    missing: &str,
+) -> Result<PackageSource> {
+    sources.sort();
+    sources.dedup();
+    match sources.as_slice() {
+        [] => bail!("{}", missing),
+        [source] => Ok(*source),
+        _ => bail!(
+            "'{}' exists in several sources ({}); pass --source to choose one",
+            package,
+            sources
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+async fn installed_source(manager: &PackageManager, package: &str) -> Result<PackageSource> {
+    let supported = hold_sources(manager);
+    let sources = manager
+        .list_all_installed()
+        .await?
+        .into_iter()
+        .filter(|installed| installed.name == package && supported.contains(&installed.source))
+        .map(|installed| installed.source)
+        .collect();
+    single_source(
+        package,
+        sources,
+        &format!(
+            "Package '{}' is not installed from a source that supports holds",
+            package
+        ),
+    )
+}
+
+async fn held_source(manager: &PackageManager, package: &str) -> Result<PackageSource> {
+    let mut sources = Vec::new();
+    for source in hold_sources(manager) {
+        if let Ok(names) = manager.list_holds(source).await {
+            if names.iter().any(|name| name == package) {
+                sources.push(source);
+            }
+        }
+    }
+    single_source(
+        package,
+        sources,
+        &format!("Package '{}' is not held", package),
+    )
+}
+
+fn print_change(writer: &OutputWriter, package: &str, source: PackageSource, held: bool) {
+    if writer.is_json() {
+        println!(
+            "{}",
+            serde_json::json!({
+                "name": package,
+                "source": format!("{:?}", source).to_lowercase(),
+                "held": held,
+            })
+        );
+    } else if held {
+        writer.success(&format!(
+            "{} is held by {}; upgrades will skip it until it is released",
+            package, source
+        ));
+    } else {
+        writer.success(&format!("{} is no longer held by {}", package, source));
+    }
+}
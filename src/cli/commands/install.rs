@@ -1,9 +1,10 @@
+use super::transaction;
+use crate::backend::transaction::OperationAction;
 use crate::backend::PackageManager;
 use crate::cli::OutputWriter;
-use crate::models::{Package, PackageSource, PackageStatus};
+use crate::models::PackageSource;
 use anyhow::{bail, Result};
 use console::style;
-use indicatif::{ProgressBar, ProgressStyle};
 use std::io::{self, Write};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -105,73 +106,15 @@ pub async fn run(
         }
     };
 
-    // Confirm installation
-    if !skip_confirm && !writer.is_json() {
-        print!(
-            "\nInstall {} ({:?})? [y/N] ",
-            style(&package.name).cyan(),
-            package.source
-        );
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        if !input.trim().eq_ignore_ascii_case("y") {
-            writer.message("Installation cancelled");
-            return Ok(());
-        }
-    }
-
-    // Show progress
-    let spinner = if !writer.is_quiet() && !writer.is_json() {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.cyan} {msg}")
-                .expect("hardcoded progress template should be valid"),
-        );
-        pb.set_message(format!("Installing {}...", package.name));
-        pb.enable_steady_tick(std::time::Duration::from_millis(80));
-        Some(pb)
-    } else {
-        None
-    };
-
-    // Create a Package struct for installation
-    let install_pkg = Package {
-        name: package.name.clone(),
-        version: package.version.clone(),
-        available_version: None,
-        description: package.description.clone(),
-        source: package.source,
-        status: PackageStatus::NotInstalled,
-        size: None,
-        homepage: None,
-        license: None,
-        maintainer: None,
-        dependencies: Vec::new(),
-        install_date: None,
-        update_category: None,
-        enrichment: None,
-    };
-
-    let result = manager.install(&install_pkg).await;
-
-    if let Some(pb) = spinner {
-        pb.finish_and_clear();
-    }
-
-    match result {
-        Ok(_) => {
-            writer.success(&format!(
-                "Successfully installed {} from {:?}",
-                package.name, package.source
-            ));
-            Ok(())
-        }
-        Err(e) => {
-            writer.anyhow_error(&e);
-            Err(e)
-        }
-    }
+    drop(manager);
+
+    let reports = transaction::review_and_execute(
+        pm,
+        OperationAction::Install,
+        std::slice::from_ref(&package),
+        skip_confirm,
+        writer,
+    )
+    .await?;
+    transaction::finish(&reports, writer)
 }
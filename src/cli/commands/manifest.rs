@@ -0,0 +1,393 @@
+use super::transaction::{self, TransactionReport};
+use crate::backend::transaction::{OperationAction, PackageRef};
+use crate::backend::{BackendCapability, PackageManager};
+use crate::cli::OutputWriter;
+use crate::models::manifest::{DriftEntry, Inventory, Manifest, ManifestDiff, VersionConstraint};
+use crate::models::{Config, ExportedPackage, Package, PackageSource};
+use anyhow::{bail, Result};
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Serialize)]
+struct DiffOutput<'a> {
+    manifest: &'a str,
+    converged: bool,
+    #[serde(flatten)]
+    diff: &'a ManifestDiff,
+}
+
+/// `linget diff`: show how the live inventory drifts from the manifest.
+pub async fn diff(
+    pm: Arc<RwLock<PackageManager>>,
+    file: &str,
+    exit_code: bool,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let manifest = Manifest::load(Path::new(file))?;
+    let (diff, _) = load_diff(&pm, &manifest, writer).await?;
+    let converged = diff.is_converged(false);
+
+    if writer.is_json() {
+        let output = DiffOutput {
+            manifest: file,
+            converged,
+            diff: &diff,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print_diff(writer, &diff, writer.is_verbose());
+        if converged {
+            writer.success(&format!("This system matches {}", file));
+        } else {
+            writer.message(&format!("\nRun 'linget apply {}' to converge", file));
+        }
+    }
+
+    if exit_code && !converged {
+        bail!("The system has drifted from {}", file);
+    }
+    Ok(())
+}
+
+/// `linget apply`: converge the system on the manifest. Sources are switched
+/// in the config first, then holds on listed packages are released, missing
+/// packages installed (pinned to a version that satisfies their constraint),
+/// outdated ones updated and, with `prune`, unlisted ones removed, each as
+/// reviewed provider plans. Holds are placed last so they pin what was just
+/// installed.
+pub async fn apply(
+    pm: Arc<RwLock<PackageManager>>,
+    file: &str,
+    prune: bool,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let manifest = Manifest::load(Path::new(file))?;
+    let (diff, inventory) = load_diff(&pm, &manifest, writer).await?;
+    print_diff(writer, &diff, prune || writer.is_verbose());
+    if diff.is_converged(prune) {
+        if writer.is_json() {
+            transaction::finish(&[], writer)?;
+        } else {
+            writer.success(&format!("This system already matches {}", file));
+        }
+        return Ok(());
+    }
+
+    if !diff.enable.is_empty() || !diff.disable.is_empty() {
+        let mut config = Config::load();
+        for source in &diff.enable {
+            config.enabled_sources.set(*source, true);
+        }
+        for source in &diff.disable {
+            config.enabled_sources.set(*source, false);
+        }
+        config.save()?;
+        writer.success("Updated enabled sources");
+    }
+
+    let mut failures = Vec::new();
+    change_holds(&pm, &diff.release, false, &mut failures, writer).await;
+
+    let mut installs = Vec::new();
+    let mut unreachable = Vec::new();
+    for entry in &diff.missing {
+        let stub = ExportedPackage {
+            name: entry.name.clone(),
+            source: entry.source,
+            version: String::new(),
+        }
+        .to_install_stub();
+        let constraint = manifest
+            .packages
+            .iter()
+            .find(|wanted| wanted.source == entry.source && wanted.name == entry.name)
+            .and_then(|wanted| wanted.version.as_ref());
+        let Some(constraint) = constraint else {
+            installs.push(PackageRef::from_package(&stub));
+            continue;
+        };
+        match satisfying_version(&pm, &stub, constraint).await {
+            Some(version) => installs.push(PackageRef::pinned(&stub, &version)),
+            None => unreachable.push(entry),
+        }
+    }
+    let updates = matching(&inventory.updates, &diff.outdated);
+    let removals = if prune {
+        matching(&inventory.installed, &diff.unlisted)
+    } else {
+        Vec::new()
+    };
+
+    let mut reports: Vec<TransactionReport> = Vec::new();
+    for (action, targets) in [
+        (OperationAction::Install, installs),
+        (
+            OperationAction::Update,
+            updates.iter().map(PackageRef::from_package).collect(),
+        ),
+        (
+            OperationAction::Remove,
+            removals.iter().map(PackageRef::from_package).collect(),
+        ),
+    ] {
+        if targets.is_empty() {
+            continue;
+        }
+        reports.extend(
+            transaction::review_and_execute_targets(
+                pm.clone(),
+                action,
+                targets,
+                skip_confirm,
+                writer,
+            )
+            .await?,
+        );
+    }
+
+    change_holds(&pm, &diff.hold, true, &mut failures, writer).await;
+    for entry in unreachable {
+        writer.warning(&format!(
+            "{} ({}) was not installed: {} cannot install a version that satisfies {}",
+            entry.name,
+            entry.source,
+            entry.source,
+            entry.wanted.as_deref().unwrap_or("the manifest")
+        ));
+    }
+    for entry in &diff.mismatched {
+        writer.warning(&format!(
+            "{} ({}) is at {} and no available version satisfies {}",
+            entry.name,
+            entry.source,
+            entry.installed.as_deref().unwrap_or("?"),
+            entry.wanted.as_deref().unwrap_or("the manifest")
+        ));
+    }
+
+    transaction::finish(&reports, writer)?;
+    if !failures.is_empty() {
+        bail!("Could not change holds: {}", failures.join("; "));
+    }
+    Ok(())
+}
+
+async fn load_diff(
+    pm: &Arc<RwLock<PackageManager>>,
+    manifest: &Manifest,
+    writer: &OutputWriter,
+) -> Result<(ManifestDiff, Inventory)> {
+    let spinner = if !writer.is_quiet() && !writer.is_json() {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.cyan} {msg}")
+                .expect("hardcoded progress template should be valid"),
+        );
+        pb.set_message("Reading the installed inventory...");
+        pb.enable_steady_tick(std::time::Duration::from_millis(80));
+        Some(pb)
+    } else {
+        None
+    };
+
+    let manager = pm.read().await;
+    let available = manager.available_sources();
+    let mut inventory = Inventory {
+        installed: manager.list_all_installed().await?,
+        enabled: Config::load()
+            .enabled_sources
+            .to_sources()
+            .into_iter()
+            .collect(),
+        ..Inventory::default()
+    };
+    let declared: Vec<PackageSource> = manifest
+        .declared_sources()
+        .into_iter()
+        .filter(|source| available.contains(source))
+        .collect();
+    let mut holds = HashMap::new();
+    for source in &declared {
+        match manager.check_updates_for_source(*source).await {
+            Ok(updates) => inventory.updates.extend(updates),
+            Err(error) => writer.warning(&format!(
+                "Could not check {} for updates: {}",
+                source, error
+            )),
+        }
+        if manager
+            .source_capability_status(*source, BackendCapability::Hold)
+            .is_supported()
+        {
+            match manager.list_holds(*source).await {
+                Ok(names) => {
+                    holds.insert(*source, names);
+                }
+                Err(error) => {
+                    writer.warning(&format!("Could not read holds for {}: {}", source, error))
+                }
+            }
+        }
+    }
+    inventory.holds = holds;
+    inventory.available = available;
+
+    let mut diff = manifest.diff(&inventory);
+    let mut prunable = Vec::new();
+    for source in &declared {
+        let unlisted: Vec<String> = diff
+            .unlisted
+            .iter()
+            .filter(|entry| entry.source == *source)
+            .map(|entry| entry.name.clone())
+            .collect();
+        if unlisted.is_empty() {
+            continue;
+        }
+        match manager.prunable(*source, &unlisted).await {
+            Ok(names) => prunable.extend(names.into_iter().map(|name| (*source, name))),
+            Err(error) => writer.warning(&format!("Not pruning {}: {:#}", source, error)),
+        }
+    }
+    diff.unlisted
+        .retain(|entry| prunable.contains(&(entry.source, entry.name.clone())));
+
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    Ok((diff, inventory))
+}
+
+/// The newest version of `package` the provider can install that satisfies
+/// `constraint`, if it can install a chosen version at all.
+async fn satisfying_version(
+    pm: &Arc<RwLock<PackageManager>>,
+    package: &Package,
+    constraint: &VersionConstraint,
+) -> Option<String> {
+    let manager = pm.read().await;
+    if !manager
+        .source_capability_status(package.source, BackendCapability::InstallVersion)
+        .is_supported()
+    {
+        return None;
+    }
+    manager
+        .available_versions(package)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|version| constraint.matches(version))
+}
+
+fn matching(packages: &[Package], entries: &[DriftEntry]) -> Vec<Package> {
+    packages
+        .iter()
+        .filter(|package| {
+            entries
+                .iter()
+                .any(|entry| entry.source == package.source && entry.name == package.name)
+        })
+        .cloned()
+        .collect()
+}
+
+async fn change_holds(
+    pm: &Arc<RwLock<PackageManager>>,
+    entries: &[DriftEntry],
+    hold: bool,
+    failures: &mut Vec<String>,
+    writer: &OutputWriter,
+) {
+    let manager = pm.read().await;
+    for entry in entries {
+        let result = if hold {
+            manager.hold(entry.source, &entry.name).await
+        } else {
+            manager.unhold(entry.source, &entry.name).await
+        };
+        match result {
+            Ok(()) if hold => writer.success(&format!("Held {} ({})", entry.name, entry.source)),
+            Ok(()) => writer.success(&format!("Released {} ({})", entry.name, entry.source)),
+            Err(error) => {
+                writer.error(&format!("{} ({}): {}", entry.name, entry.source, error));
+                failures.push(format!("{} ({})", entry.name, entry.source));
+            }
+        }
+    }
+}
+
+fn print_diff(writer: &OutputWriter, diff: &ManifestDiff, show_unlisted: bool) {
+    if writer.is_json() || writer.is_quiet() {
+        return;
+    }
+    for source in &diff.unavailable {
+        writer.warning(&format!(
+            "{} is not available on this system; its packages are skipped",
+            source
+        ));
+    }
+    let unlisted: &[DriftEntry] = if show_unlisted { &diff.unlisted } else { &[] };
+    let sections = [
+        ("Missing", style("+").green(), diff.missing.as_slice()),
+        ("Outdated", style("~").yellow(), diff.outdated.as_slice()),
+        (
+            "Outside the manifest's version",
+            style("!").red(),
+            diff.mismatched.as_slice(),
+        ),
+        ("Not in the manifest", style("-").red(), unlisted),
+        ("Hold", style("=").cyan(), diff.hold.as_slice()),
+        ("Release", style("≠").cyan(), diff.release.as_slice()),
+    ];
+    for (title, marker, entries) in sections {
+        if entries.is_empty() {
+            continue;
+        }
+        writer.header(&format!("{} ({})", title, entries.len()));
+        for entry in entries {
+            let versions = match (&entry.installed, &entry.wanted) {
+                (Some(installed), Some(wanted)) => format!("{} → {}", installed, wanted),
+                (Some(installed), None) => installed.clone(),
+                (None, Some(wanted)) => wanted.clone(),
+                (None, None) => String::new(),
+            };
+            println!(
+                "  {} {} ({}) {}",
+                marker,
+                style(&entry.name).cyan(),
+                entry.source,
+                style(versions).dim()
+            );
+        }
+    }
+    if !show_unlisted && !diff.unlisted.is_empty() {
+        writer.message(&format!(
+            "\n{} installed package{} from the manifest's providers {} not listed; \
+             'linget apply --prune' removes them (--verbose lists them)",
+            diff.unlisted.len(),
+            if diff.unlisted.len() == 1 { "" } else { "s" },
+            if diff.unlisted.len() == 1 {
+                "is"
+            } else {
+                "are"
+            }
+        ));
+    }
+    if !diff.enable.is_empty() || !diff.disable.is_empty() {
+        writer.header("Sources");
+        for source in &diff.enable {
+            println!("  {} enable {}", style("+").green(), source);
+        }
+        for source in &diff.disable {
+            println!("  {} disable {}", style("-").red(), source);
+        }
+    }
+}
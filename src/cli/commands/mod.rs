@@ -2,14 +2,21 @@ pub mod backup;
 pub mod check;
 pub mod cohort_report;
 pub mod completions;
+pub mod hold;
 pub mod ignore;
 pub mod info;
 pub mod install;
 pub mod list;
+pub mod manifest;
 pub mod permissions;
+pub mod policy;
 pub mod providers;
 pub mod remove;
+pub mod rollback;
 pub mod schedule;
 pub mod search;
 pub mod sources;
+pub mod transaction;
+pub mod tree;
 pub mod update;
+pub mod why;
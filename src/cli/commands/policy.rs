@@ -0,0 +1,155 @@
+use super::hold::single_source;
+use super::schedule::capitalize;
+use crate::backend::PackageManager;
+use crate::cli::{OutputWriter, PolicyAction};
+use crate::models::policy::PolicyDecision;
+use crate::models::{Package, PackageSource, UpdatePolicy, UpdateSightings};
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local, Utc};
+use console::style;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Serialize)]
+struct ExplainOutput {
+    name: String,
+    source: String,
+    version: String,
+    available_version: Option<String>,
+    policy: String,
+    /// `None` when the package has no pending update to decide.
+    decision: Option<PolicyDecision>,
+}
+
+pub async fn run(
+    pm: Arc<RwLock<PackageManager>>,
+    action: PolicyAction,
+    writer: &OutputWriter,
+) -> Result<()> {
+    match action {
+        PolicyAction::Explain { package, source } => {
+            explain(pm, &package, source.map(Into::into), writer).await
+        }
+    }
+}
+
+async fn explain(
+    pm: Arc<RwLock<PackageManager>>,
+    package: &str,
+    source: Option<PackageSource>,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let path = UpdatePolicy::default_path();
+    let Some(policy) = UpdatePolicy::load_default()? else {
+        bail!("No update policy at {}", path.display());
+    };
+
+    let manager = pm.read().await;
+    let installed: Vec<Package> = manager
+        .list_all_installed()
+        .await?
+        .into_iter()
+        .filter(|installed| {
+            installed.name == package && source.is_none_or(|source| installed.source == source)
+        })
+        .collect();
+    let source = single_source(
+        package,
+        installed.iter().map(|installed| installed.source).collect(),
+        &format!("Package '{}' is not installed", package),
+    )?;
+    let installed = installed
+        .into_iter()
+        .find(|installed| installed.source == source)
+        .expect("single_source picks an installed source");
+    let update = manager
+        .check_updates_for_source(source)
+        .await?
+        .into_iter()
+        .find(|update| update.name == installed.name);
+    drop(manager);
+
+    // Explaining is read-only: an update seen for the first time is treated
+    // as seen now without recording it.
+    let now = Utc::now();
+    let seen = update
+        .as_ref()
+        .and_then(|update| UpdateSightings::load().since(update));
+    let decision = update
+        .as_ref()
+        .map(|update| policy.evaluate(update, seen.unwrap_or(now), now));
+
+    if writer.is_json() {
+        let output = ExplainOutput {
+            name: installed.name,
+            source: format!("{:?}", source).to_lowercase(),
+            version: installed.version,
+            available_version: update.and_then(|update| update.available_version),
+            policy: path.display().to_string(),
+            decision,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    let (Some(update), Some(decision)) = (update, decision) else {
+        writer.message(&format!(
+            "{} ({}) {} is up to date; the policy only decides pending updates",
+            style(&installed.name).cyan(),
+            source,
+            installed.version
+        ));
+        return Ok(());
+    };
+
+    writer.message(&format!(
+        "{} ({}) {}",
+        style(&update.name).cyan().bold(),
+        source,
+        update.display_version()
+    ));
+    let age = match (update.released_at, seen) {
+        (Some(released), _) => format!("released {}", when(released, now)),
+        (None, Some(seen)) => format!("first seen {}", when(seen, now)),
+        (None, None) => "not seen before".to_string(),
+    };
+    writer.message(&format!(
+        "  Lane: {} · Bump: {} · {}",
+        decision.lane.label(),
+        decision.bump.map_or("unknown", |bump| bump.label()),
+        age
+    ));
+    match &decision.rule {
+        Some(rule) => writer.message(&format!("  Matched {}: {}", rule, decision.action)),
+        None => writer.message(&format!(
+            "  No rule matched; the default is to {}",
+            decision.action
+        )),
+    }
+    let verdict = format!("  → {}", capitalize(&decision.verdict.to_string()));
+    if decision.applies() {
+        writer.message(&style(verdict).green().to_string());
+    } else {
+        writer.message(&style(verdict).yellow().to_string());
+    }
+    writer.message(
+        &style(format!("  Policy: {}", path.display()))
+            .dim()
+            .to_string(),
+    );
+    Ok(())
+}
+
+fn when(time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let ago = match (now - time).num_days() {
+        0 => "today".to_string(),
+        1 => "1 day ago".to_string(),
+        days => format!("{} days ago", days),
+    };
+    format!(
+        "{} ({})",
+        time.with_timezone(&Local).format("%b %d, %I:%M %p"),
+        ago
+    )
+}
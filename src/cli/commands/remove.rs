@@ -1,9 +1,10 @@
+use super::transaction;
+use crate::backend::transaction::OperationAction;
 use crate::backend::PackageManager;
 use crate::cli::OutputWriter;
 use crate::models::PackageSource;
 use anyhow::{bail, Result};
 use console::style;
-use indicatif::{ProgressBar, ProgressStyle};
 use std::io::{self, Write};
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -92,56 +93,15 @@ pub async fn run(
         }
     };
 
-    // Confirm removal
-    if !skip_confirm && !writer.is_json() {
-        print!(
-            "\n{} {} ({:?})? [y/N] ",
-            style("Remove").red().bold(),
-            style(&package.name).cyan(),
-            package.source
-        );
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
-        if !input.trim().eq_ignore_ascii_case("y") {
-            writer.message("Removal cancelled");
-            return Ok(());
-        }
-    }
-
-    // Show progress
-    let spinner = if !writer.is_quiet() && !writer.is_json() {
-        let pb = ProgressBar::new_spinner();
-        pb.set_style(
-            ProgressStyle::default_spinner()
-                .template("{spinner:.cyan} {msg}")
-                .expect("hardcoded progress template should be valid"),
-        );
-        pb.set_message(format!("Removing {}...", package.name));
-        pb.enable_steady_tick(std::time::Duration::from_millis(80));
-        Some(pb)
-    } else {
-        None
-    };
-
-    let result = manager.remove(&package).await;
-
-    if let Some(pb) = spinner {
-        pb.finish_and_clear();
-    }
-
-    match result {
-        Ok(_) => {
-            writer.success(&format!(
-                "Successfully removed {} from {:?}",
-                package.name, package.source
-            ));
-            Ok(())
-        }
-        Err(e) => {
-            writer.anyhow_error(&e);
-            Err(e)
-        }
-    }
+    drop(manager);
+
+    let reports = transaction::review_and_execute(
+        pm,
+        OperationAction::Remove,
+        std::slice::from_ref(&package),
+        skip_confirm,
+        writer,
+    )
+    .await?;
+    transaction::finish(&reports, writer)
 }
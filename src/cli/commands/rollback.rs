@@ -0,0 +1,17 @@
+use super::transaction;
+use crate::backend::PackageManager;
+use crate::cli::OutputWriter;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub async fn run(
+    pm: Arc<RwLock<PackageManager>>,
+    operation_id: &str,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let reports =
+        transaction::review_and_execute_rollback(pm, operation_id, skip_confirm, writer).await?;
+    transaction::finish(&reports, writer)
+}
@@ -1,23 +1,602 @@
+use super::hold::single_source;
 use crate::backend::PackageManager;
-use crate::cli::{OutputWriter, ScheduleAction};
-use crate::models::Config;
+use crate::cli::{ConditionArgs, OutputWriter, ScheduleAction, ScheduleOp, SourceArg};
+use crate::models::scheduler::{parse_schedule_time, Deferral, RuleDecision, RunConditions};
+use crate::models::{
+    Config, Package, PackageSource, RecurringRule, RuleOutcome, RuleRun, ScheduledOperation,
+    ScheduledTask,
+};
 use crate::scheduler_runtime::{
-    execute_scheduled_task, sync_systemd_runtime, ScheduledTaskExecutionLock,
+    deferral_reasons, execute_recurring_rule, execute_scheduled_task, probe_systemd_runtime,
+    sync_systemd_runtime, ScheduledTaskExecutionLock, SchedulerRuntimeStatus,
 };
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, Utc};
+use console::style;
+use serde::Serialize;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+#[derive(Debug, Serialize)]
+struct TaskJson<'a> {
+    #[serde(flatten)]
+    task: &'a ScheduledTask,
+    status: &'static str,
+}
+
+impl<'a> TaskJson<'a> {
+    fn new(task: &'a ScheduledTask) -> Self {
+        Self {
+            task,
+            status: task_status(task),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AddOutput<'a> {
+    task: TaskJson<'a>,
+    /// The pending task for the same package that this one replaced.
+    replaced: Option<String>,
+    #[serde(flatten)]
+    runtime: SchedulerRuntimeStatus,
+}
+
+#[derive(Debug, Serialize)]
+struct ListOutput<'a> {
+    tasks: Vec<TaskJson<'a>>,
+    rules: &'a [RecurringRule],
+}
+
+#[derive(Debug, Serialize)]
+struct CancelOutput {
+    kind: &'static str,
+    id: String,
+    description: String,
+}
+
+#[derive(Debug, Serialize)]
+struct HistoryEntry {
+    kind: &'static str,
+    id: String,
+    description: String,
+    scheduled_for: DateTime<Utc>,
+    finished_at: DateTime<Utc>,
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<usize>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    caught_up: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusOutput {
+    #[serde(flatten)]
+    runtime: SchedulerRuntimeStatus,
+    timer: Option<String>,
+    pending_tasks: usize,
+    due_tasks: usize,
+    /// Due tasks and rules whose conditions are keeping them waiting.
+    deferred: usize,
+    enabled_rules: usize,
+    next_run: Option<DateTime<Utc>>,
+}
+
 pub async fn run(
     pm: Arc<RwLock<PackageManager>>,
     action: ScheduleAction,
     writer: &OutputWriter,
 ) -> Result<()> {
     match action {
+        ScheduleAction::Add {
+            package,
+            op,
+            at,
+            source,
+            conditions,
+        } => add(pm, &package, op, &at, source, conditions, writer).await,
+        ScheduleAction::List { all } => list(all, writer),
+        ScheduleAction::Cancel { id } => cancel(&id, writer).await,
+        ScheduleAction::History { limit } => history(limit, writer),
+        ScheduleAction::Status => status(writer).await,
         ScheduleAction::RunDue => run_due(pm, writer).await,
     }
 }
 
+async fn add(
+    pm: Arc<RwLock<PackageManager>>,
+    package_name: &str,
+    op: ScheduleOp,
+    at: &str,
+    source: Option<SourceArg>,
+    conditions: ConditionArgs,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let scheduled_at = parse_schedule_time(at, &Local::now()).ok_or_else(|| {
+        anyhow!(
+            "Could not read '{}' as a time; use \"YYYY-MM-DD HH:MM\", RFC 3339 or \"HH:MM\"",
+            at
+        )
+    })?;
+    if scheduled_at <= Utc::now() {
+        bail!("{} is in the past", at);
+    }
+
+    let operation = ScheduledOperation::from(op);
+    let source = source.map(PackageSource::from);
+    let package = {
+        let manager = pm.read().await;
+        let package = find_package(&manager, package_name, operation, source).await?;
+        if operation == ScheduledOperation::Update {
+            let updates = manager.check_updates_for_source(package.source).await?;
+            if !updates.iter().any(|update| update.name == package.name) {
+                writer.warning(&format!(
+                    "No update is available for {} yet; the task fails if there is still none at {}",
+                    package.name, at
+                ));
+            }
+        }
+        package
+    };
+
+    let mut task = ScheduledTask::new(
+        package.id(),
+        package.name.clone(),
+        package.source,
+        operation,
+        scheduled_at,
+    );
+    task.conditions = conditions.into();
+    let mut config = Config::load();
+    let replaced = config
+        .scheduler
+        .get_pending_for_package(&task.package_id)
+        .map(|pending| pending.id.clone());
+    config.scheduler.add_task(task.clone());
+    config.save()?;
+    let runtime = sync_systemd_runtime(config.scheduler.needs_runner()).await;
+
+    if writer.is_json() {
+        let output = AddOutput {
+            task: TaskJson::new(&task),
+            replaced,
+            runtime,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if let Some(replaced) = replaced {
+        writer.warning(&format!(
+            "Replaced the pending task {} for {}",
+            short_id(&replaced),
+            task.package_name
+        ));
+    }
+    writer.success(&format!(
+        "Scheduled {} of {} ({}) for {} [{}]",
+        operation.display_name().to_lowercase(),
+        task.package_name,
+        task.source,
+        task.scheduled_time_display(),
+        short_id(&task.id)
+    ));
+    if !task.conditions.is_empty() {
+        writer.message(&format!(
+            "  Runs only when {}",
+            task.conditions.labels().join(", ")
+        ));
+    }
+    if let SchedulerRuntimeStatus::InAppFallback { reason } = runtime {
+        writer.warning(&format!(
+            "Background scheduler unavailable; tasks run only while LinGet is open ({})",
+            reason
+        ));
+    }
+    Ok(())
+}
+
+/// Find the package a task will act on: an installed one for updates and
+/// removals, a search result for installs.
+async fn find_package(
+    manager: &PackageManager,
+    name: &str,
+    operation: ScheduledOperation,
+    source: Option<PackageSource>,
+) -> Result<Package> {
+    let (candidates, missing) = match operation {
+        ScheduledOperation::Install => (
+            manager.search(name).await?,
+            format!("Package '{}' not found in any source", name),
+        ),
+        ScheduledOperation::Update | ScheduledOperation::Remove => (
+            manager.list_all_installed().await?,
+            format!("Package '{}' is not installed", name),
+        ),
+    };
+    let mut candidates: Vec<Package> = candidates
+        .into_iter()
+        .filter(|package| {
+            package.name == name && source.is_none_or(|source| package.source == source)
+        })
+        .collect();
+    let source = single_source(
+        name,
+        candidates.iter().map(|package| package.source).collect(),
+        &match source {
+            Some(source) => format!("{} in {}", missing, source),
+            None => missing,
+        },
+    )?;
+    let index = candidates
+        .iter()
+        .position(|package| package.source == source)
+        .expect("single_source picks a candidate's source");
+    Ok(candidates.swap_remove(index))
+}
+
+fn list(all: bool, writer: &OutputWriter) -> Result<()> {
+    let config = Config::load();
+    let scheduler = &config.scheduler;
+    let mut tasks: Vec<&ScheduledTask> = scheduler
+        .tasks
+        .iter()
+        .filter(|task| all || task.is_active())
+        .collect();
+    tasks.sort_by_key(|task| task.scheduled_at);
+
+    if writer.is_json() {
+        let output = ListOutput {
+            tasks: tasks.iter().map(|task| TaskJson::new(task)).collect(),
+            rules: &scheduler.rules,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if tasks.is_empty() && scheduler.rules.is_empty() {
+        writer.message("Nothing is scheduled.");
+        return Ok(());
+    }
+    if !tasks.is_empty() {
+        writer.header("Scheduled Tasks");
+        for task in &tasks {
+            let when = if task.is_active() {
+                format!("{} ({})", task.scheduled_time_display(), task.time_until())
+            } else {
+                format!("{} ({})", task.completed_time_display(), task_status(task))
+            };
+            println!(
+                "  {} {} {} ({}) {}",
+                style(short_id(&task.id)).dim(),
+                task.operation.display_name(),
+                style(&task.package_name).cyan(),
+                task.source,
+                style(when).dim()
+            );
+            print_waiting(&task.conditions, task.deferred.as_ref());
+        }
+    }
+    if !scheduler.rules.is_empty() {
+        writer.header("Recurring Rules");
+        for rule in &scheduler.rules {
+            let next = if !rule.enabled {
+                "disabled".to_string()
+            } else {
+                rule.next_run
+                    .map(|next| format!("next {}", local_time(next)))
+                    .unwrap_or_else(|| "no upcoming run".to_string())
+            };
+            println!(
+                "  {} {} {} {}",
+                style(short_id(&rule.id)).dim(),
+                style(rule.calendar.expression()).cyan(),
+                rule.describe(),
+                style(next).dim()
+            );
+            print_waiting(&rule.conditions, rule.deferred.as_ref());
+        }
+    }
+    Ok(())
+}
+
+async fn cancel(id: &str, writer: &OutputWriter) -> Result<()> {
+    if id.is_empty() {
+        bail!("Pass the ID of a task or rule to cancel");
+    }
+    let mut config = Config::load();
+    let tasks: Vec<String> = config
+        .scheduler
+        .tasks
+        .iter()
+        .filter(|task| task.is_active() && task.id.starts_with(id))
+        .map(|task| task.id.clone())
+        .collect();
+    let rules: Vec<String> = config
+        .scheduler
+        .rules
+        .iter()
+        .filter(|rule| rule.id.starts_with(id))
+        .map(|rule| rule.id.clone())
+        .collect();
+
+    let output = match (tasks.as_slice(), rules.as_slice()) {
+        ([task_id], []) => {
+            let task = config
+                .scheduler
+                .remove_task(task_id)
+                .expect("the task was just found");
+            CancelOutput {
+                kind: "task",
+                id: task.id,
+                description: format!(
+                    "{} of {} ({})",
+                    task.operation.display_name().to_lowercase(),
+                    task.package_name,
+                    task.source
+                ),
+            }
+        }
+        ([], [rule_id]) => {
+            let rule = config
+                .scheduler
+                .remove_rule(rule_id)
+                .expect("the rule was just found");
+            CancelOutput {
+                kind: "rule",
+                id: rule.id.clone(),
+                description: format!("{} ({})", rule.describe(), rule.calendar.expression()),
+            }
+        }
+        ([], []) => bail!("No pending task or recurring rule has the ID '{}'", id),
+        _ => bail!(
+            "'{}' matches several tasks or rules; use more of the ID",
+            id
+        ),
+    };
+    config.save()?;
+    let _ = sync_systemd_runtime(config.scheduler.needs_runner()).await;
+
+    if writer.is_json() {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else if output.kind == "task" {
+        writer.success(&format!("Cancelled {}", output.description));
+    } else {
+        writer.success(&format!("Deleted the rule to {}", output.description));
+    }
+    Ok(())
+}
+
+fn history(limit: usize, writer: &OutputWriter) -> Result<()> {
+    let config = Config::load();
+    let mut entries: Vec<HistoryEntry> = config
+        .scheduler
+        .tasks
+        .iter()
+        .filter(|task| task.completed)
+        .map(|task| HistoryEntry {
+            kind: "task",
+            id: task.id.clone(),
+            description: format!(
+                "{} {} ({})",
+                task.operation.display_name(),
+                task.package_name,
+                task.source
+            ),
+            scheduled_for: task.scheduled_at,
+            finished_at: task.completed_at.unwrap_or(task.scheduled_at),
+            status: if task.error.is_some() {
+                "failed"
+            } else {
+                "succeeded"
+            },
+            updated: None,
+            caught_up: false,
+            detail: task.error.clone(),
+        })
+        .collect();
+    for rule in &config.scheduler.rules {
+        entries.extend(rule.history.iter().map(|run| rule_history_entry(rule, run)));
+    }
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.finished_at));
+    entries.truncate(limit);
+
+    if writer.is_json() {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        writer.message("No scheduled work has run yet.");
+        return Ok(());
+    }
+
+    writer.header("Schedule History");
+    for entry in &entries {
+        let marker = match entry.status {
+            "succeeded" => style("✓").green(),
+            "failed" => style("✗").red(),
+            _ => style("-").dim(),
+        };
+        let mut detail = entry.detail.clone().unwrap_or_default();
+        if let Some(updated) = entry.updated {
+            let counted = format!(
+                "updated {} package{}",
+                updated,
+                if updated == 1 { "" } else { "s" }
+            );
+            // A successful rule run's detail names what the policy held back.
+            detail = match &entry.detail {
+                Some(held_back) => format!("{}. {}", counted, held_back),
+                None => counted,
+            };
+        }
+        if entry.caught_up {
+            detail.push_str(" after a missed run");
+        }
+        println!(
+            "  {} {} {} {} {}",
+            marker,
+            style(local_time(entry.finished_at)).dim(),
+            style(short_id(&entry.id)).dim(),
+            entry.description,
+            style(detail).dim()
+        );
+    }
+    Ok(())
+}
+
+fn rule_history_entry(rule: &RecurringRule, run: &RuleRun) -> HistoryEntry {
+    let (status, updated, detail) = match &run.outcome {
+        RuleOutcome::Succeeded { updated, held_back } => (
+            "succeeded",
+            Some(*updated),
+            (!held_back.is_empty())
+                .then(|| format!("Held back by policy: {}", held_back.join("; "))),
+        ),
+        RuleOutcome::Failed { error } => ("failed", None, Some(error.clone())),
+        RuleOutcome::Skipped { reason } => ("skipped", None, Some(reason.clone())),
+    };
+    HistoryEntry {
+        kind: "rule",
+        id: rule.id.clone(),
+        description: capitalize(&rule.describe()),
+        scheduled_for: run.scheduled_for,
+        finished_at: run.finished_at,
+        status,
+        updated,
+        caught_up: run.caught_up,
+        detail,
+    }
+}
+
+async fn status(writer: &OutputWriter) -> Result<()> {
+    let config = Config::load();
+    let scheduler = &config.scheduler;
+    let (runtime, timer) = probe_systemd_runtime().await;
+    let enabled_rules: Vec<&RecurringRule> =
+        scheduler.rules.iter().filter(|rule| rule.enabled).collect();
+    let output = StatusOutput {
+        runtime,
+        timer,
+        pending_tasks: scheduler.pending_count(),
+        due_tasks: scheduler.due_tasks().len(),
+        deferred: scheduler
+            .pending_tasks()
+            .iter()
+            .filter(|task| task.deferred.is_some())
+            .count()
+            + enabled_rules
+                .iter()
+                .filter(|rule| rule.deferred.is_some())
+                .count(),
+        enabled_rules: enabled_rules.len(),
+        next_run: scheduler
+            .pending_tasks()
+            .iter()
+            .map(|task| task.scheduled_at)
+            .chain(enabled_rules.iter().filter_map(|rule| rule.next_run))
+            .min(),
+    };
+
+    if writer.is_json() {
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    writer.header("Scheduler");
+    match &output.runtime {
+        SchedulerRuntimeStatus::SystemdUser => writer.message(&format!(
+            "  Runner: systemd user timer ({})",
+            output.timer.as_deref().unwrap_or("unknown")
+        )),
+        SchedulerRuntimeStatus::InAppFallback { reason } => {
+            writer.message("  Runner: in-app fallback; tasks run only while LinGet is open");
+            writer.message(&format!("  Reason: {}", reason));
+        }
+    }
+    writer.message(&format!(
+        "  Pending tasks: {} ({} due)",
+        output.pending_tasks, output.due_tasks
+    ));
+    writer.message(&format!(
+        "  Recurring rules: {} enabled",
+        output.enabled_rules
+    ));
+    if output.deferred > 0 {
+        writer.message(&format!(
+            "  Deferred: {} (see 'linget schedule list')",
+            output.deferred
+        ));
+    }
+    if let Some(next_run) = output.next_run {
+        writer.message(&format!("  Next run: {}", local_time(next_run)));
+    }
+    if output.runtime == SchedulerRuntimeStatus::SystemdUser
+        && scheduler.needs_runner()
+        && output.timer.as_deref() != Some("active")
+    {
+        writer.warning("The timer is not active; adding or cancelling a task re-enables it");
+    }
+    Ok(())
+}
+
+/// The conditions under a listed task or rule, and why it last waited.
+fn print_waiting(conditions: &RunConditions, deferred: Option<&Deferral>) {
+    if !conditions.is_empty() {
+        println!(
+            "           {}",
+            style(format!("when {}", conditions.labels().join(", "))).dim()
+        );
+    }
+    if let Some(deferral) = deferred {
+        println!(
+            "           {}",
+            style(format!(
+                "⏸ {} ({})",
+                deferral.describe(),
+                local_time(deferral.checked_at)
+            ))
+            .yellow()
+        );
+    }
+}
+
+fn task_status(task: &ScheduledTask) -> &'static str {
+    if !task.completed {
+        if task.deferred.is_some() {
+            "deferred"
+        } else if task.is_due() {
+            "due"
+        } else {
+            "pending"
+        }
+    } else if task.error.is_some() {
+        "failed"
+    } else {
+        "completed"
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
+
+fn local_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local)
+        .format("%b %d, %I:%M %p")
+        .to_string()
+}
+
+pub(super) fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
 async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Result<()> {
     let Some(_lock) = ScheduledTaskExecutionLock::try_acquire()? else {
         writer.verbose("Another LinGet scheduler execution is already running");
@@ -40,7 +619,20 @@ async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Resu
             .map(|task| task.scheduled_at)
     });
 
-    if due_task_ids.is_empty() {
+    let now = Utc::now();
+    let scheduled_new_rules = config.scheduler.schedule_new_rules(now);
+    let due_rules: Vec<(String, RuleDecision)> = config
+        .scheduler
+        .rules
+        .iter()
+        .filter_map(|rule| rule.decide(now).map(|decision| (rule.id.clone(), decision)))
+        .collect();
+
+    if due_task_ids.is_empty() && due_rules.is_empty() {
+        if scheduled_new_rules {
+            config.save()?;
+            let _ = sync_systemd_runtime(config.scheduler.needs_runner()).await;
+        }
         writer.verbose("No scheduled tasks are due");
         return Ok(());
     }
@@ -52,6 +644,7 @@ async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Resu
 
     let mut completed = 0usize;
     let mut failed = 0usize;
+    let mut deferred = 0usize;
 
     for task_id in due_task_ids {
         let task_opt = config
@@ -64,6 +657,34 @@ async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Resu
             continue;
         };
 
+        let reasons = {
+            let manager = pm.read().await;
+            deferral_reasons(&manager, &task.conditions, Some(task.source)).await
+        };
+        if !reasons.is_empty() {
+            let deferral = Deferral {
+                checked_at: Utc::now(),
+                reasons,
+            };
+            writer.verbose(&format!(
+                "{} of {}: {}",
+                task.operation.display_name(),
+                task.package_name,
+                deferral.describe()
+            ));
+            if let Some(task) = config
+                .scheduler
+                .tasks
+                .iter_mut()
+                .find(|entry| entry.id == task_id)
+            {
+                task.deferred = Some(deferral);
+            }
+            deferred += 1;
+            config.save()?;
+            continue;
+        }
+
         let result = {
             let manager = pm.read().await;
             execute_scheduled_task(&manager, &task).await
@@ -98,12 +719,120 @@ async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Resu
         config.save()?;
     }
 
-    if failed == 0 {
-        writer.success(&format!(
-            "Ran {} scheduled task{}",
-            completed,
-            if completed == 1 { "" } else { "s" }
+    for (rule_id, decision) in due_rules {
+        let Some(rule) = config
+            .scheduler
+            .rules
+            .iter()
+            .find(|rule| rule.id == rule_id)
+            .cloned()
+        else {
+            continue;
+        };
+
+        let run = match decision {
+            RuleDecision::Skip { scheduled_for } => {
+                writer.verbose(&format!(
+                    "Skipping the missed {} run of '{}'",
+                    scheduled_for
+                        .with_timezone(&chrono::Local)
+                        .format("%b %d %H:%M"),
+                    rule.describe()
+                ));
+                RuleRun {
+                    scheduled_for,
+                    finished_at: Utc::now(),
+                    caught_up: false,
+                    outcome: RuleOutcome::Skipped {
+                        reason: "Missed while LinGet was not running".to_string(),
+                    },
+                }
+            }
+            RuleDecision::Run {
+                scheduled_for,
+                caught_up,
+            } => {
+                let reasons = {
+                    let manager = pm.read().await;
+                    deferral_reasons(&manager, &rule.conditions, rule.source).await
+                };
+                if !reasons.is_empty() {
+                    let deferral = Deferral {
+                        checked_at: Utc::now(),
+                        reasons,
+                    };
+                    writer.verbose(&format!(
+                        "{}: {}",
+                        capitalize(&rule.describe()),
+                        deferral.describe()
+                    ));
+                    if let Some(rule) = config
+                        .scheduler
+                        .rules
+                        .iter_mut()
+                        .find(|rule| rule.id == rule_id)
+                    {
+                        rule.deferred = Some(deferral);
+                    }
+                    deferred += 1;
+                    config.save()?;
+                    continue;
+                }
+                let result = {
+                    let manager = pm.read().await;
+                    execute_recurring_rule(&manager, &rule).await
+                };
+                let outcome = match result {
+                    Ok(updates) => {
+                        completed += 1;
+                        RuleOutcome::Succeeded {
+                            updated: updates.updated,
+                            held_back: updates.held_back,
+                        }
+                    }
+                    Err(error) => {
+                        failed += 1;
+                        RuleOutcome::Failed {
+                            error: error.to_string(),
+                        }
+                    }
+                };
+                RuleRun {
+                    scheduled_for,
+                    finished_at: Utc::now(),
+                    caught_up,
+                    outcome,
+                }
+            }
+        };
+
+        if let Some(rule) = config
+            .scheduler
+            .rules
+            .iter_mut()
+            .find(|rule| rule.id == rule_id)
+        {
+            rule.record(run, Utc::now());
+        }
+        config.save()?;
+    }
+
+    if deferred > 0 {
+        writer.message(&format!(
+            "Deferred {} scheduled task{} until {} conditions hold",
+            deferred,
+            if deferred == 1 { "" } else { "s" },
+            if deferred == 1 { "its" } else { "their" }
         ));
+    }
+    if failed == 0 {
+        if completed > 0 || deferred == 0 {
+            writer.success(&format!(
+                "Ran {} scheduled task{}",
+                completed,
+                if completed == 1 { "" } else { "s" }
+            ));
+        }
     } else if completed == 0 {
         writer.warning(&format!(
             "{} scheduled task{} failed",
@@ -120,7 +849,7 @@ async fn run_due(pm: Arc<RwLock<PackageManager>>, writer: &OutputWriter) -> Resu
         ));
     }
 
-    let _ = sync_systemd_runtime(config.scheduler.pending_count() > 0).await;
+    let _ = sync_systemd_runtime(config.scheduler.needs_runner()).await;
 
     Ok(())
 }
@@ -0,0 +1,692 @@
+use crate::backend::streaming::{with_log_lines, StreamLine};
+use crate::backend::transaction::{
+    describe_change, CancellationFlag, OperationAction, OperationRequest, PackageRef, PlanDiff,
+    ProviderDescriptor, ProviderError, ProviderErrorCode, ProviderPlan, RequestedBy,
+    RiskAssessment, RiskLevel, RollbackCapability, StalePlan, TransactionBundle, TransactionEngine,
+    VerificationOutcome, VerificationReceipt,
+};
+use crate::backend::{transaction_store_path, FilesystemSnapshot, PackageManager, RemovalImpact};
+use crate::cli::OutputWriter;
+use crate::models::{Config, Package};
+use anyhow::{bail, Result};
+use console::style;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::sync::Arc;
+use tokio::sync::{mpsc, RwLock};
+
+/// One reviewed provider transaction as reported to the user: the exact plan,
+/// its risk, and either the verification receipt or the provider error.
+#[derive(Debug, Serialize)]
+pub struct TransactionReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+    pub plan: ProviderPlan,
+    pub risk: RiskAssessment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receipt: Option<VerificationReceipt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProviderError>,
+    /// What moved when the reviewed plan went stale and `plan` is its
+    /// re-plan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_diff: Option<PlanDiff>,
+    /// Filesystem snapshot taken before the plan ran.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<FilesystemSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ConfirmationGate {
+    Proceed,
+    Prompt,
+    Refuse(String),
+}
+
+/// Decide how a plan may proceed. Blocked plans never run; High-risk plans
+/// need either an interactive "y" or an explicit `--yes` when nobody can be
+/// asked (JSON output).
+fn confirmation_gate(
+    risk: &RiskAssessment,
+    skip_confirm: bool,
+    interactive: bool,
+) -> ConfirmationGate {
+    if risk.level == RiskLevel::Blocked {
+        return ConfirmationGate::Refuse(
+            "The provider plan is blocked; LinGet cannot run it safely".to_string(),
+        );
+    }
+    if skip_confirm {
+        return ConfirmationGate::Proceed;
+    }
+    if interactive {
+        return ConfirmationGate::Prompt;
+    }
+    if risk.requires_explicit_confirmation {
+        ConfirmationGate::Refuse(format!(
+            "The provider plan is {:?} risk; pass --yes to confirm it explicitly",
+            risk.level
+        ))
+    } else {
+        ConfirmationGate::Proceed
+    }
+}
+
+fn action_verb(action: OperationAction) -> &'static str {
+    match action {
+        OperationAction::Install => "Install",
+        OperationAction::Remove => "Remove",
+        OperationAction::Update => "Update",
+        OperationAction::Rollback => "Roll back",
+    }
+}
+
+/// Plan `packages` through the transaction engine, show the expected changes
+/// and risk, confirm once, execute, and report each verification receipt.
+/// Packages from several providers are planned as one `TransactionBundle`.
+/// Provider failures are kept on the returned reports; [`finish`] turns them
+/// into the command's exit status.
+pub async fn review_and_execute(
+    pm: Arc<RwLock<PackageManager>>,
+    action: OperationAction,
+    packages: &[Package],
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<Vec<TransactionReport>> {
+    let targets = packages.iter().map(PackageRef::from_package).collect();
+    review_and_execute_targets(pm, action, targets, skip_confirm, writer).await
+}
+
+/// [`review_and_execute`] for targets built by the caller, such as ones
+/// pinned to a version with [`PackageRef::pinned`].
+pub async fn review_and_execute_targets(
+    pm: Arc<RwLock<PackageManager>>,
+    action: OperationAction,
+    targets: Vec<PackageRef>,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<Vec<TransactionReport>> {
+    let request = OperationRequest::new(action, targets, RequestedBy::Cli);
+    let config = Config::load();
+    let engine = TransactionEngine::load(pm, transaction_store_path())
+        .await
+        .map_err(|error| anyhow::anyhow!(error.to_string()))?
+        .with_snapshots(config.snapshots)
+        .with_hooks(config.hooks);
+
+    let (bundle, planned) = if request.split_by_source().len() > 1 {
+        let bundle = with_spinner(
+            writer,
+            "Planning transaction bundle...",
+            engine.plan_bundle(request),
+        )
+        .await
+        .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        let planned: Vec<_> = bundle
+            .plans
+            .iter()
+            .map(|plan| (plan.clone(), RiskAssessment::for_plan(plan)))
+            .collect();
+        (Some(bundle), planned)
+    } else {
+        let planned = with_spinner(writer, "Planning transaction...", engine.plan(request))
+            .await
+            .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+        (None, vec![planned])
+    };
+    for (plan, risk) in &planned {
+        print_plan(writer, plan, risk);
+    }
+    if !confirm(writer, action, &planned, skip_confirm)? {
+        return Ok(Vec::new());
+    }
+
+    if let Some(bundle) = bundle {
+        return execute_bundle(&engine, bundle, planned, writer).await;
+    }
+    execute_plans(&engine, planned, skip_confirm, writer).await
+}
+
+/// Plan the reverse of a finished operation from its verification receipt,
+/// then review, execute and verify it like any other plan.
+pub async fn review_and_execute_rollback(
+    pm: Arc<RwLock<PackageManager>>,
+    operation_id: &str,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<Vec<TransactionReport>> {
+    let config = Config::load();
+    let engine = TransactionEngine::load(pm, transaction_store_path())
+        .await
+        .map_err(|error| anyhow::anyhow!(error.to_string()))?
+        .with_snapshots(config.snapshots)
+        .with_hooks(config.hooks);
+    let planned = with_spinner(
+        writer,
+        "Planning rollback...",
+        engine.plan_rollback(operation_id),
+    )
+    .await
+    .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+    print_plan(writer, &planned.0, &planned.1);
+    let planned = vec![planned];
+    if !confirm(writer, OperationAction::Rollback, &planned, skip_confirm)? {
+        return Ok(Vec::new());
+    }
+    execute_plans(&engine, planned, skip_confirm, writer).await
+}
+
+/// Apply the confirmation gate to every plan and prompt once if needed.
+/// Returns `false` when the user declined.
+fn confirm(
+    writer: &OutputWriter,
+    action: OperationAction,
+    planned: &[(ProviderPlan, RiskAssessment)],
+    skip_confirm: bool,
+) -> Result<bool> {
+    let interactive = !writer.is_json();
+    let mut needs_prompt = false;
+    let mut high_risk = false;
+    for (plan, risk) in planned {
+        match confirmation_gate(risk, skip_confirm, interactive) {
+            ConfirmationGate::Refuse(reason) => match plan
+                .removal_impact
+                .as_ref()
+                .filter(|impact| impact.is_blocked())
+            {
+                Some(impact) => bail!(impact.refusal()),
+                None => bail!(reason),
+            },
+            ConfirmationGate::Prompt => needs_prompt = true,
+            ConfirmationGate::Proceed => {}
+        }
+        high_risk |= risk.requires_explicit_confirmation;
+    }
+    if needs_prompt {
+        let question = if high_risk {
+            format!(
+                "\n{} this {} plan? [y/N] ",
+                action_verb(action),
+                style("high-risk").red().bold()
+            )
+        } else {
+            format!("\n{} as planned? [y/N] ", action_verb(action))
+        };
+        print!("{}", question);
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("y") {
+            writer.message("Transaction cancelled");
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+async fn execute_plans(
+    engine: &TransactionEngine,
+    planned: Vec<(ProviderPlan, RiskAssessment)>,
+    skip_confirm: bool,
+    writer: &OutputWriter,
+) -> Result<Vec<TransactionReport>> {
+    let mut reports = Vec::new();
+    for (plan, risk) in planned {
+        let result = execute_plan(engine, &plan, writer).await;
+        let stale_code = match &result {
+            Err(error)
+                if matches!(
+                    error.code,
+                    ProviderErrorCode::PlanExpired | ProviderErrorCode::PlanChanged
+                ) =>
+            {
+                error.code
+            }
+            _ => {
+                reports.push(report_for(engine, writer, plan, risk, None, result).await);
+                continue;
+            }
+        };
+        // The plan went stale while the user was reading it. Re-plan and ask
+        // only about what moved instead of failing the whole command.
+        let stale = match engine
+            .replan_stale(&plan, stale_code, RequestedBy::Cli)
+            .await
+        {
+            Ok(stale) => stale,
+            Err(error) => {
+                reports.push(report_for(engine, writer, plan, risk, None, Err(error)).await);
+                continue;
+            }
+        };
+        print_stale(writer, &stale);
+        let approved = if stale.needs_approval() {
+            confirm(
+                writer,
+                stale.fresh.action,
+                &[(stale.fresh.clone(), stale.risk.clone())],
+                skip_confirm,
+            )?
+        } else {
+            true
+        };
+        if !approved {
+            reports.push(report_for(engine, writer, plan, risk, None, result).await);
+            continue;
+        }
+        let result = execute_plan(engine, &stale.fresh, writer).await;
+        let mut report = report_for(engine, writer, stale.fresh, stale.risk, None, result).await;
+        report.stale_diff = Some(stale.diff);
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+async fn execute_plan(
+    engine: &TransactionEngine,
+    plan: &ProviderPlan,
+    writer: &OutputWriter,
+) -> Result<VerificationReceipt, ProviderError> {
+    let message = format!(
+        "{} {} package{} with {}...",
+        action_verb(plan.action),
+        plan.targets.len(),
+        if plan.targets.len() == 1 { "" } else { "s" },
+        plan.provider.source
+    );
+    with_spinner(
+        writer,
+        &message,
+        engine.execute(plan.clone(), CancellationFlag::default()),
+    )
+    .await
+}
+
+fn print_stale(writer: &OutputWriter, stale: &StalePlan) {
+    if writer.is_json() || writer.is_quiet() {
+        return;
+    }
+    let reason = if stale.reason == ProviderErrorCode::PlanExpired {
+        "expired"
+    } else {
+        "was overtaken by a package change"
+    };
+    writer.warning(&format!(
+        "The reviewed {} plan {}; re-planned against the current inventory",
+        stale.fresh.provider.source, reason
+    ));
+    if !stale.needs_approval() {
+        writer.message("Nothing you approved moved; continuing with the refreshed plan");
+        return;
+    }
+    let action = stale.fresh.action;
+    println!("  {}", style("Changes since review:").bold());
+    for change in &stale.diff.added {
+        println!(
+            "    {} {} {}",
+            style("+").green(),
+            style(&change.name).cyan(),
+            describe_change(action, change)
+        );
+    }
+    for change in &stale.diff.removed {
+        println!(
+            "    {} {} no longer changes",
+            style("-").red(),
+            style(&change.name).cyan()
+        );
+    }
+    for revision in &stale.diff.reversioned {
+        println!(
+            "    {} {} {} (was {})",
+            style("~").yellow(),
+            style(&revision.fresh.name).cyan(),
+            describe_change(action, &revision.fresh),
+            describe_change(action, &revision.reviewed)
+        );
+    }
+    if stale.risk.level > stale.reviewed_risk {
+        println!(
+            "  {:10} raised from {:?} to {:?}",
+            style("Risk:").bold(),
+            stale.reviewed_risk,
+            stale.risk.level
+        );
+    }
+}
+
+async fn execute_bundle(
+    engine: &TransactionEngine,
+    bundle: TransactionBundle,
+    planned: Vec<(ProviderPlan, RiskAssessment)>,
+    writer: &OutputWriter,
+) -> Result<Vec<TransactionReport>> {
+    let bundle_id = bundle.id.clone();
+    let message = format!(
+        "{} {} packages across {} providers...",
+        action_verb(bundle.action),
+        bundle.target_count(),
+        bundle.plans.len()
+    );
+    let summary = with_spinner(
+        writer,
+        &message,
+        engine.execute_bundle(bundle, CancellationFlag::default()),
+    )
+    .await
+    .map_err(|error| anyhow::anyhow!(error.to_string()))?;
+
+    let mut reports = Vec::new();
+    for (plan, risk) in planned {
+        let result = if let Some(receipt) = summary
+            .receipts
+            .iter()
+            .find(|receipt| receipt.operation_id == plan.operation_id)
+        {
+            Ok(receipt.clone())
+        } else if let Some(error) = summary
+            .failed
+            .iter()
+            .find(|error| error.provider == Some(plan.provider.source))
+        {
+            Err(error.clone())
+        } else {
+            Err(ProviderError::protocol(
+                Some(plan.provider.source),
+                "Skipped after an earlier provider in the bundle stopped",
+            ))
+        };
+        reports.push(report_for(engine, writer, plan, risk, Some(bundle_id.clone()), result).await);
+    }
+    Ok(reports)
+}
+
+async fn report_for(
+    engine: &TransactionEngine,
+    writer: &OutputWriter,
+    plan: ProviderPlan,
+    risk: RiskAssessment,
+    bundle_id: Option<String>,
+    result: Result<VerificationReceipt, ProviderError>,
+) -> TransactionReport {
+    let snapshot = engine.snapshot(&plan.operation_id).await;
+    if let Some(snapshot) = &snapshot {
+        print_snapshot(writer, snapshot);
+    }
+    match result {
+        Ok(receipt) => {
+            print_receipt(writer, &receipt);
+            TransactionReport {
+                bundle_id,
+                plan,
+                risk,
+                receipt: Some(receipt),
+                error: None,
+                stale_diff: None,
+                snapshot,
+            }
+        }
+        Err(error) => {
+            writer.error(&format!("{}: {}", plan.provider.source, error));
+            TransactionReport {
+                bundle_id,
+                plan,
+                risk,
+                receipt: None,
+                error: Some(error),
+                stale_diff: None,
+                snapshot,
+            }
+        }
+    }
+}
+
+/// Emit JSON reports and turn failed or unverified transactions into an error.
+pub fn finish(reports: &[TransactionReport], writer: &OutputWriter) -> Result<()> {
+    if writer.is_json() {
+        #[derive(Serialize)]
+        struct TransactionsOutput<'a> {
+            transactions: &'a [TransactionReport],
+        }
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&TransactionsOutput {
+                transactions: reports
+            })?
+        );
+    }
+    let failed = reports
+        .iter()
+        .filter(|report| {
+            report.error.is_some()
+                || report
+                    .receipt
+                    .as_ref()
+                    .is_some_and(|receipt| receipt.outcome == VerificationOutcome::Mismatch)
+        })
+        .count();
+    if failed > 0 {
+        bail!(
+            "{} of {} provider transaction{} did not complete as planned",
+            failed,
+            reports.len(),
+            if reports.len() == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
+async fn with_spinner<T>(
+    writer: &OutputWriter,
+    message: &str,
+    future: impl std::future::Future<Output = T>,
+) -> T {
+    let spinner = if !writer.is_quiet() && !writer.is_json() {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.cyan} {msg}")
+                .expect("hardcoded progress template should be valid"),
+        );
+        pb.set_message(message.to_string());
+        pb.enable_steady_tick(std::time::Duration::from_millis(80));
+        Some(pb)
+    } else {
+        None
+    };
+    // Hook output is printed above the spinner; quiet and JSON runs drop it.
+    let (lines_tx, mut lines_rx) = mpsc::channel::<StreamLine>(64);
+    let printer = {
+        let spinner = spinner.clone();
+        let show = !writer.is_quiet() && !writer.is_json();
+        tokio::spawn(async move {
+            while let Some(line) = lines_rx.recv().await {
+                let (StreamLine::Stdout(line) | StreamLine::Stderr(line)) = line;
+                match &spinner {
+                    Some(pb) => pb.println(line),
+                    None if show => eprintln!("{}", line),
+                    None => {}
+                }
+            }
+        })
+    };
+    let result = with_log_lines(lines_tx, future).await;
+    let _ = printer.await;
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    result
+}
+
+fn print_plan(writer: &OutputWriter, plan: &ProviderPlan, risk: &RiskAssessment) {
+    if writer.is_json() || writer.is_quiet() {
+        return;
+    }
+    writer.header(&format!(
+        "{} plan: {} ({:?} provider, {:?} fidelity)",
+        action_verb(plan.action),
+        plan.provider.source,
+        plan.provider.tier,
+        plan.provider.fidelity
+    ));
+    let level = match risk.level {
+        RiskLevel::Low => style(format!("{:?}", risk.level)).green(),
+        RiskLevel::Caution => style(format!("{:?}", risk.level)).yellow(),
+        RiskLevel::High | RiskLevel::Blocked => style(format!("{:?}", risk.level)).red().bold(),
+    };
+    if risk.reasons.is_empty() {
+        println!("  {:10} {}", style("Risk:").bold(), level);
+    } else {
+        let reasons: Vec<String> = risk
+            .reasons
+            .iter()
+            .map(|reason| format!("{:?}", reason))
+            .collect();
+        println!(
+            "  {:10} {} ({})",
+            style("Risk:").bold(),
+            level,
+            reasons.join(", ")
+        );
+    }
+    println!("  {}", style("Expected changes:").bold());
+    for change in &plan.expected_changes {
+        let versions = describe_change(plan.action, change);
+        println!("    {} {}", style(&change.name).cyan(), versions);
+    }
+    if let Some(impact) = &plan.removal_impact {
+        print_removal_impact(writer, impact);
+    }
+    for command in &plan.exact_commands {
+        writer.verbose(&format!("{} {}", command.program, command.args.join(" ")));
+    }
+}
+
+fn print_removal_impact(writer: &OutputWriter, impact: &RemovalImpact) {
+    if !impact.protected.is_empty() {
+        println!("  {}", style("Protected packages:").red().bold());
+        for package in &impact.protected {
+            println!(
+                "    {} ({})",
+                style(&package.name).red(),
+                package.reason.label()
+            );
+        }
+    }
+    if !impact.dependents.is_empty() {
+        println!("  {}", style("Breaks installed dependents:").bold());
+        for dependent in &impact.dependents {
+            let chain = impact.chain(&dependent.name);
+            println!(
+                "    {} (needs {})",
+                style(&dependent.name).yellow(),
+                chain[1..].join(" → ")
+            );
+        }
+    }
+    if !impact.auto_removed.is_empty() {
+        println!("  {}", style("No longer needed:").bold());
+        for name in &impact.auto_removed {
+            println!("    {}", style(name).dim());
+        }
+    }
+    if impact.incomplete {
+        writer
+            .warning("Dependency analysis is incomplete; the provider did not answer every query");
+    }
+}
+
+fn print_snapshot(writer: &OutputWriter, snapshot: &FilesystemSnapshot) {
+    writer.message(&format!(
+        "Snapshot {} taken before the change; restore with: {}",
+        snapshot.label(),
+        snapshot.restore_hint()
+    ));
+}
+
+fn print_receipt(writer: &OutputWriter, receipt: &VerificationReceipt) {
+    let summary = format!(
+        "{} verification {:?} ({} of {} expected change{} observed)",
+        receipt.provider,
+        receipt.outcome,
+        receipt
+            .observed
+            .iter()
+            .filter(|observed| receipt.expected.iter().any(|expected| {
+                expected.name == observed.name && expected.after == observed.after
+            }))
+            .count(),
+        receipt.expected.len(),
+        if receipt.expected.len() == 1 { "" } else { "s" }
+    );
+    match receipt.outcome {
+        VerificationOutcome::Verified => writer.success(&summary),
+        VerificationOutcome::Inconclusive | VerificationOutcome::Mismatch => {
+            writer.warning(&summary)
+        }
+    }
+    for warning in &receipt.warnings {
+        writer.warning(warning);
+    }
+    if receipt.outcome != VerificationOutcome::Mismatch
+        && ProviderDescriptor::for_source(receipt.provider).rollback
+            == RollbackCapability::ProviderManaged
+    {
+        writer.verbose(&format!(
+            "Undo with: linget rollback {}",
+            receipt.operation_id
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::transaction::RiskReason;
+
+    fn risk(level: RiskLevel) -> RiskAssessment {
+        RiskAssessment {
+            level,
+            reasons: vec![RiskReason::RemovesPackages],
+            requires_explicit_confirmation: matches!(level, RiskLevel::High | RiskLevel::Blocked),
+        }
+    }
+
+    #[test]
+    fn blocked_plans_are_refused_even_with_yes() {
+        assert!(matches!(
+            confirmation_gate(&risk(RiskLevel::Blocked), true, true),
+            ConfirmationGate::Refuse(_)
+        ));
+    }
+
+    #[test]
+    fn high_risk_json_runs_need_explicit_yes() {
+        assert!(matches!(
+            confirmation_gate(&risk(RiskLevel::High), false, false),
+            ConfirmationGate::Refuse(_)
+        ));
+        assert_eq!(
+            confirmation_gate(&risk(RiskLevel::High), true, false),
+            ConfirmationGate::Proceed
+        );
+        assert_eq!(
+            confirmation_gate(&risk(RiskLevel::Caution), false, false),
+            ConfirmationGate::Proceed
+        );
+    }
+
+    #[test]
+    fn interactive_runs_prompt_unless_skipped() {
+        assert_eq!(
+            confirmation_gate(&risk(RiskLevel::Low), false, true),
+            ConfirmationGate::Prompt
+        );
+        assert_eq!(
+            confirmation_gate(&risk(RiskLevel::High), true, true),
+            ConfirmationGate::Proceed
+        );
+    }
+}
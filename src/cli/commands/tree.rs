@@ -0,0 +1,113 @@
+use super::why::dependency_source;
+use crate::backend::{DependencyNode, PackageManager};
+use crate::cli::OutputWriter;
+use crate::models::PackageSource;
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Serialize)]
+struct TreeOutput {
+    source: String,
+    root: DependencyNode,
+    incomplete: bool,
+}
+
+pub async fn run(
+    pm: Arc<RwLock<PackageManager>>,
+    package: &str,
+    source: Option<PackageSource>,
+    depth: usize,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let manager = pm.read().await;
+    let source = match source {
+        Some(source) => source,
+        None => dependency_source(&manager, package).await?,
+    };
+    let tree = manager.dependency_tree(source, package, depth).await?;
+    drop(manager);
+
+    if writer.is_json() {
+        let output = TreeOutput {
+            source: format!("{:?}", source).to_lowercase(),
+            root: tree.root,
+            incomplete: tree.incomplete,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    println!("{} ({})", style(&tree.root.name).cyan().bold(), source);
+    for line in render_tree(&tree.root) {
+        println!("{}", line);
+    }
+    if tree.incomplete {
+        writer.warning(
+            "The tree was cut short by --depth or a failed provider query; it may be incomplete",
+        );
+    }
+    Ok(())
+}
+
+/// The lines below `root`, drawn with box-drawing guides. Repeated packages
+/// are marked instead of expanded again.
+pub fn render_tree(root: &DependencyNode) -> Vec<String> {
+    let mut lines = Vec::new();
+    render_children(root, "", &mut lines);
+    lines
+}
+
+fn render_children(node: &DependencyNode, prefix: &str, lines: &mut Vec<String>) {
+    let count = node.dependencies.len();
+    for (index, child) in node.dependencies.iter().enumerate() {
+        let last = index + 1 == count;
+        let marker = if child.repeated { " (see above)" } else { "" };
+        lines.push(format!(
+            "{}{}{}{}",
+            prefix,
+            if last { "└── " } else { "├── " },
+            child.name,
+            marker
+        ));
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_children(child, &child_prefix, lines);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, dependencies: Vec<DependencyNode>) -> DependencyNode {
+        DependencyNode {
+            name: name.to_string(),
+            repeated: false,
+            dependencies,
+        }
+    }
+
+    #[test]
+    fn trees_render_with_guides_and_repeat_markers() {
+        let mut repeated = node("libc", Vec::new());
+        repeated.repeated = true;
+        let root = node(
+            "git",
+            vec![
+                node("curl", vec![node("libc", Vec::new())]),
+                node("perl", vec![repeated]),
+            ],
+        );
+        assert_eq!(
+            render_tree(&root),
+            [
+                "├── curl",
+                "│   └── libc",
+                "└── perl",
+                "    └── libc (see above)",
+            ]
+        );
+    }
+}
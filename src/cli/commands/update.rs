@@ -1,10 +1,11 @@
+use super::transaction;
+use crate::backend::transaction::{OperationAction, PackageRef};
 use crate::backend::PackageManager;
 use crate::cli::OutputWriter;
-use crate::models::PackageSource;
+use crate::models::{Package, PackageSource, UpdatePolicy, UpdateSightings, Verdict};
 use anyhow::{bail, Result};
-use console::style;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use std::io::{self, Write};
+use chrono::Utc;
+use indicatif::{ProgressBar, ProgressStyle};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -13,6 +14,7 @@ pub async fn run(
     package_name: Option<&str>,
     source: Option<PackageSource>,
     update_all: bool,
+    use_policy: bool,
     skip_confirm: bool,
     writer: &OutputWriter,
 ) -> Result<()> {
@@ -50,93 +52,48 @@ pub async fn run(
             return Ok(());
         }
 
-        // Show what will be updated
-        if !writer.is_json() && !writer.is_quiet() {
-            writer.header(&format!("Updates Available ({})", updates.len()));
-            for pkg in &updates {
-                let version_info = if let Some(ref avail) = pkg.available_version {
-                    format!("{} → {}", pkg.version, avail)
-                } else {
-                    pkg.version.clone()
-                };
-                println!(
-                    "  {} ({:?}) {}",
-                    style(&pkg.name).cyan(),
-                    pkg.source,
-                    version_info
-                );
-            }
-        }
-
-        // Confirm
-        if !skip_confirm && !writer.is_json() {
-            print!("\nUpdate all {} packages? [y/N] ", updates.len());
-            io::stdout().flush()?;
-
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                writer.message("Update cancelled");
-                return Ok(());
-            }
-        }
-
-        // Update each package
-        let multi = if !writer.is_quiet() && !writer.is_json() {
-            Some(MultiProgress::new())
+        // Updates the policy wants reviewed go to the confirmation prompt;
+        // runs that would not show one hold them back instead.
+        let reviewable = !skip_confirm && !writer.is_json();
+        let (updates, review) = if use_policy {
+            apply_policy(updates, reviewable, writer)?
         } else {
-            None
+            (
+                updates.iter().map(PackageRef::from_package).collect(),
+                Vec::new(),
+            )
         };
-
-        let mut success_count = 0;
-        let mut fail_count = 0;
-
-        for pkg in &updates {
-            let spinner = multi.as_ref().map(|m| {
-                let pb = m.add(ProgressBar::new_spinner());
-                pb.set_style(
-                    ProgressStyle::default_spinner()
-                        .template("{spinner:.cyan} {msg}")
-                        .unwrap(),
-                );
-                pb.set_message(format!("Updating {}...", pkg.name));
-                pb.enable_steady_tick(std::time::Duration::from_millis(80));
-                pb
-            });
-
-            match manager.update(pkg).await {
-                Ok(_) => {
-                    if let Some(pb) = spinner {
-                        pb.finish_with_message(format!(
-                            "{} {} updated",
-                            style("✓").green(),
-                            pkg.name
-                        ));
-                    }
-                    success_count += 1;
-                }
-                Err(e) => {
-                    if let Some(pb) = spinner {
-                        pb.finish_with_message(format!(
-                            "{} {} failed: {}",
-                            style("✗").red(),
-                            pkg.name,
-                            e
-                        ));
-                    }
-                    fail_count += 1;
-                }
-            }
+        if use_policy && updates.is_empty() && review.is_empty() {
+            writer.success("The update policy clears no updates to apply now");
+            return Ok(());
         }
 
-        if fail_count == 0 {
-            writer.success(&format!("Successfully updated {} packages", success_count));
-        } else {
-            writer.warning(&format!(
-                "Updated {} packages, {} failed",
-                success_count, fail_count
-            ));
+        drop(manager);
+        let mut reports = Vec::new();
+        if !updates.is_empty() {
+            reports = transaction::review_and_execute_targets(
+                pm.clone(),
+                OperationAction::Update,
+                updates,
+                skip_confirm,
+                writer,
+            )
+            .await?;
         }
+        if !review.is_empty() {
+            writer.header("Updates the Policy Asks You to Review");
+            reports.extend(
+                transaction::review_and_execute_targets(
+                    pm,
+                    OperationAction::Update,
+                    review,
+                    false,
+                    writer,
+                )
+                .await?,
+            );
+        }
+        transaction::finish(&reports, writer)?;
     } else if let Some(name) = package_name {
         // Update specific package
         let installed = manager.list_all_installed().await?;
@@ -164,53 +121,81 @@ pub async fn run(
                 .unwrap_or_else(|| candidates.remove(0))
         };
 
-        // Confirm
-        if !skip_confirm && !writer.is_json() {
-            print!(
-                "\nUpdate {} ({:?})? [y/N] ",
-                style(&package.name).cyan(),
-                package.source
-            );
-            io::stdout().flush()?;
-
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                writer.message("Update cancelled");
-                return Ok(());
-            }
-        }
-
-        let spinner = if !writer.is_quiet() && !writer.is_json() {
-            let pb = ProgressBar::new_spinner();
-            pb.set_style(
-                ProgressStyle::default_spinner()
-                    .template("{spinner:.cyan} {msg}")
-                    .expect("hardcoded progress template should be valid"),
-            );
-            pb.set_message(format!("Updating {}...", package.name));
-            pb.enable_steady_tick(std::time::Duration::from_millis(80));
-            Some(pb)
-        } else {
-            None
+        // Carry the provider's target version into the plan so the receipt
+        // can prove the update instead of reporting it as inconclusive.
+        let package = match manager.check_updates_for_source(package.source).await {
+            Ok(updates) => updates
+                .into_iter()
+                .find(|update| update.name == package.name)
+                .unwrap_or(package),
+            Err(_) => package,
         };
 
-        let result = manager.update(&package).await;
+        drop(manager);
+        let reports = transaction::review_and_execute(
+            pm,
+            OperationAction::Update,
+            std::slice::from_ref(&package),
+            skip_confirm,
+            writer,
+        )
+        .await?;
+        transaction::finish(&reports, writer)?;
+    }
 
-        if let Some(pb) = spinner {
-            pb.finish_and_clear();
-        }
+    Ok(())
+}
 
-        match result {
-            Ok(_) => {
-                writer.success(&format!("Successfully updated {}", package.name));
-            }
-            Err(e) => {
-                writer.anyhow_error(&e);
-                return Err(e);
-            }
-        }
+/// Split `updates` into the ones the update policy lets apply unattended and
+/// the ones it wants reviewed, listing the ones it holds back. Updates that
+/// need review are held back too unless `reviewable`. Updates under a
+/// release cooldown are pinned to the release it judged.
+fn apply_policy(
+    updates: Vec<Package>,
+    reviewable: bool,
+    writer: &OutputWriter,
+) -> Result<(Vec<PackageRef>, Vec<PackageRef>)> {
+    let Some(policy) = UpdatePolicy::load_default()? else {
+        bail!(
+            "No update policy at {}",
+            UpdatePolicy::default_path().display()
+        );
+    };
+    let mut sightings = UpdateSightings::load();
+    let (cleared, held) = policy.partition(updates, &mut sightings, Utc::now());
+    let (review, held): (Vec<_>, Vec<_>) = held
+        .into_iter()
+        .partition(|(_, decision)| reviewable && decision.verdict == Verdict::Review);
+    let target = |package: &Package| match policy.pinned_version(package) {
+        Some(version) => PackageRef::pinned(package, version),
+        None => PackageRef::from_package(package),
+    };
+    let cleared = cleared.iter().map(target).collect();
+    let review = review.iter().map(|(package, _)| target(package)).collect();
+    if let Err(error) = sightings.save() {
+        writer.warning(&format!(
+            "Could not record when updates were seen: {:#}",
+            error
+        ));
     }
 
-    Ok(())
+    if !held.is_empty() {
+        writer.header("Held Back by Policy");
+        for (package, decision) in &held {
+            let rule = decision
+                .rule
+                .as_ref()
+                .map_or_else(|| "no rule matched".to_string(), ToString::to_string);
+            writer.message(&format!(
+                "  • {} ({}) {}: {}, {}",
+                package.name,
+                package.source,
+                package.display_version(),
+                decision.verdict,
+                rule
+            ));
+        }
+        writer.message("Run 'linget policy explain <package>' for the details of a decision.");
+    }
+    Ok((cleared, review))
 }
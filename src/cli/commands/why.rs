@@ -0,0 +1,101 @@
+use super::hold::single_source;
+use crate::backend::{BackendCapability, PackageManager};
+use crate::cli::OutputWriter;
+use crate::models::PackageSource;
+use anyhow::Result;
+use console::style;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[derive(Debug, Serialize)]
+struct WhyOutput {
+    name: String,
+    source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    explicit: Option<bool>,
+    chains: Vec<Vec<String>>,
+    incomplete: bool,
+}
+
+pub async fn run(
+    pm: Arc<RwLock<PackageManager>>,
+    package: &str,
+    source: Option<PackageSource>,
+    writer: &OutputWriter,
+) -> Result<()> {
+    let manager = pm.read().await;
+    let source = match source {
+        Some(source) => source,
+        None => dependency_source(&manager, package).await?,
+    };
+    let why = manager.why_installed(source, package).await?;
+    drop(manager);
+
+    if writer.is_json() {
+        let output = WhyOutput {
+            name: why.name,
+            source: format!("{:?}", source).to_lowercase(),
+            explicit: why.explicit,
+            chains: why.chains,
+            incomplete: why.incomplete,
+        };
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if why.explicit == Some(true) {
+        writer.message(&format!(
+            "{} ({}) was installed explicitly",
+            style(&why.name).cyan(),
+            source
+        ));
+    }
+    if why.chains.is_empty() {
+        if why.explicit != Some(true) {
+            writer.message(&format!(
+                "Nothing installed from {} depends on {}",
+                source,
+                style(&why.name).cyan()
+            ));
+        }
+    } else {
+        writer.header(&format!("{} is required by", why.name));
+        for chain in &why.chains {
+            let (root, rest) = chain.split_first().expect("chains are never empty");
+            println!("  {} → {}", style(root).bold(), rest.join(" → "));
+        }
+    }
+    if why.incomplete {
+        writer.warning("The provider did not answer every query; the list may be incomplete");
+    }
+    Ok(())
+}
+
+/// The one source that can answer dependency queries for an installed
+/// `package`.
+pub(super) async fn dependency_source(
+    manager: &PackageManager,
+    package: &str,
+) -> Result<PackageSource> {
+    let sources = manager
+        .list_all_installed()
+        .await?
+        .into_iter()
+        .filter(|installed| {
+            installed.name == package
+                && manager
+                    .source_capability_status(installed.source, BackendCapability::DependencyTree)
+                    .is_supported()
+        })
+        .map(|installed| installed.source)
+        .collect();
+    single_source(
+        package,
+        sources,
+        &format!(
+            "Package '{}' is not installed from a source that can report dependencies",
+            package
+        ),
+    )
+}
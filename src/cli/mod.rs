@@ -4,10 +4,13 @@ pub mod tui;
 pub mod tui_next;
 
 use crate::backend::PackageManager;
-use crate::models::PackageSource;
+use crate::models::calendar::MaintenanceWindow;
+use crate::models::scheduler::RunConditions;
+use crate::models::{PackageSource, PluginId, ScheduledOperation};
 use crate::product::{APP_NAME, APP_VERSION};
 use anyhow::Context;
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::builder::{PossibleValue, TypedValueParser, ValueParserFactory};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -100,6 +103,20 @@ pub enum Commands {
         #[arg(short, long)]
         all: bool,
 
+        /// Only apply the updates the update policy lets apply unattended
+        #[arg(long, conflicts_with = "package")]
+        policy: bool,
+
+        /// Skip confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Reverse a finished operation using its verification receipt
+    Rollback {
+        /// Operation ID from the transaction's JSON report
+        operation_id: String,
+
         /// Skip confirmation
         #[arg(short, long)]
         yes: bool,
@@ -115,6 +132,30 @@ pub enum Commands {
         source: Option<SourceArg>,
     },
 
+    /// Show the chains of installed packages that pull a package in
+    Why {
+        /// Package name
+        package: String,
+
+        /// Package source (optional when the package is installed from one source)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+    },
+
+    /// Show the dependency tree of an installed package
+    Tree {
+        /// Package name
+        package: String,
+
+        /// Package source (optional when the package is installed from one source)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+
+        /// How many levels of dependencies to show
+        #[arg(short, long, default_value_t = 6)]
+        depth: usize,
+    },
+
     /// Manage package sources
     Sources {
         #[command(subcommand)]
@@ -183,12 +224,44 @@ pub enum Commands {
         port: u16,
     },
 
+    /// Hold packages at their installed version using the provider's own pinning
+    Hold {
+        #[command(subcommand)]
+        action: HoldAction,
+    },
+
     /// Manage ignored packages (excluded from update checks)
     Ignore {
         #[command(subcommand)]
         action: IgnoreAction,
     },
 
+    /// Show how this system drifts from a linget.toml manifest
+    Diff {
+        /// Manifest path
+        #[arg(default_value = crate::models::manifest::DEFAULT_MANIFEST_FILE)]
+        file: String,
+
+        /// Exit with an error when the system has drifted
+        #[arg(long)]
+        exit_code: bool,
+    },
+
+    /// Converge this system on a linget.toml manifest
+    Apply {
+        /// Manifest path
+        #[arg(default_value = crate::models::manifest::DEFAULT_MANIFEST_FILE)]
+        file: String,
+
+        /// Also remove packages the manifest does not list, from the providers it declares
+        #[arg(long)]
+        prune: bool,
+
+        /// Skip confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
     /// Backup and restore package lists
     Backup {
         #[command(subcommand)]
@@ -200,6 +273,12 @@ pub enum Commands {
         #[command(subcommand)]
         action: ScheduleAction,
     },
+
+    /// Inspect the update policy in policy.toml
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
 }
 
 #[derive(Subcommand)]
@@ -218,6 +297,32 @@ pub enum SourcesAction {
     },
 }
 
+#[derive(Subcommand)]
+pub enum HoldAction {
+    /// List held packages
+    List {
+        /// Only list holds from this source
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+    },
+    /// Hold a package at its installed version
+    Add {
+        /// Package name
+        package: String,
+        /// Package source (optional when the package is installed from one source)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+    },
+    /// Release a held package
+    Remove {
+        /// Package name
+        package: String,
+        /// Package source (optional when the package is held by one source)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum IgnoreAction {
     /// List ignored packages
@@ -252,6 +357,10 @@ pub enum BackupAction {
     Restore {
         /// Backup file path
         file: String,
+        /// Install the recorded versions, substituting the nearest available
+        /// version where one is gone
+        #[arg(long)]
+        exact: bool,
         /// Skip confirmation prompts
         #[arg(short, long)]
         yes: bool,
@@ -260,11 +369,108 @@ pub enum BackupAction {
 
 #[derive(Subcommand)]
 pub enum ScheduleAction {
+    /// Schedule a one-off operation on a package
+    Add {
+        /// Package name
+        package: String,
+        /// Operation to run
+        #[arg(long, value_enum, default_value = "update")]
+        op: ScheduleOp,
+        /// When to run: local "YYYY-MM-DD HH:MM", an RFC 3339 timestamp, or
+        /// "HH:MM" for its next occurrence
+        #[arg(long)]
+        at: String,
+        /// Package source (optional when the package is found in one source)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+        #[command(flatten)]
+        conditions: ConditionArgs,
+    },
+    /// List pending tasks and recurring rules
+    List {
+        /// Include finished tasks
+        #[arg(long)]
+        all: bool,
+    },
+    /// Cancel a pending task or delete a recurring rule
+    Cancel {
+        /// Task or rule ID (a unique prefix is enough)
+        id: String,
+    },
+    /// Show finished tasks and recurring rule runs, newest first
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// Show whether scheduled work runs from the systemd timer or only while LinGet is open
+    Status,
     /// Run every scheduled task that is due right now
     RunDue,
 }
 
+#[derive(Subcommand)]
+pub enum PolicyAction {
+    /// Show which policy rule decides a package's pending update, and why
+    Explain {
+        /// Package name
+        package: String,
+        /// Package source (optional, for disambiguation)
+        #[arg(short, long)]
+        source: Option<SourceArg>,
+    },
+}
+
+/// Conditions a scheduled task waits for once it is due.
+#[derive(Args, Debug, Default)]
+pub struct ConditionArgs {
+    /// Only run on AC power
+    #[arg(long)]
+    on_ac: bool,
+    /// Not while NetworkManager reports a metered connection
+    #[arg(long)]
+    not_metered: bool,
+    /// Only run once the session has been idle this many minutes
+    #[arg(long, value_name = "MINUTES")]
+    idle: Option<u32>,
+    /// Not while the focused window is fullscreen
+    #[arg(long)]
+    no_fullscreen: bool,
+    /// Only run inside a local time window such as "22:00-06:00" or "Sat,Sun 01:00-05:00"
+    #[arg(long, value_parser = MaintenanceWindow::parse)]
+    window: Option<MaintenanceWindow>,
+}
+
+impl From<ConditionArgs> for RunConditions {
+    fn from(args: ConditionArgs) -> Self {
+        Self {
+            on_ac_power: args.on_ac,
+            not_metered: args.not_metered,
+            idle_minutes: args.idle,
+            no_fullscreen: args.no_fullscreen,
+            window: args.window,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ScheduleOp {
+    Update,
+    Install,
+    Remove,
+}
+
+impl From<ScheduleOp> for ScheduledOperation {
+    fn from(op: ScheduleOp) -> Self {
+        match op {
+            ScheduleOp::Update => ScheduledOperation::Update,
+            ScheduleOp::Install => ScheduledOperation::Install,
+            ScheduleOp::Remove => ScheduledOperation::Remove,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum SourceArg {
     Apt,
     Dnf,
@@ -281,6 +487,101 @@ pub enum SourceArg {
     Conda,
     Mamba,
     Dart,
+    /// A provider plugin, written `plugin:<name>`
+    Plugin(PluginId),
+}
+
+impl SourceArg {
+    const BUILT_IN: [SourceArg; 15] = [
+        SourceArg::Apt,
+        SourceArg::Dnf,
+        SourceArg::Pacman,
+        SourceArg::Zypper,
+        SourceArg::Flatpak,
+        SourceArg::Snap,
+        SourceArg::Npm,
+        SourceArg::Pip,
+        SourceArg::Pipx,
+        SourceArg::Cargo,
+        SourceArg::Brew,
+        SourceArg::Aur,
+        SourceArg::Conda,
+        SourceArg::Mamba,
+        SourceArg::Dart,
+    ];
+}
+
+/// Parses a built-in source name or `plugin:<name>`. Plugins are found at
+/// run time, so they cannot be listed as clap values.
+#[derive(Clone)]
+pub struct SourceArgParser;
+
+impl TypedValueParser for SourceArgParser {
+    type Value = SourceArg;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<SourceArg, clap::Error> {
+        let value = value.to_string_lossy().to_ascii_lowercase();
+        if let Some(source) = SourceArg::BUILT_IN
+            .into_iter()
+            .find(|source| source.to_string() == value)
+        {
+            return Ok(source);
+        }
+        match value
+            .strip_prefix("plugin:")
+            .filter(|name| !name.is_empty())
+            .and_then(PluginId::intern)
+        {
+            Some(id) => Ok(SourceArg::Plugin(id)),
+            None => {
+                let mut error =
+                    clap::Error::new(clap::error::ErrorKind::InvalidValue).with_cmd(cmd);
+                if let Some(arg) = arg {
+                    error.insert(
+                        clap::error::ContextKind::InvalidArg,
+                        clap::error::ContextValue::String(arg.to_string()),
+                    );
+                }
+                error.insert(
+                    clap::error::ContextKind::InvalidValue,
+                    clap::error::ContextValue::String(value),
+                );
+                error.insert(
+                    clap::error::ContextKind::ValidValue,
+                    clap::error::ContextValue::Strings(
+                        self.possible_values()
+                            .into_iter()
+                            .flatten()
+                            .map(|value| value.get_name().to_string())
+                            .collect(),
+                    ),
+                );
+                Err(error)
+            }
+        }
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(
+            SourceArg::BUILT_IN
+                .into_iter()
+                .map(|source| PossibleValue::new(PackageSource::from(source).as_config_str()))
+                .chain([PossibleValue::new("plugin:<name>")]),
+        ))
+    }
+}
+
+impl ValueParserFactory for SourceArg {
+    type Parser = SourceArgParser;
+
+    fn value_parser() -> SourceArgParser {
+        SourceArgParser
+    }
 }
 
 impl From<SourceArg> for PackageSource {
@@ -301,6 +602,7 @@ impl From<SourceArg> for PackageSource {
             SourceArg::Conda => PackageSource::Conda,
             SourceArg::Mamba => PackageSource::Mamba,
             SourceArg::Dart => PackageSource::Dart,
+            SourceArg::Plugin(id) => PackageSource::Plugin(id),
         }
     }
 }
@@ -323,6 +625,7 @@ impl std::fmt::Display for SourceArg {
             SourceArg::Conda => "conda",
             SourceArg::Mamba => "mamba",
             SourceArg::Dart => "dart",
+            SourceArg::Plugin(id) => return write!(f, "plugin:{}", id.name()),
         };
         write!(f, "{}", s)
     }
@@ -378,6 +681,7 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
             package,
             source,
             all,
+            policy,
             yes,
         } => {
             commands::update::run(
@@ -385,6 +689,7 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
                 package.as_deref(),
                 source.map(Into::into),
                 all,
+                policy,
                 yes,
                 &writer,
             )
@@ -393,6 +698,14 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         Commands::Info { package, source } => {
             commands::info::run(pm, &package, source.map(Into::into), &writer).await
         }
+        Commands::Why { package, source } => {
+            commands::why::run(pm, &package, source.map(Into::into), &writer).await
+        }
+        Commands::Tree {
+            package,
+            source,
+            depth,
+        } => commands::tree::run(pm, &package, source.map(Into::into), depth, &writer).await,
         Commands::Sources { action } => commands::sources::run(pm, action, &writer).await,
         Commands::Check => commands::check::run(pm, &writer).await,
         Commands::Providers { all } => commands::providers::run(&writer, all).await,
@@ -431,8 +744,19 @@ pub async fn run(cli: Cli) -> anyhow::Result<()> {
         Commands::Web { .. } => {
             unreachable!("Web command should be handled in main.rs")
         }
+        Commands::Rollback { operation_id, yes } => {
+            commands::rollback::run(pm, &operation_id, yes, &writer).await
+        }
+        Commands::Hold { action } => commands::hold::run(pm, action, &writer).await,
         Commands::Ignore { action } => commands::ignore::run(action, &writer).await,
+        Commands::Diff { file, exit_code } => {
+            commands::manifest::diff(pm, &file, exit_code, &writer).await
+        }
+        Commands::Apply { file, prune, yes } => {
+            commands::manifest::apply(pm, &file, prune, yes, &writer).await
+        }
         Commands::Backup { action } => commands::backup::run(pm, action, &writer).await,
         Commands::Schedule { action } => commands::schedule::run(pm, action, &writer).await,
+        Commands::Policy { action } => commands::policy::run(pm, action, &writer).await,
     }
 }
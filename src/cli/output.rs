@@ -38,6 +38,10 @@ impl OutputWriter {
         self.quiet
     }
 
+    pub fn is_verbose(&self) -> bool {
+        self.verbose
+    }
+
     /// Get the output format
     pub fn format(&self) -> OutputFormat {
         self.format
@@ -4,11 +4,13 @@ mod input;
 use super::ui;
 use super::update_center;
 use crate::backend::transaction::{
-    OperationAction, OperationRequest, PackageRef, ProviderPlan, RequestedBy, TransactionEngine,
+    rollback_operation_id, CancellationSupport, OperationAction, OperationRequest, PackageRef,
+    ProviderDescriptor, ProviderPlan, RequestedBy, TransactionEngine,
 };
 use crate::backend::{
-    BackendCapability, HistoryTracker, PackageLoadProgress, PackageManager, SearchCatalog,
-    SearchProviderSummary, SourceCapabilityContext, TaskQueueEvent, TaskQueueExecutor,
+    request_task_cancellation, transaction_store_path, BackendCapability, DependencyTree,
+    HistoryTracker, PackageLoadProgress, PackageManager, SearchCatalog, SearchProviderSummary,
+    SourceCapabilityContext, TaskQueueEvent, TaskQueueExecutor,
 };
 use crate::cli::tui::components::layout::{compute_layout, LayoutRegions};
 use crate::cli::tui::state::filters::{DetailsTab, Filter, Focus, LayoutTier, ViewMode};
@@ -68,6 +70,8 @@ const PREFLIGHT_VERIFICATION_TIMEOUT: Duration = Duration::from_secs(45);
 /// second attempt within this window quits.
 const QUIT_CONFIRM_WINDOW: Duration = Duration::from_secs(3);
 const SEARCH_CACHE_LIMIT: usize = 5;
+/// Levels of dependencies the inspector's Deps tab shows.
+const DEPENDENCY_TREE_DEPTH: usize = 3;
 const TUI_CATALOG_CACHE_FILE: &str = "tui-catalog-cache.json";
 const TUI_CATALOG_CACHE_VERSION: u32 = 1;
 
@@ -282,12 +286,27 @@ type PackageLoadResult = Result<Vec<Package>, String>;
 pub enum LoadEvent {
     Progress(PackageLoadProgress),
     InstalledComplete(PackageLoadResult),
+    Holds(HashSet<String>),
     Complete(PackageLoadResult),
 }
 
 type SearchResult = Result<SearchCatalog, String>;
 type RepositoriesResult = Result<Vec<crate::models::Repository>, String>;
 
+/// Dependency tree of a package for the inspector's Deps tab.
+#[derive(Debug, Clone)]
+pub enum DependencyTreeState {
+    Loading,
+    Ready(DependencyTree),
+    Error(String),
+}
+
+#[derive(Debug)]
+struct DependencyTreeResult {
+    package_id: String,
+    result: Result<DependencyTree, String>,
+}
+
 #[derive(Debug, Clone)]
 pub enum ChangelogState {
     Loading,
@@ -370,6 +389,7 @@ pub enum CommandId {
     QueueRetry,
     QueueRetrySafe,
     QueueRemediate,
+    QueueRollback,
     QueueLogOlder,
     QueueLogNewer,
     ExportPackages,
@@ -609,6 +629,12 @@ const COMMAND_REGISTRY: &[CommandDefinition] = &[
         shortcut: "M",
         enabled: command_queue_remediate_enabled,
     },
+    CommandDefinition {
+        id: CommandId::QueueRollback,
+        label: "Roll back completed task",
+        shortcut: "Z",
+        enabled: command_queue_rollback_enabled,
+    },
     CommandDefinition {
         id: CommandId::QueueLogOlder,
         label: "Show older queue logs",
@@ -653,6 +679,8 @@ pub struct App {
     pub selected: HashSet<String>,
     pub favorite_packages: HashSet<String>,
     pub favorites_updates_only: bool,
+    /// Package ids pinned by their provider's native hold mechanism.
+    pub held_packages: HashSet<String>,
 
     pub filter: Filter,
     pub source: Option<PackageSource>,
@@ -739,6 +767,9 @@ pub struct App {
     next_preflight_verification_id: u64,
     active_preflight_verification_id: Option<u64>,
     changelog_cache: HashMap<String, ChangelogState>,
+    dependency_tree_rx: Option<mpsc::Receiver<DependencyTreeResult>>,
+    dependency_tree_tx: Option<mpsc::Sender<DependencyTreeResult>>,
+    dependency_trees: HashMap<String, DependencyTreeState>,
     pub refresh_after_idle: bool,
     pub cursor_anchor_id: Option<String>,
     pub last_click: Option<(u16, u16, Instant)>,
@@ -758,6 +789,7 @@ impl App {
         task_events_tx: Option<mpsc::Sender<TaskQueueEvent>>,
     ) -> Self {
         let (changelog_tx, changelog_rx) = mpsc::channel(32);
+        let (dependency_tree_tx, dependency_tree_rx) = mpsc::channel(8);
         let (preflight_verification_tx, preflight_verification_rx) = mpsc::channel(32);
         Self {
             local_packages: Vec::new(),
@@ -771,6 +803,7 @@ impl App {
             selected: HashSet::new(),
             favorite_packages: HashSet::new(),
             favorites_updates_only: false,
+            held_packages: HashSet::new(),
             filter: Filter::All,
             source: None,
             source_management: SourceManagementState::new(),
@@ -837,6 +870,9 @@ impl App {
             next_preflight_verification_id: 1,
             active_preflight_verification_id: None,
             changelog_cache: HashMap::new(),
+            dependency_tree_rx: Some(dependency_tree_rx),
+            dependency_tree_tx: Some(dependency_tree_tx),
+            dependency_trees: HashMap::new(),
             refresh_after_idle: false,
             cursor_anchor_id: None,
             last_click: None,
@@ -1045,6 +1081,15 @@ impl App {
             Ok(tracker) => {
                 let mut guard = self.history_tracker.lock().await;
                 *guard = Some(tracker);
+                drop(guard);
+                let executor =
+                    TaskQueueExecutor::new(self.pm.clone(), self.history_tracker.clone());
+                if let Err(e) = executor.recover_interrupted_transactions().await {
+                    self.set_status(
+                        format!("Could not recover interrupted transactions: {}", e),
+                        true,
+                    );
+                }
             }
             Err(e) => {
                 self.set_status(format!("History tracker unavailable: {}", e), true);
@@ -1344,7 +1389,7 @@ impl App {
                 }
                 CommandId::Refresh => "Refresh is already in progress",
                 CommandId::ToggleQueue => "Queue is empty",
-                CommandId::QueueCancel => "Select a queued or failed task in Queue",
+                CommandId::QueueCancel => "Select a queued, running or failed task in Queue",
                 CommandId::QueueRetry => "Select a failed task in Queue",
                 CommandId::QueueRetrySafe => {
                     if self.is_queue_view() && self.focus == Focus::Queue {
@@ -1358,6 +1403,9 @@ impl App {
                     }
                     "Open Queue to run the recovery bundle"
                 }
+                CommandId::QueueRollback => {
+                    "Select a completed APT, DNF or Flatpak transaction in Queue"
+                }
                 CommandId::QueueLogOlder => "No older queue logs available",
                 CommandId::QueueLogNewer => "No newer queue logs available",
                 CommandId::ExportPackages => "No packages to export",
@@ -1559,7 +1607,7 @@ impl App {
             && self.tasks.get(self.task_cursor).is_some_and(|task| {
                 matches!(
                     task.status,
-                    TaskQueueStatus::Queued | TaskQueueStatus::Failed
+                    TaskQueueStatus::Queued | TaskQueueStatus::Running | TaskQueueStatus::Failed
                 )
             })
     }
@@ -1572,6 +1620,14 @@ impl App {
                 .is_some_and(|task| task.status == TaskQueueStatus::Failed)
     }
 
+    fn can_rollback_selected_task_command(&self) -> bool {
+        self.queue_focus_active()
+            && self
+                .tasks
+                .get(self.task_cursor)
+                .is_some_and(|task| rollback_operation_id(task).is_some())
+    }
+
     fn can_retry_safe_failed_tasks_command(&self) -> bool {
         self.queue_focus_active() && self.queue_clinic_actionability().safe_retry_count > 0
     }
@@ -1957,10 +2013,7 @@ impl App {
     }
 
     fn stable_transaction_source(source: PackageSource) -> bool {
-        matches!(
-            source,
-            PackageSource::Apt | PackageSource::Flatpak | PackageSource::Npm
-        )
+        crate::backend::transaction::STABLE_PROVIDERS.contains(&source)
     }
 
     fn preflight_dependency_verification_supported(
@@ -2493,11 +2546,12 @@ impl App {
                 return;
             }
 
-            let updates = {
+            let (updates, holds) = {
                 let manager = pm.read().await;
-                manager
+                let updates = manager
                     .check_all_updates_progressive(progress_tx.clone())
-                    .await
+                    .await;
+                (updates, manager.list_all_holds().await)
             };
             drop(progress_tx);
             if let Err(join) = progress_forwarder.await {
@@ -2508,6 +2562,7 @@ impl App {
                 Ok(updates) => Ok(Self::merge_installed_with_updates(installed, updates)),
                 Err(error) => Err(error.to_string()),
             };
+            let _ = tx.send(LoadEvent::Holds(holds)).await;
             let _ = tx.send(LoadEvent::Complete(result)).await;
         });
 
@@ -2609,6 +2664,9 @@ impl App {
                         true,
                     );
                 }
+                Ok(LoadEvent::Holds(held)) => {
+                    self.held_packages = held;
+                }
                 Ok(LoadEvent::InstalledComplete(Err(error))) => {
                     self.catalog_activity = None;
                     self.set_status(format!("Package load error: {}", error), true);
@@ -2907,6 +2965,63 @@ impl App {
         }
     }
 
+    /// The Deps tab's tree for `package`, if one was requested.
+    pub fn dependency_tree_state(&self, package: &Package) -> Option<&DependencyTreeState> {
+        self.dependency_trees.get(&package.id())
+    }
+
+    /// Collect finished dependency trees, and start one for the selected
+    /// package while the Deps tab is showing. Each package is queried once
+    /// per session.
+    pub fn poll_dependency_tree(&mut self) {
+        if let Some(rx) = self.dependency_tree_rx.as_mut() {
+            while let Ok(event) = rx.try_recv() {
+                let state = match event.result {
+                    Ok(tree) => DependencyTreeState::Ready(tree),
+                    Err(error) => DependencyTreeState::Error(error),
+                };
+                self.dependency_trees.insert(event.package_id, state);
+            }
+        }
+
+        if self.active_details_tab != DetailsTab::Dependencies {
+            return;
+        }
+        let Some(package) = self.current_package() else {
+            return;
+        };
+        if !matches!(
+            package.status,
+            PackageStatus::Installed | PackageStatus::UpdateAvailable
+        ) {
+            return;
+        }
+        let package_id = package.id();
+        if self.dependency_trees.contains_key(&package_id) {
+            return;
+        }
+        let Some(sender) = self.dependency_tree_tx.clone() else {
+            return;
+        };
+        let (source, name) = (package.source, package.name.clone());
+        self.dependency_trees
+            .insert(package_id.clone(), DependencyTreeState::Loading);
+
+        let pm = self.pm.clone();
+        tokio::spawn(async move {
+            let result = {
+                let manager = pm.read().await;
+                manager
+                    .dependency_tree(source, &name, DEPENDENCY_TREE_DEPTH)
+                    .await
+            }
+            .map_err(|error| error.to_string());
+            let _ = sender
+                .send(DependencyTreeResult { package_id, result })
+                .await;
+        });
+    }
+
     fn clear_preflight_verification_tracking(&mut self) {
         self.active_preflight_verification_id = None;
     }
@@ -3042,7 +3157,7 @@ impl App {
                 .await
                 .map_err(|error| error.safe_message)?;
             if risk.level == crate::backend::transaction::RiskLevel::Blocked {
-                return Err(format!("{} provider plan is blocked", plan.provider.source));
+                return Err(plan.blocked_reason());
             }
             plans.push(plan);
         }
@@ -3081,23 +3196,23 @@ impl App {
 
         match action {
             TaskQueueAction::Remove => {
-                let target_names: HashSet<&str> = packages
-                    .iter()
-                    .map(|package| package.name.as_str())
-                    .collect();
+                let mut targets_by_source: HashMap<PackageSource, Vec<String>> = HashMap::new();
+                for package in &packages {
+                    targets_by_source
+                        .entry(package.source)
+                        .or_default()
+                        .push(package.name.clone());
+                }
                 let mut dependents: HashSet<String> = HashSet::new();
+                let mut protected: Vec<String> = Vec::new();
                 let manager = pm.read().await;
-                for package in &packages {
-                    match manager.get_reverse_dependencies(package).await {
-                        Ok(names) => {
-                            // Removing a package also removes everything that
-                            // depends on it — count those cascade removals
-                            // instead of probing and throwing the answer away.
-                            for name in names {
-                                if !target_names.contains(name.as_str()) {
-                                    dependents.insert(name);
-                                }
-                            }
+                for (source, names) in &targets_by_source {
+                    // The transitive analysis counts the whole cascade: what
+                    // depends on the targets, and what depends on those.
+                    match manager.removal_impact(*source, names).await {
+                        Ok(impact) => {
+                            dependents.extend(impact.dependents.into_iter().map(|dep| dep.name));
+                            protected.extend(impact.protected.into_iter().map(|pkg| pkg.name));
                         }
                         Err(error) => {
                             let detail = error
@@ -3111,12 +3226,25 @@ impl App {
                                 None,
                                 Some(format!(
                                     "Dependency probe failed for {} ({}): {}",
-                                    package.name, package.source, detail
+                                    names.join(", "),
+                                    source,
+                                    detail
                                 )),
                             );
                         }
                     }
                 }
+                if !protected.is_empty() {
+                    protected.sort();
+                    return (
+                        true,
+                        None,
+                        Some(format!(
+                            "Removal reaches protected packages ({}); LinGet will refuse it.",
+                            protected.join(", ")
+                        )),
+                    );
+                }
                 let dependent_count = dependents.len();
                 let impact = PreflightDependencyImpact {
                     remove_count: packages.len() + dependent_count,
@@ -3129,14 +3257,14 @@ impl App {
                 source_names.sort();
                 let note = if dependent_count > 0 {
                     format!(
-                        "Dependency impact verified via reverse dependency probes for {} ({} additional dependent{} would be removed).",
+                        "Dependency impact verified via transitive reverse dependency analysis for {} ({} additional dependent{} would be removed).",
                         source_names.join(", "),
                         dependent_count,
                         if dependent_count == 1 { "" } else { "s" }
                     )
                 } else {
                     format!(
-                        "Dependency impact verified via reverse dependency probes for {}.",
+                        "Dependency impact verified via transitive reverse dependency analysis for {}.",
                         source_names.join(", ")
                     )
                 };
@@ -3462,6 +3590,7 @@ impl App {
                 if let Some(existing) = installed.get_mut(position) {
                     existing.status = PackageStatus::UpdateAvailable;
                     existing.available_version = update.available_version.clone();
+                    existing.released_at = update.released_at;
                     if !update.version.is_empty() {
                         existing.version = update.version;
                     }
@@ -3619,6 +3748,7 @@ impl App {
                 self.mutate_package_catalogs(&entry.package_id, |package| {
                     package.status = PackageStatus::Installed;
                     package.available_version = None;
+                    package.released_at = None;
                 });
             }
         }
@@ -4735,6 +4865,9 @@ impl App {
             KeyCode::Char('m') | KeyCode::Char('M') => {
                 self.execute_command(CommandId::QueueRemediate).await;
             }
+            KeyCode::Char('z') | KeyCode::Char('Z') => {
+                self.execute_command(CommandId::QueueRollback).await;
+            }
             KeyCode::Char('0') => self.set_queue_failure_filter(QueueFailureFilter::All),
             KeyCode::Char('1') => self.set_queue_failure_filter(QueueFailureFilter::Permissions),
             KeyCode::Char('2') => self.set_queue_failure_filter(QueueFailureFilter::Network),
@@ -4799,8 +4932,7 @@ impl App {
                 continue;
             }
 
-            let entry =
-                TaskQueueEntry::new(action, package_id, package.name.clone(), package.source);
+            let entry = TaskQueueEntry::for_package(action, &package);
             self.enqueue_task_entry(entry).await;
             queued += 1;
         }
@@ -4842,7 +4974,11 @@ impl App {
 
         tokio::spawn(async move {
             loop {
-                let executor = TaskQueueExecutor::new(pm.clone(), history_tracker.clone());
+                let config = Config::load();
+                let executor = TaskQueueExecutor::new(pm.clone(), history_tracker.clone())
+                    .with_concurrency(config.queue_concurrency)
+                    .with_snapshots(config.snapshots)
+                    .with_hooks(config.hooks);
                 if let Err(error) = executor.run(sender.clone()).await {
                     error!(error = %error, "Task queue executor stopped");
                 }
@@ -4912,7 +5048,30 @@ impl App {
                     );
                 }
             }
-            TaskQueueStatus::Running => self.set_status("Cannot cancel running task", true),
+            TaskQueueStatus::Running => {
+                if !request_task_cancellation(&task.id) {
+                    self.set_status("This task is running in another LinGet instance", true);
+                } else if ProviderDescriptor::for_source(task.package_source).cancellation
+                    == CancellationSupport::Cooperative
+                {
+                    self.set_status(
+                        format!(
+                            "Stopping {} for {}",
+                            action_label(task.action),
+                            task.package_name
+                        ),
+                        true,
+                    );
+                } else {
+                    self.set_status(
+                        format!(
+                            "{} will stop after its current package step",
+                            task.package_name
+                        ),
+                        true,
+                    );
+                }
+            }
             TaskQueueStatus::Failed => {
                 self.tasks.remove(self.task_cursor);
                 self.cleanup_task_logs();
@@ -4970,6 +5129,8 @@ impl App {
             .reviewed_plan_json
             .clone_from(&task.reviewed_plan_json);
         retry.retry_of = Some(task.id.clone());
+        retry.approved_stale_operation_id = task.stale_plan_approval();
+        retry.available_version = task.available_version.clone();
 
         let state = self
             .task_recovery_states
@@ -5016,6 +5177,75 @@ impl App {
         );
     }
 
+    async fn rollback_selected_task(&mut self) {
+        if !self.is_queue_view() {
+            return;
+        }
+        let Some(task) = self.tasks.get(self.task_cursor).cloned() else {
+            self.set_status("No task selected", true);
+            return;
+        };
+        let Some(operation_id) = rollback_operation_id(&task) else {
+            self.set_status(
+                "Only completed APT, DNF or Flatpak transactions can be rolled back",
+                true,
+            );
+            return;
+        };
+
+        let planned = tokio::time::timeout(PREFLIGHT_VERIFICATION_TIMEOUT, async {
+            let engine = TransactionEngine::load(self.pm.clone(), transaction_store_path())
+                .await
+                .map_err(|error| error.safe_message)?;
+            engine
+                .plan_rollback(&operation_id)
+                .await
+                .map_err(|error| error.safe_message)
+        })
+        .await
+        .unwrap_or_else(|_| Err("timed out while planning the rollback".to_string()));
+        let (plan, risk) = match planned {
+            Ok(planned) => planned,
+            Err(error) => {
+                self.set_status(format!("Rollback plan failed: {error}"), true);
+                return;
+            }
+        };
+        if risk.level == crate::backend::transaction::RiskLevel::Blocked {
+            self.set_status(
+                format!("{} rollback plan is blocked", plan.provider.source),
+                true,
+            );
+            return;
+        }
+        let entry = match plan.rollback_queue_entry() {
+            Ok(entry) => entry,
+            Err(error) => {
+                self.set_status(format!("Rollback plan could not be queued: {error}"), true);
+                return;
+            }
+        };
+
+        let entry_id = entry.id.clone();
+        self.enqueue_task_entry(entry).await;
+        if let Some(index) = self.tasks.iter().position(|entry| entry.id == entry_id) {
+            self.set_task_cursor(index);
+        }
+        self.ensure_queue_cursor_matches_filter();
+        self.queue_completed_at = None;
+        self.queue_completion_digest_emitted = false;
+        self.spawn_task_executor();
+        self.set_status(
+            format!(
+                "Queued rollback of {} ({} package change{})",
+                task.package_name,
+                plan.targets.len(),
+                if plan.targets.len() == 1 { "" } else { "s" }
+            ),
+            true,
+        );
+    }
+
     fn clinic_failure_candidates(&self) -> Vec<TaskQueueEntry> {
         self.queue_visible_task_indices()
             .into_iter()
@@ -5405,6 +5635,10 @@ fn command_queue_remediate_enabled(app: &App) -> bool {
     app.can_queue_remediate_command()
 }
 
+fn command_queue_rollback_enabled(app: &App) -> bool {
+    app.can_rollback_selected_task_command()
+}
+
 fn command_queue_log_older_enabled(app: &App) -> bool {
     app.can_queue_log_older_command()
 }
@@ -5446,6 +5680,7 @@ fn command_group(command: CommandId) -> &'static str {
         | CommandId::QueueRetry
         | CommandId::QueueRetrySafe
         | CommandId::QueueRemediate
+        | CommandId::QueueRollback
         | CommandId::QueueLogOlder
         | CommandId::QueueLogNewer => "Queue",
     }
@@ -5614,6 +5849,7 @@ async fn run_app(
         app.poll_search();
         app.poll_repositories();
         app.poll_changelog();
+        app.poll_dependency_tree();
         app.poll_preflight_verification();
         app.poll_task_events();
         app.maybe_autohide_queue().await;
@@ -5688,6 +5924,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -8427,6 +8664,9 @@ Remove   1 Package
             inventory_fingerprint: "inventory".to_string(),
             created_at: Utc::now(),
             expires_at: Utc::now() + chrono::Duration::minutes(2),
+            rollback_of: None,
+            restore_points: Default::default(),
+            removal_impact: None,
         };
 
         let queued = app
@@ -297,6 +297,10 @@ impl App {
         self.favorite_packages.contains(package_id)
     }
 
+    pub fn is_held_id(&self, package_id: &str) -> bool {
+        self.held_packages.contains(package_id)
+    }
+
     pub fn provider_search_scope_label(&self) -> Option<String> {
         self.search_results.as_ref()?;
 
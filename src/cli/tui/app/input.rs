@@ -224,6 +224,7 @@ impl App {
             CommandId::QueueRetry => self.retry_selected_task().await,
             CommandId::QueueRetrySafe => self.retry_safe_failed_tasks().await,
             CommandId::QueueRemediate => self.apply_selected_task_remediation().await,
+            CommandId::QueueRollback => self.rollback_selected_task().await,
             CommandId::QueueLogOlder => self.queue_log_scroll_up(),
             CommandId::QueueLogNewer => self.queue_log_scroll_down(),
             CommandId::ExportPackages => self.export_packages().await,
@@ -557,10 +558,8 @@ impl App {
             KeyCode::Char('d') | KeyCode::Char('x') => {
                 self.execute_command(CommandId::Remove).await
             }
-            KeyCode::Char('D') => {
-                if self.filter == Filter::Duplicates {
-                    self.dismiss_duplicate_keep_cursor();
-                }
+            KeyCode::Char('D') if self.filter == Filter::Duplicates => {
+                self.dismiss_duplicate_keep_cursor();
             }
             KeyCode::Char('u') => self.execute_command(CommandId::Update).await,
             KeyCode::Char('w') => self.execute_command(CommandId::RunRecommended).await,
@@ -599,6 +598,7 @@ impl App {
             KeyCode::Char('R') => self.execute_command(CommandId::QueueRetry).await,
             KeyCode::Char('A') => self.execute_command(CommandId::QueueRetrySafe).await,
             KeyCode::Char('M') => self.execute_command(CommandId::QueueRemediate).await,
+            KeyCode::Char('Z') => self.execute_command(CommandId::QueueRollback).await,
             KeyCode::Char('E') => self.execute_command(CommandId::ExportPackages).await,
             KeyCode::Char('I') => self.execute_command(CommandId::ImportPackages).await,
             KeyCode::Char('T') => self.execute_command(CommandId::CycleTheme).await,
@@ -875,10 +875,8 @@ impl App {
                         .await;
                 }
             }
-            MouseEventKind::Drag(MouseButton::Left) => {
-                if rect_contains(regions.packages, pos) {
-                    self.handle_mouse_packages_drag(event.row, &regions.packages);
-                }
+            MouseEventKind::Drag(MouseButton::Left) if rect_contains(regions.packages, pos) => {
+                self.handle_mouse_packages_drag(event.row, &regions.packages);
             }
             MouseEventKind::Up(MouseButton::Left) => {
                 self.drag_select_anchor = None;
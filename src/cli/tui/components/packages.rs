@@ -103,6 +103,7 @@ pub fn draw_packages_panel(frame: &mut Frame, app: &App, area: Rect, _compact: b
         let is_cursor = row_index == app.cursor;
         let is_selected = app.selected.contains(&package_id);
         let is_favorite = app.is_favorite_id(&package_id);
+        let is_held = app.is_held_id(&package_id);
         // Cursor gets the background highlight; other selected rows keep a
         // distinct tint so a bulk selection is visible at a glance.
         let row_style = if is_cursor {
@@ -170,6 +171,9 @@ pub fn draw_packages_panel(frame: &mut Frame, app: &App, area: Rect, _compact: b
         if !favorite_marker.is_empty() {
             name_spans.push(Span::styled(favorite_marker, warning()));
         }
+        if is_held {
+            name_spans.push(Span::styled(" ‖ held", muted()));
+        }
 
         let (status_glyph, status_style) = package_status_short(package.status);
         let (released, _) = release_date_cell(package);
@@ -23,7 +23,7 @@
 //! └───────────────────────────────────────────────────────────────────┘
 //! ```
 
-use crate::backend::transaction::VerificationOutcome;
+use crate::backend::transaction::{OperationAction, PlanDiff, ProviderPlan, VerificationOutcome};
 use crate::cli::tui::app::App;
 use crate::cli::tui::state::queue::QueueJourneyLane;
 use crate::cli::tui::theme::{
@@ -617,6 +617,16 @@ fn append_task_rows(rows: &mut Vec<LaneRow>, app: &App, task: &TaskQueueEntry, w
                 Span::styled(short, error()),
             ]));
         }
+        if let Some(delta) = stale_plan_delta(task) {
+            let delta = truncate(
+                &format!("plan moved: {delta} · retry approves it"),
+                width.saturating_sub(4),
+            );
+            lines.push(Line::from(vec![
+                Span::styled("   ", dim()),
+                Span::styled(delta, warning()),
+            ]));
+        }
         if is_cursor {
             lines.push(Line::from(vec![
                 Span::styled(" › ", dim()),
@@ -738,6 +748,29 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
+/// What moved when the task's reviewed plan went stale, e.g.
+/// `~vim (9.0 → 9.1, was 9.0 → 9.0.2)`.
+fn stale_plan_delta(task: &TaskQueueEntry) -> Option<String> {
+    let diff: PlanDiff = serde_json::from_str(task.stale_plan_diff_json.as_deref()?).ok()?;
+    let action = task
+        .reviewed_plan_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<ProviderPlan>(json).ok())
+        .map_or(
+            match task.action {
+                TaskQueueAction::Install => OperationAction::Install,
+                TaskQueueAction::Remove => OperationAction::Remove,
+                TaskQueueAction::Update => OperationAction::Update,
+            },
+            |plan| plan.action,
+        );
+    Some(diff.summary(action))
+}
+
+fn task_snapshot(task: &TaskQueueEntry) -> Option<crate::backend::FilesystemSnapshot> {
+    serde_json::from_str(task.snapshot_json.as_deref()?).ok()
+}
+
 #[derive(Debug, PartialEq, Eq)]
 struct VerificationReceiptSummary {
     outcome: VerificationOutcome,
@@ -745,6 +778,9 @@ struct VerificationReceiptSummary {
 }
 
 fn verification_receipt_summary(task: &TaskQueueEntry) -> Option<VerificationReceiptSummary> {
+    if let Some(summary_json) = task.bundle_summary_json.as_deref() {
+        return bundle_summary(summary_json);
+    }
     let receipt: crate::backend::transaction::VerificationReceipt =
         serde_json::from_str(task.verification_receipt_json.as_deref()?).ok()?;
     let (label, meaning) = match receipt.outcome {
@@ -771,6 +807,37 @@ fn verification_receipt_summary(task: &TaskQueueEntry) -> Option<VerificationRec
     })
 }
 
+fn bundle_summary(summary_json: &str) -> Option<VerificationReceiptSummary> {
+    let summary: crate::backend::transaction::BundleSummary =
+        serde_json::from_str(summary_json).ok()?;
+    let label = match summary.outcome {
+        VerificationOutcome::Verified => "VERIFIED",
+        VerificationOutcome::Mismatch => "MISMATCH",
+        VerificationOutcome::Inconclusive => "INCONCLUSIVE",
+    };
+    let verified = summary
+        .receipts
+        .iter()
+        .filter(|receipt| receipt.outcome == VerificationOutcome::Verified)
+        .count();
+    let providers =
+        summary.receipts.len() + summary.failed.len() + summary.skipped_operation_ids.len();
+    let mut text = format!("{label} · bundle · {verified}/{providers} providers verified");
+    if !summary.failed.is_empty() {
+        text.push_str(&format!(" · {} failed", summary.failed.len()));
+    }
+    if !summary.skipped_operation_ids.is_empty() {
+        text.push_str(&format!(
+            " · {} skipped",
+            summary.skipped_operation_ids.len()
+        ));
+    }
+    Some(VerificationReceiptSummary {
+        outcome: summary.outcome,
+        text,
+    })
+}
+
 fn draw_details_strip(frame: &mut Frame, app: &App, area: Rect) {
     if area.height == 0 {
         return;
@@ -787,7 +854,8 @@ fn draw_details_strip(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let verb = action_verb(task.action);
-    let first = Line::from(vec![
+    let snapshot = task_snapshot(task);
+    let mut first = vec![
         Span::styled(" ", text()),
         Span::styled(
             task.package_name.clone(),
@@ -802,7 +870,12 @@ fn draw_details_strip(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(verb, muted()),
         Span::styled("  · ", dim()),
         Span::styled(task_timing(task), dim()),
-    ]);
+    ];
+    if let Some(snapshot) = &snapshot {
+        first.push(Span::styled("  · snapshot ", dim()));
+        first.push(Span::styled(snapshot.label(), muted()));
+    }
+    let first = Line::from(first);
 
     let second = if app.task_awaiting_authentication(task) {
         Line::from(vec![
@@ -830,6 +903,17 @@ fn draw_details_strip(frame: &mut Frame, app: &App, area: Rect) {
                 error(),
             ),
         ])
+    } else if let Some(snapshot) = &snapshot {
+        Line::from(vec![
+            Span::styled(" restore ", dim()),
+            Span::styled(
+                truncate(
+                    &snapshot.restore_hint(),
+                    area.width.saturating_sub(10) as usize,
+                ),
+                muted(),
+            ),
+        ])
     } else if let Some(receipt) = verification_receipt_summary(task) {
         let receipt_style = match receipt.outcome {
             VerificationOutcome::Verified => success(),
@@ -902,6 +986,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stale_plan_delta_lists_what_moved() {
+        use crate::backend::transaction::{ChangeRevision, PackageChange};
+
+        let change = |before: &str| PackageChange {
+            name: "vim".to_string(),
+            before: Some(before.to_string()),
+            after: Some("9.1".to_string()),
+        };
+        let diff = PlanDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            reversioned: vec![ChangeRevision {
+                reviewed: change("9.0"),
+                fresh: change("9.0.2"),
+            }],
+        };
+        let mut task = TaskQueueEntry::new(
+            TaskQueueAction::Update,
+            "APT:vim".to_string(),
+            "vim".to_string(),
+            PackageSource::Apt,
+        );
+        assert_eq!(stale_plan_delta(&task), None);
+
+        task.stale_plan_diff_json = Some(serde_json::to_string(&diff).unwrap());
+        assert_eq!(
+            stale_plan_delta(&task).as_deref(),
+            Some("~vim (9.0.2 → 9.1, was 9.0 → 9.1)")
+        );
+    }
+
+    #[test]
+    fn bundle_summary_counts_verified_failed_and_skipped_providers() {
+        let summary = crate::backend::transaction::BundleSummary {
+            bundle_id: "bundle-1".to_string(),
+            outcome: VerificationOutcome::Mismatch,
+            receipts: vec![VerificationReceipt {
+                operation_id: "operation-1".to_string(),
+                plan_id: "plan-1".to_string(),
+                provider: PackageSource::Apt,
+                expected: Vec::new(),
+                observed: Vec::new(),
+                outcome: VerificationOutcome::Verified,
+                warnings: Vec::new(),
+                verified_at: Utc::now(),
+            }],
+            failed: vec![crate::backend::transaction::ProviderError::classify(
+                PackageSource::Flatpak,
+                "network unreachable",
+            )],
+            skipped_operation_ids: vec!["operation-3".to_string()],
+            verified_at: Utc::now(),
+        };
+        let mut task = TaskQueueEntry::new(
+            TaskQueueAction::Update,
+            "bundle:bundle-1".to_string(),
+            "3 packages across APT, Flatpak, npm".to_string(),
+            PackageSource::Apt,
+        );
+        task.bundle_summary_json = Some(serde_json::to_string(&summary).unwrap());
+
+        let summary = verification_receipt_summary(&task).expect("bundle summary");
+        assert_eq!(summary.outcome, VerificationOutcome::Mismatch);
+        assert_eq!(
+            summary.text,
+            "MISMATCH · bundle · 1/3 providers verified · 1 failed · 1 skipped"
+        );
+    }
+
     #[test]
     fn durable_receipt_summary_explains_non_verified_outcomes_and_warnings() {
         let make_task = |outcome, warnings: Vec<String>| {
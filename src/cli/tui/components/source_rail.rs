@@ -134,6 +134,7 @@ fn source_label(source: crate::models::PackageSource) -> &'static str {
         crate::models::PackageSource::Winget => "WinGet",
         crate::models::PackageSource::Chocolatey => "Chocolatey",
         crate::models::PackageSource::Scoop => "Scoop",
+        crate::models::PackageSource::Plugin(id) => id.name(),
     }
 }
 
@@ -7,9 +7,10 @@
 use super::packages::draw_packages_panel;
 use super::source_rail;
 use crate::backend::{BackendCapability, SourceCapabilityContext};
-use crate::cli::tui::app::App;
+use crate::cli::commands::tree::render_tree;
+use crate::cli::tui::app::{App, DependencyTreeState};
 use crate::cli::tui::format::truncate_to_width;
-use crate::cli::tui::state::filters::{Filter, Focus};
+use crate::cli::tui::state::filters::{DetailsTab, Filter, Focus};
 use crate::cli::tui::theme::{
     accent, border_focused, border_unfocused, dim, error, muted, palette, row_cursor, success,
     text, warning,
@@ -368,7 +369,20 @@ fn draw_inspector(frame: &mut Frame, app: &App, area: Rect) {
         truncate_to_width(&package.name, content_width),
         text().add_modifier(Modifier::BOLD),
     )));
+    lines.push(details_tabs_line(app.active_details_tab));
     lines.push(separator_line(content_width));
+    if app.active_details_tab == DetailsTab::Dependencies {
+        append_dependency_tree(
+            &mut lines,
+            app,
+            package,
+            content_width,
+            chunks[0].height as usize,
+        );
+        frame.render_widget(Paragraph::new(lines), chunks[0]);
+        draw_inspector_buttons(frame, package, chunks[1]);
+        return;
+    }
     push_fact_line(&mut lines, "Source", &source_detail_label(package), muted());
     lines.push(version_line(package));
     push_fact_line(
@@ -437,15 +451,94 @@ fn draw_inspector(frame: &mut Frame, app: &App, area: Rect) {
     }
 
     frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), chunks[0]);
+    draw_inspector_buttons(frame, package, chunks[1]);
+}
+
+fn draw_inspector_buttons(frame: &mut Frame, package: &Package, area: Rect) {
+    if area.height == 0 {
+        return;
+    }
+    let mut buttons = Vec::new();
+    inspector_button(&mut buttons, "Enter", package_action_label(package.status));
+    buttons.push(Span::raw("  "));
+    inspector_button(&mut buttons, "c", "Changelog");
+    buttons.push(Span::raw("  "));
+    inspector_button(&mut buttons, "Space", "Select");
+    frame.render_widget(Paragraph::new(Line::from(buttons)), area);
+}
+
+fn details_tabs_line(active: DetailsTab) -> Line<'static> {
+    let tab = |label: &'static str, tab: DetailsTab| {
+        if active == tab {
+            Span::styled(label, accent().add_modifier(Modifier::BOLD))
+        } else {
+            Span::styled(label, dim())
+        }
+    };
+    Line::from(vec![
+        tab("Info", DetailsTab::Info),
+        Span::styled(" · ", dim()),
+        tab("Deps", DetailsTab::Dependencies),
+        Span::styled("  Alt+1/2", dim()),
+    ])
+}
+
+/// The Deps tab: the selected package's forward dependency tree, cut to the
+/// lines the inspector has room for.
+fn append_dependency_tree(
+    lines: &mut Vec<Line<'static>>,
+    app: &App,
+    package: &Package,
+    width: usize,
+    height: usize,
+) {
+    if !matches!(
+        package.status,
+        PackageStatus::Installed | PackageStatus::UpdateAvailable
+    ) {
+        lines.push(Line::from(Span::styled(
+            "Install the package to see its dependency tree.",
+            dim(),
+        )));
+        return;
+    }
+    let tree = match app.dependency_tree_state(package) {
+        None | Some(DependencyTreeState::Loading) => {
+            lines.push(Line::from(Span::styled("Loading dependency tree…", dim())));
+            return;
+        }
+        Some(DependencyTreeState::Error(error)) => {
+            lines.push(Line::from(Span::styled(
+                truncate_to_width(error, width),
+                muted(),
+            )));
+            return;
+        }
+        Some(DependencyTreeState::Ready(tree)) => tree,
+    };
 
-    if chunks[1].height > 0 {
-        let mut buttons = Vec::new();
-        inspector_button(&mut buttons, "Enter", package_action_label(package.status));
-        buttons.push(Span::raw("  "));
-        inspector_button(&mut buttons, "c", "Changelog");
-        buttons.push(Span::raw("  "));
-        inspector_button(&mut buttons, "Space", "Select");
-        frame.render_widget(Paragraph::new(Line::from(buttons)), chunks[1]);
+    let rows = render_tree(&tree.root);
+    if rows.is_empty() {
+        lines.push(Line::from(Span::styled("No dependencies", muted())));
+        return;
+    }
+    let room = height.saturating_sub(lines.len() + 1).max(1);
+    let shown = if rows.len() > room {
+        room.saturating_sub(1)
+    } else {
+        rows.len()
+    };
+    for row in rows.iter().take(shown) {
+        lines.push(Line::from(Span::styled(
+            truncate_to_width(row, width),
+            muted(),
+        )));
+    }
+    if shown < rows.len() || tree.incomplete {
+        lines.push(Line::from(Span::styled(
+            truncate_to_width(&format!("… full tree: linget tree {}", package.name), width),
+            dim(),
+        )));
     }
 }
 
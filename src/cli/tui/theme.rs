@@ -624,6 +624,7 @@ pub fn source_color(source: PackageSource) -> Style {
         PackageSource::Winget => t.lavender,
         PackageSource::Chocolatey => t.yellow,
         PackageSource::Scoop => t.sky,
+        PackageSource::Plugin(_) => t.light_gray,
     };
     plain(color)
 }
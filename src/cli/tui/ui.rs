@@ -464,9 +464,13 @@ fn draw_queue_bar(frame: &mut Frame, app: &App, area: Rect) {
         let phase = active_task
             .map(|task| running_task_phase(app, task))
             .unwrap_or(RunningTaskPhase::Resolve);
-        let active_label = active_task
+        let mut active_label = active_task
             .map(|task| format!("{} {}", action_label(task.action), task.package_name))
             .unwrap_or_else(|| "Working".to_string());
+        if running > 1 {
+            // Providers in separate lanes run side by side.
+            active_label.push_str(&format!(" +{} running", running - 1));
+        }
         let task_eta_hint = active_task.and_then(|task| running_task_eta_hint(task, &app.tasks));
         let trust_hint = active_task.and_then(|task| running_task_signal_hint(app, task));
         let eta_confidence = queue_eta_confidence(&app.tasks, trust_hint.as_deref());
@@ -1974,6 +1978,7 @@ fn draw_help_overlay(frame: &mut Frame, app: &mut App) {
     if app.is_queue_view() {
         lines.push(Line::from(Span::styled("Queue", section_header())));
         lines.push(Line::from("  R retry selected failure"));
+        lines.push(Line::from("  Z roll back selected completed transaction"));
         lines.push(Line::from(
             "  M inspect/apply filtered fixes   A retry safe failures",
         ));
@@ -2814,6 +2819,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -3007,7 +3013,13 @@ mod tests {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         };
 
@@ -3054,7 +3066,13 @@ mod tests {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         };
 
@@ -3104,7 +3122,13 @@ mod tests {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         };
         app.tasks = vec![running.clone()];
@@ -3234,7 +3258,13 @@ mod tests {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         };
 
@@ -3252,7 +3282,13 @@ mod tests {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         };
 
@@ -1,28 +1,4 @@
-use crate::models::{Package, PackageSource, UpdateCategory};
-
-const RISKY_PACKAGE_KEYWORDS: [&str; 12] = [
-    "linux", "kernel", "systemd", "glibc", "libc", "openssl", "gnutls", "firmware", "mesa", "grub",
-    "nvidia", "llvm",
-];
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum UpdateLane {
-    Security,
-    Recommended,
-    Optional,
-    Risky,
-}
-
-impl UpdateLane {
-    fn sort_key(self) -> u8 {
-        match self {
-            UpdateLane::Security => 0,
-            UpdateLane::Recommended => 1,
-            UpdateLane::Optional => 2,
-            UpdateLane::Risky => 3,
-        }
-    }
-}
+use crate::models::{Package, UpdateLane};
 
 #[derive(Debug, Clone)]
 pub struct UpdateCandidate {
@@ -35,19 +11,15 @@ pub fn classify_updates(packages: &[Package]) -> Vec<UpdateCandidate> {
         .iter()
         .filter(|pkg| pkg.has_update())
         .cloned()
-        .map(|pkg| {
-            let category = pkg
-                .update_category
-                .unwrap_or_else(|| pkg.detect_update_category());
-            let lane = classify_lane(&pkg, category);
-            UpdateCandidate { package: pkg, lane }
+        .map(|pkg| UpdateCandidate {
+            lane: UpdateLane::of(&pkg),
+            package: pkg,
         })
         .collect();
 
     candidates.sort_by(|a, b| {
         a.lane
-            .sort_key()
-            .cmp(&b.lane.sort_key())
+            .cmp(&b.lane)
             .then_with(|| a.package.source.cmp(&b.package.source))
             .then_with(|| {
                 a.package
@@ -60,65 +32,10 @@ pub fn classify_updates(packages: &[Package]) -> Vec<UpdateCandidate> {
     candidates
 }
 
-fn classify_lane(pkg: &Package, category: UpdateCategory) -> UpdateLane {
-    if category == UpdateCategory::Security {
-        return UpdateLane::Security;
-    }
-
-    if is_risky_update(pkg, category) {
-        return UpdateLane::Risky;
-    }
-
-    if category == UpdateCategory::Feature {
-        return UpdateLane::Optional;
-    }
-
-    UpdateLane::Recommended
-}
-
-fn is_risky_update(pkg: &Package, category: UpdateCategory) -> bool {
-    let name = pkg.name.to_lowercase();
-    if RISKY_PACKAGE_KEYWORDS
-        .iter()
-        .any(|keyword| name.contains(keyword))
-    {
-        return true;
-    }
-
-    let system_source = matches!(
-        pkg.source,
-        PackageSource::Apt
-            | PackageSource::Dnf
-            | PackageSource::Pacman
-            | PackageSource::Zypper
-            | PackageSource::Deb
-    );
-    if system_source && category == UpdateCategory::Feature {
-        return true;
-    }
-
-    has_major_version_jump(pkg)
-}
-
-fn has_major_version_jump(pkg: &Package) -> bool {
-    let Some(available) = pkg.available_version.as_ref() else {
-        return false;
-    };
-
-    let Some(current) = semver::Version::parse(&pkg.version).ok() else {
-        return false;
-    };
-    let Some(next) = semver::Version::parse(available).ok() else {
-        return false;
-    };
-
-    next.major > current.major
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{PackageEnrichment, PackageStatus};
+    use crate::models::{PackageEnrichment, PackageSource, PackageStatus, UpdateCategory};
 
     fn make_pkg(
         name: &str,
@@ -142,6 +59,7 @@ mod tests {
             install_date: None,
             update_category: category,
             enrichment: Some(PackageEnrichment::default()),
+            released_at: None,
         }
     }
 
@@ -10,8 +10,11 @@
 use crate::backend::history_tracker::HistoryTracker;
 use crate::backend::{PackageLoadProgress, PackageManager, TaskQueueEvent};
 use crate::models::history::{TaskQueueAction, TaskQueueEntry, TaskQueueStatus};
-use crate::models::{Package, PackageSource, PackageStatus, UpdateCategory};
+use crate::models::{
+    Package, PackageSource, PackageStatus, SchedulerState, UpdateCategory, UpdatePolicy,
+};
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -27,6 +30,8 @@ pub const MIN_HEIGHT: u16 = 16;
 pub const ORPHAN_AGE: Duration = Duration::from_secs(2 * 60 * 60);
 /// Terminal-wide breakpoint for docking the expansion to the right.
 pub const DOCK_WIDTH: u16 = 140;
+/// How often scheduled work is re-read from the config.
+const SCHEDULER_REFRESH: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Filter {
@@ -80,6 +85,7 @@ pub enum LoadMsg {
     InstalledDone,
     UpdatesDone(Vec<Package>),
     UpdatesFailed(String),
+    HoldsLoaded(HashSet<String>),
 }
 
 /// Result of a background stable-provider planning run, drained by the event
@@ -93,10 +99,7 @@ pub enum PlanOutcome {
 /// Sources whose queue tasks run as verified transactions: they must carry a
 /// reviewed provider plan, planned per source-batch, not per package.
 pub fn stable_transaction_source(source: PackageSource) -> bool {
-    matches!(
-        source,
-        PackageSource::Apt | PackageSource::Flatpak | PackageSource::Npm
-    )
+    crate::backend::transaction::STABLE_PROVIDERS.contains(&source)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -150,6 +153,8 @@ pub struct App {
     pub cached_at: Option<chrono::DateTime<chrono::Local>>,
     /// Starred package ids, persisted via Config.favorite_packages.
     pub favorites: HashSet<String>,
+    /// Package ids the provider itself holds (apt-mark hold, versionlock, …).
+    pub held: HashSet<String>,
 
     pub filter: Filter,
     pub rows: Vec<Row>,
@@ -163,6 +168,12 @@ pub struct App {
 
     pub queue: Vec<TaskQueueEntry>,
     pub queue_open: bool,
+    /// Scheduled tasks and recurring rules, re-read from the config every
+    /// `SCHEDULER_REFRESH` so deferrals from the background runner show up.
+    pub scheduler: SchedulerState,
+    scheduler_read_at: Instant,
+    /// The update policy, read once at startup for its release cooldowns.
+    pub policy: UpdatePolicy,
     pub live_logs: HashMap<String, String>,
     pub executor_running: bool,
     /// Background stable-provider planning runs in flight; the ambient line
@@ -210,6 +221,7 @@ impl App {
             fresh_installed: Vec::new(),
             cached_at: None,
             favorites: HashSet::new(),
+            held: HashSet::new(),
             filter: Filter::Updates,
             rows: Vec::new(),
             cursor: 0,
@@ -220,6 +232,9 @@ impl App {
             search: Search::default(),
             queue: Vec::new(),
             queue_open: false,
+            scheduler: SchedulerState::default(),
+            scheduler_read_at: Instant::now(),
+            policy: UpdatePolicy::default(),
             live_logs: HashMap::new(),
             executor_running: false,
             planning: 0,
@@ -264,6 +279,7 @@ impl App {
                     }
                 });
                 let updates = guard.check_all_updates_progressive(progress_tx2).await;
+                let holds = guard.list_all_holds().await;
                 drop(guard);
                 let _ = forward2.await;
                 match updates {
@@ -274,6 +290,7 @@ impl App {
                         let _ = tx.send(LoadMsg::UpdatesFailed(error.to_string())).await;
                     }
                 }
+                let _ = tx.send(LoadMsg::HoldsLoaded(holds)).await;
             }
         });
     }
@@ -360,6 +377,9 @@ impl App {
                 self.fresh_installed.clear();
                 self.set_status(format!("update check failed: {error}"));
             }
+            LoadMsg::HoldsLoaded(held) => {
+                self.held = held;
+            }
         }
         self.rebuild_rows();
     }
@@ -382,6 +402,7 @@ impl App {
                         .clone()
                         .or_else(|| Some(package.version.clone()));
                     existing.update_category = package.update_category;
+                    existing.released_at = package.released_at;
                 }
             } else if !are_updates {
                 self.pkg_by_id.insert(id, self.packages.len());
@@ -584,6 +605,8 @@ impl App {
             KeyCode::Char('x') if self.queue_open => {
                 self.reap_orphans().await?;
             }
+            KeyCode::Char('z') if self.queue_open => self.rollback_last_completed(),
+            KeyCode::Char('c') if self.queue_open => self.cancel_running(),
             KeyCode::Char('1') => self.set_filter(Filter::Updates),
             KeyCode::Char('2') => self.set_filter(Filter::Security),
             KeyCode::Char('3') => self.set_filter(Filter::Installed),
@@ -886,6 +909,54 @@ impl App {
         (queued, running, failed, done)
     }
 
+    /// (pending scheduled tasks, due tasks and rules their conditions are
+    /// holding back).
+    pub fn scheduled_counts(&self) -> (usize, usize) {
+        let pending = self.scheduler.pending_count();
+        let deferred = self
+            .scheduler
+            .tasks
+            .iter()
+            .filter(|task| task.is_active() && task.deferred.is_some())
+            .count()
+            + self
+                .scheduler
+                .rules
+                .iter()
+                .filter(|rule| rule.enabled && rule.deferred.is_some())
+                .count();
+        (pending, deferred)
+    }
+
+    pub fn set_scheduler(&mut self, scheduler: SchedulerState) {
+        self.scheduler = scheduler;
+        self.scheduler_read_at = Instant::now();
+    }
+
+    /// "available in 3 days" while `package`'s update waits out its
+    /// provider's release cooldown.
+    pub fn cooldown_label(&self, package: &Package, now: DateTime<Utc>) -> Option<String> {
+        if package.status != PackageStatus::UpdateAvailable {
+            return None;
+        }
+        let until = self.policy.cooldown_until(package, package.released_at?)?;
+        let minutes = (until - now).num_minutes();
+        if minutes <= 0 {
+            return None;
+        }
+        let hours = (minutes + 59) / 60;
+        Some(if hours < 24 {
+            format!("available in {}h", hours)
+        } else {
+            let days = (hours + 23) / 24;
+            format!(
+                "available in {} day{}",
+                days,
+                if days == 1 { "" } else { "s" }
+            )
+        })
+    }
+
     pub fn orphan_count(&self) -> usize {
         self.queue
             .iter()
@@ -945,7 +1016,10 @@ impl App {
                 entry.status,
                 TaskQueueStatus::Queued | TaskQueueStatus::Running
             );
-            if active && entry.package_id.starts_with("transaction:") {
+            if active
+                && (entry.package_id.starts_with("transaction:")
+                    || entry.package_id.starts_with("bundle:"))
+            {
                 if let Some(targets) = transaction_targets(entry) {
                     covered.extend(targets);
                 }
@@ -987,12 +1061,7 @@ impl App {
             if already_queued {
                 continue;
             }
-            entries.push(TaskQueueEntry::new(
-                action,
-                id,
-                package.name.clone(),
-                package.source,
-            ));
+            entries.push(TaskQueueEntry::for_package(action, package));
             queued += 1;
         }
 
@@ -1060,37 +1129,57 @@ impl App {
                     TaskQueueAction::Update => crate::backend::transaction::OperationAction::Update,
                 };
 
-                let mut sources: Vec<_> = groups.into_iter().collect();
-                sources.sort_by_key(|(source, _)| source.to_string());
                 let mut entries = Vec::new();
                 let mut planned = 0;
-                for (_source, targets) in sources {
-                    let request =
-                        OperationRequest::new(operation_action, targets, RequestedBy::Tui);
-                    let (plan, risk) = engine
-                        .plan(request)
+                if groups.len() > 1 {
+                    // Several providers: one bundle, one review, one queue row.
+                    let request = OperationRequest::new(
+                        operation_action,
+                        groups.into_values().flatten().collect(),
+                        RequestedBy::Tui,
+                    );
+                    let bundle = engine
+                        .plan_bundle(request)
                         .await
                         .map_err(|error| anyhow::anyhow!(error.safe_message))?;
-                    if risk.level == RiskLevel::Blocked {
-                        anyhow::bail!("{} provider plan is blocked", plan.provider.source);
+                    if bundle.risk.level == RiskLevel::Blocked {
+                        anyhow::bail!("transaction bundle is blocked");
                     }
-                    let plan_json = serde_json::to_string(&plan)
-                        .context("provider plan could not be serialized")?;
-                    let package_name = if plan.targets.len() == 1 {
-                        plan.targets[0].name.clone()
-                    } else {
-                        format!("{} {} packages", plan.targets.len(), plan.provider.source)
-                    };
-                    let mut entry = TaskQueueEntry::new(
-                        action,
-                        format!("transaction:{}", plan.operation_id),
-                        package_name,
-                        plan.provider.source,
+                    planned += bundle.target_count();
+                    entries.push(
+                        bundle
+                            .queue_entry()
+                            .context("transaction bundle could not be serialized")?,
                     );
-                    entry.reviewed_operation_id = Some(plan.operation_id.clone());
-                    entry.reviewed_plan_json = Some(plan_json);
-                    planned += plan.targets.len();
-                    entries.push(entry);
+                } else {
+                    for (_source, targets) in groups {
+                        let request =
+                            OperationRequest::new(operation_action, targets, RequestedBy::Tui);
+                        let (plan, risk) = engine
+                            .plan(request)
+                            .await
+                            .map_err(|error| anyhow::anyhow!(error.safe_message))?;
+                        if risk.level == RiskLevel::Blocked {
+                            anyhow::bail!(plan.blocked_reason());
+                        }
+                        let plan_json = serde_json::to_string(&plan)
+                            .context("provider plan could not be serialized")?;
+                        let package_name = if plan.targets.len() == 1 {
+                            plan.targets[0].name.clone()
+                        } else {
+                            format!("{} {} packages", plan.targets.len(), plan.provider.source)
+                        };
+                        let mut entry = TaskQueueEntry::new(
+                            action,
+                            format!("transaction:{}", plan.operation_id),
+                            package_name,
+                            plan.provider.source,
+                        );
+                        entry.reviewed_operation_id = Some(plan.operation_id.clone());
+                        entry.reviewed_plan_json = Some(plan_json);
+                        planned += plan.targets.len();
+                        entries.push(entry);
+                    }
                 }
 
                 {
@@ -1120,6 +1209,92 @@ impl App {
         });
     }
 
+    /// Stop the task this process is running. Cooperative providers have
+    /// their command terminated; the rest stop at the next package step.
+    pub fn cancel_running(&mut self) {
+        use crate::backend::transaction::{CancellationSupport, ProviderDescriptor};
+
+        let Some(entry) = self
+            .queue
+            .iter()
+            .find(|entry| entry.status == TaskQueueStatus::Running)
+        else {
+            self.set_status("nothing is running");
+            return;
+        };
+        let cooperative = ProviderDescriptor::for_source(entry.package_source).cancellation
+            == CancellationSupport::Cooperative;
+        let name = entry.package_name.clone();
+        if !crate::backend::request_task_cancellation(&entry.id) {
+            self.set_status("that task belongs to another LinGet instance");
+        } else if cooperative {
+            self.set_status(format!("stopping {name}…"));
+        } else {
+            self.set_status(format!("{name} will stop after its current step"));
+        }
+    }
+
+    /// The most recently finished queue entry that can be rolled back.
+    pub fn rollback_candidate(&self) -> Option<&TaskQueueEntry> {
+        self.queue
+            .iter()
+            .filter(|entry| crate::backend::transaction::rollback_operation_id(entry).is_some())
+            .max_by_key(|entry| entry.completed_at)
+    }
+
+    /// Plan the reverse of the latest finished transaction off the render
+    /// loop and queue it as a reviewed rollback.
+    pub fn rollback_last_completed(&mut self) {
+        use crate::backend::transaction::{rollback_operation_id, RiskLevel, TransactionEngine};
+
+        let Some(operation_id) = self.rollback_candidate().and_then(rollback_operation_id) else {
+            self.set_status("no finished transaction to roll back");
+            return;
+        };
+        self.planning += 1;
+        self.set_status("planning rollback for review…");
+        let pm = self.pm.clone();
+        let history = self.history.clone();
+        let plan_tx = self.plan_tx.clone();
+
+        tokio::spawn(async move {
+            let outcome = async {
+                let engine = TransactionEngine::load(pm, crate::backend::transaction_store_path())
+                    .await
+                    .map_err(|error| anyhow::anyhow!(error.safe_message))?;
+                let (plan, risk) = engine
+                    .plan_rollback(&operation_id)
+                    .await
+                    .map_err(|error| anyhow::anyhow!(error.safe_message))?;
+                if risk.level == RiskLevel::Blocked {
+                    anyhow::bail!("{} rollback plan is blocked", plan.provider.source);
+                }
+                let entry = plan
+                    .rollback_queue_entry()
+                    .context("rollback plan could not be serialized")?;
+                let mut guard = history.lock().await;
+                let tracker = guard.as_mut().context("history tracker not initialized")?;
+                tracker
+                    .enqueue_task(entry)
+                    .await
+                    .context("failed to enqueue rollback task")?;
+                tracker.save().await.context("failed to save task queue")?;
+                Ok::<usize, anyhow::Error>(plan.targets.len())
+            }
+            .await;
+
+            let _ = plan_tx
+                .send(match outcome {
+                    Ok(planned) => PlanOutcome::Queued {
+                        queued: planned,
+                        label: "rollback".to_string(),
+                    },
+                    Err(error) => PlanOutcome::Failed(error.to_string()),
+                })
+                .await;
+        });
+    }
+
     async fn queue_selected_updates(&mut self) -> Result<()> {
         let ids: Vec<String> = if self.selected.is_empty() {
             self.cursor_package().map(|p| p.id()).into_iter().collect()
@@ -1141,10 +1316,19 @@ impl App {
             .filter(|package| package.status == PackageStatus::UpdateAvailable)
             .map(|package| package.id())
             .collect();
+        // Held packages are pinned by their provider; queueing them would only
+        // produce a refused or no-op transaction.
+        let (held, ids): (Vec<String>, Vec<String>) =
+            ids.into_iter().partition(|id| self.held.contains(id));
         let total = ids.len();
         let queued = self.queue_action_for(ids, TaskQueueAction::Update).await?;
-        if queued > 0 {
+        if queued > 0 && held.is_empty() {
             self.set_status(format!("queued {queued} of {total} updates"));
+        } else if queued > 0 {
+            self.set_status(format!(
+                "queued {queued} of {total} updates · {} held",
+                held.len()
+            ));
         }
         Ok(())
     }
@@ -1173,7 +1357,8 @@ impl App {
         let mut retries: Vec<TaskQueueEntry> = Vec::new();
         for entry in retryable_failed_entries(&self.queue) {
             let stable = stable_transaction_source(entry.package_source);
-            if stable && entry.reviewed_plan_json.is_none() {
+            if stable && entry.reviewed_plan_json.is_none() && entry.reviewed_bundle_json.is_none()
+            {
                 replan_ids.push(entry.package_id.clone());
                 continue;
             }
@@ -1185,7 +1370,10 @@ impl App {
             );
             retry.reviewed_operation_id = entry.reviewed_operation_id.clone();
             retry.reviewed_plan_json = entry.reviewed_plan_json.clone();
+            retry.reviewed_bundle_json = entry.reviewed_bundle_json.clone();
             retry.retry_of = Some(entry.id.clone());
+            retry.approved_stale_operation_id = entry.stale_plan_approval();
+            retry.available_version = entry.available_version.clone();
             retries.push(retry);
         }
         if retries.is_empty() && replan_ids.is_empty() {
@@ -1259,8 +1447,12 @@ impl App {
             return;
         }
         self.executor_running = true;
+        let config = crate::models::Config::load();
         let executor =
-            crate::backend::TaskQueueExecutor::new(self.pm.clone(), self.history.clone());
+            crate::backend::TaskQueueExecutor::new(self.pm.clone(), self.history.clone())
+                .with_concurrency(config.queue_concurrency)
+                .with_snapshots(config.snapshots)
+                .with_hooks(config.hooks);
         let tx = self.queue_tx.clone();
         let done_tx = self.executor_done_tx.clone();
         tokio::spawn(async move {
@@ -1332,6 +1524,9 @@ impl App {
 
     pub fn tick(&mut self) {
         self.spinner = self.spinner.wrapping_add(1);
+        if self.scheduler_read_at.elapsed() > SCHEDULER_REFRESH {
+            self.set_scheduler(crate::models::Config::load().scheduler);
+        }
         if let Some((_, at)) = &self.status {
             if at.elapsed() > Duration::from_secs(4) {
                 self.status = None;
@@ -1380,6 +1575,16 @@ struct ParsedQuery {
 /// (source, name) targets of a queue entry's attached provider plan, so a
 /// new queue request can skip packages an active transaction already covers.
 fn transaction_targets(entry: &TaskQueueEntry) -> Option<Vec<(PackageSource, String)>> {
+    if let Some(bundle_json) = entry.reviewed_bundle_json.as_deref() {
+        let bundle: crate::backend::transaction::TransactionBundle =
+            serde_json::from_str(bundle_json).ok()?;
+        return Some(
+            bundle
+                .targets()
+                .map(|target| (target.source, target.name.clone()))
+                .collect(),
+        );
+    }
     let plan_json = entry.reviewed_plan_json.as_deref()?;
     let plan: crate::backend::transaction::ProviderPlan = serde_json::from_str(plan_json).ok()?;
     Some(
@@ -1553,12 +1758,16 @@ mod tests {
                 source: PackageSource::Npm,
                 installed_version: Some("11.0".to_string()),
                 available_version: Some("12.0".to_string()),
+                pinned: false,
             }],
             exact_commands: Vec::new(),
             expected_changes: Vec::new(),
             inventory_fingerprint: String::new(),
             created_at: chrono::Utc::now(),
             expires_at: chrono::Utc::now() + chrono::Duration::minutes(10),
+            rollback_of: None,
+            restore_points: Default::default(),
+            removal_impact: None,
         };
         let mut entry = TaskQueueEntry::new(
             TaskQueueAction::Update,
@@ -1597,6 +1806,7 @@ mod tests {
             available_version: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -1611,6 +1821,73 @@ mod tests {
         assert_eq!(plain.text, "firefox");
     }
 
+    #[test]
+    fn scheduled_counts_include_deferred_tasks_and_rules() {
+        use crate::models::calendar::CalendarSpec;
+        use crate::models::scheduler::Deferral;
+        use crate::models::{RecurringRule, ScheduledOperation, ScheduledTask};
+
+        let mut app = App::new_test();
+        let task = |name: &str| {
+            ScheduledTask::new(
+                format!("APT:{name}"),
+                name.to_string(),
+                PackageSource::Apt,
+                ScheduledOperation::Update,
+                chrono::Utc::now(),
+            )
+        };
+        let deferral = Some(Deferral {
+            checked_at: chrono::Utc::now(),
+            reasons: vec!["running on battery power".to_string()],
+        });
+        let mut scheduler = SchedulerState::default();
+        scheduler.add_task(task("curl"));
+        let mut waiting = task("git");
+        waiting.deferred = deferral.clone();
+        scheduler.add_task(waiting);
+        let mut done = task("vim");
+        done.deferred = deferral.clone();
+        done.mark_completed();
+        scheduler.add_task(done);
+        let mut rule = RecurringRule::new(CalendarSpec::parse("daily").unwrap());
+        rule.deferred = deferral;
+        scheduler.add_rule(rule);
+
+        app.set_scheduler(scheduler);
+        assert_eq!(app.scheduled_counts(), (2, 2));
+    }
+
+    #[test]
+    fn cooldown_label_counts_down_to_the_end_of_the_cooldown() {
+        let mut app = App::new_test();
+        app.policy = UpdatePolicy::from_toml_str("[cooldown]\nnpm = 7\n").unwrap();
+        let now = chrono::Utc::now();
+        let mut package = sample_package("left-pad", PackageSource::Npm);
+        package.status = PackageStatus::UpdateAvailable;
+        package.available_version = Some("1.3.0".to_string());
+        package.released_at = Some(now - chrono::Duration::days(4));
+
+        assert_eq!(
+            app.cooldown_label(&package, now).as_deref(),
+            Some("available in 3 days")
+        );
+        package.released_at = Some(now - chrono::Duration::days(7) + chrono::Duration::hours(5));
+        assert_eq!(
+            app.cooldown_label(&package, now).as_deref(),
+            Some("available in 5h")
+        );
+        package.released_at = Some(now - chrono::Duration::days(8));
+        assert_eq!(app.cooldown_label(&package, now), None);
+
+        // Providers without a cooldown, and releases of unknown age, show nothing.
+        package.released_at = None;
+        assert_eq!(app.cooldown_label(&package, now), None);
+        package.source = PackageSource::Apt;
+        package.released_at = Some(now);
+        assert_eq!(app.cooldown_label(&package, now), None);
+    }
+
     #[test]
     fn favorites_view_shows_only_starred_packages() {
         let mut app = App::new_test();
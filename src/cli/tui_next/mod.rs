@@ -56,6 +56,12 @@ async fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Resu
 
     let tracker = HistoryTracker::load().await.ok();
     let history = Arc::new(Mutex::new(tracker));
+    if let Err(error) = crate::backend::TaskQueueExecutor::new(pm.clone(), history.clone())
+        .recover_interrupted_transactions()
+        .await
+    {
+        tracing::warn!(error = %error, "Failed to recover interrupted transactions");
+    }
 
     let (load_tx, load_rx) = tokio::sync::mpsc::channel(200);
     let (queue_tx, queue_rx) = tokio::sync::mpsc::channel(400);
@@ -71,10 +77,13 @@ async fn run_loop(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Resu
         executor_done_rx,
     );
     app.sources_total = pm.read().await.available_sources().len();
-    app.favorites = crate::models::Config::load()
-        .favorite_packages
-        .into_iter()
-        .collect();
+    let config = crate::models::Config::load();
+    app.favorites = config.favorite_packages.into_iter().collect();
+    app.set_scheduler(config.scheduler);
+    match crate::models::UpdatePolicy::load_default() {
+        Ok(policy) => app.policy = policy.unwrap_or_default(),
+        Err(error) => tracing::warn!(error = %error, "Ignoring the update policy"),
+    }
     app.sync_queue_from_history().await;
     // Serve the last catalog immediately (if any), then revalidate in the
     // background. First paint should never wait on package backends.
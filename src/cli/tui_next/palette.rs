@@ -13,6 +13,7 @@ pub enum PaletteAction {
     Changelog,
     ToggleFavorite,
     RetryFailed,
+    RollBack,
     ReapOrphans,
     Refresh,
     FilterUpdates,
@@ -113,6 +114,13 @@ pub fn commands_for(app: &App) -> Vec<PaletteCommand> {
             action: PaletteAction::RetryFailed,
         });
     }
+    if let Some(entry) = app.rollback_candidate() {
+        commands.push(PaletteCommand {
+            title: format!("Roll back · {}", entry.package_name),
+            hint: "z",
+            action: PaletteAction::RollBack,
+        });
+    }
     let orphans = app.queue.iter().filter(|e| App::is_orphan(e)).count();
     if orphans > 0 {
         commands.push(PaletteCommand {
@@ -249,6 +257,9 @@ pub async fn run(app: &mut App, action: PaletteAction) -> Result<()> {
         PaletteAction::RetryFailed => {
             app.retry_failed().await?;
         }
+        PaletteAction::RollBack => {
+            app.rollback_last_completed();
+        }
         PaletteAction::ReapOrphans => {
             app.reap_orphans().await?;
         }
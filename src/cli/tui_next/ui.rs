@@ -11,8 +11,9 @@ use crate::cli::tui_next::app::{
 };
 use crate::cli::tui_next::palette;
 use crate::models::history::{TaskQueueEntry, TaskQueueStatus};
-use crate::models::{Package, PackageStatus, UpdateCategory};
-use chrono::Local;
+use crate::models::scheduler::Deferral;
+use crate::models::{Package, PackageStatus, ScheduledTask, UpdateCategory};
+use chrono::{Local, Utc};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
@@ -293,12 +294,12 @@ fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
         .filter_map(|row| match row {
             Row::Item { id } => app
                 .package(id)
-                .map(|package| UnicodeWidthStr::width(meta_text(package).as_str())),
+                .map(|package| UnicodeWidthStr::width(meta_text(app, package).as_str())),
             Row::Header { .. } => None,
         })
         .max()
         .unwrap_or(10);
-    let meta_width = meta_max.clamp(8, 20);
+    let meta_width = meta_max.clamp(8, 28);
     // Column budget: markers(4) + name + gap(2) + versions + gap(2) + meta.
     let fixed = 4 + 2 + meta_width + 2;
     let spare = (list_area.width as usize).saturating_sub(fixed);
@@ -328,7 +329,7 @@ fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
                     ));
                     // Inline expansion: the list breathes.
                     if !dock && app.expanded.as_deref() == Some(id.as_str()) {
-                        for line in expansion_lines(package, list_area.width) {
+                        for line in expansion_lines(app, package, list_area.width) {
                             if lines.len() < height {
                                 lines.push(line);
                             }
@@ -344,7 +345,7 @@ fn draw_list(frame: &mut Frame, app: &mut App, area: Rect) {
 
     if let (Some(dock_area), Some(expanded_id)) = (dock_area, app.expanded.clone()) {
         if let Some(package) = app.package(&expanded_id) {
-            draw_dock(frame, package, dock_area);
+            draw_dock(frame, app, package, dock_area);
         }
     }
 }
@@ -413,14 +414,28 @@ fn section_header(label: &str, count: usize, is_cursor: bool, width: u16) -> Lin
     ])
 }
 
-fn meta_text(package: &Package) -> String {
+fn meta_text(app: &App, package: &Package) -> String {
     let mut parts = vec![package.source.to_string().to_lowercase()];
-    if package.size.is_some() {
+    if let Some(cooldown) = app.cooldown_label(package, Utc::now()) {
+        parts.push(cooldown);
+    } else if package.size.is_some() {
         parts.push(package.size_display());
     }
     parts.join(" · ")
 }
 
+/// "released Oct 15 · available in 3 days" for an update its provider's
+/// release cooldown is holding back.
+fn cooldown_text(app: &App, package: &Package) -> Option<String> {
+    let cooldown = app.cooldown_label(package, Utc::now())?;
+    let released = package.released_at?.with_timezone(&Local);
+    Some(format!(
+        "released {} · {}",
+        released.format("%b %d"),
+        cooldown
+    ))
+}
+
 fn package_row(
     app: &App,
     package: &Package,
@@ -452,6 +467,7 @@ fn package_row(
         {
             ("⚠", if is_cursor { cursor_style() } else { red() })
         }
+        _ if app.held.contains(&id) => ("‖", if is_cursor { cursor_style() } else { dim() }),
         PackageStatus::UpdateAvailable => ("↑", if is_cursor { cursor_style() } else { amber() }),
         _ if app.favorites.contains(&id) => (
             "★",
@@ -516,7 +532,7 @@ fn package_row(
         ));
     }
 
-    let meta_string = truncate(&meta_text(package), meta_width);
+    let meta_string = truncate(&meta_text(app, package), meta_width);
     let meta_pad = meta_width.saturating_sub(UnicodeWidthStr::width(meta_string.as_str()));
     spans.push(Span::styled("  ".to_string(), base));
     spans.push(Span::styled(" ".repeat(meta_pad), base));
@@ -525,7 +541,7 @@ fn package_row(
     Line::from(spans)
 }
 
-fn expansion_lines(package: &Package, width: u16) -> Vec<Line<'static>> {
+fn expansion_lines(app: &App, package: &Package, width: u16) -> Vec<Line<'static>> {
     let width = width as usize;
     let bar = "  │ ";
     let content_width = width.saturating_sub(UnicodeWidthStr::width(bar));
@@ -569,6 +585,12 @@ fn expansion_lines(package: &Package, width: u16) -> Vec<Line<'static>> {
         Span::styled(bar.to_string(), faint()),
         Span::styled(version_text, amber()),
     ]));
+    if let Some(cooldown) = cooldown_text(app, package) {
+        lines.push(Line::from(vec![
+            Span::styled(bar.to_string(), faint()),
+            Span::styled(truncate(&cooldown, content_width), dim()),
+        ]));
+    }
 
     let mut facts = Vec::new();
     if !package.dependencies.is_empty() {
@@ -602,7 +624,7 @@ fn expansion_lines(package: &Package, width: u16) -> Vec<Line<'static>> {
     lines
 }
 
-fn draw_dock(frame: &mut Frame, package: &Package, area: Rect) {
+fn draw_dock(frame: &mut Frame, app: &App, package: &Package, area: Rect) {
     let mut lines = Vec::new();
     lines.push(Line::from(Span::styled(
         format!(" {}", package.name),
@@ -634,6 +656,9 @@ fn draw_dock(frame: &mut Frame, package: &Package, area: Rect) {
         _ => format!(" {}", package.version),
     };
     lines.push(Line::from(Span::styled(version_text, amber())));
+    if let Some(cooldown) = cooldown_text(app, package) {
+        lines.push(Line::from(Span::styled(format!(" {cooldown}"), dim())));
+    }
 
     let mut facts = Vec::new();
     if package.size.is_some() {
@@ -784,11 +809,12 @@ fn draw_empty(frame: &mut Frame, app: &App, area: Rect) {
 // ---------------------------------------------------------------------
 
 fn queue_peek_height(app: &App) -> u16 {
-    if app.queue_open || app.queue.is_empty() {
+    if app.queue_open {
         return 0;
     }
     let (queued, running, failed, _) = app.queue_counts();
-    if queued + running + failed > 0 {
+    let (scheduled, deferred) = app.scheduled_counts();
+    if queued + running + failed + scheduled + deferred > 0 {
         1
     } else {
         0
@@ -822,6 +848,17 @@ fn draw_queue_peek(frame: &mut Frame, app: &App, area: Rect) {
         spans.push(Span::styled("   ·  ".to_string(), dim()));
         spans.push(Span::styled(format!("⚠ {orphans} orphaned"), amber()));
     }
+    let (scheduled, deferred) = app.scheduled_counts();
+    if scheduled > 0 {
+        spans.push(Span::styled(
+            format!("   ·  ◷ {scheduled} scheduled"),
+            dim(),
+        ));
+    }
+    if deferred > 0 {
+        spans.push(Span::styled("   ·  ".to_string(), dim()));
+        spans.push(Span::styled(format!("⏸ {deferred} deferred"), amber()));
+    }
     spans.push(Span::styled("   ·  tab to open", faint()));
     frame.render_widget(Paragraph::new(Line::from(spans)), area);
 }
@@ -949,6 +986,51 @@ fn draw_queue_drawer(frame: &mut Frame, app: &App, area: Rect) {
             ),
         ]));
     }
+    let mut scheduled: Vec<&ScheduledTask> = app
+        .scheduler
+        .tasks
+        .iter()
+        .filter(|task| task.is_active())
+        .collect();
+    scheduled.sort_by_key(|task| (task.deferred.is_none(), task.scheduled_at));
+    for task in scheduled.iter().take(4) {
+        let (glyph, glyph_style) = if task.deferred.is_some() {
+            (" ⏸ ", amber())
+        } else {
+            (" ◷ ", faint())
+        };
+        lines.push(Line::from(vec![
+            Span::styled(glyph, glyph_style),
+            Span::styled(truncate(&task.package_name, 30), fg()),
+            Span::styled(
+                format!(
+                    "  {} · {}",
+                    task.operation.display_name().to_lowercase(),
+                    task.scheduled_time_display()
+                ),
+                dim(),
+            ),
+        ]));
+        if let Some(deferral) = &task.deferred {
+            lines.push(deferral_line(deferral, area.width));
+        }
+    }
+    for rule in app
+        .scheduler
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled && rule.deferred.is_some())
+        .take(2)
+    {
+        lines.push(Line::from(vec![
+            Span::styled(" ⏸ ", amber()),
+            Span::styled(truncate(&rule.describe(), 40), fg()),
+            Span::styled(format!("  {}", rule.calendar), dim()),
+        ]));
+        if let Some(deferral) = &rule.deferred {
+            lines.push(deferral_line(deferral, area.width));
+        }
+    }
     for orphan in orphans.iter().take(2) {
         lines.push(Line::from(vec![
             Span::styled(" ⚠ ", amber()),
@@ -976,6 +1058,16 @@ fn draw_queue_drawer(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(Paragraph::new(lines), area);
 }
 
+fn deferral_line(deferral: &Deferral, width: u16) -> Line<'static> {
+    Line::from(vec![
+        Span::styled("   ", faint()),
+        Span::styled(
+            truncate(&deferral.describe(), width.saturating_sub(8) as usize),
+            amber(),
+        ),
+    ])
+}
+
 // ---------------------------------------------------------------------
 // Command bar
 // ---------------------------------------------------------------------
@@ -1121,7 +1213,7 @@ fn draw_palette(frame: &mut Frame, app: &App, query: &str) {
 
 fn draw_help(frame: &mut Frame) {
     dim_backdrop(frame);
-    let area = centered(frame, 62, 22);
+    let area = centered(frame, 62, 29);
     let inner = overlay_card(frame, area, "keys");
 
     let groups: &[(&str, &[(&str, &str)])] = &[
@@ -1139,7 +1231,7 @@ fn draw_help(frame: &mut Frame) {
                 ("enter", "expand / collapse"),
                 ("space", "select"),
                 ("u", "queue update"),
-                ("a", "queue all updates"),
+                ("a", "queue all updates (skips ‖ held)"),
                 ("f", "star / unstar"),
             ],
         ),
@@ -1157,6 +1249,8 @@ fn draw_help(frame: &mut Frame) {
                 ("tab", "queue drawer"),
                 ("r", "refresh · retry (in queue)"),
                 ("x", "reap orphaned (in queue)"),
+                ("z", "roll back last finished (in queue)"),
+                ("c", "cancel running task (in queue)"),
                 ("?", "help"),
                 ("q", "quit"),
             ],
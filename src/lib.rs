@@ -4,6 +4,7 @@ pub mod backend;
 pub mod cli;
 pub mod models;
 pub mod product;
+mod scheduler_conditions;
 mod scheduler_runtime;
 #[cfg(feature = "web")]
 pub mod web;
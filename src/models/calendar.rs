@@ -0,0 +1,459 @@
+//! Calendar expressions for recurring schedules. The syntax is the subset of
+//! systemd's `OnCalendar=` that LinGet needs: `[weekdays] [date] [time]`, such
+//! as `Sun 03:00`, `Mon..Fri *-*-* 08:30`, `*-*-01 00:00` or `*:0/15`, plus the
+//! `minutely`, `hourly`, `daily`, `weekly`, `monthly` and `yearly` shorthands.
+//! Maintenance windows reuse the weekday syntax: `[weekdays] HH:MM-HH:MM`.
+
+use anyhow::{bail, Context, Result};
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday,
+};
+use serde::{Deserialize, Serialize};
+
+/// How far ahead to look for the next occurrence before giving up.
+const SEARCH_DAYS: i64 = 366 * 8;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+/// A parsed calendar expression. It is stored as the text it was parsed
+/// from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CalendarSpec {
+    expression: String,
+    /// Allowed weekdays as a bit set over `Weekday::num_days_from_monday`;
+    /// zero means any day.
+    weekdays: u8,
+    years: Option<Vec<u32>>,
+    months: Option<Vec<u32>>,
+    days: Option<Vec<u32>>,
+    hours: Option<Vec<u32>>,
+    minutes: Option<Vec<u32>>,
+    seconds: Option<Vec<u32>>,
+}
+
+impl CalendarSpec {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.split_whitespace().collect::<Vec<_>>().join(" ");
+        let normalized = match expression.to_ascii_lowercase().as_str() {
+            "minutely" => "*-*-* *:*:00".to_string(),
+            "hourly" => "*-*-* *:00:00".to_string(),
+            "daily" => "*-*-* 00:00:00".to_string(),
+            "weekly" => "Mon *-*-* 00:00:00".to_string(),
+            "monthly" => "*-*-01 00:00:00".to_string(),
+            "yearly" | "annually" => "*-01-01 00:00:00".to_string(),
+            _ => expression.clone(),
+        };
+
+        let mut tokens = normalized.split(' ').filter(|token| !token.is_empty());
+        let mut token = tokens.next();
+        let mut weekdays = 0u8;
+        if let Some(value) = token.filter(|value| value.starts_with(char::is_alphabetic)) {
+            weekdays = parse_weekdays(value)?;
+            token = tokens.next();
+        }
+        let mut date = None;
+        if let Some(value) = token.filter(|value| value.contains('-')) {
+            date = Some(value);
+            token = tokens.next();
+        }
+        let mut time = None;
+        if let Some(value) = token.filter(|value| value.contains(':')) {
+            time = Some(value);
+            token = tokens.next();
+        }
+        if let Some(value) = token {
+            bail!("Unexpected `{}` in calendar expression", value);
+        }
+        if weekdays == 0 && date.is_none() && time.is_none() {
+            bail!("Calendar expression is empty");
+        }
+
+        let (years, months, days) = match date.map(|date| date.split('-').collect::<Vec<_>>()) {
+            None => (None, None, None),
+            Some(parts) if parts.len() == 3 => (
+                parse_field(parts[0], 1970, 2199).context("Bad year")?,
+                parse_field(parts[1], 1, 12).context("Bad month")?,
+                parse_field(parts[2], 1, 31).context("Bad day")?,
+            ),
+            Some(parts) if parts.len() == 2 => (
+                None,
+                parse_field(parts[0], 1, 12).context("Bad month")?,
+                parse_field(parts[1], 1, 31).context("Bad day")?,
+            ),
+            Some(_) => bail!("Dates are written YYYY-MM-DD or MM-DD"),
+        };
+        let (hours, minutes, seconds) = match time.map(|time| time.split(':').collect::<Vec<_>>()) {
+            None => (Some(vec![0]), Some(vec![0]), Some(vec![0])),
+            Some(parts) if parts.len() == 2 || parts.len() == 3 => (
+                parse_field(parts[0], 0, 23).context("Bad hour")?,
+                parse_field(parts[1], 0, 59).context("Bad minute")?,
+                match parts.get(2) {
+                    Some(part) => parse_field(part, 0, 59).context("Bad second")?,
+                    None => Some(vec![0]),
+                },
+            ),
+            Some(_) => bail!("Times are written HH:MM or HH:MM:SS"),
+        };
+
+        Ok(Self {
+            expression,
+            weekdays,
+            years,
+            months,
+            days,
+            hours,
+            minutes,
+            seconds,
+        })
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// The first occurrence strictly after `after`, in the same time zone.
+    /// Local times skipped by a daylight-saving change do not occur.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let start = after.naive_local().with_nanosecond(0)? + Duration::seconds(1);
+        let mut date = start.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.matches_date(date) {
+                let earliest = (date == start.date()).then(|| start.time());
+                for time in self.times_from(earliest) {
+                    if let Some(found) = timezone
+                        .from_local_datetime(&date.and_time(time))
+                        .earliest()
+                    {
+                        return Some(found);
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let allowed = |field: &Option<Vec<u32>>, value: u32| {
+            field.as_ref().is_none_or(|values| values.contains(&value))
+        };
+        (self.weekdays == 0 || self.weekdays & (1 << date.weekday().num_days_from_monday()) != 0)
+            && allowed(&self.years, date.year() as u32)
+            && allowed(&self.months, date.month())
+            && allowed(&self.days, date.day())
+    }
+
+    /// Matching times of day in order, from `earliest` when given.
+    fn times_from(&self, earliest: Option<NaiveTime>) -> impl Iterator<Item = NaiveTime> + '_ {
+        let values = |field: &Option<Vec<u32>>, max: u32| -> Vec<u32> {
+            field.clone().unwrap_or_else(|| (0..=max).collect())
+        };
+        let (hours, minutes, seconds) = (
+            values(&self.hours, 23),
+            values(&self.minutes, 59),
+            values(&self.seconds, 59),
+        );
+        hours
+            .into_iter()
+            .flat_map(move |hour| {
+                let seconds = seconds.clone();
+                minutes.clone().into_iter().flat_map(move |minute| {
+                    seconds
+                        .clone()
+                        .into_iter()
+                        .filter_map(move |second| NaiveTime::from_hms_opt(hour, minute, second))
+                })
+            })
+            .filter(move |time| earliest.is_none_or(|earliest| *time >= earliest))
+    }
+}
+
+impl TryFrom<String> for CalendarSpec {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        Self::parse(&expression).map_err(|error| format!("{:#}", error))
+    }
+}
+
+impl From<CalendarSpec> for String {
+    fn from(spec: CalendarSpec) -> Self {
+        spec.expression
+    }
+}
+
+impl std::fmt::Display for CalendarSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// A recurring local time window such as `22:00-06:00` or
+/// `Sat,Sun 01:00-05:00`. A window that ends before it starts runs past
+/// midnight, and its weekdays name the day it opens on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MaintenanceWindow {
+    expression: String,
+    /// Weekdays the window opens on, as in `CalendarSpec`; zero means any.
+    weekdays: u8,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl MaintenanceWindow {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.split_whitespace().collect::<Vec<_>>().join(" ");
+        let (weekdays, range) = match expression.split_once(' ') {
+            Some((days, range)) => (parse_weekdays(days)?, range),
+            None => (0, expression.as_str()),
+        };
+        let (start, end) = range
+            .split_once('-')
+            .with_context(|| format!("Expected `HH:MM-HH:MM`, found `{}`", range))?;
+        let time = |text: &str| {
+            NaiveTime::parse_from_str(text, "%H:%M")
+                .with_context(|| format!("`{}` is not a time of day", text))
+        };
+        let (start, end) = (time(start)?, time(end)?);
+        if start == end {
+            bail!("The window `{}` is empty", range);
+        }
+        Ok(Self {
+            expression,
+            weekdays,
+            start,
+            end,
+        })
+    }
+
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Whether the local time `at` falls inside the window.
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let opens_on = |date: NaiveDate| {
+            self.weekdays == 0 || self.weekdays & (1 << date.weekday().num_days_from_monday()) != 0
+        };
+        let time = at.time();
+        if self.start < self.end {
+            opens_on(at.date()) && time >= self.start && time < self.end
+        } else {
+            (time >= self.start && opens_on(at.date()))
+                || (time < self.end && at.date().pred_opt().is_some_and(opens_on))
+        }
+    }
+}
+
+impl TryFrom<String> for MaintenanceWindow {
+    type Error = String;
+
+    fn try_from(expression: String) -> Result<Self, Self::Error> {
+        Self::parse(&expression).map_err(|error| format!("{:#}", error))
+    }
+}
+
+impl From<MaintenanceWindow> for String {
+    fn from(window: MaintenanceWindow) -> Self {
+        window.expression
+    }
+}
+
+impl std::fmt::Display for MaintenanceWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+/// A weekday by its full name or any prefix of at least three letters.
+fn parse_weekday(value: &str) -> Result<Weekday> {
+    let lowered = value.to_ascii_lowercase();
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| lowered.len() >= 3 && name.starts_with(&lowered))
+        .map(|(_, weekday)| *weekday)
+        .with_context(|| format!("Unknown weekday `{}`", value))
+}
+
+fn parse_weekdays(value: &str) -> Result<u8> {
+    let mut set = 0u8;
+    for item in value.split(',') {
+        match item.split_once("..") {
+            Some((first, last)) => {
+                let (first, last) = (
+                    parse_weekday(first)?.num_days_from_monday(),
+                    parse_weekday(last)?.num_days_from_monday(),
+                );
+                if first > last {
+                    bail!("Weekday range `{}` runs backwards", item);
+                }
+                for day in first..=last {
+                    set |= 1 << day;
+                }
+            }
+            None => set |= 1 << parse_weekday(item)?.num_days_from_monday(),
+        }
+    }
+    Ok(set)
+}
+
+/// Parse one numeric field: `*`, a value, `a..b`, `a/step` or `*/step`, or a
+/// comma-separated list of those. `None` means any value.
+fn parse_field(value: &str, min: u32, max: u32) -> Result<Option<Vec<u32>>> {
+    if value == "*" {
+        return Ok(None);
+    }
+    let number = |text: &str| -> Result<u32> {
+        let parsed: u32 = text
+            .parse()
+            .with_context(|| format!("`{}` is not a number", text))?;
+        if !(min..=max).contains(&parsed) {
+            bail!("{} is outside {}..{}", parsed, min, max);
+        }
+        Ok(parsed)
+    };
+    let mut values = Vec::new();
+    for item in value.split(',') {
+        if let Some((start, step)) = item.split_once('/') {
+            let start = if start == "*" { min } else { number(start)? };
+            let step: u32 = step
+                .parse()
+                .ok()
+                .filter(|step| *step > 0)
+                .with_context(|| format!("`{}` is not a step", step))?;
+            values.extend((start..=max).step_by(step as usize));
+        } else if let Some((first, last)) = item.split_once("..") {
+            let (first, last) = (number(first)?, number(last)?);
+            if first > last {
+                bail!("Range `{}` runs backwards", item);
+            }
+            values.extend(first..=last);
+        } else {
+            values.push(number(item)?);
+        }
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(Some(values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn next(expression: &str, after: &str) -> Option<String> {
+        CalendarSpec::parse(expression)
+            .unwrap()
+            .next_after(&at(after))
+            .map(|next| next.to_rfc3339())
+    }
+
+    #[test]
+    fn weekly_rules_land_on_the_named_day() {
+        // 2026-10-14 is a Wednesday.
+        assert_eq!(
+            next("Sun 03:00", "2026-10-14T12:00:00Z").as_deref(),
+            Some("2026-10-18T03:00:00+00:00")
+        );
+        assert_eq!(
+            next("Sun 03:00", "2026-10-18T03:00:00Z").as_deref(),
+            Some("2026-10-25T03:00:00+00:00")
+        );
+        assert_eq!(
+            next("weekly", "2026-10-14T12:00:00Z").as_deref(),
+            Some("2026-10-19T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn shorthands_ranges_and_steps() {
+        assert_eq!(
+            next("daily", "2026-10-14T12:00:00Z").as_deref(),
+            Some("2026-10-15T00:00:00+00:00")
+        );
+        assert_eq!(
+            next("Mon..Fri *:0/15", "2026-10-16T23:50:00Z").as_deref(),
+            Some("2026-10-19T00:00:00+00:00")
+        );
+        assert_eq!(
+            next("*:0/15", "2026-10-14T12:07:30Z").as_deref(),
+            Some("2026-10-14T12:15:00+00:00")
+        );
+        assert_eq!(
+            next("*-*-31 04:00", "2026-11-01T00:00:00Z").as_deref(),
+            Some("2026-12-31T04:00:00+00:00")
+        );
+        assert_eq!(next("2025-01-01 00:00", "2026-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in [
+            "",
+            "Funday 03:00",
+            "25:00",
+            "*-13-*",
+            "03:00 extra",
+            "*:0/0",
+        ] {
+            assert!(
+                CalendarSpec::parse(expression).is_err(),
+                "`{}` should not parse",
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn round_trips_through_its_expression() {
+        let spec = CalendarSpec::parse("Sat,Sun  04:30").unwrap();
+        let json = serde_json::to_string(&spec).unwrap();
+
+        assert_eq!(json, "\"Sat,Sun 04:30\"");
+        assert_eq!(serde_json::from_str::<CalendarSpec>(&json).unwrap(), spec);
+    }
+
+    #[test]
+    fn maintenance_windows_wrap_past_midnight_from_their_opening_day() {
+        let local = |text: &str| NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").unwrap();
+        let nightly = MaintenanceWindow::parse("22:00-06:00").unwrap();
+        assert!(nightly.contains(local("2026-10-14 23:30")));
+        assert!(nightly.contains(local("2026-10-15 05:59")));
+        assert!(!nightly.contains(local("2026-10-15 06:00")));
+        assert!(!nightly.contains(local("2026-10-15 12:00")));
+
+        // 2026-10-16 is a Friday and 2026-10-17 a Saturday.
+        let weekend = MaintenanceWindow::parse("Sat,Sun 23:00-02:00").unwrap();
+        assert!(!weekend.contains(local("2026-10-16 23:30")));
+        assert!(!weekend.contains(local("2026-10-17 01:00")));
+        assert!(weekend.contains(local("2026-10-17 23:30")));
+        assert!(weekend.contains(local("2026-10-19 01:00")));
+        assert!(!weekend.contains(local("2026-10-20 01:00")));
+
+        let office = MaintenanceWindow::parse("Mon..Fri 12:00-13:00").unwrap();
+        assert!(office.contains(local("2026-10-16 12:30")));
+        assert!(!office.contains(local("2026-10-17 12:30")));
+
+        for invalid in ["22:00", "22:00-22:00", "Someday 01:00-02:00", "25:00-02:00"] {
+            assert!(MaintenanceWindow::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
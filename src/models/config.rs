@@ -1,11 +1,11 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 
 use super::appearance::AppearanceConfig;
 use super::scheduler::SchedulerState;
-use super::PackageSource;
+use super::{PackageSource, PluginId};
 
 /// Application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -122,6 +122,19 @@ pub struct Config {
     /// Retain completed task queue entries across sessions
     #[serde(default = "default_retain_task_queue_history")]
     pub retain_task_queue_history: bool,
+
+    /// How many task queue lanes may run at once. Tasks for one provider,
+    /// and providers behind the same privilege prompt, always run in turn.
+    #[serde(default = "default_queue_concurrency")]
+    pub queue_concurrency: usize,
+
+    /// Filesystem snapshots taken before high-risk or system-provider plans
+    #[serde(default)]
+    pub snapshots: SnapshotConfig,
+
+    /// Commands run before and after matching package operations
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
@@ -230,6 +243,126 @@ fn default_retain_task_queue_history() -> bool {
     true
 }
 
+fn default_queue_concurrency() -> usize {
+    3
+}
+
+/// Tool used to take a filesystem snapshot before a plan runs.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotDriverKind {
+    Snapper,
+    Timeshift,
+    Btrfs,
+}
+
+impl SnapshotDriverKind {
+    /// Detection order when no driver is configured.
+    pub const ALL: [SnapshotDriverKind; 3] = [
+        SnapshotDriverKind::Snapper,
+        SnapshotDriverKind::Timeshift,
+        SnapshotDriverKind::Btrfs,
+    ];
+
+    pub fn program(&self) -> &'static str {
+        match self {
+            SnapshotDriverKind::Snapper => "snapper",
+            SnapshotDriverKind::Timeshift => "timeshift",
+            SnapshotDriverKind::Btrfs => "btrfs",
+        }
+    }
+}
+
+impl std::fmt::Display for SnapshotDriverKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.program())
+    }
+}
+
+/// Pre-transaction filesystem snapshots. Off by default.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SnapshotConfig {
+    pub enabled: bool,
+    /// Driver to use; the first one installed is used when unset
+    pub driver: Option<SnapshotDriverKind>,
+    /// Snapper configuration to snapshot
+    pub snapper_config: String,
+    /// Subvolume the `btrfs` driver snapshots
+    pub btrfs_subvolume: PathBuf,
+    /// Directory the `btrfs` driver creates read-only snapshots in
+    pub btrfs_snapshot_dir: PathBuf,
+}
+
+impl Default for SnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            driver: None,
+            snapper_config: "root".to_string(),
+            btrfs_subvolume: PathBuf::from("/"),
+            btrfs_snapshot_dir: PathBuf::from("/.snapshots"),
+        }
+    }
+}
+
+/// When a hook runs relative to the operation it matches.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HookStage {
+    /// Before the provider runs; a non-zero exit cancels the operation.
+    Pre,
+    /// After the operation finished, successfully or not.
+    Post,
+}
+
+impl std::fmt::Display for HookStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookStage::Pre => f.write_str("pre"),
+            HookStage::Post => f.write_str("post"),
+        }
+    }
+}
+
+/// A user command run around package operations. Empty match lists match
+/// everything.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HookConfig {
+    /// Shown in the task log; defaults to the command
+    #[serde(default)]
+    pub name: String,
+    pub stage: HookStage,
+    /// Run with `sh -c`; the plan (and the receipt, for post hooks) arrives
+    /// as JSON on stdin
+    pub command: String,
+    /// Provider names such as `apt` or `flatpak`
+    #[serde(default)]
+    pub providers: Vec<String>,
+    /// `install`, `remove`, `update` or `rollback`
+    #[serde(default)]
+    pub actions: Vec<String>,
+    /// Package name globs (`*` and `?`); the hook runs if any target matches
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default = "default_hook_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl HookConfig {
+    pub fn label(&self) -> &str {
+        if self.name.is_empty() {
+            &self.command
+        } else {
+            &self.name
+        }
+    }
+}
+
+fn default_hook_timeout_secs() -> u64 {
+    600
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct EnabledSources {
@@ -253,6 +386,9 @@ pub struct EnabledSources {
     pub winget: bool,
     pub chocolatey: bool,
     pub scoop: bool,
+    /// Provider plugins switched off; any other plugin found is enabled.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub disabled_plugins: BTreeSet<String>,
 }
 
 impl Default for EnabledSources {
@@ -278,6 +414,7 @@ impl Default for EnabledSources {
             winget: true,
             chocolatey: true,
             scoop: true,
+            disabled_plugins: BTreeSet::new(),
         }
     }
 }
@@ -305,6 +442,13 @@ impl EnabledSources {
             PackageSource::Winget => self.winget = enabled,
             PackageSource::Chocolatey => self.chocolatey = enabled,
             PackageSource::Scoop => self.scoop = enabled,
+            PackageSource::Plugin(id) => {
+                if enabled {
+                    self.disabled_plugins.remove(id.name());
+                } else {
+                    self.disabled_plugins.insert(id.name().to_string());
+                }
+            }
         }
     }
 
@@ -330,6 +474,7 @@ impl EnabledSources {
             PackageSource::Winget => self.winget,
             PackageSource::Chocolatey => self.chocolatey,
             PackageSource::Scoop => self.scoop,
+            PackageSource::Plugin(id) => !self.disabled_plugins.contains(id.name()),
         }
     }
 
@@ -355,6 +500,11 @@ impl EnabledSources {
             winget: sources.contains(&PackageSource::Winget),
             chocolatey: sources.contains(&PackageSource::Chocolatey),
             scoop: sources.contains(&PackageSource::Scoop),
+            disabled_plugins: PluginId::known()
+                .into_iter()
+                .filter(|id| !sources.contains(&PackageSource::Plugin(*id)))
+                .map(|id| id.name().to_string())
+                .collect(),
         }
     }
 
@@ -420,6 +570,12 @@ impl EnabledSources {
         if self.scoop {
             sources.insert(PackageSource::Scoop);
         }
+        sources.extend(
+            PluginId::known()
+                .into_iter()
+                .map(PackageSource::Plugin)
+                .filter(|source| self.get(*source)),
+        );
         sources
     }
 }
@@ -459,6 +615,9 @@ impl Default for Config {
             appearance: AppearanceConfig::default(),
             scheduler: SchedulerState::default(),
             retain_task_queue_history: default_retain_task_queue_history(),
+            queue_concurrency: default_queue_concurrency(),
+            snapshots: SnapshotConfig::default(),
+            hooks: Vec::new(),
         }
     }
 }
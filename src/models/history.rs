@@ -1,6 +1,6 @@
 #![allow(dead_code)]
 
-use crate::models::PackageSource;
+use crate::models::{Package, PackageSource, PackageStatus};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
@@ -464,11 +464,36 @@ pub struct TaskQueueEntry {
     pub reviewed_plan_json: Option<String>,
     #[serde(default)]
     pub verification_receipt_json: Option<String>,
+    /// A reviewed multi-provider `TransactionBundle`; when present it takes the
+    /// place of `reviewed_plan_json` and `reviewed_operation_id` holds the
+    /// bundle id.
+    #[serde(default)]
+    pub reviewed_bundle_json: Option<String>,
+    #[serde(default)]
+    pub bundle_summary_json: Option<String>,
+    /// `FilesystemSnapshot` taken just before the task's operation ran.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_json: Option<String>,
+    /// `PlanDiff` between the reviewed plan and its re-plan, when the reviewed
+    /// plan went stale before it could run. `reviewed_plan_json` then holds the
+    /// fresh plan, and retrying the task approves it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_plan_diff_json: Option<String>,
     /// Task id of the failed attempt this entry retries. The reviewed plan is
     /// refreshed by the executor before a retry runs; this durable link keeps
     /// the queue journey coherent across restarts.
     #[serde(default)]
     pub retry_of: Option<String>,
+    /// On a retry of a task whose plan went stale: the operation id of the
+    /// refreshed plan that retrying approved. That plan runs as stored
+    /// instead of being planned afresh.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved_stale_operation_id: Option<String>,
+    /// For updates queued without a reviewed plan: the version the provider
+    /// offered, or the installed version when no update was pending. The
+    /// executor verifies the outcome against it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub available_version: Option<String>,
     /// The process that started this task, so a second LinGet instance can tell
     /// a genuinely running task from one orphaned by a dead session. Absent on
     /// entries written before this was recorded.
@@ -497,11 +522,36 @@ impl TaskQueueEntry {
             reviewed_operation_id: None,
             reviewed_plan_json: None,
             verification_receipt_json: None,
+            reviewed_bundle_json: None,
+            bundle_summary_json: None,
+            snapshot_json: None,
+            stale_plan_diff_json: None,
             retry_of: None,
+            approved_stale_operation_id: None,
+            available_version: None,
             owner_pid: None,
         }
     }
 
+    /// A task for `package`, remembering what an update expects to land.
+    pub fn for_package(action: TaskQueueAction, package: &Package) -> Self {
+        let mut entry = Self::new(action, package.id(), package.name.clone(), package.source);
+        if action == TaskQueueAction::Update {
+            entry.available_version = match package.status {
+                PackageStatus::UpdateAvailable => package.available_version.clone(),
+                _ => Some(package.version.clone()).filter(|version| !version.is_empty()),
+            };
+        }
+        entry
+    }
+
+    /// The refreshed plan a retry of this failed task approves, when the
+    /// task failed because its reviewed plan went stale.
+    pub fn stale_plan_approval(&self) -> Option<String> {
+        self.stale_plan_diff_json.as_ref()?;
+        self.reviewed_operation_id.clone()
+    }
+
     pub fn mark_running(&mut self) {
         self.status = TaskQueueStatus::Running;
         self.owner_pid = Some(std::process::id());
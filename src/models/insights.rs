@@ -12,7 +12,15 @@ pub struct PackageInsights {
     pub package_commands: Vec<String>,
     pub log_command: Option<String>,
     pub is_safe_to_remove: bool,
+    /// Installed packages the removal would break, counting dependents of
+    /// dependents; zero until the transitive analysis has run.
+    pub transitive_dependents: usize,
+    /// The removal reaches a package the provider marks essential or
+    /// protected, so LinGet would refuse it.
+    pub is_protected: bool,
     pub shared_deps_count: usize,
+    /// Pinned by the provider's own hold mechanism (apt-mark, versionlock, …)
+    pub is_held: bool,
 }
 
 impl PackageInsights {
@@ -37,6 +45,16 @@ impl PackageInsights {
         self
     }
 
+    /// Refine the verdict with the transitive removal analysis, which can
+    /// find breakage the direct reverse dependencies do not show.
+    pub fn with_removal_impact(mut self, dependents: usize, protected: bool) -> Self {
+        self.transitive_dependents = dependents;
+        self.is_protected = protected;
+        self.is_safe_to_remove =
+            self.reverse_dependencies.is_empty() && dependents == 0 && !protected;
+        self
+    }
+
     pub fn with_config_paths(mut self, paths: Vec<String>) -> Self {
         self.config_paths = paths;
         self
@@ -52,6 +70,11 @@ impl PackageInsights {
         self
     }
 
+    pub fn with_held(mut self, held: bool) -> Self {
+        self.is_held = held;
+        self
+    }
+
     pub fn install_age_display(&self) -> Option<String> {
         let install_date = self.install_date?;
         let now = Local::now();
@@ -133,7 +156,12 @@ impl PackageInsights {
     }
 
     pub fn safe_to_remove_display(&self) -> (&'static str, &'static str) {
-        if self.is_safe_to_remove {
+        if self.is_protected {
+            (
+                "action-unavailable-symbolic",
+                "Protected; LinGet will not remove it",
+            )
+        } else if self.is_safe_to_remove {
             ("emblem-ok-symbolic", "Safe to remove")
         } else {
             ("dialog-warning-symbolic", "May break other packages")
@@ -0,0 +1,577 @@
+//! Hand-written `linget.toml` manifests: the packages, versions, holds and
+//! sources a machine should have, and the drift between that and what it
+//! actually has.
+
+use super::{Package, PackageSource};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+pub const DEFAULT_MANIFEST_FILE: &str = "linget.toml";
+
+/// A parsed and validated manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// Sources that should be enabled, when the manifest manages them.
+    pub sources: Option<Vec<PackageSource>>,
+    pub packages: Vec<ManifestPackage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestPackage {
+    pub source: PackageSource,
+    pub name: String,
+    pub version: Option<VersionConstraint>,
+    pub hold: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawManifest {
+    #[serde(default)]
+    sources: Option<Vec<String>>,
+    #[serde(default)]
+    packages: BTreeMap<String, BTreeMap<String, RawPackageSpec>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawPackageSpec {
+    Version(String),
+    Table(RawPackageTable),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPackageTable {
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    hold: bool,
+}
+
+fn parse_source(raw: &str) -> Result<PackageSource> {
+    PackageSource::from_config_str(raw).with_context(|| format!("Unknown source `{}`", raw))
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read manifest {}", path.display()))?;
+        Self::from_toml_str(&content)
+            .map_err(|error| anyhow!("Invalid manifest {}: {:#}", path.display(), error))
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let raw: RawManifest = toml::from_str(content)?;
+        let sources = raw
+            .sources
+            .map(|sources| {
+                sources
+                    .iter()
+                    .map(|source| parse_source(source))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        let mut packages = Vec::new();
+        for (source_key, specs) in raw.packages {
+            let source = parse_source(&source_key)?;
+            if sources
+                .as_ref()
+                .is_some_and(|sources| !sources.contains(&source))
+            {
+                bail!(
+                    "[packages.{}] lists packages for a source that `sources` does not enable",
+                    source_key
+                );
+            }
+            for (name, spec) in specs {
+                let (version, hold) = match spec {
+                    RawPackageSpec::Version(version) => (Some(version), false),
+                    RawPackageSpec::Table(table) => (table.version, table.hold),
+                };
+                let version = version
+                    .as_deref()
+                    .map(VersionConstraint::parse)
+                    .transpose()
+                    .with_context(|| {
+                        format!("Bad version for {} in [packages.{}]", name, source_key)
+                    })?
+                    .flatten();
+                packages.push(ManifestPackage {
+                    source,
+                    name,
+                    version,
+                    hold,
+                });
+            }
+        }
+
+        Ok(Self { sources, packages })
+    }
+
+    /// Providers the manifest lists packages for.
+    pub fn declared_sources(&self) -> Vec<PackageSource> {
+        let mut sources: Vec<_> = self.packages.iter().map(|package| package.source).collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Compare the manifest with the live inventory.
+    pub fn diff(&self, inventory: &Inventory) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        let declared = self.declared_sources();
+        diff.unavailable = declared
+            .iter()
+            .filter(|source| !inventory.available.contains(source))
+            .copied()
+            .collect();
+
+        let installed: HashMap<(PackageSource, &str), &Package> = inventory
+            .installed
+            .iter()
+            .map(|package| ((package.source, package.name.as_str()), package))
+            .collect();
+
+        for wanted in &self.packages {
+            if diff.unavailable.contains(&wanted.source) {
+                continue;
+            }
+            let held = inventory
+                .holds
+                .get(&wanted.source)
+                .is_some_and(|holds| holds.iter().any(|name| name == &wanted.name));
+            let constraint = wanted.version.as_ref().map(ToString::to_string);
+            match installed.get(&(wanted.source, wanted.name.as_str())) {
+                None => diff.missing.push(DriftEntry::new(wanted, None, constraint)),
+                Some(package) => {
+                    let satisfied = wanted
+                        .version
+                        .as_ref()
+                        .is_none_or(|version| version.matches(&package.version));
+                    if !satisfied {
+                        let update = inventory.updates.iter().find_map(|update| {
+                            (update.source == wanted.source && update.name == wanted.name)
+                                .then_some(update.available_version.as_deref())
+                                .flatten()
+                                .filter(|available| {
+                                    wanted
+                                        .version
+                                        .as_ref()
+                                        .is_some_and(|version| version.matches(available))
+                                })
+                        });
+                        let entry = DriftEntry::new(
+                            wanted,
+                            Some(package.version.clone()),
+                            update.map(str::to_string).or(constraint),
+                        );
+                        match update {
+                            Some(_) => diff.outdated.push(entry),
+                            None => diff.mismatched.push(entry),
+                        }
+                    }
+                    if wanted.hold && !held {
+                        diff.hold.push(DriftEntry::new(
+                            wanted,
+                            Some(package.version.clone()),
+                            None,
+                        ));
+                    }
+                }
+            }
+            if !wanted.hold && held {
+                diff.release.push(DriftEntry::new(wanted, None, None));
+            }
+            if wanted.hold && !installed.contains_key(&(wanted.source, wanted.name.as_str())) {
+                diff.hold.push(DriftEntry::new(wanted, None, None));
+            }
+        }
+
+        let listed: HashSet<(PackageSource, &str)> = self
+            .packages
+            .iter()
+            .map(|package| (package.source, package.name.as_str()))
+            .collect();
+        diff.unlisted = inventory
+            .installed
+            .iter()
+            .filter(|package| {
+                declared.contains(&package.source)
+                    && !diff.unavailable.contains(&package.source)
+                    && !listed.contains(&(package.source, package.name.as_str()))
+            })
+            .map(|package| DriftEntry {
+                source: package.source,
+                name: package.name.clone(),
+                installed: Some(package.version.clone()),
+                wanted: None,
+            })
+            .collect();
+        diff.unlisted
+            .sort_by(|left, right| (left.source, &left.name).cmp(&(right.source, &right.name)));
+
+        if let Some(sources) = &self.sources {
+            diff.enable = sources
+                .iter()
+                .filter(|source| !inventory.enabled.contains(source))
+                .copied()
+                .collect();
+            let mut disable: Vec<_> = inventory
+                .enabled
+                .iter()
+                .filter(|source| inventory.available.contains(source) && !sources.contains(source))
+                .copied()
+                .collect();
+            disable.sort();
+            diff.disable = disable;
+        }
+
+        diff
+    }
+}
+
+/// What the machine has right now, as far as the manifest is concerned.
+#[derive(Debug, Clone, Default)]
+pub struct Inventory {
+    pub installed: Vec<Package>,
+    /// Pending updates, with `available_version` set.
+    pub updates: Vec<Package>,
+    pub holds: HashMap<PackageSource, Vec<String>>,
+    pub enabled: HashSet<PackageSource>,
+    pub available: HashSet<PackageSource>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DriftEntry {
+    pub source: PackageSource,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed: Option<String>,
+    /// The version constraint, or the update that satisfies it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wanted: Option<String>,
+}
+
+impl DriftEntry {
+    fn new(package: &ManifestPackage, installed: Option<String>, wanted: Option<String>) -> Self {
+        Self {
+            source: package.source,
+            name: package.name.clone(),
+            installed,
+            wanted,
+        }
+    }
+}
+
+/// Drift between a manifest and the live inventory. `unlisted` is only acted
+/// on when pruning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ManifestDiff {
+    /// Listed but not installed.
+    pub missing: Vec<DriftEntry>,
+    /// Installed outside the constraint, with an update that satisfies it.
+    pub outdated: Vec<DriftEntry>,
+    /// Installed outside the constraint, with no update that satisfies it.
+    pub mismatched: Vec<DriftEntry>,
+    /// Installed from a declared provider but not listed. `linget diff` and
+    /// `apply` narrow it to what pruning can remove: packages installed
+    /// explicitly, not protected, and needed by nothing that stays.
+    pub unlisted: Vec<DriftEntry>,
+    pub hold: Vec<DriftEntry>,
+    pub release: Vec<DriftEntry>,
+    pub enable: Vec<PackageSource>,
+    pub disable: Vec<PackageSource>,
+    /// Declared providers that are not available on this system.
+    pub unavailable: Vec<PackageSource>,
+}
+
+impl ManifestDiff {
+    /// True when nothing needs to change, ignoring unlisted packages unless
+    /// `prune` is set.
+    pub fn is_converged(&self, prune: bool) -> bool {
+        self.missing.is_empty()
+            && self.outdated.is_empty()
+            && self.mismatched.is_empty()
+            && (!prune || self.unlisted.is_empty())
+            && self.hold.is_empty()
+            && self.release.is_empty()
+            && self.enable.is_empty()
+            && self.disable.is_empty()
+    }
+}
+
+/// A version requirement: `1.2.3` (that version, with any distro revision),
+/// `1.24.*`, or a comparison such as `>=13`. `*` means any version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionConstraint {
+    Exact(String),
+    Prefix(String),
+    Compare(Ordering, bool, String),
+}
+
+impl VersionConstraint {
+    /// Parse a constraint; `*` and the empty string yield `None`.
+    pub fn parse(raw: &str) -> Result<Option<Self>> {
+        let raw = raw.trim();
+        if raw.is_empty() || raw == "*" {
+            return Ok(None);
+        }
+        let (constraint, version) = if let Some(version) = raw.strip_prefix(">=") {
+            (
+                Self::Compare(Ordering::Greater, true, String::new()),
+                version,
+            )
+        } else if let Some(version) = raw.strip_prefix("<=") {
+            (Self::Compare(Ordering::Less, true, String::new()), version)
+        } else if let Some(version) = raw.strip_prefix('>') {
+            (
+                Self::Compare(Ordering::Greater, false, String::new()),
+                version,
+            )
+        } else if let Some(version) = raw.strip_prefix('<') {
+            (Self::Compare(Ordering::Less, false, String::new()), version)
+        } else if let Some(version) = raw.strip_suffix(".*") {
+            (Self::Prefix(String::new()), version)
+        } else {
+            (
+                Self::Exact(String::new()),
+                raw.strip_prefix('=').unwrap_or(raw),
+            )
+        };
+        let version = version.trim().to_string();
+        if version.is_empty() || version.contains(char::is_whitespace) || version.contains('*') {
+            bail!("`{}` is not a version constraint", raw);
+        }
+        Ok(Some(match constraint {
+            Self::Compare(ordering, or_equal, _) => Self::Compare(ordering, or_equal, version),
+            Self::Prefix(_) => Self::Prefix(version),
+            Self::Exact(_) => Self::Exact(version),
+        }))
+    }
+
+    pub fn matches(&self, installed: &str) -> bool {
+        match self {
+            Self::Exact(version) => {
+                installed == version
+                    || installed
+                        .strip_prefix(version.as_str())
+                        .is_some_and(|rest| rest.starts_with(['-', '+', '~']))
+            }
+            Self::Prefix(prefix) => {
+                installed == prefix
+                    || installed
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            }
+            Self::Compare(ordering, or_equal, version) => {
+                let found = compare_versions(installed, version);
+                found == *ordering || (*or_equal && found == Ordering::Equal)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for VersionConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exact(version) => write!(f, "{}", version),
+            Self::Prefix(prefix) => write!(f, "{}.*", prefix),
+            Self::Compare(ordering, or_equal, version) => {
+                let operator = if *ordering == Ordering::Greater {
+                    ">"
+                } else {
+                    "<"
+                };
+                write!(
+                    f,
+                    "{}{}{}",
+                    operator,
+                    if *or_equal { "=" } else { "" },
+                    version
+                )
+            }
+        }
+    }
+}
+
+/// The numeric parts of a version string, ignoring an epoch.
+pub(crate) fn version_parts(version: &str) -> Vec<u64> {
+    let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+    version
+        .split(['.', '-', '+', '~'])
+        .map_while(|part| {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse::<u64>().ok()
+        })
+        .collect()
+}
+
+/// Compare the numeric parts of two version strings, ignoring an epoch.
+pub(crate) fn compare_versions(left: &str, right: &str) -> Ordering {
+    let (left, right) = (version_parts(left), version_parts(right));
+    for index in 0..left.len().max(right.len()) {
+        let ordering = left
+            .get(index)
+            .unwrap_or(&0)
+            .cmp(right.get(index).unwrap_or(&0));
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PackageStatus;
+
+    fn installed(name: &str, source: PackageSource, version: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            available_version: None,
+            description: String::new(),
+            source,
+            status: PackageStatus::Installed,
+            size: None,
+            homepage: None,
+            license: None,
+            maintainer: None,
+            dependencies: Vec::new(),
+            install_date: None,
+            update_category: None,
+            enrichment: None,
+            released_at: None,
+        }
+    }
+
+    const MANIFEST: &str = r#"
+sources = ["apt", "flatpak"]
+
+[packages.apt]
+git = "*"
+ripgrep = ">=13"
+nginx = { version = "1.24.*", hold = true }
+curl = "8.5.0"
+
+[packages.flatpak]
+"org.gnome.Calculator" = "*"
+"#;
+
+    #[test]
+    fn parses_packages_constraints_and_holds() {
+        let manifest = Manifest::from_toml_str(MANIFEST).unwrap();
+
+        assert_eq!(
+            manifest.sources,
+            Some(vec![PackageSource::Apt, PackageSource::Flatpak])
+        );
+        assert_eq!(manifest.packages.len(), 5);
+        let nginx = manifest
+            .packages
+            .iter()
+            .find(|package| package.name == "nginx")
+            .unwrap();
+        assert!(nginx.hold);
+        assert_eq!(
+            nginx.version,
+            Some(VersionConstraint::Prefix("1.24".to_string()))
+        );
+        assert_eq!(
+            manifest.declared_sources(),
+            vec![PackageSource::Apt, PackageSource::Flatpak]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_sources_bad_constraints_and_undeclared_sources() {
+        assert!(Manifest::from_toml_str("[packages.nope]\nfoo = \"*\"").is_err());
+        assert!(Manifest::from_toml_str("[packages.apt]\nfoo = \">= 1 2\"").is_err());
+        assert!(
+            Manifest::from_toml_str("sources = [\"apt\"]\n[packages.pip]\nfoo = \"*\"").is_err()
+        );
+        assert!(Manifest::from_toml_str("[packages.apt]\nfoo = { pin = true }").is_err());
+    }
+
+    #[test]
+    fn version_constraints_match_distro_versions() {
+        let at_least = VersionConstraint::parse(">=13").unwrap().unwrap();
+        assert!(at_least.matches("14.1.0-1"));
+        assert!(at_least.matches("13.0.0"));
+        assert!(!at_least.matches("12.9"));
+
+        let series = VersionConstraint::parse("1.24.*").unwrap().unwrap();
+        assert!(series.matches("1.24.0-2ubuntu7"));
+        assert!(!series.matches("1.240.1"));
+
+        let exact = VersionConstraint::parse("8.5.0").unwrap().unwrap();
+        assert!(exact.matches("8.5.0-2ubuntu10"));
+        assert!(!exact.matches("8.5.01"));
+
+        let below = VersionConstraint::parse("<2:1.0").unwrap().unwrap();
+        assert!(below.matches("1:0.9"));
+        assert_eq!(VersionConstraint::parse("*").unwrap(), None);
+    }
+
+    #[test]
+    fn diff_reports_every_kind_of_drift() {
+        let manifest = Manifest::from_toml_str(MANIFEST).unwrap();
+        let mut update = installed("ripgrep", PackageSource::Apt, "12.1.1");
+        update.available_version = Some("13.0.0".to_string());
+        let inventory = Inventory {
+            installed: vec![
+                installed("ripgrep", PackageSource::Apt, "12.1.1"),
+                installed("nginx", PackageSource::Apt, "1.24.0-2"),
+                installed("curl", PackageSource::Apt, "8.4.0-1"),
+                installed("htop", PackageSource::Apt, "3.3.0"),
+                installed("org.gnome.Calculator", PackageSource::Flatpak, "46.0"),
+                installed("black", PackageSource::Pip, "24.1"),
+            ],
+            updates: vec![update],
+            holds: HashMap::from([(PackageSource::Apt, vec!["curl".to_string()])]),
+            enabled: HashSet::from([PackageSource::Apt, PackageSource::Pip]),
+            available: HashSet::from([
+                PackageSource::Apt,
+                PackageSource::Flatpak,
+                PackageSource::Pip,
+            ]),
+        };
+
+        let diff = manifest.diff(&inventory);
+        let names = |entries: &[DriftEntry]| -> Vec<String> {
+            entries.iter().map(|entry| entry.name.clone()).collect()
+        };
+
+        assert_eq!(names(&diff.missing), vec!["git"]);
+        assert_eq!(names(&diff.outdated), vec!["ripgrep"]);
+        assert_eq!(diff.outdated[0].wanted.as_deref(), Some("13.0.0"));
+        assert_eq!(names(&diff.mismatched), vec!["curl"]);
+        assert_eq!(names(&diff.unlisted), vec!["htop"]);
+        assert_eq!(names(&diff.hold), vec!["nginx"]);
+        assert_eq!(names(&diff.release), vec!["curl"]);
+        assert_eq!(diff.enable, vec![PackageSource::Flatpak]);
+        assert_eq!(diff.disable, vec![PackageSource::Pip]);
+        assert!(!diff.is_converged(false));
+    }
+
+    #[test]
+    fn providers_missing_from_this_system_are_skipped() {
+        let manifest = Manifest::from_toml_str("[packages.snap]\ncode = \"*\"").unwrap();
+        let inventory = Inventory {
+            available: HashSet::from([PackageSource::Apt]),
+            ..Inventory::default()
+        };
+
+        let diff = manifest.diff(&inventory);
+
+        assert_eq!(diff.unavailable, vec![PackageSource::Snap]);
+        assert!(diff.missing.is_empty());
+        assert!(diff.is_converged(true));
+    }
+}
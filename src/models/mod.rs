@@ -1,5 +1,6 @@
 pub mod alias;
 pub mod appearance;
+pub mod calendar;
 pub mod changelog;
 mod config;
 mod enrichment;
@@ -8,8 +9,10 @@ pub mod health;
 pub mod history;
 mod icons;
 pub mod insights;
+pub mod manifest;
 mod package;
 pub mod package_list;
+pub mod policy;
 pub mod recommendations;
 mod repository;
 pub mod scheduler;
@@ -24,16 +27,21 @@ pub use history::{
 };
 pub use icons::*;
 pub use insights::{guess_config_paths, guess_log_command, parse_install_date, PackageInsights};
+pub use manifest::{Manifest, ManifestDiff};
 pub use package::{
     detect_duplicates, normalize_name_for_dedup, Package, PackageEnrichment, PackageSource,
-    PackageStatus, UpdateCategory,
+    PackageStatus, PluginId, UpdateCategory,
 };
 pub use package_list::{ExportedPackage, PackageListExport};
+pub use policy::{UpdateLane, UpdatePolicy, UpdateSightings, Verdict};
 pub use recommendations::{
     get_global_recommendations, get_package_recommendations, Recommendation,
 };
 pub use repository::*;
-pub use scheduler::{SchedulePreset, ScheduledOperation, ScheduledTask, SchedulerState};
+pub use scheduler::{
+    CatchUp, RecurringRule, RuleOutcome, RuleRun, SchedulePreset, ScheduledOperation,
+    ScheduledTask, SchedulerState,
+};
 
 pub use appearance::{
     AppearanceConfig, BorderRadius, BorderStyle, CardSize, FontScale, GlowIntensity, GridColumns,
@@ -1,5 +1,7 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::sync::RwLock;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SourcePlatform {
@@ -35,7 +37,7 @@ impl ElevationModel {
 }
 
 /// Represents which package manager a package belongs to
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PackageSource {
     Apt,
     Dnf,
@@ -57,6 +59,110 @@ pub enum PackageSource {
     Winget,
     Chocolatey,
     Scoop,
+    /// An external provider speaking the plugin protocol.
+    Plugin(PluginId),
+}
+
+/// Names of the external providers seen by this process, indexed by
+/// `PluginId`. Each name is leaked once so a plugin source stays `Copy`
+/// and its labels can be `&'static str` like the built-in ones. The table
+/// stops growing once every `PluginId` is taken, which bounds the leak.
+static PLUGIN_NAMES: RwLock<Vec<PluginNames>> = RwLock::new(Vec::new());
+
+struct PluginNames {
+    name: &'static str,
+    config: &'static str,
+}
+
+/// An external provider, known by the `<name>` of its
+/// `linget-provider-<name>` executable.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PluginId(u16);
+
+impl PluginId {
+    /// The id for `name`, registering it on first use. `None` once every
+    /// id is taken by another name.
+    pub fn intern(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if let Some(id) = Self::find(&name) {
+            return Some(id);
+        }
+        let mut names = PLUGIN_NAMES
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(index) = names.iter().position(|entry| entry.name == name) {
+            return Some(Self(index as u16));
+        }
+        let id = Self(u16::try_from(names.len()).ok()?);
+        let config: &'static str = Box::leak(format!("plugin:{}", name).into_boxed_str());
+        names.push(PluginNames {
+            name: &config["plugin:".len()..],
+            config,
+        });
+        Some(id)
+    }
+
+    fn find(name: &str) -> Option<Self> {
+        PLUGIN_NAMES
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .iter()
+            .position(|entry| entry.name == name)
+            .map(|index| Self(index as u16))
+    }
+
+    fn names(self) -> (&'static str, &'static str) {
+        let names = PLUGIN_NAMES
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        let entry = &names[self.0 as usize];
+        (entry.name, entry.config)
+    }
+
+    pub fn name(self) -> &'static str {
+        self.names().0
+    }
+
+    /// Every plugin this process has seen so far.
+    pub fn known() -> Vec<Self> {
+        let count = PLUGIN_NAMES
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .len();
+        (0..count).map(|index| Self(index as u16)).collect()
+    }
+}
+
+impl fmt::Debug for PluginId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Built-in sources are stored under their variant name (`"Apt"`), plugins
+/// as `"plugin:<name>"`, so every source is a plain string in JSON.
+impl Serialize for PackageSource {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PackageSource::Plugin(_) => serializer.serialize_str(self.as_config_str()),
+            source => serializer.serialize_str(&format!("{:?}", source)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PackageSource {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        if name.starts_with("plugin:") {
+            return PackageSource::from_config_str(&name)
+                .ok_or_else(|| serde::de::Error::custom("a plugin source needs a name"));
+        }
+        PackageSource::ALL
+            .into_iter()
+            .find(|source| format!("{:?}", source) == name)
+            .or_else(|| PackageSource::from_config_str(&name))
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown package source `{}`", name)))
+    }
 }
 
 impl fmt::Display for PackageSource {
@@ -82,6 +188,7 @@ impl fmt::Display for PackageSource {
             PackageSource::Winget => write!(f, "WinGet"),
             PackageSource::Chocolatey => write!(f, "Chocolatey"),
             PackageSource::Scoop => write!(f, "Scoop"),
+            PackageSource::Plugin(id) => write!(f, "{}", id.name()),
         }
     }
 }
@@ -166,7 +273,8 @@ impl PackageSource {
             | PackageSource::Cargo
             | PackageSource::Conda
             | PackageSource::Mamba
-            | PackageSource::Dart => SourcePlatform::CrossPlatform,
+            | PackageSource::Dart
+            | PackageSource::Plugin(_) => SourcePlatform::CrossPlatform,
             PackageSource::Winget | PackageSource::Chocolatey | PackageSource::Scoop => {
                 SourcePlatform::Windows
             }
@@ -203,7 +311,8 @@ impl PackageSource {
             | PackageSource::Mamba
             | PackageSource::Dart
             | PackageSource::AppImage
-            | PackageSource::Scoop => ElevationModel::None,
+            | PackageSource::Scoop
+            | PackageSource::Plugin(_) => ElevationModel::None,
         }
     }
 
@@ -234,6 +343,7 @@ impl PackageSource {
             PackageSource::Winget => Some("Install App Installer / `winget`"),
             PackageSource::Chocolatey => Some("Install Chocolatey (`choco`)"),
             PackageSource::Scoop => Some("Install Scoop (`scoop`)"),
+            PackageSource::Plugin(_) => None,
         }
     }
 
@@ -259,6 +369,7 @@ impl PackageSource {
             PackageSource::Winget => "system-software-install-symbolic",
             PackageSource::Chocolatey => "system-software-install-symbolic",
             PackageSource::Scoop => "system-software-install-symbolic",
+            PackageSource::Plugin(_) => "application-x-addon-symbolic",
         }
     }
 
@@ -284,6 +395,7 @@ impl PackageSource {
             PackageSource::Dart => 17,
             PackageSource::Brew => 18,
             PackageSource::Aur => 19,
+            PackageSource::Plugin(_) => 20,
         }
     }
 
@@ -309,6 +421,7 @@ impl PackageSource {
             PackageSource::Winget => "source-winget",
             PackageSource::Chocolatey => "source-chocolatey",
             PackageSource::Scoop => "source-scoop",
+            PackageSource::Plugin(_) => "source-plugin",
         }
     }
 
@@ -334,6 +447,7 @@ impl PackageSource {
             PackageSource::Winget => "WinGet packages (Windows package manager)",
             PackageSource::Chocolatey => "Chocolatey packages",
             PackageSource::Scoop => "Scoop packages",
+            PackageSource::Plugin(_) => "External provider plugin",
         }
     }
 
@@ -346,6 +460,12 @@ impl PackageSource {
     }
 
     pub fn from_config_str(s: &str) -> Option<Self> {
+        if let Some(name) = s.strip_prefix("plugin:") {
+            if name.is_empty() {
+                return None;
+            }
+            return PluginId::intern(name).map(PackageSource::Plugin);
+        }
         match s.to_lowercase().as_str() {
             "apt" => Some(PackageSource::Apt),
             "dnf" => Some(PackageSource::Dnf),
@@ -394,6 +514,7 @@ impl PackageSource {
             PackageSource::Winget => "winget",
             PackageSource::Chocolatey => "chocolatey",
             PackageSource::Scoop => "scoop",
+            PackageSource::Plugin(id) => id.names().1,
         }
     }
 }
@@ -480,6 +601,9 @@ pub struct Package {
     pub update_category: Option<UpdateCategory>,
     #[serde(default)]
     pub enrichment: Option<PackageEnrichment>,
+    /// When `available_version` was published, for registries that say.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub released_at: Option<DateTime<Utc>>,
 }
 
 /// Rich metadata fetched from online sources
@@ -698,4 +822,27 @@ mod tests {
         assert!(PackageSource::Scoop.privileged_probe_commands().is_empty());
         assert!(PackageSource::Winget.privileged_probe_commands().is_empty());
     }
+
+    #[test]
+    fn plugin_sources_round_trip_by_name() {
+        let source = PackageSource::from_config_str("plugin:Fleet-Tools").expect("plugin source");
+        assert_eq!(
+            source,
+            PackageSource::Plugin(PluginId::intern("fleet-tools").unwrap())
+        );
+        assert_eq!(source.as_config_str(), "plugin:fleet-tools");
+        assert_eq!(source.to_string(), "fleet-tools");
+        assert_eq!(PackageSource::from_config_str("plugin:"), None);
+
+        let json = serde_json::to_string(&source).expect("serialize");
+        assert_eq!(json, r#""plugin:fleet-tools""#);
+        assert_eq!(
+            serde_json::to_string(&PackageSource::AppImage).expect("serialize"),
+            r#""AppImage""#
+        );
+        assert_eq!(
+            serde_json::from_str::<PackageSource>(&json).expect("deserialize"),
+            source
+        );
+    }
 }
@@ -1,6 +1,8 @@
+use super::manifest::{compare_versions, version_parts};
 use super::{Config, Package, PackageSource, PackageStatus};
 use chrono::Utc;
 use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -32,8 +34,81 @@ impl ExportedPackage {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
+
+    /// Decide how an exact restore installs this package. `available` is the
+    /// provider's installable versions (empty when it cannot list them) and
+    /// `installs_versions` whether it can install a chosen version at all.
+    pub fn restore_choice(&self, available: &[String], installs_versions: bool) -> RestoreChoice {
+        if self.version.is_empty() {
+            return RestoreChoice::Latest {
+                reason: "The backup did not record a version".to_string(),
+            };
+        }
+        if !installs_versions {
+            return RestoreChoice::Latest {
+                reason: format!("{} cannot install a chosen version", self.source),
+            };
+        }
+        if available.contains(&self.version) {
+            return RestoreChoice::Exact {
+                version: self.version.clone(),
+                verified: true,
+            };
+        }
+        match nearest_version(&self.version, available) {
+            Some(version) => RestoreChoice::Substitute {
+                recorded: self.version.clone(),
+                version: version.to_string(),
+            },
+            None => RestoreChoice::Exact {
+                version: self.version.clone(),
+                verified: false,
+            },
+        }
+    }
+}
+
+/// How `backup restore --exact` installs one recorded package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RestoreChoice {
+    /// Install the recorded version. `verified` is false when the provider
+    /// could not list its versions, so availability is only known by trying.
+    Exact { version: String, verified: bool },
+    /// The recorded version is no longer available; install the nearest one.
+    Substitute { recorded: String, version: String },
+    /// Install whatever version the provider picks.
+    Latest { reason: String },
+}
+
+/// The available version closest to `recorded`: the one sharing the most
+/// leading version numbers, preferring the oldest newer version over the
+/// newest older one.
+pub fn nearest_version<'a>(recorded: &str, available: &'a [String]) -> Option<&'a str> {
+    let wanted = version_parts(recorded);
+    let shared = |version: &str| {
+        version_parts(version)
+            .iter()
+            .zip(&wanted)
+            .take_while(|(left, right)| left == right)
+            .count()
+    };
+    available.iter().map(String::as_str).max_by(|left, right| {
+        let newer = |version: &str| compare_versions(version, recorded) != Ordering::Less;
+        shared(left)
+            .cmp(&shared(right))
+            .then_with(|| newer(left).cmp(&newer(right)))
+            .then_with(|| {
+                if newer(left) {
+                    compare_versions(right, left)
+                } else {
+                    compare_versions(left, right)
+                }
+            })
+    })
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -257,6 +332,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         }
     }
 
@@ -316,6 +392,59 @@ mod tests {
         assert!(parsed.warnings.is_empty());
     }
 
+    #[test]
+    fn nearest_version_prefers_the_closest_release_line() {
+        let available: Vec<String> = ["1.3.0", "1.2.5", "1.2.4", "1.1.9"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(nearest_version("1.2.3", &available), Some("1.2.4"));
+        assert_eq!(nearest_version("1.2.9", &available), Some("1.2.5"));
+        assert_eq!(nearest_version("2.0.0", &available), Some("1.3.0"));
+        assert_eq!(nearest_version("1.2.3", &[]), None);
+    }
+
+    #[test]
+    fn exact_restores_separate_exact_matches_from_substitutions() {
+        let package = |version: &str| ExportedPackage {
+            name: "ripgrep".to_string(),
+            source: PackageSource::Cargo,
+            version: version.to_string(),
+        };
+        let available = vec!["14.1.0".to_string(), "13.0.0".to_string()];
+
+        assert_eq!(
+            package("13.0.0").restore_choice(&available, true),
+            RestoreChoice::Exact {
+                version: "13.0.0".to_string(),
+                verified: true
+            }
+        );
+        assert_eq!(
+            package("13.0.1").restore_choice(&available, true),
+            RestoreChoice::Substitute {
+                recorded: "13.0.1".to_string(),
+                version: "13.0.0".to_string()
+            }
+        );
+        assert_eq!(
+            package("13.0.1").restore_choice(&[], true),
+            RestoreChoice::Exact {
+                version: "13.0.1".to_string(),
+                verified: false
+            }
+        );
+        assert!(matches!(
+            package("13.0.0").restore_choice(&available, false),
+            RestoreChoice::Latest { .. }
+        ));
+        assert!(matches!(
+            package("").restore_choice(&available, true),
+            RestoreChoice::Latest { .. }
+        ));
+    }
+
     #[test]
     fn applying_exported_preferences_updates_lists_only() {
         let snapshot = PackageListConfig {
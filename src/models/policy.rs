@@ -0,0 +1,875 @@
+//! The update policy: ordered rules in `policy.toml` that decide, per
+//! pending update, whether it applies unattended, waits for review, waits a
+//! number of days, or never applies. The first matching rule wins.
+
+use super::{Config, Package, PackageSource, UpdateCategory};
+use crate::backend::hooks::glob_match;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Duration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_POLICY_FILE: &str = "policy.toml";
+
+const SIGHTINGS_FILE: &str = "update-sightings.json";
+
+/// Providers whose registries report when a version was published, the only
+/// ones a release cooldown can apply to.
+const RELEASE_TIME_SOURCES: [PackageSource; 4] = [
+    PackageSource::Npm,
+    PackageSource::Pip,
+    PackageSource::Pipx,
+    PackageSource::Cargo,
+];
+
+const RISKY_PACKAGE_KEYWORDS: [&str; 12] = [
+    "linux", "kernel", "systemd", "glibc", "libc", "openssl", "gnutls", "firmware", "mesa", "grub",
+    "nvidia", "llvm",
+];
+
+/// How urgent and how risky an update looks, in the order the update center
+/// lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateLane {
+    Security,
+    Recommended,
+    Optional,
+    Risky,
+}
+
+impl UpdateLane {
+    pub fn label(self) -> &'static str {
+        match self {
+            UpdateLane::Security => "security",
+            UpdateLane::Recommended => "recommended",
+            UpdateLane::Optional => "optional",
+            UpdateLane::Risky => "risky",
+        }
+    }
+
+    pub fn of(pkg: &Package) -> Self {
+        let category = pkg
+            .update_category
+            .unwrap_or_else(|| pkg.detect_update_category());
+        if category == UpdateCategory::Security {
+            return UpdateLane::Security;
+        }
+        if is_risky_update(pkg, category) {
+            return UpdateLane::Risky;
+        }
+        if category == UpdateCategory::Feature {
+            return UpdateLane::Optional;
+        }
+        UpdateLane::Recommended
+    }
+}
+
+fn is_risky_update(pkg: &Package, category: UpdateCategory) -> bool {
+    let name = pkg.name.to_lowercase();
+    if RISKY_PACKAGE_KEYWORDS
+        .iter()
+        .any(|keyword| name.contains(keyword))
+    {
+        return true;
+    }
+
+    let system_source = matches!(
+        pkg.source,
+        PackageSource::Apt
+            | PackageSource::Dnf
+            | PackageSource::Pacman
+            | PackageSource::Zypper
+            | PackageSource::Deb
+    );
+    if system_source && category == UpdateCategory::Feature {
+        return true;
+    }
+
+    has_major_version_jump(pkg)
+}
+
+fn has_major_version_jump(pkg: &Package) -> bool {
+    let Some(available) = pkg.available_version.as_ref() else {
+        return false;
+    };
+
+    let Some(current) = semver::Version::parse(&pkg.version).ok() else {
+        return false;
+    };
+    let Some(next) = semver::Version::parse(available).ok() else {
+        return false;
+    };
+
+    next.major > current.major
+}
+
+/// The first version component an update changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionBump {
+    Major,
+    Minor,
+    /// The third component or anything after it, including distribution
+    /// revisions such as `1.2.3-1` to `1.2.3-2`.
+    Patch,
+}
+
+impl VersionBump {
+    pub fn label(self) -> &'static str {
+        match self {
+            VersionBump::Major => "major",
+            VersionBump::Minor => "minor",
+            VersionBump::Patch => "patch",
+        }
+    }
+
+    /// Compare the leading numeric components of two versions, leniently
+    /// enough for distribution versions (`2:9.1.0-1ubuntu2`, `v1.4`). `None`
+    /// when either version has no numeric part or they are identical.
+    pub fn between(current: &str, available: &str) -> Option<Self> {
+        if current == available {
+            return None;
+        }
+        let (current, available) = (numeric_parts(current)?, numeric_parts(available)?);
+        let changed = (0..current.len().max(available.len())).find(|&index| {
+            current.get(index).copied().unwrap_or(0) != available.get(index).copied().unwrap_or(0)
+        });
+        Some(match changed {
+            Some(0) => VersionBump::Major,
+            Some(1) => VersionBump::Minor,
+            _ => VersionBump::Patch,
+        })
+    }
+}
+
+fn numeric_parts(version: &str) -> Option<Vec<u64>> {
+    let version = version.split_once(':').map_or(version, |(_, rest)| rest);
+    let version = version.trim_start_matches(['v', 'V']);
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let parts: Vec<u64> = version[..end]
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+    (!parts.is_empty()).then_some(parts)
+}
+
+/// What a rule does with the updates it matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum UpdateAction {
+    /// Apply without asking, including from the scheduler.
+    Auto,
+    /// Leave for someone to apply by hand.
+    Review,
+    /// Apply automatically once the update has been available this long.
+    Defer { days: u32 },
+    /// Never apply through the policy.
+    Never,
+}
+
+impl std::fmt::Display for UpdateAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdateAction::Auto => f.write_str("auto-apply"),
+            UpdateAction::Review => f.write_str("require review"),
+            UpdateAction::Defer { days } => {
+                write!(f, "defer {} day{}", days, if *days == 1 { "" } else { "s" })
+            }
+            UpdateAction::Never => f.write_str("never apply"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyRule {
+    pub name: Option<String>,
+    pub providers: Vec<PackageSource>,
+    /// Package name globs (`*` and `?`).
+    pub packages: Vec<String>,
+    pub lanes: Vec<UpdateLane>,
+    pub bumps: Vec<VersionBump>,
+    /// Match updates available for at least this many days.
+    pub older_than_days: Option<u32>,
+    /// Match updates available for fewer than this many days.
+    pub younger_than_days: Option<u32>,
+    pub action: UpdateAction,
+}
+
+impl PolicyRule {
+    fn matches(
+        &self,
+        package: &Package,
+        lane: UpdateLane,
+        bump: Option<VersionBump>,
+        age: Duration,
+    ) -> bool {
+        (self.providers.is_empty() || self.providers.contains(&package.source))
+            && (self.packages.is_empty()
+                || self
+                    .packages
+                    .iter()
+                    .any(|pattern| glob_match(pattern, &package.name)))
+            && (self.lanes.is_empty() || self.lanes.contains(&lane))
+            && (self.bumps.is_empty() || bump.is_some_and(|bump| self.bumps.contains(&bump)))
+            && self
+                .older_than_days
+                .is_none_or(|days| age >= Duration::days(days.into()))
+            && self
+                .younger_than_days
+                .is_none_or(|days| age < Duration::days(days.into()))
+    }
+}
+
+/// A parsed and validated policy file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdatePolicy {
+    /// The action for updates no rule matches.
+    pub default: UpdateAction,
+    pub rules: Vec<PolicyRule>,
+    /// Days a provider's new releases wait after publication, whatever the
+    /// rules say.
+    pub cooldown: HashMap<PackageSource, u32>,
+}
+
+impl Default for UpdatePolicy {
+    fn default() -> Self {
+        Self {
+            default: UpdateAction::Review,
+            rules: Vec::new(),
+            cooldown: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawPolicy {
+    #[serde(default)]
+    default: Option<RawAction>,
+    #[serde(default)]
+    rules: Vec<RawRule>,
+    #[serde(default)]
+    cooldown: BTreeMap<String, u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRule {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    providers: Vec<String>,
+    #[serde(default)]
+    packages: Vec<String>,
+    #[serde(default)]
+    lanes: Vec<UpdateLane>,
+    #[serde(default)]
+    bumps: Vec<VersionBump>,
+    #[serde(default)]
+    older_than_days: Option<u32>,
+    #[serde(default)]
+    younger_than_days: Option<u32>,
+    action: RawAction,
+    #[serde(default)]
+    days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum RawAction {
+    Auto,
+    Review,
+    Defer,
+    Never,
+}
+
+impl UpdatePolicy {
+    pub fn default_path() -> PathBuf {
+        Config::config_dir().join(DEFAULT_POLICY_FILE)
+    }
+
+    /// The policy at `default_path()`, or `None` when there is no policy file.
+    pub fn load_default() -> Result<Option<Self>> {
+        let path = Self::default_path();
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read update policy {}", path.display()))?;
+        Self::from_toml_str(&content)
+            .map_err(|error| anyhow!("Invalid update policy {}: {:#}", path.display(), error))
+    }
+
+    pub fn from_toml_str(content: &str) -> Result<Self> {
+        let raw: RawPolicy = toml::from_str(content)?;
+        let default = match raw.default.unwrap_or(RawAction::Review) {
+            RawAction::Auto => UpdateAction::Auto,
+            RawAction::Review => UpdateAction::Review,
+            RawAction::Never => UpdateAction::Never,
+            RawAction::Defer => bail!("`default` must be auto, review or never"),
+        };
+
+        let mut rules = Vec::new();
+        for (index, rule) in raw.rules.into_iter().enumerate() {
+            let label = rule
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("rule {}", index + 1));
+            let action = match (rule.action, rule.days) {
+                (RawAction::Defer, Some(days)) if days > 0 => UpdateAction::Defer { days },
+                (RawAction::Defer, _) => bail!("{}: `defer` needs `days` of at least 1", label),
+                (_, Some(_)) => bail!("{}: `days` only applies to `defer`", label),
+                (RawAction::Auto, None) => UpdateAction::Auto,
+                (RawAction::Review, None) => UpdateAction::Review,
+                (RawAction::Never, None) => UpdateAction::Never,
+            };
+            let providers = rule
+                .providers
+                .iter()
+                .map(|name| {
+                    PackageSource::from_config_str(name)
+                        .with_context(|| format!("{}: unknown provider `{}`", label, name))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if let (Some(older), Some(younger)) = (rule.older_than_days, rule.younger_than_days) {
+                if older >= younger {
+                    bail!(
+                        "{}: no update is older than {} days and younger than {}",
+                        label,
+                        older,
+                        younger
+                    );
+                }
+            }
+            rules.push(PolicyRule {
+                name: rule.name,
+                providers,
+                packages: rule.packages,
+                lanes: rule.lanes,
+                bumps: rule.bumps,
+                older_than_days: rule.older_than_days,
+                younger_than_days: rule.younger_than_days,
+                action,
+            });
+        }
+
+        let mut cooldown = HashMap::new();
+        for (name, days) in raw.cooldown {
+            let source = PackageSource::from_config_str(&name)
+                .with_context(|| format!("cooldown: unknown provider `{}`", name))?;
+            if !RELEASE_TIME_SOURCES.contains(&source) {
+                bail!(
+                    "cooldown: {} does not report release times; only npm, pip, pipx and cargo do",
+                    source
+                );
+            }
+            if days == 0 {
+                bail!("cooldown: {} needs at least 1 day", name);
+            }
+            cooldown.insert(source, days);
+        }
+
+        Ok(Self {
+            default,
+            rules,
+            cooldown,
+        })
+    }
+
+    /// Split `updates` into those the policy lets apply now and the ones it
+    /// holds back, recording each update in `sightings`.
+    pub fn partition(
+        &self,
+        updates: Vec<Package>,
+        sightings: &mut UpdateSightings,
+        now: DateTime<Utc>,
+    ) -> (Vec<Package>, Vec<(Package, PolicyDecision)>) {
+        let mut cleared = Vec::new();
+        let mut held = Vec::new();
+        for package in updates {
+            let since = sightings.observe(&package, now);
+            let decision = self.evaluate(&package, since, now);
+            if decision.applies() {
+                cleared.push(package);
+            } else {
+                held.push((package, decision));
+            }
+        }
+        (cleared, held)
+    }
+
+    /// When the release cooldown for `package`'s provider ends, for an
+    /// update published at `since`.
+    pub fn cooldown_until(&self, package: &Package, since: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let days = self.cooldown.get(&package.source)?;
+        Some(since + Duration::days((*days).into()))
+    }
+
+    /// The version `package` must be updated to once the policy clears it.
+    /// Under a release cooldown that is the release the cooldown judged, not
+    /// whatever the registry publishes by the time the update runs.
+    pub fn pinned_version<'a>(&self, package: &'a Package) -> Option<&'a str> {
+        if !self.cooldown.contains_key(&package.source) {
+            return None;
+        }
+        package.available_version.as_deref()
+    }
+
+    /// Decide what happens to `package`'s pending update, which LinGet first
+    /// saw at `seen`. The update's age counts from its release when the
+    /// registry reported one.
+    pub fn evaluate(
+        &self,
+        package: &Package,
+        seen: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> PolicyDecision {
+        let since = package.released_at.unwrap_or(seen);
+        let lane = UpdateLane::of(package);
+        let bump = package
+            .available_version
+            .as_deref()
+            .and_then(|available| VersionBump::between(&package.version, available));
+        let age = (now - since).max(Duration::zero());
+        let rule = self
+            .rules
+            .iter()
+            .position(|rule| rule.matches(package, lane, bump, age));
+        let action = rule.map_or(self.default, |index| self.rules[index].action);
+        let verdict = match action {
+            UpdateAction::Auto => Verdict::Apply,
+            UpdateAction::Review => Verdict::Review,
+            UpdateAction::Never => Verdict::Never,
+            UpdateAction::Defer { days } => {
+                let until = since + Duration::days(days.into());
+                if now >= until {
+                    Verdict::Apply
+                } else {
+                    Verdict::Deferred { until }
+                }
+            }
+        };
+        // A cooldown only holds back updates that would otherwise become
+        // available sooner.
+        let verdict = match (verdict, self.cooldown_until(package, since)) {
+            (Verdict::Deferred { until }, Some(cooled)) if until >= cooled => verdict,
+            (Verdict::Never, _) => verdict,
+            (_, Some(until)) if now < until => Verdict::CoolingDown { until },
+            _ => verdict,
+        };
+        PolicyDecision {
+            rule: rule.map(|index| MatchedRule {
+                number: index + 1,
+                name: self.rules[index].name.clone(),
+            }),
+            action,
+            lane,
+            bump,
+            since,
+            verdict,
+        }
+    }
+}
+
+/// The rule that decided an update, numbered from 1 in file order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatchedRule {
+    pub number: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+impl std::fmt::Display for MatchedRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "rule {} ({})", self.number, name),
+            None => write!(f, "rule {}", self.number),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "verdict", rename_all = "snake_case")]
+pub enum Verdict {
+    Apply,
+    Review,
+    Deferred {
+        until: DateTime<Utc>,
+    },
+    /// Published too recently for the provider's release cooldown.
+    CoolingDown {
+        until: DateTime<Utc>,
+    },
+    Never,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PolicyDecision {
+    /// `None` when no rule matched and the default action applied.
+    pub rule: Option<MatchedRule>,
+    #[serde(flatten)]
+    pub action: UpdateAction,
+    pub lane: UpdateLane,
+    pub bump: Option<VersionBump>,
+    /// When the update was published, or first seen when the registry does
+    /// not say.
+    pub since: DateTime<Utc>,
+    #[serde(flatten)]
+    pub verdict: Verdict,
+}
+
+impl PolicyDecision {
+    /// True when the update may be applied without asking.
+    pub fn applies(&self) -> bool {
+        self.verdict == Verdict::Apply
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Apply => f.write_str("applies automatically"),
+            Verdict::Review => f.write_str("needs review"),
+            Verdict::Deferred { until } => write!(
+                f,
+                "deferred until {}",
+                until.with_timezone(&Local).format("%b %d, %I:%M %p")
+            ),
+            Verdict::CoolingDown { until } => write!(
+                f,
+                "held for its release cooldown until {}",
+                until.with_timezone(&Local).format("%b %d, %I:%M %p")
+            ),
+            Verdict::Never => f.write_str("never applies"),
+        }
+    }
+}
+
+/// When each pending update was first seen, so `defer` and the age filters
+/// have a clock to go by. Kept per package in the data dir; a new version
+/// restarts the clock.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateSightings {
+    entries: HashMap<String, Sighting>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Sighting {
+    version: String,
+    first_seen: DateTime<Utc>,
+}
+
+fn sighting_key(package: &Package) -> String {
+    format!("{:?}:{}", package.source, package.name)
+}
+
+impl UpdateSightings {
+    fn path() -> PathBuf {
+        if let Some(dir) = std::env::var_os("LINGET_DATA_DIR") {
+            return PathBuf::from(dir).join(SIGHTINGS_FILE);
+        }
+        dirs::data_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("linget")
+            .join(SIGHTINGS_FILE)
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context("Failed to create data directory")?;
+        }
+        std::fs::write(&path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// When `package`'s pending update was first seen, or `None` if it
+    /// never was.
+    pub fn since(&self, package: &Package) -> Option<DateTime<Utc>> {
+        let available = package.available_version.as_deref()?;
+        self.entries
+            .get(&sighting_key(package))
+            .filter(|sighting| sighting.version == available)
+            .map(|sighting| sighting.first_seen)
+    }
+
+    /// Record `package`'s pending update as seen at `now` unless it was
+    /// seen before, and return when it was first seen.
+    pub fn observe(&mut self, package: &Package, now: DateTime<Utc>) -> DateTime<Utc> {
+        if let Some(since) = self.since(package) {
+            return since;
+        }
+        if let Some(available) = &package.available_version {
+            self.entries.insert(
+                sighting_key(package),
+                Sighting {
+                    version: available.clone(),
+                    first_seen: now,
+                },
+            );
+        }
+        now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PackageStatus;
+
+    fn update(name: &str, source: PackageSource, version: &str, available: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: version.to_string(),
+            available_version: Some(available.to_string()),
+            description: String::new(),
+            source,
+            status: PackageStatus::UpdateAvailable,
+            size: None,
+            homepage: None,
+            license: None,
+            maintainer: None,
+            dependencies: Vec::new(),
+            install_date: None,
+            update_category: Some(UpdateCategory::Bugfix),
+            enrichment: None,
+            released_at: None,
+        }
+    }
+
+    const POLICY: &str = r#"
+        default = "review"
+
+        [[rules]]
+        name = "Kernel stays manual"
+        packages = ["linux-image-*"]
+        action = "never"
+
+        [[rules]]
+        lanes = ["security"]
+        action = "auto"
+
+        [[rules]]
+        providers = ["npm", "pip"]
+        bumps = ["major"]
+        action = "review"
+
+        [[rules]]
+        providers = ["flatpak"]
+        action = "defer"
+        days = 3
+
+        [[rules]]
+        bumps = ["patch", "minor"]
+        older_than_days = 2
+        action = "auto"
+    "#;
+
+    #[test]
+    fn version_bumps_name_the_first_changed_component() {
+        assert_eq!(
+            VersionBump::between("1.2.3", "2.0.0"),
+            Some(VersionBump::Major)
+        );
+        assert_eq!(
+            VersionBump::between("v1.2", "v1.3"),
+            Some(VersionBump::Minor)
+        );
+        assert_eq!(
+            VersionBump::between("2:9.1.0-1ubuntu1", "2:9.1.2-1ubuntu1"),
+            Some(VersionBump::Patch)
+        );
+        assert_eq!(
+            VersionBump::between("1.2.3-1", "1.2.3-2"),
+            Some(VersionBump::Patch)
+        );
+        assert_eq!(
+            VersionBump::between("1.2", "1.2.0.1"),
+            Some(VersionBump::Patch)
+        );
+        assert_eq!(VersionBump::between("latest", "1.0"), None);
+        assert_eq!(VersionBump::between("1.0", "1.0"), None);
+    }
+
+    #[test]
+    fn the_first_matching_rule_decides() {
+        let policy = UpdatePolicy::from_toml_str(POLICY).unwrap();
+        let now = Utc::now();
+        let decide = |package: &Package, days_ago: i64| {
+            policy.evaluate(package, now - Duration::days(days_ago), now)
+        };
+
+        let kernel = decide(
+            &update("linux-image-generic", PackageSource::Apt, "6.8.0", "6.8.1"),
+            30,
+        );
+        assert_eq!(kernel.rule.as_ref().unwrap().number, 1);
+        assert_eq!(kernel.verdict, Verdict::Never);
+
+        let mut openssl = update("openssl", PackageSource::Apt, "3.0.0", "3.0.1");
+        openssl.update_category = Some(UpdateCategory::Security);
+        assert_eq!(decide(&openssl, 0).verdict, Verdict::Apply);
+
+        let react = decide(&update("react", PackageSource::Npm, "18.2.0", "19.0.0"), 30);
+        assert_eq!(react.rule.as_ref().unwrap().number, 3);
+        assert_eq!(react.verdict, Verdict::Review);
+
+        let app = update("org.gimp.GIMP", PackageSource::Flatpak, "2.10", "3.0");
+        assert_eq!(
+            decide(&app, 1).verdict,
+            Verdict::Deferred {
+                until: now + Duration::days(2)
+            }
+        );
+        assert_eq!(decide(&app, 3).verdict, Verdict::Apply);
+
+        let ripgrep = update("ripgrep", PackageSource::Cargo, "14.0.0", "14.0.1");
+        assert_eq!(decide(&ripgrep, 5).rule.unwrap().number, 5);
+        let fresh = decide(&ripgrep, 1);
+        assert_eq!(fresh.rule, None);
+        assert_eq!(fresh.verdict, Verdict::Review);
+    }
+
+    #[test]
+    fn invalid_policies_name_the_broken_rule() {
+        let error = |content: &str| {
+            UpdatePolicy::from_toml_str(content)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("[[rules]]\naction = \"defer\""),
+            "rule 1: `defer` needs `days` of at least 1"
+        );
+        assert_eq!(
+            error("[[rules]]\nname = \"apps\"\nproviders = [\"fltpak\"]\naction = \"auto\""),
+            "apps: unknown provider `fltpak`"
+        );
+        assert_eq!(
+            error("[[rules]]\naction = \"auto\"\ndays = 2"),
+            "rule 1: `days` only applies to `defer`"
+        );
+        assert_eq!(
+            error("default = \"defer\""),
+            "`default` must be auto, review or never"
+        );
+        assert!(UpdatePolicy::from_toml_str("[[rules]]\naction = \"sometimes\"").is_err());
+    }
+
+    #[test]
+    fn young_releases_wait_out_their_provider_cooldown() {
+        let policy = UpdatePolicy::from_toml_str(
+            r#"
+            default = "auto"
+
+            [cooldown]
+            npm = 7
+            cargo = 2
+
+            [[rules]]
+            packages = ["legacy-*"]
+            action = "never"
+
+            [[rules]]
+            providers = ["cargo"]
+            action = "defer"
+            days = 5
+            "#,
+        )
+        .unwrap();
+        let now = Utc::now();
+        let released = |mut package: Package, days_ago: i64| {
+            package.released_at = Some(now - Duration::days(days_ago));
+            package
+        };
+
+        // Published 4 days ago and first seen just now: the release time
+        // decides, so 3 days remain.
+        let lodash = released(
+            update("lodash", PackageSource::Npm, "4.17.20", "4.17.21"),
+            4,
+        );
+        assert_eq!(
+            policy.evaluate(&lodash, now, now).verdict,
+            Verdict::CoolingDown {
+                until: now + Duration::days(3)
+            }
+        );
+        let lodash = released(lodash, 8);
+        assert_eq!(policy.evaluate(&lodash, now, now).verdict, Verdict::Apply);
+
+        // Without a release time the cooldown counts from the first sighting.
+        let unknown = update("chalk", PackageSource::Npm, "5.3.0", "5.4.0");
+        assert_eq!(
+            policy
+                .evaluate(&unknown, now - Duration::days(6), now)
+                .verdict,
+            Verdict::CoolingDown {
+                until: now + Duration::days(1)
+            }
+        );
+
+        // A longer deferral or a `never` rule still decides.
+        let ripgrep = released(
+            update("ripgrep", PackageSource::Cargo, "14.0.0", "14.1.0"),
+            1,
+        );
+        assert_eq!(
+            policy.evaluate(&ripgrep, now, now).verdict,
+            Verdict::Deferred {
+                until: now + Duration::days(4)
+            }
+        );
+        let legacy = released(
+            update("legacy-tool", PackageSource::Npm, "1.0.0", "1.0.1"),
+            1,
+        );
+        assert_eq!(policy.evaluate(&legacy, now, now).verdict, Verdict::Never);
+
+        // Providers without a cooldown are not held back.
+        let apt = update("vim", PackageSource::Apt, "9.0", "9.1");
+        assert_eq!(policy.evaluate(&apt, now, now).verdict, Verdict::Apply);
+
+        // Cleared updates under a cooldown install the release it judged.
+        assert_eq!(policy.pinned_version(&lodash), Some("4.17.21"));
+        assert_eq!(policy.pinned_version(&apt), None);
+
+        assert_eq!(
+            UpdatePolicy::from_toml_str("[cooldown]\napt = 3")
+                .unwrap_err()
+                .to_string(),
+            "cooldown: APT does not report release times; only npm, pip, pipx and cargo do"
+        );
+    }
+
+    #[test]
+    fn a_new_version_restarts_the_sighting_clock() {
+        let mut sightings = UpdateSightings::default();
+        let mut app = update("firefox", PackageSource::Flatpak, "120.0", "121.0");
+        let monday = Utc::now() - Duration::days(7);
+
+        assert_eq!(sightings.since(&app), None);
+        assert_eq!(sightings.observe(&app, monday), monday);
+        assert_eq!(sightings.observe(&app, Utc::now()), monday);
+
+        app.available_version = Some("122.0".to_string());
+        assert_eq!(sightings.since(&app), None);
+        let now = Utc::now();
+        assert_eq!(sightings.observe(&app, now), now);
+    }
+}
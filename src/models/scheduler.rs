@@ -1,7 +1,15 @@
-use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
-use super::PackageSource;
+use super::calendar::{CalendarSpec, MaintenanceWindow};
+use super::{PackageSource, UpdateCategory};
+
+/// An occurrence that was due longer ago than this was missed, for example
+/// because the machine was off, and is handled by the rule's `CatchUp`.
+pub const MISSED_RUN_GRACE: Duration = Duration::minutes(15);
+
+/// Runs kept in each recurring rule's history.
+const RULE_HISTORY_LIMIT: usize = 20;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -122,6 +130,102 @@ impl SchedulePreset {
     }
 }
 
+/// Read when a one-shot task should run: a local `YYYY-MM-DD HH:MM[:SS]`
+/// (a `T` separator works too), an RFC 3339 timestamp, or a bare
+/// `HH:MM[:SS]` meaning its next occurrence after `now`. Local times are in
+/// `now`'s time zone.
+pub fn parse_schedule_time<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Option<DateTime<Utc>> {
+    let input = input.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(input) {
+        return Some(at.with_timezone(&Utc));
+    }
+    let naive = [
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+    .or_else(|| {
+        let time = ["%H:%M", "%H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveTime::parse_from_str(input, format).ok())?;
+        let today = now.date_naive().and_time(time);
+        Some(if today > now.naive_local() {
+            today
+        } else {
+            today + Duration::days(1)
+        })
+    })?;
+    naive
+        .and_local_timezone(now.timezone())
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// Conditions a due task or rule waits for before it runs. Unset conditions
+/// always hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConditions {
+    /// Only on mains power.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub on_ac_power: bool,
+    /// Not while NetworkManager reports a metered connection.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub not_metered: bool,
+    /// Only once the session has been idle this many minutes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_minutes: Option<u32>,
+    /// Not while the focused window is fullscreen.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub no_fullscreen: bool,
+    /// Only inside this local time window.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub window: Option<MaintenanceWindow>,
+}
+
+impl RunConditions {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Short labels such as "on AC power" or "idle 10 min", for listings.
+    pub fn labels(&self) -> Vec<String> {
+        let mut labels = Vec::new();
+        if self.on_ac_power {
+            labels.push("on AC power".to_string());
+        }
+        if self.not_metered {
+            labels.push("not metered".to_string());
+        }
+        if let Some(minutes) = self.idle_minutes {
+            labels.push(format!("idle {} min", minutes));
+        }
+        if self.no_fullscreen {
+            labels.push("no fullscreen app".to_string());
+        }
+        if let Some(window) = &self.window {
+            labels.push(format!("within {}", window));
+        }
+        labels
+    }
+}
+
+/// Why a due task or rule did not run at its last check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deferral {
+    pub checked_at: DateTime<Utc>,
+    pub reasons: Vec<String>,
+}
+
+impl Deferral {
+    pub fn describe(&self) -> String {
+        format!("Deferred because {}", self.reasons.join("; "))
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledTask {
@@ -136,6 +240,11 @@ pub struct ScheduledTask {
     #[serde(default)]
     pub completed_at: Option<DateTime<Utc>>,
     pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "RunConditions::is_empty")]
+    pub conditions: RunConditions,
+    /// Set while the task is due but its conditions keep it waiting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred: Option<Deferral>,
 }
 
 #[allow(dead_code)]
@@ -158,6 +267,8 @@ impl ScheduledTask {
             completed: false,
             completed_at: None,
             error: None,
+            conditions: RunConditions::default(),
+            deferred: None,
         }
     }
 
@@ -228,19 +339,205 @@ impl ScheduledTask {
     pub fn mark_completed(&mut self) {
         self.completed = true;
         self.completed_at = Some(Utc::now());
+        self.deferred = None;
     }
 
     pub fn mark_failed(&mut self, error: String) {
         self.completed = true;
         self.completed_at = Some(Utc::now());
         self.error = Some(error);
+        self.deferred = None;
+    }
+}
+
+/// What a recurring rule does when it misses occurrences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUp {
+    /// Run once as soon as possible, however many occurrences were missed.
+    #[default]
+    RunOnce,
+    /// Wait for the next occurrence.
+    Skip,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RuleOutcome {
+    Succeeded {
+        updated: usize,
+        /// Selected updates the update policy held back, with the reason.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        held_back: Vec<String>,
+    },
+    Failed {
+        error: String,
+    },
+    Skipped {
+        reason: String,
+    },
+}
+
+/// One occurrence of a recurring rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleRun {
+    /// The occurrence this run stands for.
+    pub scheduled_for: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    /// True when the occurrence was missed and run late.
+    #[serde(default)]
+    pub caught_up: bool,
+    #[serde(flatten)]
+    pub outcome: RuleOutcome,
+}
+
+/// A recurring update: whenever `calendar` fires, update the pending updates
+/// it selects. An empty selection updates everything that has an update.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecurringRule {
+    #[serde(default = "new_rule_id")]
+    pub id: String,
+    pub calendar: CalendarSpec,
+    /// Only updates from this source.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<PackageSource>,
+    /// Only updates in this category, such as `Security`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<UpdateCategory>,
+    /// Only these packages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    /// The next occurrence; `None` until first scheduled, or when the
+    /// calendar never fires again.
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<RuleRun>,
+    #[serde(default, skip_serializing_if = "RunConditions::is_empty")]
+    pub conditions: RunConditions,
+    /// Set while an occurrence is due but the conditions keep it waiting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deferred: Option<Deferral>,
+}
+
+fn new_rule_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// How a due rule should be handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleDecision {
+    Run {
+        scheduled_for: DateTime<Utc>,
+        caught_up: bool,
+    },
+    Skip {
+        scheduled_for: DateTime<Utc>,
+    },
+}
+
+impl RecurringRule {
+    pub fn new(calendar: CalendarSpec) -> Self {
+        let mut rule = Self {
+            id: new_rule_id(),
+            calendar,
+            source: None,
+            category: None,
+            packages: Vec::new(),
+            catch_up: CatchUp::default(),
+            enabled: true,
+            created_at: Utc::now(),
+            next_run: None,
+            history: Vec::new(),
+            conditions: RunConditions::default(),
+            deferred: None,
+        };
+        rule.schedule_after(rule.created_at);
+        rule
+    }
+
+    /// Set `next_run` to the first occurrence after `after`, in local time.
+    pub fn schedule_after(&mut self, after: DateTime<Utc>) {
+        self.next_run = self
+            .calendar
+            .next_after(&after.with_timezone(&Local))
+            .map(|next| next.with_timezone(&Utc));
+    }
+
+    /// What to do with the rule at `now`, or `None` when it is not due. An
+    /// occurrence its conditions deferred is waiting, not missed.
+    pub fn decide(&self, now: DateTime<Utc>) -> Option<RuleDecision> {
+        let scheduled_for = self.next_run.filter(|next| self.enabled && *next <= now)?;
+        let missed = self.deferred.is_none() && now - scheduled_for > MISSED_RUN_GRACE;
+        Some(match (missed, self.catch_up) {
+            (true, CatchUp::Skip) => RuleDecision::Skip { scheduled_for },
+            _ => RuleDecision::Run {
+                scheduled_for,
+                caught_up: missed,
+            },
+        })
+    }
+
+    /// Record a finished occurrence and schedule the next one after `now`.
+    pub fn record(&mut self, run: RuleRun, now: DateTime<Utc>) {
+        self.deferred = None;
+        self.history.push(run);
+        if self.history.len() > RULE_HISTORY_LIMIT {
+            self.history.remove(0);
+        }
+        self.schedule_after(now);
+    }
+
+    /// Whether `package` is one of the updates this rule selects.
+    pub fn selects(&self, package: &super::Package) -> bool {
+        self.source.is_none_or(|source| source == package.source)
+            && self.category.is_none_or(|category| {
+                package
+                    .update_category
+                    .unwrap_or_else(|| package.detect_update_category())
+                    == category
+            })
+            && (self.packages.is_empty() || self.packages.contains(&package.name))
+    }
+
+    /// A short description such as "update Security APT packages".
+    pub fn describe(&self) -> String {
+        let mut words = vec!["update".to_string()];
+        if self.packages.is_empty() {
+            words.push("all".to_string());
+        }
+        if let Some(category) = self.category {
+            words.push(category.label().to_string());
+        }
+        if let Some(source) = self.source {
+            words.push(source.to_string());
+        }
+        if self.packages.is_empty() {
+            words.push("packages".to_string());
+        } else {
+            words.push(self.packages.join(", "));
+        }
+        words.join(" ")
     }
 }
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SchedulerState {
+    #[serde(default)]
     pub tasks: Vec<ScheduledTask>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RecurringRule>,
 }
 
 #[allow(dead_code)]
@@ -297,6 +594,34 @@ impl SchedulerState {
     pub fn pending_count(&self) -> usize {
         self.tasks.iter().filter(|t| t.is_active()).count()
     }
+
+    /// Whether the background runner is needed: a one-shot task is pending
+    /// or a recurring rule is enabled.
+    pub fn needs_runner(&self) -> bool {
+        self.pending_count() > 0 || self.rules.iter().any(|rule| rule.enabled)
+    }
+
+    pub fn add_rule(&mut self, rule: RecurringRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn remove_rule(&mut self, rule_id: &str) -> Option<RecurringRule> {
+        let position = self.rules.iter().position(|rule| rule.id == rule_id)?;
+        Some(self.rules.remove(position))
+    }
+
+    /// Give rules written by hand without a `next_run` their first
+    /// occurrence. Returns true when any rule changed.
+    pub fn schedule_new_rules(&mut self, now: DateTime<Utc>) -> bool {
+        let mut changed = false;
+        for rule in &mut self.rules {
+            if rule.next_run.is_none() && rule.history.is_empty() {
+                rule.schedule_after(now);
+                changed |= rule.next_run.is_some();
+            }
+        }
+        changed
+    }
 }
 
 #[cfg(test)]
@@ -357,6 +682,125 @@ mod tests {
         assert_eq!(scheduler.pending_tasks().len(), 1);
     }
 
+    fn rule(expression: &str, next_run: DateTime<Utc>) -> RecurringRule {
+        let mut rule = RecurringRule::new(CalendarSpec::parse(expression).unwrap());
+        rule.next_run = Some(next_run);
+        rule
+    }
+
+    #[test]
+    fn recurring_rules_run_on_time_and_catch_up_once() {
+        let now = Utc::now();
+        let on_time = rule("*:0/5", now - Duration::minutes(1));
+        assert_eq!(
+            on_time.decide(now),
+            Some(RuleDecision::Run {
+                scheduled_for: now - Duration::minutes(1),
+                caught_up: false
+            })
+        );
+
+        let mut missed = rule("daily", now - Duration::days(3));
+        assert!(matches!(
+            missed.decide(now),
+            Some(RuleDecision::Run {
+                caught_up: true,
+                ..
+            })
+        ));
+        missed.catch_up = CatchUp::Skip;
+        assert!(matches!(
+            missed.decide(now),
+            Some(RuleDecision::Skip { .. })
+        ));
+
+        missed.record(
+            RuleRun {
+                scheduled_for: now - Duration::days(3),
+                finished_at: now,
+                caught_up: false,
+                outcome: RuleOutcome::Skipped {
+                    reason: "missed".to_string(),
+                },
+            },
+            now,
+        );
+        assert_eq!(missed.history.len(), 1);
+        assert!(missed.next_run.unwrap() > now);
+        assert_eq!(missed.decide(now), None);
+    }
+
+    #[test]
+    fn deferred_rules_wait_instead_of_counting_as_missed() {
+        let now = Utc::now();
+        let mut rule = rule("daily", now - Duration::hours(2));
+        rule.catch_up = CatchUp::Skip;
+        rule.conditions =
+            toml::from_str("on_ac_power = true\nidle_minutes = 10\nwindow = \"22:00-06:00\"")
+                .unwrap();
+        assert_eq!(
+            rule.conditions.labels(),
+            ["on AC power", "idle 10 min", "within 22:00-06:00"]
+        );
+        assert!(matches!(rule.decide(now), Some(RuleDecision::Skip { .. })));
+
+        rule.deferred = Some(Deferral {
+            checked_at: now - Duration::hours(2),
+            reasons: vec!["on battery power".to_string()],
+        });
+        assert_eq!(
+            rule.decide(now),
+            Some(RuleDecision::Run {
+                scheduled_for: now - Duration::hours(2),
+                caught_up: false
+            })
+        );
+        assert!(toml::from_str::<RunConditions>("on_battery = true").is_err());
+        assert!(toml::from_str::<RunConditions>("window = \"whenever\"").is_err());
+    }
+
+    #[test]
+    fn recurring_rules_select_by_source_category_and_name() {
+        let mut rule = rule("daily", Utc::now());
+        rule.source = Some(PackageSource::Apt);
+        rule.category = Some(UpdateCategory::Security);
+        let mut package = crate::models::Package {
+            name: "openssl".to_string(),
+            version: "3.0.1".to_string(),
+            available_version: Some("3.0.2".to_string()),
+            description: String::new(),
+            source: PackageSource::Apt,
+            status: crate::models::PackageStatus::UpdateAvailable,
+            size: None,
+            homepage: None,
+            license: None,
+            maintainer: None,
+            dependencies: Vec::new(),
+            install_date: None,
+            update_category: Some(UpdateCategory::Security),
+            enrichment: None,
+            released_at: None,
+        };
+
+        assert!(rule.selects(&package));
+        assert_eq!(rule.describe(), "update all Security APT packages");
+        package.update_category = Some(UpdateCategory::Feature);
+        assert!(!rule.selects(&package));
+        package.update_category = Some(UpdateCategory::Security);
+        package.source = PackageSource::Dnf;
+        assert!(!rule.selects(&package));
+    }
+
+    #[test]
+    fn enabled_rules_keep_the_runner_alive() {
+        let mut scheduler = SchedulerState::default();
+        assert!(!scheduler.needs_runner());
+        scheduler.add_rule(rule("weekly", Utc::now()));
+        assert!(scheduler.needs_runner());
+        scheduler.rules[0].enabled = false;
+        assert!(!scheduler.needs_runner());
+    }
+
     #[test]
     fn scheduler_add_task_replaces_existing_active_task_for_package() {
         let mut scheduler = SchedulerState::default();
@@ -378,4 +822,25 @@ mod tests {
         assert_eq!(scheduler.tasks.len(), 1);
         assert_eq!(scheduler.tasks[0].operation, ScheduledOperation::Remove);
     }
+
+    #[test]
+    fn schedule_times_parse_dates_timestamps_and_bare_times() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 17, 12, 0, 0).unwrap();
+        let at = |input| parse_schedule_time(input, &now);
+
+        let expected = Utc.with_ymd_and_hms(2026, 11, 1, 22, 0, 0).unwrap();
+        assert_eq!(at("2026-11-01 22:00"), Some(expected));
+        assert_eq!(at("2026-11-01T22:00:00"), Some(expected));
+        assert_eq!(at("2026-11-01T23:00:00+01:00"), Some(expected));
+        assert_eq!(
+            at("18:30"),
+            Some(Utc.with_ymd_and_hms(2026, 10, 17, 18, 30, 0).unwrap())
+        );
+        assert_eq!(
+            at("09:00"),
+            Some(Utc.with_ymd_and_hms(2026, 10, 18, 9, 0, 0).unwrap())
+        );
+        assert_eq!(at("next tuesday"), None);
+        assert_eq!(at("2026-13-01 22:00"), None);
+    }
 }
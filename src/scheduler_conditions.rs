@@ -0,0 +1,391 @@
+//! Checks for the conditions scheduled work waits for. Power comes from
+//! sysfs, metered connections from `nmcli`, idle time from `loginctl` and the
+//! focused window from `xprop`, all under roots that tests point at fixture
+//! trees and fake binaries.
+
+use crate::backend::LockStatus;
+use crate::models::scheduler::RunConditions;
+use crate::models::PackageSource;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// How long a helper binary may take before its answer is treated as unknown.
+const PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Where the system state is read from.
+#[derive(Debug, Clone)]
+pub struct SystemProbe {
+    sysfs: PathBuf,
+    /// Directory holding `nmcli`, `loginctl` and `xprop`; `None` searches
+    /// `PATH`.
+    bin_dir: Option<PathBuf>,
+    /// The user whose session idle time counts.
+    user: String,
+}
+
+impl Default for SystemProbe {
+    fn default() -> Self {
+        Self {
+            sysfs: PathBuf::from("/sys"),
+            bin_dir: None,
+            // SAFETY: getuid has no preconditions and cannot fail.
+            user: unsafe { libc::getuid() }.to_string(),
+        }
+    }
+}
+
+impl SystemProbe {
+    /// Whether the machine runs on its battery: it has a system battery and
+    /// no external supply is online. Without any external supply listed, a
+    /// discharging battery decides. Machines without a battery are on AC.
+    pub fn on_battery(&self) -> bool {
+        let Ok(entries) = std::fs::read_dir(self.sysfs.join("class/power_supply")) else {
+            return false;
+        };
+        let (mut has_battery, mut discharging) = (false, false);
+        let (mut has_external, mut external_online) = (false, false);
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let read = |name: &str| read_trimmed(&path.join(name));
+            match read("type").as_deref() {
+                // Batteries in mice and keyboards report a `Device` scope.
+                Some("Battery") if read("scope").as_deref() != Some("Device") => {
+                    has_battery = true;
+                    discharging |= read("status").as_deref() == Some("Discharging");
+                }
+                Some("Battery") | None => {}
+                Some(_) => {
+                    has_external = true;
+                    external_online |= read("online").as_deref() == Some("1");
+                }
+            }
+        }
+        has_battery
+            && if has_external {
+                !external_online
+            } else {
+                discharging
+            }
+    }
+
+    /// Connected NetworkManager devices on a metered connection, or `None`
+    /// when NetworkManager cannot be asked.
+    pub async fn metered_devices(&self) -> Option<Vec<String>> {
+        let output = self
+            .run(
+                "nmcli",
+                &[
+                    "-t",
+                    "-f",
+                    "GENERAL.DEVICE,GENERAL.STATE,GENERAL.METERED",
+                    "device",
+                    "show",
+                ],
+            )
+            .await?;
+        Some(parse_metered_devices(&output))
+    }
+
+    /// How long the user's sessions have been idle as logind reports it, or
+    /// `None` when logind cannot be asked.
+    pub async fn idle_for(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let output = self
+            .run(
+                "loginctl",
+                &[
+                    "show-user",
+                    &self.user,
+                    "-p",
+                    "IdleHint",
+                    "-p",
+                    "IdleSinceHint",
+                ],
+            )
+            .await?;
+        parse_idle_time(&output, now)
+    }
+
+    /// The class of the focused window when it is fullscreen. `None` also
+    /// covers sessions `xprop` cannot reach.
+    pub async fn fullscreen_window(&self) -> Option<String> {
+        let root = self.run("xprop", &["-root", "_NET_ACTIVE_WINDOW"]).await?;
+        let window = root
+            .split_whitespace()
+            .last()
+            .filter(|id| id.starts_with("0x") && *id != "0x0")?;
+        let state = self
+            .run("xprop", &["-id", window, "_NET_WM_STATE", "WM_CLASS"])
+            .await?;
+        parse_fullscreen_class(&state)
+    }
+
+    async fn run(&self, program: &str, args: &[&str]) -> Option<String> {
+        let path = match &self.bin_dir {
+            Some(dir) => Some(dir.join(program)).filter(|path| path.exists())?,
+            None => which::which(program).ok()?,
+        };
+        let output = tokio::time::timeout(
+            PROBE_TIMEOUT,
+            Command::new(path).args(args).kill_on_drop(true).output(),
+        )
+        .await
+        .ok()?
+        .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// The reasons `conditions` keep scheduled work from running at `now`;
+/// empty when it may run.
+pub async fn unmet_conditions<Tz: TimeZone>(
+    conditions: &RunConditions,
+    probe: &SystemProbe,
+    now: &DateTime<Tz>,
+) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if let Some(window) = &conditions.window {
+        if !window.contains(now.naive_local()) {
+            reasons.push(format!("outside the maintenance window {}", window));
+        }
+    }
+    if conditions.on_ac_power && probe.on_battery() {
+        reasons.push("running on battery power".to_string());
+    }
+    if conditions.not_metered {
+        if let Some(devices) = probe.metered_devices().await {
+            if !devices.is_empty() {
+                reasons.push(format!("on a metered connection ({})", devices.join(", ")));
+            }
+        }
+    }
+    if let Some(minutes) = conditions.idle_minutes {
+        match probe.idle_for(now.with_timezone(&Utc)).await {
+            Some(idle) if idle >= Duration::minutes(minutes.into()) => {}
+            Some(idle) => reasons.push(format!(
+                "the system has been idle {} of {} minutes",
+                idle.num_minutes(),
+                minutes
+            )),
+            None => reasons.push("the idle time is unknown (logind did not answer)".to_string()),
+        }
+    }
+    if conditions.no_fullscreen {
+        if let Some(class) = probe.fullscreen_window().await {
+            reasons.push(format!("a fullscreen window is open ({})", class));
+        }
+    }
+    reasons
+}
+
+/// Reasons from busy package managers: the lock of `source`, or of any
+/// source when work spans them all.
+pub fn busy_package_managers(
+    locks: &[(PackageSource, LockStatus)],
+    source: Option<PackageSource>,
+) -> Vec<String> {
+    locks
+        .iter()
+        .filter(|(lock_source, status)| {
+            status.is_locked && source.is_none_or(|source| source == *lock_source)
+        })
+        .map(|(lock_source, status)| match &status.lock_holder {
+            Some(holder) => format!("{} is busy ({} holds its lock)", lock_source, holder),
+            None => format!("{} is busy", lock_source),
+        })
+        .collect()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|text| text.trim().to_string())
+}
+
+fn parse_metered_devices(output: &str) -> Vec<String> {
+    let mut devices = Vec::new();
+    let mut device = String::new();
+    let (mut connected, mut metered) = (false, false);
+    let mut finish = |device: &str, connected: bool, metered: bool| {
+        if connected && metered && !device.is_empty() {
+            devices.push(device.to_string());
+        }
+    };
+    for line in output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key {
+            "GENERAL.DEVICE" => {
+                finish(&device, connected, metered);
+                device = value.to_string();
+                connected = false;
+                metered = false;
+            }
+            "GENERAL.STATE" => connected = value.starts_with("100"),
+            "GENERAL.METERED" => metered = value.starts_with("yes"),
+            _ => {}
+        }
+    }
+    finish(&device, connected, metered);
+    devices
+}
+
+fn parse_idle_time(output: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let property = |name: &str| {
+        output.lines().find_map(|line| {
+            line.strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('='))
+                .map(str::trim)
+        })
+    };
+    if property("IdleHint")? != "yes" {
+        return Some(Duration::zero());
+    }
+    let since = Utc
+        .timestamp_micros(property("IdleSinceHint")?.parse().ok()?)
+        .single()?;
+    Some((now - since).max(Duration::zero()))
+}
+
+fn parse_fullscreen_class(output: &str) -> Option<String> {
+    let fullscreen = output
+        .lines()
+        .any(|line| line.starts_with("_NET_WM_STATE") && line.contains("_NET_WM_STATE_FULLSCREEN"));
+    if !fullscreen {
+        return None;
+    }
+    let class = output
+        .lines()
+        .find_map(|line| line.strip_prefix("WM_CLASS(STRING) = "))
+        .and_then(|classes| classes.split(", ").last())
+        .map(|class| class.trim_matches('"').to_string())
+        .filter(|class| !class.is_empty());
+    Some(class.unwrap_or_else(|| "unknown application".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::calendar::MaintenanceWindow;
+
+    fn fixtures(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/conditions")
+            .join(name)
+    }
+
+    fn probe(sysfs: &str) -> SystemProbe {
+        SystemProbe {
+            sysfs: fixtures(sysfs),
+            bin_dir: Some(fixtures("bin")),
+            user: "1000".to_string(),
+        }
+    }
+
+    #[test]
+    fn power_comes_from_sysfs_supplies() {
+        assert!(probe("laptop-on-battery").on_battery());
+        assert!(!probe("laptop-on-ac").on_battery());
+        // A desktop lists no supplies, or only a mouse battery.
+        assert!(!probe("desktop").on_battery());
+        assert!(!probe("missing").on_battery());
+    }
+
+    #[test]
+    fn helper_output_is_parsed_for_metering_idle_and_fullscreen() {
+        let devices = parse_metered_devices(
+            "GENERAL.DEVICE:wlp2s0\nGENERAL.STATE:100 (connected)\nGENERAL.METERED:yes (guessed)\n\n\
+             GENERAL.DEVICE:enp0s31f6\nGENERAL.STATE:20 (unavailable)\nGENERAL.METERED:yes\n\n\
+             GENERAL.DEVICE:lo\nGENERAL.STATE:100 (connected (externally))\nGENERAL.METERED:no\n",
+        );
+        assert_eq!(devices, ["wlp2s0"]);
+
+        let now = Utc.timestamp_opt(1_800_000_000, 0).unwrap();
+        assert_eq!(
+            parse_idle_time("IdleHint=yes\nIdleSinceHint=1799999400000000\n", now),
+            Some(Duration::minutes(10))
+        );
+        assert_eq!(
+            parse_idle_time("IdleHint=no\nIdleSinceHint=0\n", now),
+            Some(Duration::zero())
+        );
+        assert_eq!(parse_idle_time("", now), None);
+
+        assert_eq!(
+            parse_fullscreen_class(
+                "_NET_WM_STATE(ATOM) = _NET_WM_STATE_FOCUSED\nWM_CLASS(STRING) = \"kitty\", \"kitty\"\n"
+            ),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn unmet_conditions_name_every_reason_to_wait() {
+        let conditions = RunConditions {
+            on_ac_power: true,
+            not_metered: true,
+            idle_minutes: Some(30),
+            no_fullscreen: true,
+            window: Some(MaintenanceWindow::parse("22:00-06:00").unwrap()),
+        };
+        // The fake loginctl reports idleness since 1 800 000 000.
+        let noon = Utc.timestamp_opt(1_800_000_000 + 20 * 60, 0).unwrap();
+
+        let reasons = unmet_conditions(&conditions, &probe("laptop-on-battery"), &noon).await;
+        assert_eq!(
+            reasons,
+            [
+                "outside the maintenance window 22:00-06:00",
+                "running on battery power",
+                "on a metered connection (wlp2s0)",
+                "the system has been idle 20 of 30 minutes",
+                "a fullscreen window is open (mpv)",
+            ]
+        );
+
+        let relaxed = RunConditions {
+            on_ac_power: true,
+            idle_minutes: Some(15),
+            ..RunConditions::default()
+        };
+        assert!(unmet_conditions(&relaxed, &probe("laptop-on-ac"), &noon)
+            .await
+            .is_empty());
+
+        let without_helpers = SystemProbe {
+            bin_dir: Some(fixtures("missing")),
+            ..probe("desktop")
+        };
+        assert_eq!(
+            unmet_conditions(&conditions, &without_helpers, &noon).await,
+            [
+                "outside the maintenance window 22:00-06:00",
+                "the idle time is unknown (logind did not answer)",
+            ]
+        );
+    }
+
+    #[test]
+    fn busy_package_managers_only_count_the_sources_in_play() {
+        let locks = vec![
+            (
+                PackageSource::Apt,
+                LockStatus {
+                    is_locked: true,
+                    lock_holder: Some("unattended-upgr".to_string()),
+                    lock_files: Vec::new(),
+                },
+            ),
+            (PackageSource::Flatpak, LockStatus::default()),
+        ];
+        assert_eq!(
+            busy_package_managers(&locks, None),
+            ["APT is busy (unattended-upgr holds its lock)"]
+        );
+        assert!(busy_package_managers(&locks, Some(PackageSource::Flatpak)).is_empty());
+    }
+}
@@ -1,6 +1,12 @@
 use crate::backend::PackageManager;
-use crate::models::{Config, Package, PackageStatus, ScheduledOperation, ScheduledTask};
+use crate::models::scheduler::RunConditions;
+use crate::models::{
+    Config, Package, PackageSource, PackageStatus, RecurringRule, ScheduledOperation,
+    ScheduledTask, UpdatePolicy, UpdateSightings,
+};
+use crate::scheduler_conditions::{busy_package_managers, unmet_conditions, SystemProbe};
 use anyhow::{bail, Context, Result};
+use serde::Serialize;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -12,7 +18,8 @@ const SCHEDULER_TIMER_NAME: &str = "linget-scheduler.timer";
 const SCHEDULER_LOCK_FILE: &str = "scheduler.lock";
 const STALE_LOCK_AGE: Duration = Duration::from_secs(60 * 60);
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "runtime", rename_all = "snake_case")]
 pub enum SchedulerRuntimeStatus {
     SystemdUser,
     InAppFallback { reason: String },
@@ -146,6 +153,37 @@ pub async fn sync_systemd_runtime(has_pending_tasks: bool) -> SchedulerRuntimeSt
     }
 }
 
+/// Report how scheduled work runs without rewriting or enabling any units,
+/// along with the timer's `systemctl is-active` state when systemd answered.
+pub async fn probe_systemd_runtime() -> (SchedulerRuntimeStatus, Option<String>) {
+    if which::which("systemctl").is_err() {
+        return (
+            SchedulerRuntimeStatus::InAppFallback {
+                reason: "systemctl is not available".to_string(),
+            },
+            None,
+        );
+    }
+    if let Err(error) = run_systemctl_user(&["show-environment"]).await {
+        return (
+            SchedulerRuntimeStatus::InAppFallback {
+                reason: error.to_string(),
+            },
+            None,
+        );
+    }
+
+    // `is-active` exits non-zero for inactive units, so only stdout matters.
+    let timer = Command::new("systemctl")
+        .args(["--user", "is-active", SCHEDULER_TIMER_NAME])
+        .output()
+        .await
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|state| !state.is_empty());
+    (SchedulerRuntimeStatus::SystemdUser, timer)
+}
+
 async fn sync_systemd_runtime_inner(has_pending_tasks: bool) -> Result<()> {
     if which::which("systemctl").is_err() {
         bail!("systemctl is not available");
@@ -175,6 +213,23 @@ async fn sync_systemd_runtime_inner(has_pending_tasks: bool) -> Result<()> {
     Ok(())
 }
 
+/// Why scheduled work on `source`, or on every source when `None`, should
+/// wait: its unmet conditions and any busy package manager. Empty when it
+/// may run now.
+pub async fn deferral_reasons(
+    manager: &PackageManager,
+    conditions: &RunConditions,
+    source: Option<PackageSource>,
+) -> Vec<String> {
+    let mut reasons =
+        unmet_conditions(conditions, &SystemProbe::default(), &chrono::Local::now()).await;
+    reasons.extend(busy_package_managers(
+        &manager.check_all_lock_status().await,
+        source,
+    ));
+    reasons
+}
+
 fn task_package_stub(task: &ScheduledTask, status: PackageStatus) -> Package {
     Package {
         name: task.package_name.clone(),
@@ -191,6 +246,7 @@ fn task_package_stub(task: &ScheduledTask, status: PackageStatus) -> Package {
         install_date: None,
         update_category: None,
         enrichment: None,
+        released_at: None,
     }
 }
 
@@ -225,6 +281,74 @@ pub async fn execute_scheduled_task(manager: &PackageManager, task: &ScheduledTa
     }
 }
 
+/// What a recurring rule run updated.
+#[derive(Debug, Default)]
+pub struct RuleUpdates {
+    pub updated: usize,
+    /// Selected updates the update policy held back, with the reason.
+    pub held_back: Vec<String>,
+}
+
+/// Update every pending update a recurring rule selects and the update
+/// policy, when there is one, lets apply unattended. One failed package does
+/// not stop the others.
+pub async fn execute_recurring_rule(
+    manager: &PackageManager,
+    rule: &RecurringRule,
+) -> Result<RuleUpdates> {
+    let updates = match rule.source {
+        Some(source) => manager.check_updates_for_source(source).await?,
+        None => manager.check_all_updates().await?,
+    };
+    let mut selected: Vec<Package> = updates
+        .into_iter()
+        .filter(|package| rule.selects(package))
+        .collect();
+
+    let mut held_back = Vec::new();
+    let policy = UpdatePolicy::load_default()?;
+    if let Some(policy) = &policy {
+        let mut sightings = UpdateSightings::load();
+        let (cleared, held) = policy.partition(selected, &mut sightings, chrono::Utc::now());
+        if let Err(error) = sightings.save() {
+            tracing::warn!("Could not record update sightings: {:#}", error);
+        }
+        selected = cleared;
+        held_back = held
+            .into_iter()
+            .map(|(package, decision)| format!("{} {}", package.name, decision.verdict))
+            .collect();
+    }
+
+    let mut failures = Vec::new();
+    for package in &selected {
+        // Under a release cooldown, install the release the policy judged
+        // rather than whatever the registry publishes meanwhile.
+        let result = match policy
+            .as_ref()
+            .and_then(|policy| policy.pinned_version(package))
+        {
+            Some(version) => manager.install_version(package, version).await,
+            None => manager.update(package).await,
+        };
+        if let Err(error) = result {
+            failures.push(format!("{}: {}", package.name, error));
+        }
+    }
+    if !failures.is_empty() {
+        bail!(
+            "{} of {} updates failed: {}",
+            failures.len(),
+            selected.len(),
+            failures.join("; ")
+        );
+    }
+    Ok(RuleUpdates {
+        updated: selected.len(),
+        held_back,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +150,7 @@ pub struct DetailsPanelWidgets {
     reverse_deps_row: adw::ActionRow,
     safe_remove_row: adw::ActionRow,
     safe_remove_icon: gtk::Image,
+    held_row: adw::ActionRow,
     config_row: adw::ActionRow,
     commands_row: adw::ActionRow,
     log_row: adw::ActionRow,
@@ -581,6 +582,18 @@ impl SimpleComponent for DetailsPanelModel {
         safe_remove_row.add_prefix(&safe_remove_icon);
         insights_group.add(&safe_remove_row);
 
+        let held_row = adw::ActionRow::builder()
+            .title("Held")
+            .subtitle("Pinned by the package manager; upgrades skip it")
+            .css_classes(vec!["property"])
+            .visible(false)
+            .build();
+        let held_icon = gtk::Image::builder()
+            .icon_name("changes-prevent-symbolic")
+            .build();
+        held_row.add_prefix(&held_icon);
+        insights_group.add(&held_row);
+
         let config_row = adw::ActionRow::builder()
             .title("Config paths")
             .subtitle("None detected")
@@ -812,6 +825,7 @@ impl SimpleComponent for DetailsPanelModel {
             reverse_deps_row,
             safe_remove_row,
             safe_remove_icon,
+            held_row,
             config_row,
             commands_row,
             log_row,
@@ -901,6 +915,13 @@ impl SimpleComponent for DetailsPanelModel {
                         .get_reverse_dependencies(&pkg_for_insights)
                         .await
                         .unwrap_or_default();
+                    let removal_impact = manager
+                        .removal_impact(
+                            pkg_for_insights.source,
+                            std::slice::from_ref(&pkg_for_insights.name),
+                        )
+                        .await
+                        .ok();
                     let package_commands = manager
                         .get_package_commands(&pkg_for_insights.name, pkg_for_insights.source)
                         .await
@@ -908,6 +929,10 @@ impl SimpleComponent for DetailsPanelModel {
                         .into_iter()
                         .map(|(command, _)| command)
                         .collect();
+                    let is_held = manager
+                        .list_holds(pkg_for_insights.source)
+                        .await
+                        .is_ok_and(|held| held.contains(&pkg_for_insights.name));
                     drop(manager);
 
                     let install_date = pkg_for_insights
@@ -922,10 +947,16 @@ impl SimpleComponent for DetailsPanelModel {
                     let insights = PackageInsights::new()
                         .with_install_date(install_date)
                         .with_dependencies(pkg_for_insights.dependencies.len(), 0)
-                        .with_reverse_deps(reverse_deps)
-                        .with_config_paths(config_paths)
-                        .with_package_commands(package_commands)
-                        .with_log_command(log_command);
+                        .with_reverse_deps(reverse_deps);
+                    let insights = match removal_impact {
+                        Some(impact) => insights
+                            .with_removal_impact(impact.dependents.len(), impact.is_blocked()),
+                        None => insights,
+                    }
+                    .with_config_paths(config_paths)
+                    .with_package_commands(package_commands)
+                    .with_log_command(log_command)
+                    .with_held(is_held);
 
                     sender_insights.input(DetailsPanelInput::InsightsLoaded(insights));
                 });
@@ -1510,6 +1541,8 @@ impl SimpleComponent for DetailsPanelModel {
                     widgets.safe_remove_icon.add_css_class("warning");
                 }
 
+                widgets.held_row.set_visible(insights.is_held);
+
                 if !insights.config_paths.is_empty() {
                     let config_subtitle =
                         crate::ui::escape_markup_text(&insights.config_paths.join(", "));
@@ -1547,6 +1580,7 @@ impl SimpleComponent for DetailsPanelModel {
                 widgets.deps_row.set_subtitle("Loading...");
                 widgets.reverse_deps_row.set_subtitle("Loading...");
                 widgets.safe_remove_row.set_subtitle("Checking...");
+                widgets.held_row.set_visible(false);
                 widgets.config_row.set_visible(false);
                 widgets.commands_row.set_visible(true);
                 widgets.commands_row.set_subtitle("Loading...");
@@ -1556,6 +1590,7 @@ impl SimpleComponent for DetailsPanelModel {
                 widgets.log_row.set_tooltip_text(None);
             } else {
                 widgets.insights_group.set_visible(false);
+                widgets.held_row.set_visible(false);
                 widgets.config_row.set_visible(false);
                 widgets.commands_row.set_visible(false);
                 widgets.commands_row.set_tooltip_text(None);
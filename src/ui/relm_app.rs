@@ -2,12 +2,13 @@ use crate::backend::streaming::StreamLine;
 use crate::backend::{
     BackendCapability, HistoryTracker, PackageManager, SearchCatalog, SourceCapabilityContext,
 };
+use crate::models::scheduler::Deferral;
 use crate::models::{
     alias::AliasViewData, get_global_recommendations, Config, EnabledSources, LayoutMode, Package,
     PackageSource, PackageStatus, Recommendation,
 };
 use crate::scheduler_runtime::{
-    execute_scheduled_task, sync_systemd_runtime, ScheduledTaskExecutionLock,
+    deferral_reasons, execute_scheduled_task, sync_systemd_runtime, ScheduledTaskExecutionLock,
     SchedulerRuntimeStatus,
 };
 use crate::ui::alias_view::AliasViewAction;
@@ -280,6 +281,7 @@ pub enum AppMsg {
         package_name: String,
         error: String,
     },
+    ScheduledTaskDeferred(String),
     CancelScheduledTask(String),
     TaskQueueAction(TaskQueueAction),
     ScheduleAllUpdates,
@@ -2409,6 +2411,7 @@ impl SimpleComponent for AppModel {
                         .find(|p| p.name == update.name && p.source == update.source)
                     {
                         pkg.available_version = update.available_version.clone();
+                        pkg.released_at = update.released_at;
                         pkg.status = PackageStatus::UpdateAvailable;
                         pkg.update_category = Some(pkg.detect_update_category());
                     }
@@ -3676,6 +3679,7 @@ impl SimpleComponent for AppModel {
                                     dependencies: Vec::new(),
                                     update_category: None,
                                     enrichment: None,
+                                    released_at: None,
                                 };
                                 sender.input(AppMsg::ExecutePackageAction(undo_pkg));
 
@@ -4334,7 +4338,7 @@ impl SimpleComponent for AppModel {
             }
 
             AppMsg::SyncSchedulerRuntime { notify_if_fallback } => {
-                let has_pending_tasks = self.config.borrow().scheduler.pending_count() > 0;
+                let has_pending_tasks = self.config.borrow().scheduler.needs_runner();
                 let sender_sync = sender.clone();
                 glib::spawn_future_local(async move {
                     let status = sync_systemd_runtime(has_pending_tasks).await;
@@ -4376,7 +4380,14 @@ impl SimpleComponent for AppModel {
                 let next_due = {
                     let config = self.config.borrow();
                     let mut due_tasks: Vec<_> = config.scheduler.due_tasks();
-                    due_tasks.sort_by_key(|task| task.scheduled_at);
+                    // Tasks not yet deferred go first, then the one whose
+                    // conditions were checked longest ago.
+                    due_tasks.sort_by_key(|task| {
+                        (
+                            task.deferred.as_ref().map(|deferral| deferral.checked_at),
+                            task.scheduled_at,
+                        )
+                    });
                     due_tasks.first().map(|task| task.id.clone())
                 };
 
@@ -4435,6 +4446,29 @@ impl SimpleComponent for AppModel {
 
                     glib::spawn_future_local(async move {
                         let _execution_lock = execution_lock;
+                        let reasons = {
+                            let manager = pm.read().await;
+                            deferral_reasons(&manager, &task.conditions, Some(task.source)).await
+                        };
+                        if !reasons.is_empty() {
+                            let mut cfg = config.borrow_mut();
+                            if let Some(t) = cfg
+                                .scheduler
+                                .tasks
+                                .iter_mut()
+                                .find(|t| t.id == task_id_clone)
+                            {
+                                t.deferred = Some(Deferral {
+                                    checked_at: chrono::Utc::now(),
+                                    reasons,
+                                });
+                            }
+                            let _ = cfg.save();
+                            drop(cfg);
+                            sender.input(AppMsg::ScheduledTaskDeferred(task_id_clone));
+                            return;
+                        }
+
                         let result = {
                             let manager = pm.read().await;
                             execute_scheduled_task(&manager, &task).await
@@ -4535,6 +4569,14 @@ impl SimpleComponent for AppModel {
                 });
             }
 
+            AppMsg::ScheduledTaskDeferred(task_id) => {
+                self.tasks_data.scheduler = self.config.borrow().scheduler.clone();
+                if self.tasks_data.running_task_id.as_deref() == Some(task_id.as_str()) {
+                    self.tasks_data.running_task_id = None;
+                }
+                self.pending_tasks_rebuild.set(true);
+            }
+
             AppMsg::CancelScheduledTask(task_id) => {
                 let mut config = self.config.borrow_mut();
                 config.scheduler.remove_task(&task_id);
@@ -352,6 +352,8 @@ where
 {
     let subtitle = if is_running {
         "Running...".to_string()
+    } else if let Some(deferral) = &task.deferred {
+        deferral.describe()
     } else {
         let time_until = task.time_until();
         let scheduled_time = task.scheduled_time_display();
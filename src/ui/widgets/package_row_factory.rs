@@ -479,6 +479,7 @@ mod tests {
             install_date: None,
             update_category: None,
             enrichment: None,
+            released_at: None,
         };
 
         let subtitle = get_subtitle(&package, &[PackageSource::Snap]);
@@ -158,6 +158,10 @@ function humanSize(bytes) {
 function errorBrief(entry) {
   const error = entry.error || '';
   const lower = error.toLowerCase();
+  const delta = staleDelta(entry);
+  if (delta) {
+    return `plan changed since review: ${delta} — retry approves the new plan`;
+  }
   if (lower.includes('externally managed') || lower.includes('pep 668')) {
     const name = entry.package_name;
     return `python is distro-managed — try "pipx install ${name}" or "apt install python3-${name.replace(/[-_]/g, '-')}"`;
@@ -168,6 +172,23 @@ function errorBrief(entry) {
   return error.split('\n')[0].slice(0, 140);
 }
 
+// The packages that moved when a queued plan went stale, e.g.
+// "+libfoo −bar ~vim 9.1". Empty when the entry has no stale diff.
+function staleDelta(entry) {
+  if (!entry.stale_plan_diff_json) return '';
+  let diff;
+  try {
+    diff = JSON.parse(entry.stale_plan_diff_json);
+  } catch {
+    return '';
+  }
+  return [
+    ...diff.added.map(change => `+${change.name}`),
+    ...diff.removed.map(change => `−${change.name}`),
+    ...diff.reversioned.map(({ fresh }) => `~${fresh.name}${fresh.after ? ` ${fresh.after}` : ''}`),
+  ].join(' ') || 'risk raised';
+}
+
 function isSecurity(pkg) {
   return !!(pkg.is_security || pkg.update_category === 'Security');
 }
@@ -61,10 +61,7 @@ pub struct WebState {
 /// Sources whose queue tasks run as verified transactions with an attached
 /// reviewed plan — same contract as the TUI queue.
 fn stable_transaction_source(source: PackageSource) -> bool {
-    matches!(
-        source,
-        PackageSource::Apt | PackageSource::Flatpak | PackageSource::Npm
-    )
+    crate::backend::transaction::STABLE_PROVIDERS.contains(&source)
 }
 
 pub async fn run(bind_addr: &str, port: u16) -> Result<()> {
@@ -75,6 +72,12 @@ pub async fn run(bind_addr: &str, port: u16) -> Result<()> {
     let pm = Arc::new(RwLock::new(PackageManager::new_fast()));
     let tracker = HistoryTracker::load().await.ok();
     let history = Arc::new(Mutex::new(tracker));
+    if let Err(error) = TaskQueueExecutor::new(pm.clone(), history.clone())
+        .recover_interrupted_transactions()
+        .await
+    {
+        tracing::warn!(error = %error, "failed to recover interrupted transactions");
+    }
     let (events, _) = broadcast::channel(512);
 
     let state = Arc::new(WebState {
@@ -333,6 +336,7 @@ async fn load_catalog_fresh(pm: Arc<RwLock<PackageManager>>) -> Result<Vec<Packa
                     .available_version
                     .or_else(|| Some(update.version.clone()));
                 existing.update_category = update.update_category;
+                existing.released_at = update.released_at;
             }
         }
     }
@@ -424,12 +428,7 @@ async fn enqueue_packages(
         if already {
             continue;
         }
-        entries.push(TaskQueueEntry::new(
-            action,
-            package.id(),
-            package.name.clone(),
-            package.source,
-        ));
+        entries.push(TaskQueueEntry::for_package(action, &package));
     }
 
     let planned_targets: usize = stable.values().map(|targets| targets.len()).sum();
@@ -473,17 +472,35 @@ async fn plan_stable_transactions(
         TaskQueueAction::Update => OperationAction::Update,
     };
 
-    let mut sources: Vec<_> = groups.into_iter().collect();
-    sources.sort_by_key(|(source, _)| source.to_string());
+    if groups.len() > 1 {
+        // Mixed providers become one reviewable bundle and one queue entry.
+        let request = OperationRequest::new(
+            operation_action,
+            groups.into_values().flatten().collect(),
+            RequestedBy::Tui,
+        );
+        let bundle = engine
+            .plan_bundle(request)
+            .await
+            .map_err(|error| anyhow::anyhow!(error.safe_message))?;
+        if bundle.risk.level == RiskLevel::Blocked {
+            anyhow::bail!("transaction bundle is blocked");
+        }
+        let entry = bundle
+            .queue_entry()
+            .context("transaction bundle could not be serialized")?;
+        return Ok(vec![entry]);
+    }
+
     let mut entries = Vec::new();
-    for (_source, targets) in sources {
+    for (_source, targets) in groups {
         let request = OperationRequest::new(operation_action, targets, RequestedBy::Tui);
         let (plan, risk) = engine
             .plan(request)
             .await
             .map_err(|error| anyhow::anyhow!(error.safe_message))?;
         if risk.level == RiskLevel::Blocked {
-            anyhow::bail!("{} provider plan is blocked", plan.provider.source);
+            anyhow::bail!(plan.blocked_reason());
         }
         let plan_json =
             serde_json::to_string(&plan).context("provider plan could not be serialized")?;
@@ -563,7 +580,10 @@ async fn retry_failed(State(state): State<Arc<WebState>>) -> Result<Json<RetryRe
         }
         // Planless stable failures come from an older build; route them
         // through fresh planning instead of failing the plan guard again.
-        if stable_transaction_source(entry.package_source) && entry.reviewed_plan_json.is_none() {
+        if stable_transaction_source(entry.package_source)
+            && entry.reviewed_plan_json.is_none()
+            && entry.reviewed_bundle_json.is_none()
+        {
             continue;
         }
         let mut retry = TaskQueueEntry::new(
@@ -574,7 +594,10 @@ async fn retry_failed(State(state): State<Arc<WebState>>) -> Result<Json<RetryRe
         );
         retry.reviewed_operation_id = entry.reviewed_operation_id.clone();
         retry.reviewed_plan_json = entry.reviewed_plan_json.clone();
+        retry.reviewed_bundle_json = entry.reviewed_bundle_json.clone();
         retry.retry_of = Some(entry.id.clone());
+        retry.approved_stale_operation_id = entry.stale_plan_approval();
+        retry.available_version = entry.available_version.clone();
         retries.push(retry);
     }
 
@@ -624,7 +647,11 @@ async fn ensure_executor(state: &WebState) {
     {
         return; // an executor is already draining the queue
     }
-    let executor = TaskQueueExecutor::new(state.pm.clone(), state.history.clone());
+    let config = Config::load();
+    let executor = TaskQueueExecutor::new(state.pm.clone(), state.history.clone())
+        .with_concurrency(config.queue_concurrency)
+        .with_snapshots(config.snapshots)
+        .with_hooks(config.hooks);
     let broadcaster = state.events.clone();
     let flag = state.executor_running.clone();
 